@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveTime, Weekday};
+use std::{fs, path::Path};
+
+use crate::{
+    parse_alarm_time,
+    store::{self, Entry, EntryKind, Imported},
+};
+
+/// One alarm line of an import file: `weekdays 08:50 standup`.
+#[derive(Debug, PartialEq)]
+struct Line {
+    repeat: Vec<Weekday>,
+    time: NaiveTime,
+    label: String,
+}
+
+/// Reads `<repeat-spec> <time> <label>`. The label may be quoted; blank lines
+/// and `#` comments are `None`.
+fn parse_line(line: &str) -> Result<Option<Line>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let (repeat, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let repeat = store::parse_repeat_spec(repeat)?;
+    let rest = rest.trim_start();
+    let (time, label) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    if time.is_empty() {
+        anyhow::bail!("Missing time after the days");
+    }
+    let time = parse_alarm_time(time)?;
+    let label = label.trim();
+    let label = label.strip_prefix('"').and_then(|label| label.strip_suffix('"')).unwrap_or(label);
+    if label.is_empty() {
+        anyhow::bail!("Missing label after the time");
+    }
+    Ok(Some(Line { repeat, time, label: label.to_string() }))
+}
+
+/// FNV-1a, so keys stay the same across builds and Rust versions.
+fn hash(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+/// Reads the alarms in `path`, due next after `now`. Errors name the line
+/// they're on; so does a label used twice, since labels tell alarms apart.
+pub fn read_file(path: &Path, now: DateTime<Local>) -> Result<Vec<Entry>> {
+    let text = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let source = fs::canonicalize(path)?;
+    let mut entries: Vec<(usize, Entry)> = Vec::new();
+
+    for (index, text) in text.lines().enumerate() {
+        let number = index + 1;
+        let line = parse_line(text).with_context(|| format!("{}, line {}", path.display(), number))?;
+        let Some(line) = line else {
+            continue;
+        };
+        if let Some((first, _)) = entries.iter().find(|(_, entry)| entry.label.as_deref() == Some(line.label.as_str())) {
+            anyhow::bail!("{}, line {}: '{}' is already on line {}", path.display(), number, line.label, first);
+        }
+        let due = store::next_occurrence(now, line.time, &line.repeat)
+            .ok_or_else(|| anyhow::anyhow!("{}, line {}: {} never comes around", path.display(), number, line.time))?;
+        let mut entry = Entry {
+            id: 0,
+            kind: EntryKind::Alarm,
+            label: Some(line.label.clone()),
+            tag: None,
+            message: None,
+            due,
+            repeat: line.repeat,
+            exec: None,
+            quiet_hours: None,
+            skip_until: None,
+            imported: None,
+            total_ms: None,
+            progress: None,
+        };
+        entry.imported = Some(Imported {
+            source: source.clone(),
+            key: hash(&line.label),
+            // Of what the line says, so respacing it or writing mon,tue,... for weekdays is no change
+            hash: hash(&format!("{} {} {}", entry.repeat_spec(), line.time, line.label)),
+        });
+        entries.push((number, entry));
+    }
+    Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_line() {
+        let line = parse_line("weekdays 08:50 standup").unwrap().unwrap();
+        assert_eq!(line.repeat.len(), 5);
+        assert_eq!(line.time, NaiveTime::from_hms_opt(8, 50, 0).unwrap());
+        assert_eq!(line.label, "standup");
+
+        let line = parse_line("  sun   21:00 \"plan week\"").unwrap().unwrap();
+        assert_eq!((line.repeat, line.label.as_str()), (vec![Weekday::Sun], "plan week"));
+        assert_eq!(parse_line("mon,thu 7:30pm gym").unwrap().unwrap().repeat, [Weekday::Mon, Weekday::Thu]);
+
+        assert!(parse_line("# a comment").unwrap().is_none());
+        assert!(parse_line("").unwrap().is_none());
+        assert!(parse_line("weekdays 08:50").is_err());
+        assert!(parse_line("someday 08:50 x").is_err());
+        assert!(parse_line("daily 25:00 x").is_err());
+    }
+
+    #[test]
+    fn test_import_file_into_store() {
+        let dir = std::env::temp_dir().join(format!("clockeroo-import-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("alarms");
+        // 2024-03-08 is a Friday
+        let now = Local.with_ymd_and_hms(2024, 3, 8, 9, 0, 0).unwrap();
+
+        fs::write(&path, "weekdays 08:50 standup\nsun 21:00 \"plan week\"\n").unwrap();
+        let mut store = store::Store::default();
+        let report = store.import(&fs::canonicalize(&path).unwrap(), read_file(&path, now).unwrap(), false);
+        assert_eq!((report.added, report.unchanged), (2, 0));
+        assert_eq!(store.entries[0].due, Local.with_ymd_and_hms(2024, 3, 11, 8, 50, 0).unwrap());
+
+        // Importing again changes nothing, an edited line keeps its id
+        let source = fs::canonicalize(&path).unwrap();
+        let report = store.import(&source, read_file(&path, now).unwrap(), false);
+        assert_eq!((report.added, report.unchanged), (0, 2));
+        fs::write(&path, "# moved\nweekdays   09:15 standup\n").unwrap();
+        let report = store.import(&source, read_file(&path, now).unwrap(), false);
+        assert_eq!((report.updated, report.unchanged, report.removed), (1, 0, 0));
+        assert_eq!(store.entries[0].id, 1);
+        assert_eq!(store.entries[0].due, Local.with_ymd_and_hms(2024, 3, 8, 9, 15, 0).unwrap());
+        assert_eq!(store.entries.len(), 2);
+
+        // Only --sync drops the line that was deleted
+        let report = store.import(&source, read_file(&path, now).unwrap(), true);
+        assert_eq!((report.unchanged, report.removed), (1, 1));
+        assert_eq!(store.entries.len(), 1);
+
+        fs::write(&path, "daily 07:00 up\n\nmon 8:00 up\n").unwrap();
+        let err = format!("{:#}", read_file(&path, now).unwrap_err());
+        assert!(err.ends_with("line 3: 'up' is already on line 1"), "{}", err);
+        fs::write(&path, "daily 07:00 up\nevery 7:00 x\n").unwrap();
+        let err = format!("{:#}", read_file(&path, now).unwrap_err());
+        assert!(err.contains("line 2: Unknown day 'every'"), "{}", err);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
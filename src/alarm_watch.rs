@@ -0,0 +1,368 @@
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use std::{
+    collections::HashSet,
+    fs,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    hooks,
+    notify::send_alarm_notification,
+    parse_alarm_time,
+    quiet_hours::is_quiet_now,
+    resolve_alarm_target,
+    sound::{play_bell, SoundEvent, SoundOptions},
+    statefile,
+    store::{self, Entry, EntryKind, Store},
+    time_format::format_clock,
+    ui,
+};
+
+/// How often the table redraws and checks the store file for outside changes.
+const REFRESH: Duration = Duration::from_secs(1);
+
+pub struct WatchOptions {
+    pub visual_bell: bool,
+    pub sound: SoundOptions,
+}
+
+/// What a key press asks the watch screen to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchEvent {
+    Continue,
+    Add(NaiveTime),
+    Delete(u64),
+    Quit,
+}
+
+/// The alarm table, its selection and the add-alarm input line.
+#[derive(Debug, Default)]
+pub struct Watch {
+    /// Pending alarms, soonest first
+    pub alarms: Vec<Entry>,
+    pub selected: usize,
+    /// Text typed after 'a', until Enter or Esc
+    pub input: Option<String>,
+    /// Last error, shown under the table until the next key
+    pub message: Option<String>,
+}
+
+impl Watch {
+    /// Replaces the table with the alarms in `store`, keeping the selection on
+    /// the same alarm where it's still there.
+    pub fn set_alarms(&mut self, store: &Store) {
+        let selected_id = self.alarms.get(self.selected).map(|alarm| alarm.id);
+        self.alarms = pending_alarms(store);
+        self.selected = selected_id
+            .and_then(|id| self.alarms.iter().position(|alarm| alarm.id == id))
+            .unwrap_or(self.selected)
+            .min(self.alarms.len().saturating_sub(1));
+    }
+
+    pub fn handle_key(&mut self, key: &KeyEvent) -> WatchEvent {
+        self.message = None;
+
+        if let Some(input) = &mut self.input {
+            match key.code {
+                KeyCode::Esc => self.input = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => match parse_alarm_time(input) {
+                    Ok(time) => {
+                        self.input = None;
+                        return WatchEvent::Add(time);
+                    }
+                    Err(err) => self.message = Some(err.to_string()),
+                },
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return WatchEvent::Continue;
+        }
+
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.alarms.len().saturating_sub(1));
+            }
+            KeyCode::Char('a') => self.input = Some(String::new()),
+            KeyCode::Char('d') => {
+                if let Some(alarm) = self.alarms.get(self.selected) {
+                    return WatchEvent::Delete(alarm.id);
+                }
+            }
+            _ if ui::is_quit_event(key) => return WatchEvent::Quit,
+            _ => {}
+        }
+        WatchEvent::Continue
+    }
+}
+
+/// Alarms in the store (background timers left out), soonest first.
+pub fn pending_alarms(store: &Store) -> Vec<Entry> {
+    let mut alarms: Vec<Entry> = store.entries.iter().filter(|entry| entry.kind == EntryKind::Alarm).cloned().collect();
+    alarms.sort_by_key(|alarm| (alarm.due, alarm.id));
+    alarms
+}
+
+/// Whether a daemon is running to fire alarms and take changes; without one
+/// this screen edits the store file and rings alarms itself.
+fn daemon_running() -> bool {
+    #[cfg(unix)]
+    return crate::daemon::running_pid().is_some();
+    #[cfg(not(unix))]
+    return false;
+}
+
+/// Adds or removes an alarm, through the daemon if one is running so its copy
+/// of the store stays current, otherwise in the file directly.
+fn apply(event: WatchEvent) -> Result<()> {
+    let entry = match event {
+        WatchEvent::Add(time) => {
+            let (target, _) = resolve_alarm_target(Local::now().naive_local(), time);
+            let due = Local
+                .from_local_datetime(&target)
+                .single()
+                .ok_or_else(|| anyhow::anyhow!("Invalid datetime conversion"))?;
+            Some(Entry { id: 0, kind: EntryKind::Alarm, label: None, tag: None, message: None, due, repeat: Vec::new(), exec: None, quiet_hours: None, skip_until: None, imported: None, total_ms: None, progress: None })
+        }
+        WatchEvent::Delete(_) => None,
+        WatchEvent::Continue | WatchEvent::Quit => return Ok(()),
+    };
+
+    #[cfg(unix)]
+    if daemon_running() {
+        use crate::ipc::{self, Request};
+        let request = match (entry, event) {
+            (Some(entry), _) => Request::Add { entry: Box::new(entry), duplicates: store::Duplicates::Allow },
+            (None, WatchEvent::Delete(id)) => Request::Cancel { id },
+            _ => return Ok(()),
+        };
+        let response = ipc::send(&request)?;
+        if let Some(err) = response.error {
+            anyhow::bail!(err);
+        }
+        return Ok(());
+    }
+
+    let _lock = statefile::lock(&store::store_path())?;
+    let mut store = Store::load()?;
+    match (entry, event) {
+        (Some(entry), _) => {
+            store.add(entry);
+        }
+        (None, WatchEvent::Delete(id)) => {
+            store.remove(id);
+        }
+        _ => {}
+    }
+    store.save()
+}
+
+/// Alarms in `alarms` that are due at `now` and haven't rung on this screen yet.
+fn newly_due(alarms: &[Entry], now: DateTime<Local>, rung: &HashSet<(u64, DateTime<Local>)>) -> Vec<Entry> {
+    alarms.iter().filter(|alarm| alarm.due <= now && !rung.contains(&(alarm.id, alarm.due))).cloned().collect()
+}
+
+fn store_modified() -> Option<SystemTime> {
+    fs::metadata(store::store_path()).and_then(|meta| meta.modified()).ok()
+}
+
+/// Notifies, beeps and runs the alarm's hook, as the daemon would.
+async fn ring(alarm: &Entry, options: &WatchOptions) {
+    if let Some(command) = &alarm.exec {
+        hooks::spawn(
+            command,
+            &[
+                ("CLOCKEROO_ID", alarm.id.to_string()),
+                ("CLOCKEROO_KIND", "alarm".to_string()),
+                ("CLOCKEROO_LABEL", alarm.label.clone().unwrap_or_default()),
+            ],
+        );
+    }
+    if is_quiet_now(alarm.quiet_hours.as_ref()) {
+        return;
+    }
+    play_bell(SoundEvent::Alarm, &options.sound);
+    send_alarm_notification("Alarm!", &format!("It's {}! ({})", format_clock(&alarm.due), alarm.display_name()));
+    if options.visual_bell {
+        ui::visual_bell().await;
+    }
+}
+
+/// `alarm watch`: a live table of every pending alarm that rings in place.
+pub async fn run_alarm_watch(options: WatchOptions) -> Result<()> {
+    let mut watch = Watch::default();
+    watch.set_alarms(&Store::load()?);
+    let mut loaded_at = store_modified();
+    let mut rung = HashSet::new();
+
+    let mut terminal = ui::enter_screen()?;
+
+    'watch: loop {
+        let now = Local::now();
+
+        // Check the table as loaded before reloading: a running daemon may
+        // already have rolled or removed the alarm that just went off
+        let due = newly_due(&watch.alarms, now, &rung);
+        if !due.is_empty() {
+            let daemon = daemon_running();
+            if !daemon {
+                let _lock = statefile::lock(&store::store_path())?;
+                let mut store = Store::load()?;
+                // Only the alarms rung here: detached timers and the rest wait for the daemon
+                let ids: Vec<u64> = due.iter().map(|alarm| alarm.id).collect();
+                store.take_due_ids(&ids, now);
+                store.save()?;
+            }
+            for alarm in due {
+                rung.insert((alarm.id, alarm.due));
+                // The daemon rings its own alarms, this screen only shows them
+                if !daemon {
+                    ring(&alarm, &options).await;
+                }
+                loop {
+                    terminal.draw(|f| ui::draw_alarm_ringing(f, alarm.due.time(), None, ui::Snooze::Unavailable))?;
+                    if let Some(key) = ui::poll_key(REFRESH)? {
+                        if key.kind == KeyEventKind::Press && ui::is_dismiss_event(&key) {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        let modified = store_modified();
+        if modified != loaded_at {
+            match Store::load() {
+                Ok(store) => {
+                    watch.set_alarms(&store);
+                    loaded_at = modified;
+                }
+                // Caught between writes, the next refresh will pick it up
+                Err(err) => watch.message = Some(format!("{:#}", err)),
+            }
+        }
+
+        terminal.draw(|f| {
+            let view = ui::AlarmWatchView {
+                alarms: &watch.alarms,
+                selected: watch.selected,
+                now: Local::now(),
+                input: watch.input.as_deref(),
+                message: watch.message.as_deref(),
+            };
+            ui::draw_alarm_watch(f, &view);
+        })?;
+
+        if let Some(key) = ui::poll_key(REFRESH)? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match watch.handle_key(&key) {
+                WatchEvent::Continue => {}
+                WatchEvent::Quit => break 'watch,
+                change => {
+                    if let Err(err) = apply(change) {
+                        watch.message = Some(format!("{:#}", err));
+                    }
+                    // Show the change now rather than on the next refresh
+                    if let Ok(store) = Store::load() {
+                        watch.set_alarms(&store);
+                        loaded_at = store_modified();
+                    }
+                }
+            }
+        }
+    }
+
+    ui::leave_screen(terminal)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn alarm(id: u64, due: DateTime<Local>) -> Entry {
+        Entry { id, kind: EntryKind::Alarm, label: None, tag: None, message: None, due, repeat: Vec::new(), exec: None, quiet_hours: None, skip_until: None, imported: None, total_ms: None, progress: None }
+    }
+
+    fn at(h: u32, m: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, 8, h, m, 0).unwrap()
+    }
+
+    fn store(entries: Vec<Entry>) -> Store {
+        let mut store = Store::default();
+        store.entries = entries;
+        store
+    }
+
+    #[test]
+    fn test_pending_alarms_soonest_first() {
+        let mut timer = alarm(1, at(6, 0));
+        timer.kind = EntryKind::Timer;
+        let store = store(vec![alarm(2, at(9, 0)), timer, alarm(3, at(7, 30))]);
+        let ids: Vec<u64> = pending_alarms(&store).iter().map(|a| a.id).collect();
+        assert_eq!(ids, vec![3, 2]);
+    }
+
+    #[test]
+    fn test_selection_follows_alarm_across_reloads() {
+        let mut watch = Watch::default();
+        watch.set_alarms(&store(vec![alarm(1, at(7, 0)), alarm(2, at(8, 0))]));
+        watch.handle_key(&key(KeyCode::Down));
+        assert_eq!(watch.handle_key(&key(KeyCode::Char('d'))), WatchEvent::Delete(2));
+
+        // Another terminal added an earlier alarm, 2 is still selected
+        watch.set_alarms(&store(vec![alarm(1, at(7, 0)), alarm(2, at(8, 0)), alarm(3, at(6, 0))]));
+        assert_eq!(watch.selected, 2);
+        // ...and once it's gone the selection stays in range
+        watch.set_alarms(&store(vec![alarm(3, at(6, 0))]));
+        assert_eq!(watch.selected, 0);
+    }
+
+    #[test]
+    fn test_add_input_line() {
+        let mut watch = Watch::default();
+        assert_eq!(watch.handle_key(&key(KeyCode::Char('a'))), WatchEvent::Continue);
+        for c in "7:3".chars() {
+            watch.handle_key(&key(KeyCode::Char(c)));
+        }
+        // 'q' is text while typing, not quit
+        assert_eq!(watch.handle_key(&key(KeyCode::Char('q'))), WatchEvent::Continue);
+        assert_eq!(watch.handle_key(&key(KeyCode::Enter)), WatchEvent::Continue);
+        assert!(watch.message.is_some());
+
+        watch.handle_key(&key(KeyCode::Backspace));
+        for c in "0am".chars() {
+            watch.handle_key(&key(KeyCode::Char(c)));
+        }
+        assert_eq!(watch.handle_key(&key(KeyCode::Enter)), WatchEvent::Add(NaiveTime::from_hms_opt(7, 30, 0).unwrap()));
+        assert_eq!(watch.input, None);
+
+        // Esc leaves the input line, then quits
+        watch.handle_key(&key(KeyCode::Char('a')));
+        assert_eq!(watch.handle_key(&key(KeyCode::Esc)), WatchEvent::Continue);
+        assert_eq!(watch.handle_key(&key(KeyCode::Esc)), WatchEvent::Quit);
+    }
+
+    #[test]
+    fn test_newly_due_rings_once() {
+        let alarms = [alarm(1, at(7, 0)), alarm(2, at(8, 0))];
+        let mut rung = HashSet::new();
+        let due = newly_due(&alarms, at(7, 0), &rung);
+        assert_eq!(due.len(), 1);
+        rung.insert((due[0].id, due[0].due));
+        assert!(newly_due(&alarms, at(7, 1), &rung).is_empty());
+    }
+}
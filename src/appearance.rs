@@ -0,0 +1,158 @@
+use ratatui::style::Color;
+use std::time::Duration;
+
+/// How long to wait for the terminal to answer the background color query.
+#[cfg(unix)]
+const QUERY_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Whether the screens use colors for a dark or a light terminal background (`--appearance`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Appearance {
+    /// Ask the terminal, falling back to dark
+    #[default]
+    Auto,
+    Light,
+    Dark,
+}
+
+impl Appearance {
+    /// `Light` or `Dark`, finding out which for `Auto`: `COLORFGBG` if it's set,
+    /// else the terminal's answer to an OSC 11 query (only asked when there's a
+    /// keyboard to read the answer from).
+    pub fn resolve(self, can_query: bool) -> Appearance {
+        if self != Appearance::Auto {
+            return self;
+        }
+        let detected = std::env::var("COLORFGBG")
+            .ok()
+            .and_then(|value| from_colorfgbg(&value))
+            .or_else(|| if can_query { query_background() } else { None });
+        match detected {
+            Some(appearance) => {
+                crate::verbose!("terminal background looks {:?}", appearance);
+                appearance
+            }
+            None => Appearance::Dark,
+        }
+    }
+}
+
+/// Reads `COLORFGBG` ("15;0", or "15;default;0" from rxvt): the last field is
+/// the background's ANSI color number, where white (7) and the bright colors
+/// other than dark gray (8) are light backgrounds.
+pub fn from_colorfgbg(value: &str) -> Option<Appearance> {
+    let background: u8 = value.rsplit(';').next()?.trim().parse().ok()?;
+    match background {
+        7 | 9..=15 => Some(Appearance::Light),
+        0..=6 | 8 => Some(Appearance::Dark),
+        _ => None,
+    }
+}
+
+/// Reads an OSC 11 reply like `ESC ] 11 ; rgb:ffff/ffff/dddd BEL`, each
+/// channel 1 to 4 hex digits, by the background's perceived brightness.
+pub fn from_osc11_reply(reply: &str) -> Option<Appearance> {
+    let rgb = reply.split("rgb:").nth(1)?;
+    let rgb = rgb.trim_end_matches(['\x07', '\\']).trim_end_matches('\x1b');
+    let channels: Vec<f32> = rgb
+        .split('/')
+        .map(|hex| {
+            let value = u32::from_str_radix(hex, 16).ok()?;
+            let max = (1u32 << (4 * hex.len().clamp(1, 4))) - 1;
+            Some(value as f32 / max as f32)
+        })
+        .collect::<Option<_>>()?;
+    let [r, g, b] = channels[..] else {
+        return None;
+    };
+    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    Some(if luminance > 0.5 { Appearance::Light } else { Appearance::Dark })
+}
+
+/// Asks the terminal for its background color. Terminals that don't answer
+/// cost the timeout and leave it undetected.
+#[cfg(unix)]
+fn query_background() -> Option<Appearance> {
+    use std::{
+        fs::OpenOptions,
+        io::{Read, Write},
+        os::unix::io::AsRawFd,
+    };
+
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+    // Raw, so the reply isn't echoed and arrives without waiting for Enter
+    crossterm::terminal::enable_raw_mode().ok()?;
+    let mut reply = Vec::new();
+    if tty.write_all(b"\x1b]11;?\x07").and_then(|()| tty.flush()).is_ok() {
+        let mut poll = libc::pollfd { fd: tty.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+        let mut buf = [0u8; 64];
+        // Until the terminator; a reply may come in more than one read
+        while !reply.ends_with(b"\x07") && !reply.ends_with(b"\x1b\\") && reply.len() < 256 {
+            if unsafe { libc::poll(&mut poll, 1, QUERY_TIMEOUT.as_millis() as i32) } <= 0 {
+                break;
+            }
+            match tty.read(&mut buf) {
+                Ok(n) if n > 0 => reply.extend_from_slice(&buf[..n]),
+                _ => break,
+            }
+        }
+    }
+    let _ = crossterm::terminal::disable_raw_mode();
+    from_osc11_reply(&String::from_utf8_lossy(&reply))
+}
+
+#[cfg(not(unix))]
+fn query_background() -> Option<Appearance> {
+    None
+}
+
+/// The color to draw in place of `color` for `appearance`. The screens are
+/// designed for a dark background; on a light one the whites and grays are
+/// swapped for darker shades and the pale accents for deeper ones.
+pub fn shade(color: Color, appearance: Appearance) -> Color {
+    if appearance != Appearance::Light {
+        return color;
+    }
+    match color {
+        Color::White => Color::Black,
+        // Exact shades: light themes often draw ANSI gray and dark gray nearly white
+        Color::Gray => Color::Rgb(80, 80, 80),
+        Color::DarkGray => Color::Rgb(120, 120, 120),
+        Color::Yellow => Color::Rgb(160, 110, 0),
+        Color::Cyan => Color::Blue,
+        Color::LightBlue => Color::Blue,
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colorfgbg() {
+        assert_eq!(from_colorfgbg("15;0"), Some(Appearance::Dark));
+        assert_eq!(from_colorfgbg("0;15"), Some(Appearance::Light));
+        assert_eq!(from_colorfgbg("0;7"), Some(Appearance::Light));
+        assert_eq!(from_colorfgbg("15;8"), Some(Appearance::Dark));
+        assert_eq!(from_colorfgbg("0;default;15"), Some(Appearance::Light));
+        assert_eq!(from_colorfgbg("15;default"), None);
+        assert_eq!(from_colorfgbg(""), None);
+    }
+
+    #[test]
+    fn test_osc11_reply() {
+        assert_eq!(from_osc11_reply("\x1b]11;rgb:ffff/ffff/ffff\x07"), Some(Appearance::Light));
+        assert_eq!(from_osc11_reply("\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\"), Some(Appearance::Dark));
+        assert_eq!(from_osc11_reply("\x1b]11;rgb:fd/f6/e3\x07"), Some(Appearance::Light));
+        assert_eq!(from_osc11_reply(""), None);
+        assert_eq!(from_osc11_reply("\x1b]11;rgb:ffff/ffff\x07"), None);
+    }
+
+    #[test]
+    fn test_shade_only_for_light() {
+        assert_eq!(shade(Color::White, Appearance::Dark), Color::White);
+        assert_eq!(shade(Color::White, Appearance::Light), Color::Black);
+        assert_eq!(shade(Color::Red, Appearance::Light), Color::Red);
+    }
+}
@@ -0,0 +1,349 @@
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs,
+    io::{self, BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use crate::{output::info, queue::Queue, saved_alarms::SavedAlarms, status::ClockStatus, store::Store, unix_millis};
+
+/// Files and sockets this process created and hasn't removed yet.
+static REGISTERED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// A file or socket only this run uses, removed when dropped, or by
+/// [`exit`] for the ways out that skip destructors.
+pub struct Artifact {
+    path: PathBuf,
+}
+
+impl Artifact {
+    /// Takes charge of `path`, which this process has just created.
+    pub fn register(path: PathBuf) -> Artifact {
+        REGISTERED.lock().unwrap().push(path.clone());
+        Artifact { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Artifact {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        REGISTERED.lock().unwrap().retain(|path| path != &self.path);
+    }
+}
+
+/// Removes every artifact still registered.
+pub fn remove_all() {
+    for path in REGISTERED.lock().unwrap().drain(..) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// `std::process::exit`, after removing what the guards still alive won't get to.
+pub fn exit(code: i32) -> ! {
+    remove_all();
+    std::process::exit(code)
+}
+
+/// A file a run left behind that nothing will use again.
+#[derive(Debug)]
+pub struct Orphan {
+    pub path: PathBuf,
+    /// Since it was last written, if the filesystem says
+    pub age: Option<Duration>,
+    pub reason: String,
+}
+
+/// Orphans in the runtime and data directories. Only files clockeroo names
+/// and whose contents have the shape it writes are considered; the
+/// stopwatch session, the store and the history are never orphans.
+pub fn scan(runtime: &Path, data: &Path, now: SystemTime) -> Vec<Orphan> {
+    let mut orphans = Vec::new();
+    for (dir, in_data_dir) in [(runtime, false), (data, true)] {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+        paths.sort();
+        for path in paths {
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let reason = if in_data_dir { data_orphan(&path, name) } else { runtime_orphan(&path, name, now) };
+            if let Some(reason) = reason {
+                let age = fs::symlink_metadata(&path)
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .and_then(|modified| now.duration_since(modified).ok());
+                orphans.push(Orphan { path, age, reason });
+            }
+        }
+    }
+    orphans
+}
+
+fn runtime_orphan(path: &Path, name: &str, now: SystemTime) -> Option<String> {
+    if let Some(pid) = status_pid(name) {
+        let pid: i32 = pid.parse().ok()?;
+        let status: ClockStatus = read_as_written(path)?;
+        return match pid_alive(pid) {
+            Some(alive) => (!alive).then(|| format!("pid {} is not running", pid)),
+            None => status.is_stale(unix_millis(now)).then(|| "not updated by its clock".to_string()),
+        };
+    }
+    match name {
+        "clockeroo.pid" => {
+            // Exactly what the daemon writes, not just something with a number in it
+            let contents = fs::read_to_string(path).ok()?;
+            let pid: i32 = contents.parse().ok()?;
+            (pid.to_string() == contents && pid_alive(pid) == Some(false))
+                .then(|| format!("daemon pid {} is not running", pid))
+        }
+        "clockeroo.sock" => stale_socket(path).then(|| "no daemon is listening on it".to_string()),
+        "clockeroo.queue" => {
+            let finished = read_as_written::<Queue>(path)?.pending().next().is_none();
+            finished.then(|| "nothing left to run".to_string())
+        }
+        _ => tmp_orphan(path, name, |target, contents| match target {
+            "clockeroo.queue" => written_as::<Queue>(contents, serde_json::to_string),
+            "clockeroo.stopwatch" => written_as::<crate::StopwatchState>(contents, serde_json::to_string),
+            _ if status_pid(target).is_some() => written_as::<ClockStatus>(contents, serde_json::to_string),
+            _ => false,
+        }),
+    }
+}
+
+fn data_orphan(path: &Path, name: &str) -> Option<String> {
+    tmp_orphan(path, name, |target, contents| match target {
+        "store.json" => written_as::<Store>(contents, serde_json::to_string_pretty),
+        "alarms.json" => written_as::<SavedAlarms>(contents, serde_json::to_string),
+        _ => false,
+    })
+}
+
+/// The pid part of a status file name.
+fn status_pid(name: &str) -> Option<&str> {
+    name.strip_prefix(crate::status::FILE_PREFIX)?.strip_suffix(".json")
+}
+
+/// A `statefile::write_atomic` temp file (`.<target>.<pid>.tmp`) whose writer
+/// died before renaming it into place. Taken only if `ours` recognizes its
+/// contents as what clockeroo writes to `target`; a name alone proves nothing.
+fn tmp_orphan(path: &Path, name: &str, ours: impl Fn(&str, &str) -> bool) -> Option<String> {
+    let (target, pid) = name.strip_prefix('.')?.strip_suffix(".tmp")?.rsplit_once('.')?;
+    let pid: i32 = pid.parse().ok()?;
+    if pid_alive(pid) != Some(false) {
+        return None;
+    }
+    let contents = fs::read_to_string(path).ok()?;
+    ours(target, &contents).then(|| format!("left by pid {}, which is not running", pid))
+}
+
+/// Whether `contents` is byte for byte what `write` makes of it. Most state
+/// fields have defaults, so merely parsing would take any JSON object.
+fn written_as<T: Serialize + DeserializeOwned>(contents: &str, write: fn(&T) -> serde_json::Result<String>) -> bool {
+    serde_json::from_str::<T>(contents).ok().and_then(|value| write(&value).ok()).is_some_and(|written| written == contents)
+}
+
+/// The state in `path`, if it's exactly what `statefile::write_json` would have
+/// written: `{}` parses as a queue or a status as well.
+fn read_as_written<T: Serialize + DeserializeOwned>(path: &Path) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+    written_as::<T>(&contents, serde_json::to_string).then(|| serde_json::from_str(&contents).ok())?
+}
+
+/// Whether process `pid` exists, where that can be checked.
+pub fn pid_alive(pid: i32) -> Option<bool> {
+    #[cfg(unix)]
+    {
+        if pid <= 0 {
+            return Some(false);
+        }
+        // Signal 0 only checks; EPERM means it exists but isn't ours
+        let alive = unsafe { libc::kill(pid, 0) } == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+        Some(alive)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// A socket nothing is accepting on anymore.
+fn stale_socket(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::{fs::FileTypeExt, net::UnixStream};
+
+        let is_socket = fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket());
+        is_socket && UnixStream::connect(path).is_err_and(|err| err.kind() == io::ErrorKind::ConnectionRefused)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+/// Rough age for listings: 45s, 12m, 3h, 2d.
+pub fn format_age(age: Duration) -> String {
+    match age.as_secs() {
+        secs @ 0..=59 => format!("{}s", secs),
+        secs @ 60..=3599 => format!("{}m", secs / 60),
+        secs @ 3600..=86399 => format!("{}h", secs / 3600),
+        secs => format!("{}d", secs / 86400),
+    }
+}
+
+/// `clockeroo clean`: lists the orphans, then removes them if `yes` or the
+/// user agrees at the prompt. Without either only lists them.
+pub fn clean(runtime: &Path, data: &Path, yes: bool) -> Result<()> {
+    let orphans = scan(runtime, data, SystemTime::now());
+    if orphans.is_empty() {
+        println!("Nothing to clean up.");
+        return Ok(());
+    }
+    for orphan in &orphans {
+        let age = orphan.age.map_or("?".to_string(), format_age);
+        println!("  {}  {} old  {}", orphan.path.display(), age, orphan.reason);
+    }
+
+    if !yes {
+        if !io::stdin().is_terminal() {
+            println!("Run with --yes to remove them.");
+            return Ok(());
+        }
+        print!("Remove {} file(s)? [y/N] ", orphans.len());
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            return Ok(());
+        }
+    }
+
+    let mut removed = 0;
+    for orphan in &orphans {
+        match fs::remove_file(&orphan.path) {
+            Ok(()) => removed += 1,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => eprintln!("[WARN] Couldn't remove {}: {}", orphan.path.display(), err),
+        }
+    }
+    info!("[CLEANED] Removed {} of {}", removed, orphans.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("clockeroo-artifacts-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_artifact_removed_on_drop_and_exit() {
+        let dir = test_dir("guard");
+        let dropped = dir.join("dropped");
+        let left = dir.join("left");
+        fs::write(&dropped, "").unwrap();
+        fs::write(&left, "").unwrap();
+
+        drop(Artifact::register(dropped.clone()));
+        assert!(!dropped.exists());
+        // What process::exit would skip
+        std::mem::forget(Artifact::register(left.clone()));
+        assert!(left.exists());
+        remove_all();
+        assert!(!left.exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_only_takes_attributable_orphans() {
+        let runtime = test_dir("runtime");
+        let data = test_dir("data");
+        // Above any pid_max, never running
+        let dead = i32::MAX;
+        let alive = std::process::id();
+        let status: ClockStatus = serde_json::from_str(r#"{"kind":"timer","started_at":1,"updated_at":1}"#).unwrap();
+        let status = serde_json::to_string(&status).unwrap();
+
+        fs::write(runtime.join(format!("clockeroo.status.{}.json", dead)), &status).unwrap();
+        fs::write(runtime.join(format!("clockeroo.status.{}.json", alive)), &status).unwrap();
+        // Named like ours, but not a status file
+        fs::write(runtime.join(format!("clockeroo.status.{}.json", dead - 1)), "hello").unwrap();
+        fs::write(runtime.join("clockeroo.pid"), dead.to_string()).unwrap();
+        fs::write(runtime.join("clockeroo.queue"), r#"{"next_id":3,"items":[]}"#).unwrap();
+        fs::write(runtime.join("clockeroo.stopwatch"), r#"{"started_at":1}"#).unwrap();
+        fs::write(runtime.join(format!(".clockeroo.queue.{}.tmp", dead)), r#"{"next_id":0,"items":[]}"#).unwrap();
+        fs::write(runtime.join(format!(".other.{}.tmp", dead)), "{}").unwrap();
+        fs::write(data.join(format!(".store.json.{}.tmp", dead)), serde_json::to_string_pretty(&Store::default()).unwrap()).unwrap();
+        fs::write(data.join("store.json"), "{}").unwrap();
+        drop(std::os::unix::net::UnixListener::bind(runtime.join("clockeroo.sock")).unwrap());
+
+        let orphans = scan(&runtime, &data, SystemTime::now());
+        let names: Vec<&str> = orphans.iter().map(|o| o.path.file_name().unwrap().to_str().unwrap()).collect();
+        let tmp = format!(".clockeroo.queue.{}.tmp", dead);
+        let status_file = format!("clockeroo.status.{}.json", dead);
+        let store_tmp = format!(".store.json.{}.tmp", dead);
+        assert_eq!(names, [tmp.as_str(), "clockeroo.pid", "clockeroo.queue", "clockeroo.sock", &status_file, &store_tmp]);
+        assert_eq!(orphans[1].reason, format!("daemon pid {} is not running", dead));
+        assert!(orphans.iter().all(|o| o.age.is_some()));
+
+        // A queue with steps left is still wanted
+        fs::write(runtime.join("clockeroo.queue"), r#"{"next_id":1,"items":[{"id":1,"label":"a","duration_ms":1000,"state":"pending"}]}"#).unwrap();
+        assert!(scan(&runtime, &data, SystemTime::now()).iter().all(|o| !o.path.ends_with("clockeroo.queue")));
+        fs::remove_dir_all(runtime).unwrap();
+        fs::remove_dir_all(data).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_leaves_look_alikes() {
+        let runtime = test_dir("foreign-runtime");
+        let data = test_dir("foreign-data");
+        let dead = i32::MAX;
+
+        // Our names, someone else's contents: JSON that would parse, but isn't what we write
+        fs::write(runtime.join(format!(".clockeroo.queue.{}.tmp", dead)), r#"{"items": []}"#).unwrap();
+        fs::write(runtime.join(format!(".clockeroo.stopwatch.{}.tmp", dead)), r#"{"started_at":1,"notes":"mine"}"#).unwrap();
+        fs::write(runtime.join("clockeroo.pid"), format!("{}\nsomeone else's\n", dead)).unwrap();
+        fs::write(data.join(format!(".store.json.{}.tmp", dead)), "{}").unwrap();
+        fs::write(data.join(format!(".alarms.json.{}.tmp", dead)), "{ }").unwrap();
+        fs::write(runtime.join("clockeroo.queue"), "{}").unwrap();
+        fs::write(runtime.join(format!("clockeroo.status.{}.json", dead)), "{}").unwrap();
+        assert!(scan(&runtime, &data, SystemTime::now()).is_empty());
+
+        // The same names with what clockeroo writes are taken
+        fs::write(runtime.join(format!(".clockeroo.stopwatch.{}.tmp", dead)), r#"{"started_at":1,"laps":[],"paused_ms":0,"paused_since":null}"#).unwrap();
+        fs::write(data.join(format!(".alarms.json.{}.tmp", dead)), serde_json::to_string(&SavedAlarms::default()).unwrap()).unwrap();
+        let orphans = scan(&runtime, &data, SystemTime::now());
+        let names: Vec<&str> = orphans.iter().map(|o| o.path.file_name().unwrap().to_str().unwrap()).collect();
+        let stopwatch_tmp = format!(".clockeroo.stopwatch.{}.tmp", dead);
+        let alarms_tmp = format!(".alarms.json.{}.tmp", dead);
+        assert_eq!(names, [stopwatch_tmp.as_str(), &alarms_tmp]);
+        fs::remove_dir_all(runtime).unwrap();
+        fs::remove_dir_all(data).unwrap();
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::from_secs(45)), "45s");
+        assert_eq!(format_age(Duration::from_secs(12 * 60 + 5)), "12m");
+        assert_eq!(format_age(Duration::from_secs(3 * 3600)), "3h");
+        assert_eq!(format_age(Duration::from_secs(2 * 86400 + 1)), "2d");
+    }
+}
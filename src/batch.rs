@@ -0,0 +1,272 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use crossterm::event::KeyCode;
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant, SystemTime},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    clock, format_delta, format_duration, format_stopwatch_time, notify, parse_duration,
+    sound::{spawn_chime, SoundOptions},
+    status, ui, unix_millis,
+};
+
+/// Chime at the end of each step, the last one gets the notification too.
+const STEP_TONE: f32 = 784.0;
+
+/// Batch redraw interval.
+const BATCH_FRAME: Duration = Duration::from_millis(200);
+
+/// One line of a batch file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub label: String,
+    pub planned: Duration,
+}
+
+/// Reads a batch file: one step per line, a duration and then its label
+/// ("90s boil water"). Blank lines and `#` comments are skipped; a step
+/// without a label is named after its position.
+pub fn parse_steps(contents: &str) -> Result<Vec<Step>> {
+    let mut steps = vec![];
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (duration, label) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let planned = parse_duration(duration).with_context(|| format!("line {}", number + 1))?;
+        let label = match label.trim() {
+            "" => format!("Step {}", steps.len() + 1),
+            label => label.to_string(),
+        };
+        steps.push(Step { label, planned });
+    }
+    if steps.is_empty() {
+        anyhow::bail!("No steps found, expected lines like \"90s boil water\"");
+    }
+    Ok(steps)
+}
+
+pub fn load_steps(path: &Path) -> Result<Vec<Step>> {
+    let contents = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_steps(&contents).with_context(|| format!("Invalid batch file {}", path.display()))
+}
+
+/// How a step ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    Finished,
+    /// Cut short with 'n'
+    Skipped,
+    /// The batch was quit during this step
+    Stopped,
+}
+
+/// A step as it actually ran, by the wall clock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Measured {
+    pub step: Step,
+    pub started: DateTime<Local>,
+    pub finished: DateTime<Local>,
+    pub ending: Ending,
+}
+
+/// One line of the summary table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub label: String,
+    pub planned: String,
+    pub actual: String,
+    pub drift: String,
+    pub note: &'static str,
+}
+
+/// Actual is wall-clock finish minus start, drift is actual minus planned.
+pub fn report_row(measured: &Measured) -> Row {
+    let actual_ms = (measured.finished - measured.started).num_milliseconds().max(0);
+    let actual = Duration::from_millis(actual_ms as u64);
+    Row {
+        label: measured.step.label.clone(),
+        planned: format_duration(measured.step.planned),
+        actual: format_stopwatch_time(actual),
+        drift: format_delta(actual_ms - measured.step.planned.as_millis() as i64),
+        note: match measured.ending {
+            Ending::Finished => "",
+            Ending::Skipped => "skipped",
+            Ending::Stopped => "stopped",
+        },
+    }
+}
+
+/// The summary table, columns padded to their widest cell.
+pub fn format_report(rows: &[Row]) -> String {
+    let header = Row {
+        label: "Label".to_string(),
+        planned: "Planned".to_string(),
+        actual: "Actual".to_string(),
+        drift: "Drift".to_string(),
+        note: "",
+    };
+    let widths = [
+        rows.iter().chain([&header]).map(|row| row.label.width()).max().unwrap_or_default(),
+        rows.iter().chain([&header]).map(|row| row.planned.width()).max().unwrap_or_default(),
+        rows.iter().chain([&header]).map(|row| row.actual.width()).max().unwrap_or_default(),
+        rows.iter().chain([&header]).map(|row| row.drift.width()).max().unwrap_or_default(),
+    ];
+    let pad = |text: &str, width: usize| format!("{}{}", text, " ".repeat(width.saturating_sub(text.width())));
+
+    let mut out = String::new();
+    for row in [&header].into_iter().chain(rows) {
+        let line = format!(
+            "{}  {}  {}  {}  {}",
+            pad(&row.label, widths[0]),
+            pad(&row.planned, widths[1]),
+            pad(&row.actual, widths[2]),
+            pad(&row.drift, widths[3]),
+            row.note
+        );
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+pub struct BatchOptions {
+    pub spinner: bool,
+    pub sound: SoundOptions,
+}
+
+/// Runs the steps one after another and returns each one that started, as it
+/// actually ran. Quitting ends the current step early and skips the rest.
+pub async fn run_batch_ui(steps: Vec<Step>, options: BatchOptions) -> Result<Vec<Measured>> {
+    let mut terminal = ui::enter_screen()?;
+
+    let mut measured = vec![];
+    let mut frame = 0usize;
+    let mut status_file: Option<status::StatusFile> = None;
+
+    'steps: for (index, step) in steps.iter().enumerate() {
+        let start = Instant::now();
+        let started = Local::now();
+        let now_ms = unix_millis(SystemTime::now());
+        let mut clock_status = status::ClockStatus::countdown(status::ClockKind::Timer, Some(step.label.clone()), now_ms, step.planned);
+        match &mut status_file {
+            Some(file) => {
+                file.update(&clock_status).ok();
+            }
+            None => status_file = status::publish(&clock_status).ok(),
+        }
+
+        let mut ending = Ending::Finished;
+        loop {
+            let elapsed = start.elapsed();
+            if elapsed >= step.planned {
+                break;
+            }
+            clock_status.touch(unix_millis(SystemTime::now()), elapsed);
+            if let Some(file) = &mut status_file {
+                file.refresh(&clock_status);
+            }
+            frame = frame.wrapping_add(1);
+
+            terminal.draw(|f| {
+                let view = ui::BatchView {
+                    index,
+                    total: steps.len(),
+                    label: &step.label,
+                    remaining: clock::ceil_secs(step.planned - elapsed),
+                    next: steps.get(index + 1).map(|next| next.label.as_str()),
+                    spinner: options.spinner.then_some(frame),
+                };
+                ui::draw_batch(f, &view);
+            })?;
+
+            // Wake right at the end of the step rather than up to a frame late
+            if let Some(key) = ui::poll_key(BATCH_FRAME.min(step.planned - elapsed))? {
+                if ui::is_quit_event(&key) {
+                    ending = Ending::Stopped;
+                    break;
+                }
+                if key.code == KeyCode::Char('n') {
+                    ending = Ending::Skipped;
+                    break;
+                }
+            }
+        }
+
+        measured.push(Measured { step: step.clone(), started, finished: Local::now(), ending });
+        if ending == Ending::Stopped {
+            break 'steps;
+        }
+        spawn_chime(STEP_TONE, &options.sound);
+    }
+
+    if measured.len() == steps.len() && measured.last().is_some_and(|m| m.ending != Ending::Stopped) {
+        notify::send_notification("Batch complete!", &format!("{} steps done.", steps.len()));
+    }
+
+    ui::leave_screen(terminal)?;
+
+    Ok(measured)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_steps() {
+        let steps = parse_steps("# warm-up\n90s boil water\n\n  2m  steep\n1m30s\n").unwrap();
+        assert_eq!(
+            steps,
+            [
+                Step { label: "boil water".into(), planned: Duration::from_secs(90) },
+                Step { label: "steep".into(), planned: Duration::from_secs(120) },
+                Step { label: "Step 3".into(), planned: Duration::from_secs(90) },
+            ]
+        );
+        let err = parse_steps("1m ok\nsoon bad\n").unwrap_err();
+        assert!(format!("{:#}", err).starts_with("line 2"), "{:#}", err);
+        assert!(parse_steps("# nothing\n").is_err());
+    }
+
+    #[test]
+    fn test_report_row() {
+        let started = Local.with_ymd_and_hms(2024, 3, 10, 9, 0, 0).unwrap();
+        let step = Step { label: "steep".into(), planned: Duration::from_secs(120) };
+        let measured = Measured {
+            step: step.clone(),
+            started,
+            finished: started + chrono::Duration::milliseconds(120_250),
+            ending: Ending::Finished,
+        };
+        assert_eq!(
+            report_row(&measured),
+            Row { label: "steep".into(), planned: "02:00".into(), actual: "02:00.250".into(), drift: "+00:00.250".into(), note: "" }
+        );
+
+        // Skipped a minute early
+        let measured = Measured { finished: started + chrono::Duration::seconds(60), ending: Ending::Skipped, ..measured };
+        let row = report_row(&measured);
+        assert_eq!((row.actual.as_str(), row.drift.as_str(), row.note), ("01:00.000", "-01:00.000", "skipped"));
+    }
+
+    #[test]
+    fn test_format_report() {
+        let rows = [
+            Row { label: "boil water".into(), planned: "01:30".into(), actual: "01:30.004".into(), drift: "+00:00.004".into(), note: "" },
+            Row { label: "steep".into(), planned: "02:00".into(), actual: "00:40.000".into(), drift: "-01:20.000".into(), note: "stopped" },
+        ];
+        assert_eq!(
+            format_report(&rows),
+            "Label       Planned  Actual     Drift\n\
+             boil water  01:30    01:30.004  +00:00.004\n\
+             steep       02:00    00:40.000  -01:20.000  stopped\n"
+        );
+    }
+}
@@ -0,0 +1,436 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use std::time::{Duration, Instant};
+
+/// Wall-clock vs monotonic disagreement that counts as a clock step.
+const STEP_THRESHOLD_MS: i64 = 2000;
+
+/// Pairs a wall-clock reading with the monotonic clock so steps in the
+/// wall clock (NTP corrections, resume from suspend, manual changes) can be
+/// told apart from time actually passing.
+pub struct WallAnchor {
+    mono: Instant,
+    wall_ms: u64,
+    boot_us: Option<u64>,
+}
+
+impl WallAnchor {
+    pub fn new(mono: Instant, wall_ms: u64) -> WallAnchor {
+        WallAnchor { mono, wall_ms, boot_us: None }
+    }
+
+    /// Also anchored to [`boottime_micros`], for [`WallAnchor::check_awake`].
+    pub fn with_boot_clock(mono: Instant, wall_ms: u64, boot_us: Option<u64>) -> WallAnchor {
+        WallAnchor { mono, wall_ms, boot_us }
+    }
+
+    /// Returns how far the wall clock stepped since the last check (positive is
+    /// forward) if that exceeds the threshold, and re-anchors at the new reading.
+    pub fn check(&mut self, mono: Instant, wall_ms: u64) -> Option<i64> {
+        self.check_awake(mono, wall_ms, None)
+    }
+
+    /// [`WallAnchor::check`], except that time the boot clock saw pass while
+    /// `Instant`s stood still (a suspend) isn't a step: the wall clock was right
+    /// to move on. Without a boot clock reading it's the same as `check`.
+    pub fn check_awake(&mut self, mono: Instant, wall_ms: u64, boot_us: Option<u64>) -> Option<i64> {
+        let mono_elapsed = mono.saturating_duration_since(self.mono);
+        let passed = match self.boot_us.zip(boot_us) {
+            Some((then, now)) => Duration::from_micros(now.saturating_sub(then)).max(mono_elapsed),
+            None => mono_elapsed,
+        };
+        let step = step_ms(passed, wall_ms as i64 - self.wall_ms as i64);
+        self.mono = mono;
+        self.wall_ms = wall_ms;
+        self.boot_us = boot_us;
+        step
+    }
+}
+
+/// The part of `wall_elapsed_ms` that monotonic time doesn't account for, if significant.
+fn step_ms(mono_elapsed: Duration, wall_elapsed_ms: i64) -> Option<i64> {
+    let step = wall_elapsed_ms - mono_elapsed.as_millis() as i64;
+    (step.abs() > STEP_THRESHOLD_MS).then_some(step)
+}
+
+/// Applies a signed millisecond step to a Unix-millisecond timestamp.
+pub fn shift_ms(timestamp: u64, step: i64) -> u64 {
+    timestamp.saturating_add_signed(step)
+}
+
+/// The system-wide monotonic clock in microseconds. Unlike an `Instant`, a
+/// reading means the same in every process until the next reboot, so one can
+/// be saved and measured against by a later run.
+#[cfg(unix)]
+pub fn monotonic_micros() -> Option<u64> {
+    clock_micros(libc::CLOCK_MONOTONIC)
+}
+
+#[cfg(not(unix))]
+pub fn monotonic_micros() -> Option<u64> {
+    None
+}
+
+/// Like [`monotonic_micros`], but still counting while the machine is
+/// suspended, which tells a sleep apart from the wall clock being set.
+#[cfg(target_os = "linux")]
+pub fn boottime_micros() -> Option<u64> {
+    clock_micros(libc::CLOCK_BOOTTIME)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn boottime_micros() -> Option<u64> {
+    None
+}
+
+#[cfg(unix)]
+fn clock_micros(clock: libc::clockid_t) -> Option<u64> {
+    let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    if unsafe { libc::clock_gettime(clock, &mut now) } != 0 {
+        return None;
+    }
+    Some(now.tv_sec as u64 * 1_000_000 + now.tv_nsec as u64 / 1_000)
+}
+
+/// The monotonic measurement of a stretch if it agrees with the wall-clock one.
+/// It doesn't after a reboot or a suspend (the monotonic clock stops), and then
+/// the wall clock, already corrected for steps, is the one to go by.
+pub fn precise_elapsed(wall: Duration, monotonic: Option<Duration>) -> Duration {
+    match monotonic {
+        Some(monotonic) if monotonic.abs_diff(wall).as_millis() as i64 <= STEP_THRESHOLD_MS => monotonic,
+        _ => wall,
+    }
+}
+
+/// The first whole multiple of `frame` after `elapsed`. Loops that wait for
+/// `start + next_boundary(..)` stay aligned to `start`, however long each
+/// iteration took, so they never accumulate drift.
+pub fn next_boundary(elapsed: Duration, frame: Duration) -> Duration {
+    let frame_nanos = frame.as_nanos().max(1);
+    let n = elapsed.as_nanos() / frame_nanos + 1;
+    Duration::from_nanos((n * frame_nanos) as u64)
+}
+
+/// Rounds up to whole seconds, so a countdown shows its full length at the
+/// start and reads 00:00 only once it's actually done.
+pub fn ceil_secs(duration: Duration) -> Duration {
+    Duration::from_secs(duration.as_secs() + u64::from(duration.subsec_nanos() > 0))
+}
+
+/// A pausable countdown. Readings are monotonic time since it started, so
+/// wall-clock steps never move it; its wall-clock end is derived on demand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Countdown {
+    duration: Duration,
+    /// Total length of finished pauses
+    paused_total: Duration,
+    /// When the current pause began, if paused
+    paused_at: Option<Duration>,
+}
+
+impl Countdown {
+    pub fn new(duration: Duration) -> Countdown {
+        Countdown { duration, paused_total: Duration::ZERO, paused_at: None }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Running time at `now`, leaving out every pause.
+    pub fn elapsed(&self, now: Duration) -> Duration {
+        let now = self.paused_at.unwrap_or(now);
+        now.saturating_sub(self.paused_total)
+    }
+
+    pub fn remaining(&self, now: Duration) -> Duration {
+        self.duration.saturating_sub(self.elapsed(now))
+    }
+
+    pub fn toggle_pause(&mut self, now: Duration) {
+        match self.paused_at.take() {
+            Some(since) => self.paused_total += now.saturating_sub(since),
+            None => self.paused_at = Some(now),
+        }
+    }
+
+    /// Adds `by` to the time left.
+    pub fn lengthen(&mut self, by: Duration) {
+        self.duration += by;
+    }
+
+    /// Takes `by` off the time left at `now`, down to zero at most.
+    pub fn shorten(&mut self, by: Duration, now: Duration) {
+        self.duration = self.duration.saturating_sub(by).max(self.elapsed(now));
+    }
+
+    /// Wall-clock end in Unix milliseconds, given the wall clock reads `now_ms`
+    /// at `now`. While paused this moves forward with the wall clock.
+    pub fn ends_at_ms(&self, now: Duration, now_ms: u64) -> u64 {
+        now_ms + self.remaining(now).as_millis() as u64
+    }
+}
+
+/// Where schedulers get the time and wait for it to pass, so their timing can
+/// be tested on virtual time instead of by sleeping.
+pub trait Clock {
+    fn now(&self) -> DateTime<Local>;
+    async fn sleep(&self, duration: Duration);
+}
+
+/// The wall clock and tokio's timer.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// Virtual time that only moves when slept through, so hours pass in no time.
+#[cfg(test)]
+pub struct MockClock {
+    now: std::cell::Cell<DateTime<Local>>,
+}
+
+#[cfg(test)]
+impl MockClock {
+    pub fn new(start: DateTime<Local>) -> MockClock {
+        MockClock { now: std::cell::Cell::new(start) }
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Local> {
+        self.now.get()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+/// Waits for `target`, calling `tick` with the time every `interval` until then
+/// (to redraw, read keys and so on). The last sleep is cut short to land right on
+/// `target`. Returns when it got there, or `None` as soon as `tick` says to stop.
+pub async fn wait_until(
+    clock: &impl Clock,
+    target: DateTime<Local>,
+    interval: Duration,
+    mut tick: impl FnMut(DateTime<Local>) -> Result<bool>,
+) -> Result<Option<DateTime<Local>>> {
+    loop {
+        let now = clock.now();
+        if now >= target {
+            return Ok(Some(now));
+        }
+        if !tick(now)? {
+            return Ok(None);
+        }
+        let left = (target - now).to_std().unwrap_or_default();
+        clock.sleep(interval.min(left)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_step_when_clocks_agree() {
+        let base = Instant::now();
+        let mut anchor = WallAnchor::new(base, 1_000_000);
+        assert_eq!(anchor.check(base + Duration::from_millis(100), 1_000_100), None);
+        // Small jitter stays under the threshold
+        assert_eq!(anchor.check(base + Duration::from_millis(200), 1_001_500), None);
+    }
+
+    #[test]
+    fn test_detects_forward_and_backward_steps() {
+        let base = Instant::now();
+        let mut anchor = WallAnchor::new(base, 1_000_000);
+
+        // NTP moved the clock back 5s while 100ms really passed
+        assert_eq!(anchor.check(base + Duration::from_millis(100), 995_100), Some(-5_000));
+        // The next check is relative to the corrected reading
+        assert_eq!(anchor.check(base + Duration::from_millis(200), 995_200), None);
+        // Someone sets the clock an hour ahead
+        assert_eq!(anchor.check(base + Duration::from_millis(300), 4_595_300), Some(3_600_000));
+    }
+
+    #[test]
+    fn test_suspend_isnt_a_step_with_a_boot_clock() {
+        let base = Instant::now();
+        let mut anchor = WallAnchor::with_boot_clock(base, 1_000_000, Some(50_000_000));
+
+        // Asleep for an hour: `Instant`s stood still, the boot clock didn't
+        assert_eq!(anchor.check_awake(base + Duration::from_millis(100), 4_600_100, Some(3_650_100_000)), None);
+        // A step back is still one
+        assert_eq!(
+            anchor.check_awake(base + Duration::from_millis(200), 4_595_200, Some(3_650_200_000)),
+            Some(-5_000)
+        );
+        // No boot clock to go by, so the same sleep looks like a step
+        let mut anchor = WallAnchor::new(base, 1_000_000);
+        assert_eq!(anchor.check_awake(base + Duration::from_millis(100), 4_600_100, None), Some(3_600_000));
+    }
+
+    #[test]
+    fn test_shift_ms() {
+        assert_eq!(shift_ms(10_000, -4_000), 6_000);
+        assert_eq!(shift_ms(10_000, 2_500), 12_500);
+        assert_eq!(shift_ms(1_000, -4_000), 0);
+    }
+
+    #[test]
+    fn test_hour_of_ticks_lands_on_whole_seconds() {
+        let duration = Duration::from_secs(3600);
+        let mut elapsed = Duration::ZERO;
+        let mut shown = vec![];
+        let mut ticks = 0;
+
+        while elapsed < duration {
+            let boundary = next_boundary(elapsed, Duration::from_secs(1)).min(duration);
+            assert_eq!(boundary.subsec_nanos(), 0, "tick {} at {:?}", ticks, boundary);
+            ticks += 1;
+            // Wake up a little late every time, as a busy loop would
+            elapsed = boundary + Duration::from_millis(7);
+            shown.push(ceil_secs(duration.saturating_sub(elapsed)).as_secs());
+        }
+
+        assert_eq!(ticks, 3600);
+        // Every second is shown exactly once, nothing skipped
+        assert_eq!(shown, (0..3600).rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_next_boundary_with_sub_second_frames() {
+        let frame = Duration::from_millis(200);
+        assert_eq!(next_boundary(Duration::ZERO, frame), Duration::from_millis(200));
+        assert_eq!(next_boundary(Duration::from_millis(950), frame), Duration::from_secs(1));
+        assert_eq!(next_boundary(Duration::from_secs(1), frame), Duration::from_millis(1200));
+    }
+
+    #[test]
+    fn test_precise_elapsed() {
+        let wall = Duration::from_millis(83_412);
+        let monotonic = Duration::from_micros(83_412_345);
+        assert_eq!(precise_elapsed(wall, Some(monotonic)), monotonic);
+        assert_eq!(precise_elapsed(wall, None), wall);
+        // Asleep for an hour: the monotonic clock missed it
+        assert_eq!(precise_elapsed(wall + Duration::from_secs(3600), Some(monotonic)), wall + Duration::from_secs(3600));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_monotonic_micros_moves_forward() {
+        let first = monotonic_micros().unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(monotonic_micros().unwrap() >= first + 2_000);
+    }
+
+    #[test]
+    fn test_ceil_secs() {
+        assert_eq!(ceil_secs(Duration::from_millis(4001)), Duration::from_secs(5));
+        assert_eq!(ceil_secs(Duration::from_secs(4)), Duration::from_secs(4));
+        assert_eq!(ceil_secs(Duration::ZERO), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_countdown_deadline_through_pauses() {
+        let secs = Duration::from_secs;
+        let base_ms = 1_000_000;
+        let mut countdown = Countdown::new(secs(600));
+        assert_eq!(countdown.ends_at_ms(secs(0), base_ms), base_ms + 600_000);
+
+        // Paused at 2 minutes for 3 minutes: the end moves 3 minutes later
+        countdown.toggle_pause(secs(120));
+        assert!(countdown.is_paused());
+        assert_eq!(countdown.remaining(secs(300)), secs(480));
+        assert_eq!(countdown.ends_at_ms(secs(300), base_ms + 300_000), base_ms + 780_000);
+        countdown.toggle_pause(secs(300));
+        assert_eq!(countdown.elapsed(secs(300)), secs(120));
+        assert_eq!(countdown.ends_at_ms(secs(400), base_ms + 400_000), base_ms + 780_000);
+
+        // A second, shorter pause adds on top
+        countdown.toggle_pause(secs(400));
+        countdown.toggle_pause(secs(430));
+        assert_eq!(countdown.ends_at_ms(secs(430), base_ms + 430_000), base_ms + 810_000);
+        assert_eq!(countdown.remaining(secs(810)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_countdown_adjustments() {
+        let secs = Duration::from_secs;
+        let mut countdown = Countdown::new(secs(300));
+        countdown.lengthen(secs(60));
+        assert_eq!(countdown.remaining(secs(100)), secs(260));
+        countdown.shorten(secs(60), secs(100));
+        assert_eq!(countdown.remaining(secs(100)), secs(200));
+        // Never into the time that has already run
+        countdown.shorten(secs(600), secs(100));
+        assert_eq!(countdown.remaining(secs(100)), Duration::ZERO);
+        assert_eq!(countdown.elapsed(secs(100)), secs(100));
+    }
+
+    #[test]
+    fn test_countdown_deadline_follows_wall_clock_steps() {
+        let secs = Duration::from_secs;
+        let mut countdown = Countdown::new(secs(600));
+        countdown.toggle_pause(secs(60));
+        countdown.toggle_pause(secs(90));
+
+        // Started at 3_600_000 on the wall clock, which then jumped back an
+        // hour: the countdown doesn't notice, its wall-clock end moves with the step
+        assert_eq!(countdown.remaining(secs(100)), secs(530));
+        assert_eq!(countdown.ends_at_ms(secs(100), 100_000), 100_000 + 530_000);
+        // Paused and resumed again after the step
+        countdown.toggle_pause(secs(100));
+        countdown.toggle_pause(secs(160));
+        assert_eq!(countdown.ends_at_ms(secs(160), 160_000), 160_000 + 530_000);
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_fires_on_time() {
+        use chrono::TimeZone;
+
+        let start = Local.with_ymd_and_hms(2024, 3, 10, 6, 0, 0).unwrap();
+        let target = Local.with_ymd_and_hms(2024, 3, 10, 7, 30, 0).unwrap() + chrono::Duration::milliseconds(50);
+        let clock = MockClock::new(start);
+        let mut ticks = 0;
+        let fired = wait_until(&clock, target, Duration::from_millis(100), |now| {
+            assert!(now < target);
+            ticks += 1;
+            Ok(true)
+        })
+        .await
+        .unwrap();
+
+        // An hour and a half in 100ms steps, then the short one onto the target
+        assert_eq!(fired, Some(target));
+        assert_eq!(ticks, 54_001);
+
+        // Already due: fires without a single tick
+        let fired = wait_until(&clock, start, Duration::from_millis(100), |_| unreachable!()).await.unwrap();
+        assert_eq!(fired, Some(target));
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_stops_when_told() {
+        use chrono::TimeZone;
+
+        let start = Local.with_ymd_and_hms(2024, 3, 10, 6, 0, 0).unwrap();
+        let clock = MockClock::new(start);
+        let cancel_at = start + chrono::Duration::minutes(10);
+        let fired = wait_until(&clock, start + chrono::Duration::hours(1), Duration::from_secs(1), |now| Ok(now < cancel_at))
+            .await
+            .unwrap();
+        assert_eq!(fired, None);
+        assert_eq!(clock.now(), cancel_at);
+    }
+}
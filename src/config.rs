@@ -0,0 +1,601 @@
+use anyhow::{Context, Result};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{parse_alarm_time, parse_color, parse_duration, parse_duration_or_zero, sound::{Bell, SoundEvent}, time_format::TimeFormat, zones::{Source, Threshold, Zones}};
+
+/// Event names a sound theme may map to a file.
+const SOUND_EVENTS: &[&str] = &["timer_done", "alarm", "lap", "tick"];
+
+const DEFAULT_QUICK_DURATIONS: &[&str] = &["1m", "5m", "10m", "25m", "1h"];
+
+const DEFAULT_WORKDAY_END: &str = "17:00";
+
+/// User configuration, read from `config.json` in the clockeroo config directory.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub sound_themes: BTreeMap<String, SoundTheme>,
+    pub presets: BTreeMap<String, Preset>,
+    pub pomodoro: PomodoroConfig,
+    /// Choices offered by the picker when `timer` is run without a duration.
+    pub quick_durations: Option<Vec<String>>,
+    /// `"12h"`, `"24h"` or `"auto"`, see [`crate::time_format::resolve`].
+    pub time_format: TimeFormat,
+    /// Tags for labels, like `"tea": "🍵"`, used when `--tag` isn't given.
+    pub tags: BTreeMap<String, String>,
+    pub focus: FocusConfig,
+    pub timer: TimerConfig,
+    pub alarm: AlarmConfig,
+    pub notifications: NotificationsConfig,
+    pub workday: WorkdayConfig,
+    /// Where bells go out, see [`crate::sound::Bell`]; `--bell`/`--bell-only` win over it.
+    pub bell: Option<Bell>,
+}
+
+/// The `timer` section: when a countdown turns yellow (`warn_at`) and red
+/// (`critical_at`), as time left like `"10m"` or a share of the timer like `"10%"`,
+/// and whether to `confirm` the duration before it starts, like `--confirm`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimerConfig {
+    pub warn_at: Option<String>,
+    pub critical_at: Option<String>,
+    pub confirm: bool,
+}
+
+/// The `alarm` section: `fade_in` starts a ringing alarm quiet and brings it up
+/// to full volume over this long (like `"60s"`), as `--fade-in` does.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlarmConfig {
+    pub fade_in: Option<String>,
+}
+
+/// The `notifications` section: `coalesce` is how long after one notification
+/// others are held back and sent together as one (like `"2s"`, `"0s"` to send
+/// each as it comes). Critical ones always go straight out.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationsConfig {
+    pub coalesce: Option<String>,
+}
+
+/// The `workday` section: `end` is when the workday is over (like `"17:30"`,
+/// 17:00 when unset), what `eod` and `eow` (Friday at that time) resolve to.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkdayConfig {
+    pub end: Option<String>,
+}
+
+impl WorkdayConfig {
+    pub fn end(&self) -> Result<NaiveTime> {
+        parse_alarm_time(self.end.as_deref().unwrap_or(DEFAULT_WORKDAY_END))
+    }
+}
+
+impl TimerConfig {
+    /// Zones for a `total` long timer from this section alone, for timers that
+    /// take no `--warn-at`/`--critical-at`.
+    pub fn zones(&self, total: Duration) -> Result<Zones> {
+        Zones::new(self.warn_at.as_deref().map(Source::Config), self.critical_at.as_deref().map(Source::Config), total)
+    }
+}
+
+/// The `focus` section: shell commands run, in order, when `clockeroo focus`
+/// starts and when it ends (however it ends).
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FocusConfig {
+    pub on_start: Vec<String>,
+    pub on_end: Vec<String>,
+}
+
+/// The `pomodoro` section. Durations use the same syntax as timers, colors are
+/// names like `"red"` or hex like `"#ff8800"`, tones are frequencies in Hz.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PomodoroConfig {
+    pub work: String,
+    pub short_break: String,
+    pub long_break: String,
+    pub rounds: u32,
+    pub long_break_every: u32,
+    pub work_color: String,
+    pub break_color: String,
+    pub work_end_tone: f32,
+    pub break_end_tone: f32,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        PomodoroConfig {
+            work: "25m".to_string(),
+            short_break: "5m".to_string(),
+            long_break: "15m".to_string(),
+            rounds: 4,
+            long_break_every: 4,
+            work_color: "red".to_string(),
+            break_color: "green".to_string(),
+            work_end_tone: 523.25,
+            break_end_tone: 880.0,
+        }
+    }
+}
+
+/// A named timer, either `"tea": "3m"` or `"tea": { "duration": "3m", "label": ..., "message": ... }`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(from = "PresetDef")]
+pub struct Preset {
+    pub duration: String,
+    pub label: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PresetDef {
+    Duration(String),
+    Full {
+        duration: String,
+        #[serde(default)]
+        label: Option<String>,
+        #[serde(default)]
+        message: Option<String>,
+    },
+}
+
+impl From<PresetDef> for Preset {
+    fn from(def: PresetDef) -> Self {
+        match def {
+            PresetDef::Duration(duration) => Preset { duration, label: None, message: None },
+            PresetDef::Full { duration, label, message } => Preset { duration, label, message },
+        }
+    }
+}
+
+/// Maps sound event names (`timer_done`, `alarm`, `lap`, `tick`) to audio files.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(try_from = "HashMap<String, PathBuf>")]
+pub struct SoundTheme {
+    sounds: HashMap<String, PathBuf>,
+}
+
+impl TryFrom<HashMap<String, PathBuf>> for SoundTheme {
+    type Error = String;
+
+    fn try_from(sounds: HashMap<String, PathBuf>) -> Result<Self, Self::Error> {
+        if let Some(unknown) = sounds.keys().find(|k| !SOUND_EVENTS.contains(&k.as_str())) {
+            return Err(format!(
+                "unknown sound event '{}' (expected one of: {})",
+                unknown,
+                SOUND_EVENTS.join(", ")
+            ));
+        }
+        Ok(SoundTheme { sounds })
+    }
+}
+
+impl Serialize for SoundTheme {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.sounds.serialize(serializer)
+    }
+}
+
+impl SoundTheme {
+    pub fn path_for(&self, event: SoundEvent) -> Option<&Path> {
+        self.sounds.get(event.name()).map(PathBuf::as_path)
+    }
+}
+
+impl Config {
+    pub fn parse(s: &str) -> Result<Config> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    pub fn sound_theme(&self, name: &str) -> Result<SoundTheme> {
+        self.sound_themes.get(name).cloned().ok_or_else(|| {
+            anyhow::anyhow!("Unknown sound theme '{}'. Available themes: {}", name, list_names(&self.sound_themes))
+        })
+    }
+
+    pub fn preset(&self, name: &str) -> Result<&Preset> {
+        self.presets.get(name).ok_or_else(|| {
+            anyhow::anyhow!("Unknown preset '{}'. Available presets: {}", name, list_names(&self.presets))
+        })
+    }
+}
+
+impl Config {
+    pub fn tag_for(&self, label: &str) -> Option<String> {
+        self.tags.get(label).cloned()
+    }
+
+    pub fn quick_durations(&self) -> Vec<String> {
+        self.quick_durations.clone().unwrap_or_else(|| {
+            DEFAULT_QUICK_DURATIONS.iter().map(|d| d.to_string()).collect()
+        })
+    }
+}
+
+/// "a, b, c", or "(none)" for an empty table.
+fn list_names<V>(table: &BTreeMap<String, V>) -> String {
+    if table.is_empty() {
+        "(none)".to_string()
+    } else {
+        table.keys().map(String::as_str).collect::<Vec<_>>().join(", ")
+    }
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("CLOCKEROO_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+
+    let config_dir = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|_| std::env::var("APPDATA").map(PathBuf::from))
+        .ok()?;
+    Some(config_dir.join("clockeroo").join("config.json"))
+}
+
+/// Loads the config file, falling back to defaults if it doesn't exist.
+pub fn load() -> Result<Config> {
+    let Some(path) = config_path() else {
+        return Ok(Config::default());
+    };
+
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read config file {}", path.display()))?;
+    Config::parse(&contents).with_context(|| format!("Invalid config file {}", path.display()))
+}
+
+/// Top-level keys of the config file.
+const CONFIG_KEYS: &[&str] = &["sound_themes", "presets", "pomodoro", "quick_durations", "time_format", "tags", "focus", "timer", "alarm", "notifications", "workday", "bell"];
+const POMODORO_KEYS: &[&str] = &[
+    "work",
+    "short_break",
+    "long_break",
+    "rounds",
+    "long_break_every",
+    "work_color",
+    "break_color",
+    "work_end_tone",
+    "break_end_tone",
+];
+const FOCUS_KEYS: &[&str] = &["on_start", "on_end"];
+const TIMER_KEYS: &[&str] = &["warn_at", "critical_at", "confirm"];
+const ALARM_KEYS: &[&str] = &["fade_in"];
+const NOTIFICATIONS_KEYS: &[&str] = &["coalesce"];
+const WORKDAY_KEYS: &[&str] = &["end"];
+const PRESET_KEYS: &[&str] = &["duration", "label", "message"];
+
+/// Something `config check` found wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// Line in the file, when it can be told
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Every problem in a config file rather than just the first: JSON errors,
+/// unknown keys (by path), and values that only fail once a feature uses them,
+/// like preset durations, pomodoro colors and missing sound files.
+pub fn check(contents: &str) -> Vec<Problem> {
+    let mut value: Value = match serde_json::from_str(contents) {
+        Ok(value) => value,
+        Err(err) => return vec![serde_problem(&err)],
+    };
+
+    let mut problems = vec![];
+    drop_unknown_keys(&mut value, &[], contents, &mut problems);
+    // Left with just known keys, so the typed parse reports what's wrong with the values.
+    // Line numbers only come from the text, so use it when nothing was dropped.
+    let parsed = match problems.is_empty() {
+        true => serde_json::from_str::<Config>(contents),
+        false => serde_json::from_value::<Config>(value),
+    };
+    let config = match parsed {
+        Ok(config) => config,
+        Err(err) => {
+            problems.push(serde_problem(&err));
+            return problems;
+        }
+    };
+
+    let mut problem = |key: &str, message: String| problems.push(Problem { line: line_of_key(contents, key), message });
+    for (name, preset) in &config.presets {
+        if let Err(err) = parse_duration(&preset.duration) {
+            problem(name, format!("presets.{}: {}", name, err));
+        }
+    }
+    for duration in config.quick_durations.iter().flatten() {
+        if let Err(err) = parse_duration(duration) {
+            problem("quick_durations", format!("quick_durations: {}", err));
+        }
+    }
+    let pomodoro = &config.pomodoro;
+    for (key, duration) in [("work", &pomodoro.work), ("short_break", &pomodoro.short_break), ("long_break", &pomodoro.long_break)] {
+        if let Err(err) = parse_duration(duration) {
+            problem(key, format!("pomodoro.{}: {}", key, err));
+        }
+    }
+    for (key, color) in [("work_color", &pomodoro.work_color), ("break_color", &pomodoro.break_color)] {
+        if let Err(err) = parse_color(color) {
+            problem(key, format!("pomodoro.{}: {}", key, err));
+        }
+    }
+    if pomodoro.rounds == 0 {
+        problem("rounds", "pomodoro.rounds: must be at least 1".to_string());
+    }
+    for (key, threshold) in [("warn_at", &config.timer.warn_at), ("critical_at", &config.timer.critical_at)] {
+        if let Some(Err(err)) = threshold.as_deref().map(Threshold::parse) {
+            problem(key, format!("timer.{}: {}", key, err));
+        }
+    }
+    if let Some(Err(err)) = config.alarm.fade_in.as_deref().map(parse_duration) {
+        problem("fade_in", format!("alarm.fade_in: {}", err));
+    }
+    if let Some(Err(err)) = config.notifications.coalesce.as_deref().map(parse_duration_or_zero) {
+        problem("coalesce", format!("notifications.coalesce: {}", err));
+    }
+    if let Err(err) = config.workday.end() {
+        problem("end", format!("workday.end: {}", err));
+    }
+    for (name, theme) in &config.sound_themes {
+        let mut sounds: Vec<_> = theme.sounds.iter().collect();
+        sounds.sort();
+        for (event, path) in sounds {
+            if !path.exists() {
+                problem(name, format!("sound_themes.{}.{}: {} doesn't exist", name, event, path.display()));
+            }
+        }
+    }
+    problems
+}
+
+/// Removes keys the config doesn't know from `value`, reporting each with its path.
+fn drop_unknown_keys(value: &mut Value, path: &[&str], contents: &str, problems: &mut Vec<Problem>) {
+    let Value::Object(map) = value else {
+        return;
+    };
+    let known: Option<&[&str]> = match path {
+        [] => Some(CONFIG_KEYS),
+        ["pomodoro"] => Some(POMODORO_KEYS),
+        ["focus"] => Some(FOCUS_KEYS),
+        ["timer"] => Some(TIMER_KEYS),
+        ["alarm"] => Some(ALARM_KEYS),
+        ["notifications"] => Some(NOTIFICATIONS_KEYS),
+        ["workday"] => Some(WORKDAY_KEYS),
+        ["presets", _] => Some(PRESET_KEYS),
+        ["sound_themes", _] => Some(SOUND_EVENTS),
+        _ => None,
+    };
+    if let Some(known) = known {
+        map.retain(|key, _| {
+            let ok = known.contains(&key.as_str());
+            if !ok {
+                let mut full: Vec<&str> = path.to_vec();
+                full.push(key);
+                problems.push(Problem {
+                    line: line_of_key(contents, key),
+                    message: format!("unknown key '{}' (expected one of: {})", full.join("."), known.join(", ")),
+                });
+            }
+            ok
+        });
+    }
+    // Only tables of named entries hold more keys to check
+    if let [] | ["presets"] | ["sound_themes"] = path {
+        for (key, child) in map.iter_mut() {
+            let mut child_path = path.to_vec();
+            child_path.push(key);
+            drop_unknown_keys(child, &child_path, contents, problems);
+        }
+    }
+}
+
+/// The first line with `"key":` on it. JSON values carry no positions, so a key
+/// used in two places points at the first.
+fn line_of_key(contents: &str, key: &str) -> Option<usize> {
+    let quoted = format!("\"{}\"", key);
+    contents.lines().position(|line| {
+        line.match_indices(&quoted)
+            .any(|(at, _)| line[at + quoted.len()..].trim_start().starts_with(':'))
+    })
+    .map(|index| index + 1)
+}
+
+/// A serde error without its " at line 3 column 5" tail, the line goes in front instead.
+fn serde_problem(err: &serde_json::Error) -> Problem {
+    let message = err.to_string();
+    let message = match message.rfind(" at line ") {
+        Some(at) => message[..at].to_string(),
+        None => message,
+    };
+    Problem { line: (err.line() > 0).then_some(err.line()), message }
+}
+
+/// What `config init` writes: every setting at its default.
+pub fn default_file() -> String {
+    let config = Config { quick_durations: Some(Config::default().quick_durations()), ..Config::default() };
+    let mut contents = serde_json::to_string_pretty(&config).unwrap_or_default();
+    contents.push('\n');
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sound_themes() {
+        let config = Config::parse(
+            r#"{
+                "sound_themes": {
+                    "soft": { "timer_done": "/sounds/chime.ogg", "lap": "/sounds/click.wav", "tick": "/sounds/tock.wav" },
+                    "loud": { "alarm": "/sounds/siren.mp3" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let soft = config.sound_theme("soft").unwrap();
+        assert_eq!(soft.path_for(SoundEvent::TimerDone), Some(Path::new("/sounds/chime.ogg")));
+        assert_eq!(soft.path_for(SoundEvent::Lap), Some(Path::new("/sounds/click.wav")));
+        assert_eq!(soft.path_for(SoundEvent::Tick), Some(Path::new("/sounds/tock.wav")));
+        assert_eq!(soft.path_for(SoundEvent::Alarm), None);
+
+        let err = config.sound_theme("quiet").unwrap_err().to_string();
+        assert!(err.contains("loud, soft"), "{}", err);
+    }
+
+    #[test]
+    fn test_presets() {
+        let config = Config::parse(
+            r#"{
+                "presets": {
+                    "tea": "3m",
+                    "focus": { "duration": "25m", "label": "Focus", "message": "Take a break" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.preset("tea").unwrap(), &Preset { duration: "3m".into(), label: None, message: None });
+        let focus = config.preset("focus").unwrap();
+        assert_eq!(focus.duration, "25m");
+        assert_eq!(focus.label.as_deref(), Some("Focus"));
+        assert_eq!(focus.message.as_deref(), Some("Take a break"));
+
+        let err = config.preset("eggs").unwrap_err().to_string();
+        assert_eq!(err, "Unknown preset 'eggs'. Available presets: focus, tea");
+        let err = Config::default().preset("eggs").unwrap_err().to_string();
+        assert!(err.ends_with("(none)"));
+    }
+
+    #[test]
+    fn test_pomodoro_defaults_fill_gaps() {
+        let config = Config::parse(r#"{ "pomodoro": { "work": "50m", "break_color": "cyan" } }"#).unwrap();
+        assert_eq!(config.pomodoro.work, "50m");
+        assert_eq!(config.pomodoro.break_color, "cyan");
+        assert_eq!(config.pomodoro.short_break, "5m");
+        assert_eq!(config.pomodoro.work_color, "red");
+        assert!(Config::parse(r#"{ "pomodoro": { "wrok": "50m" } }"#).is_err());
+    }
+
+    #[test]
+    fn test_focus_hooks() {
+        let config = Config::parse(r#"{ "focus": { "on_start": ["dnd on", "slack pause"] } }"#).unwrap();
+        assert_eq!(config.focus.on_start, ["dnd on", "slack pause"]);
+        assert!(config.focus.on_end.is_empty());
+        assert!(Config::parse(r#"{ "focus": { "on_stop": [] } }"#).is_err());
+    }
+
+    #[test]
+    fn test_alarm_fade_in() {
+        let config = Config::parse(r#"{ "alarm": { "fade_in": "90s" } }"#).unwrap();
+        assert_eq!(config.alarm.fade_in.as_deref(), Some("90s"));
+        let problems = check(r#"{ "alarm": { "fade_in": "slowly" } }"#);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.starts_with("alarm.fade_in: Invalid duration"), "{}", problems[0]);
+    }
+
+    #[test]
+    fn test_workday_end() {
+        let config = Config::parse("{}").unwrap();
+        assert_eq!(config.workday.end().unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+        let config = Config::parse(r#"{ "workday": { "end": "6:30pm" } }"#).unwrap();
+        assert_eq!(config.workday.end().unwrap(), NaiveTime::from_hms_opt(18, 30, 0).unwrap());
+        let problems = check(r#"{ "workday": { "end": "late" } }"#);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.starts_with("workday.end: "), "{}", problems[0]);
+    }
+
+    #[test]
+    fn test_notifications_coalesce() {
+        let config = Config::parse(r#"{ "notifications": { "coalesce": "0s" } }"#).unwrap();
+        assert_eq!(config.notifications.coalesce.as_deref(), Some("0s"));
+        assert!(check(r#"{ "notifications": { "coalesce": "0s" } }"#).is_empty());
+        let problems = check(r#"{ "notifications": { "coalesce": "a while" } }"#);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.starts_with("notifications.coalesce: Invalid duration"), "{}", problems[0]);
+    }
+
+    #[test]
+    fn test_tags_for_labels() {
+        let config = Config::parse(r#"{ "tags": { "tea": "🍵" } }"#).unwrap();
+        assert_eq!(config.tag_for("tea").as_deref(), Some("🍵"));
+        assert_eq!(config.tag_for("focus"), None);
+    }
+
+    #[test]
+    fn test_quick_durations() {
+        assert_eq!(Config::default().quick_durations(), ["1m", "5m", "10m", "25m", "1h"]);
+        let config = Config::parse(r#"{ "quick_durations": ["90s", "2h"] }"#).unwrap();
+        assert_eq!(config.quick_durations(), ["90s", "2h"]);
+    }
+
+    #[test]
+    fn test_reject_unknown_sound_event() {
+        let err = Config::parse(r#"{ "sound_themes": { "x": { "finish": "/a.wav" } } }"#).unwrap_err();
+        assert!(format!("{:#}", err).contains("unknown sound event 'finish'"));
+    }
+
+    #[test]
+    fn test_check_reports_every_problem() {
+        let contents = r#"{
+    "presets": {
+        "tea": "soon",
+        "eggs": { "duration": "7m", "lable": "Eggs" }
+    },
+    "pomodoro": { "work_color": "sparkly", "rounds": 0 },
+    "colour": "red"
+}"#;
+        let problems: Vec<String> = check(contents).iter().map(|problem| problem.to_string()).collect();
+        assert_eq!(problems.len(), 5, "{:#?}", problems);
+        assert!(problems[0].starts_with("line 7: unknown key 'colour'"), "{}", problems[0]);
+        assert!(problems[1].starts_with("line 4: unknown key 'presets.eggs.lable' (expected one of: duration, label, message)"), "{}", problems[1]);
+        assert!(problems[2].starts_with("line 3: presets.tea: Invalid duration"), "{}", problems[2]);
+        assert!(problems[3].starts_with("line 6: pomodoro.work_color:"), "{}", problems[3]);
+        assert_eq!(problems[4], "line 6: pomodoro.rounds: must be at least 1");
+    }
+
+    #[test]
+    fn test_check_syntax_and_type_errors() {
+        let problems = check("{\n  \"tags\": {\n    \"tea\": \"x\",\n  }\n}");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].line, Some(4));
+        let problems = check("{\n  \"pomodoro\": { \"rounds\": \"four\" }\n}");
+        assert_eq!(problems[0].line, Some(2));
+        assert!(problems[0].message.starts_with("invalid type"), "{}", problems[0]);
+    }
+
+    #[test]
+    fn test_default_file_checks_clean() {
+        assert_eq!(check(&default_file()), []);
+        assert_eq!(check("{}"), []);
+    }
+}
@@ -0,0 +1,320 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    signal::unix::{signal, SignalKind},
+    sync::Notify,
+    time::sleep,
+};
+
+use crate::{
+    artifacts::{self, Artifact},
+    dnd,
+    history,
+    hooks,
+    ipc::{self, Request, Response},
+    notify,
+    quiet_hours,
+    sound::{self, SoundEvent, SoundOptions},
+    statefile,
+    store::{Entry, EntryKind, Store},
+    time_format::format_clock,
+    ui,
+};
+
+/// Longest the daemon sleeps between checks, so wall-clock jumps are noticed.
+const MAX_SLEEP: Duration = Duration::from_secs(1);
+
+/// Wall-clock vs monotonic disagreement that counts as a clock change.
+const CLOCK_JUMP_THRESHOLD: chrono::Duration = chrono::Duration::seconds(2);
+
+pub fn pid_file() -> PathBuf {
+    crate::runtime_dir().join("clockeroo.pid")
+}
+
+/// Returns the PID of a live daemon, if one is running.
+pub fn running_pid() -> Option<i32> {
+    let pid: i32 = fs::read_to_string(pid_file()).ok()?.trim().parse().ok()?;
+    // A daemon under another user (EPERM) is still running, as far as the pid file goes
+    artifacts::pid_alive(pid).unwrap_or(false).then_some(pid)
+}
+
+/// Starts the daemon detached from the terminal and returns its PID.
+/// `sound_args` are the sound flags it should ring with.
+pub fn spawn_background(sound_args: &[String]) -> Result<u32> {
+    use std::os::unix::process::CommandExt;
+
+    if let Some(pid) = running_pid() {
+        anyhow::bail!("The clockeroo daemon is already running (pid {})", pid);
+    }
+
+    let child = Command::new(std::env::current_exe()?)
+        .args(sound_args)
+        .args(["daemon", "--foreground"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .context("Failed to start the daemon")?;
+    Ok(child.id())
+}
+
+/// How long `ensure_running` waits for a freshly started daemon to answer.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Starts the daemon unless one is already running, and waits until it answers.
+/// A daemon started here rings with `sound_args`, as `spawn_background` does.
+pub fn ensure_running(sound_args: &[String]) -> Result<()> {
+    if running_pid().is_some() {
+        return Ok(());
+    }
+
+    spawn_background(sound_args)?;
+    let started = Instant::now();
+    while started.elapsed() < STARTUP_TIMEOUT {
+        if ipc::send_with_timeout(&Request::Status, Duration::from_millis(100)).is_ok() {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    anyhow::bail!("The daemon didn't answer within {}s of starting", STARTUP_TIMEOUT.as_secs())
+}
+
+pub async fn run_foreground(sound: SoundOptions) -> Result<()> {
+    // Held from the check until our pid is written, so two daemons started at
+    // once can't both find none running and both take the socket
+    let starting = statefile::lock(&pid_file())?;
+    if let Some(pid) = running_pid() {
+        anyhow::bail!("The clockeroo daemon is already running (pid {})", pid);
+    }
+
+    let socket_path = ipc::socket_path();
+    // Only a socket nobody answers on is left over; a live one is another daemon's
+    if std::os::unix::net::UnixStream::connect(&socket_path).is_ok() {
+        anyhow::bail!("Another clockeroo daemon is answering on {}", socket_path.display());
+    }
+    let _ = fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path)
+        .with_context(|| format!("Failed to bind {}", socket_path.display()))?;
+    let _socket = Artifact::register(socket_path);
+    fs::write(pid_file(), std::process::id().to_string())?;
+    let _pid_file = Artifact::register(pid_file());
+    drop(starting);
+
+    // Running for days, DND is asked about at each ring rather than once
+    dnd::set_uncached();
+    let store = Arc::new(Mutex::new(Store::load()?));
+    let changed = Arc::new(Notify::new());
+    eprintln!("clockeroo daemon started (pid {})", std::process::id());
+
+    let accept_store = store.clone();
+    let accept_changed = changed.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let store = accept_store.clone();
+            let changed = accept_changed.clone();
+            tokio::spawn(async move {
+                let _ = handle_client(stream, store, changed).await;
+            });
+        }
+    });
+
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut last_check = (Instant::now(), Local::now());
+
+    loop {
+        let now = Local::now();
+
+        // Detect wall-clock jumps (NTP steps, suspend/resume, manual changes)
+        let mono_elapsed = chrono::Duration::from_std(last_check.0.elapsed()).unwrap_or_default();
+        let drift = (now - last_check.1) - mono_elapsed;
+        if drift.abs() > CLOCK_JUMP_THRESHOLD {
+            eprintln!("wall clock jumped by {}s, recomputing deadlines", drift.num_seconds());
+            let mut store = store.lock().unwrap();
+            store.shift_timers(drift);
+            let _ = store.save();
+        }
+        last_check = (Instant::now(), now);
+
+        let (fired, wait) = {
+            let mut store = store.lock().unwrap();
+            let fired = store.take_due(now);
+            if !fired.is_empty() {
+                let _ = store.save();
+            }
+            let wait = store
+                .next_due()
+                .and_then(|due| (due - now).to_std().ok())
+                .map_or(MAX_SLEEP, |until| until.min(MAX_SLEEP));
+            (fired, wait)
+        };
+
+        for entry in fired {
+            fire(entry, &sound);
+        }
+
+        tokio::select! {
+            _ = sleep(wait) => {}
+            _ = changed.notified() => {}
+            _ = hangup.recv() => {
+                eprintln!("SIGHUP received, reloading store");
+                match Store::load() {
+                    Ok(reloaded) => *store.lock().unwrap() = reloaded,
+                    Err(err) => eprintln!("failed to reload store: {:#}", err),
+                }
+            }
+            _ = terminate.recv() => break,
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_client(stream: UnixStream, store: Arc<Mutex<Store>>, changed: Arc<Notify>) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+
+    let response = match serde_json::from_str::<Request>(&line) {
+        Ok(request) => handle_request(request, &store, &changed),
+        Err(err) => Response::error(format!("Invalid request: {}", err)),
+    };
+
+    let mut reply = serde_json::to_string(&response)?;
+    reply.push('\n');
+    writer.write_all(reply.as_bytes()).await?;
+    Ok(())
+}
+
+fn handle_request(request: Request, store: &Mutex<Store>, changed: &Notify) -> Response {
+    let mut store = store.lock().unwrap();
+
+    match request {
+        Request::Status => {
+            let mut entries = store.entries.clone();
+            entries.sort_by_key(|e| e.due);
+            let now = Local::now();
+            for entry in &mut entries {
+                entry.progress = entry.progress_at(now);
+            }
+            Response { ok: true, entries, ..Default::default() }
+        }
+        // Checked and added under the store lock, so two starts fired at once can't both get in
+        Request::Add { entry, duplicates } => {
+            let (id, replaced) = match store.add_checked(*entry, duplicates, Local::now()) {
+                Ok(added) => added,
+                Err(err) => return Response::error(err.to_string()),
+            };
+            if let Err(err) = store.save() {
+                return Response::error(format!("Failed to save store: {}", err));
+            }
+            changed.notify_one();
+            Response { ok: true, id: Some(id), entries: replaced, ..Default::default() }
+        }
+        Request::Cancel { id } => match store.remove(id) {
+            Some(entry) => {
+                let _ = store.save();
+                changed.notify_one();
+                Response { ok: true, id: Some(id), entries: vec![entry], ..Default::default() }
+            }
+            None => Response::error(format!("No alarm or timer with id {}", id)),
+        },
+        Request::CancelMatching { which } => {
+            let cancelled = match store.remove_matching(&which, Local::now()) {
+                Ok(cancelled) => cancelled,
+                Err(err) => return Response::error(format!("{:#}", err)),
+            };
+            if let Err(err) = store.save() {
+                return Response::error(format!("Failed to save store: {}", err));
+            }
+            changed.notify_one();
+            Response { ok: true, entries: cancelled, ..Default::default() }
+        }
+        Request::Skip { id, until } => {
+            let result = store.skip(id, until, Local::now()).cloned();
+            skip_response(&mut store, changed, result)
+        }
+        Request::Unskip { id } => {
+            let result = store.unskip(id, Local::now()).cloned();
+            skip_response(&mut store, changed, result)
+        }
+        Request::Import { source, entries, sync } => {
+            let report = store.import(&source, entries, sync);
+            if let Err(err) = store.save() {
+                return Response::error(format!("Failed to save store: {}", err));
+            }
+            changed.notify_one();
+            Response { ok: true, import: Some(report), ..Default::default() }
+        }
+    }
+}
+
+/// Saves a skip or unskip and replies with the alarm as it now stands.
+fn skip_response(store: &mut Store, changed: &Notify, result: Result<Entry>) -> Response {
+    let entry = match result {
+        Ok(entry) => entry,
+        Err(err) => return Response::error(format!("{:#}", err)),
+    };
+    if let Err(err) = store.save() {
+        return Response::error(format!("Failed to save store: {}", err));
+    }
+    changed.notify_one();
+    Response { ok: true, id: Some(entry.id), entries: vec![entry], ..Default::default() }
+}
+
+fn fire(entry: Entry, sound: &SoundOptions) {
+    let (title, body) = match entry.kind {
+        EntryKind::Timer => (
+            ui::with_tag(entry.tag.as_deref(), "Timer Finished!"),
+            entry.message.clone().unwrap_or_else(|| format!("{} has completed!", entry.display_name())),
+        ),
+        EntryKind::Alarm => ("Alarm!".to_string(), format!("It's {}! ({})", format_clock(&entry.due), entry.display_name())),
+    };
+
+    if let Some(command) = &entry.exec {
+        hooks::spawn(
+            command,
+            &[
+                ("CLOCKEROO_ID", entry.id.to_string()),
+                ("CLOCKEROO_KIND", format!("{:?}", entry.kind).to_lowercase()),
+                ("CLOCKEROO_LABEL", entry.label.clone().unwrap_or_default()),
+            ],
+        );
+    }
+
+    let quiet = quiet_hours::is_quiet_now(entry.quiet_hours.as_ref());
+
+    // History, notifications and audio block, keep them off the scheduler
+    let sound = sound.clone();
+    tokio::task::spawn_blocking(move || {
+        // For `stats` and `suggest`, as the cancel command logs the ones that never got here
+        let record = history::Record::for_entry(&entry, history::Outcome::Finished, Local::now());
+        if let Err(err) = history::append(&record) {
+            eprintln!("failed to log #{} to the history: {:#}", entry.id, err);
+        }
+        if quiet {
+            return;
+        }
+        match entry.kind {
+            EntryKind::Alarm => notify::send_alarm_notification(&title, &body),
+            _ => notify::send_notification(&title, &body),
+        }
+        // Through the same Do Not Disturb policy as interactive runs, --respect-dnd and --ignore-dnd included
+        let event = match entry.kind {
+            EntryKind::Alarm => SoundEvent::Alarm,
+            EntryKind::Timer => SoundEvent::TimerDone,
+        };
+        sound::play_bell(event, &sound);
+    });
+}
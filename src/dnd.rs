@@ -0,0 +1,143 @@
+use std::{
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::output::verbose;
+
+/// Longest we wait on the DND query before assuming it isn't active.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How sounds react to the system's Do Not Disturb mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DndPolicy {
+    /// Mute ordinary timer sounds, let alarms and critical timers through
+    #[default]
+    Auto,
+    /// Mute every sound while DND is on, alarms included
+    Respect,
+    /// Never check DND
+    Ignore,
+}
+
+impl DndPolicy {
+    /// Whether a sound should be skipped. `punch_through` marks alarms and critical timers.
+    pub fn mutes(self, punch_through: bool) -> bool {
+        match self {
+            DndPolicy::Ignore => false,
+            DndPolicy::Respect => is_active(),
+            DndPolicy::Auto => !punch_through && is_active(),
+        }
+    }
+}
+
+/// Set by processes that outlive a DND toggle (the daemon), so each sound asks afresh.
+static UNCACHED: AtomicBool = AtomicBool::new(false);
+
+/// Makes `is_active` query every time rather than once per run.
+pub fn set_uncached() {
+    UNCACHED.store(true, Ordering::Relaxed);
+}
+
+/// The desktop's Do Not Disturb state, queried once per run (or every time, see `set_uncached`).
+pub fn is_active() -> bool {
+    static ACTIVE: OnceLock<bool> = OnceLock::new();
+    if UNCACHED.load(Ordering::Relaxed) {
+        return check();
+    }
+    *ACTIVE.get_or_init(check)
+}
+
+/// Best-effort uncached check. Any failure to ask counts as "not in DND".
+fn check() -> bool {
+    let active = query().unwrap_or_else(|err| {
+        verbose!("DND query failed ({}), assuming it's off", err);
+        false
+    });
+    verbose!("Do Not Disturb is {}", if active { "on" } else { "off" });
+    active
+}
+
+#[cfg(target_os = "macos")]
+fn query() -> Result<bool, String> {
+    let output = run_with_timeout(Command::new("defaults").args([
+        "-currentHost",
+        "read",
+        "com.apple.notificationcenterui",
+        "doNotDisturb",
+    ]))?;
+    Ok(output.trim() == "1")
+}
+
+#[cfg(not(target_os = "macos"))]
+fn query() -> Result<bool, String> {
+    // The freedesktop notification server exposes DND as its `Inhibited` property
+    let output = run_with_timeout(Command::new("busctl").args([
+        "--user",
+        "get-property",
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        "org.freedesktop.Notifications",
+        "Inhibited",
+    ]))?;
+    parse_busctl_bool(&output)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn parse_busctl_bool(output: &str) -> Result<bool, String> {
+    match output.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["b", "true"] => Ok(true),
+        ["b", "false"] => Ok(false),
+        _ => Err(format!("unexpected reply '{}'", output.trim())),
+    }
+}
+
+/// Runs a query command, killing it if it doesn't answer within `QUERY_TIMEOUT`.
+fn run_with_timeout(command: &mut Command) -> Result<String, String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let started = Instant::now();
+    loop {
+        match child.try_wait().map_err(|e| e.to_string())? {
+            Some(status) if status.success() => break,
+            Some(status) => return Err(format!("exited with {}", status)),
+            None if started.elapsed() > QUERY_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("no answer within {}ms", QUERY_TIMEOUT.as_millis()));
+            }
+            None => std::thread::sleep(Duration::from_millis(10)),
+        }
+    }
+
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_never_mutes() {
+        assert!(!DndPolicy::Ignore.mutes(false));
+        assert!(!DndPolicy::Ignore.mutes(true));
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_parse_busctl_bool() {
+        assert_eq!(parse_busctl_bool("b true\n"), Ok(true));
+        assert_eq!(parse_busctl_bool("b false\n"), Ok(false));
+        assert!(parse_busctl_bool("").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use clap::{Args, ValueEnum};
+use serde_json::json;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{format_raw_seconds, Precision};
+
+#[derive(Args, Clone, Default)]
+pub struct ExportArgs {
+    /// Write the session and laps to a file (.csv or .json)
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+    /// Export format, inferred from the --out extension by default
+    #[arg(long, value_enum)]
+    pub format: Option<ExportFormat>,
+    /// Overwrite the --out file if it already exists
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportArgs {
+    fn resolve_format(&self, path: &Path) -> Result<ExportFormat> {
+        if let Some(format) = self.format {
+            return Ok(format);
+        }
+        match path.extension().and_then(|e| e.to_str()).map(str::to_lowercase).as_deref() {
+            Some("csv") => Ok(ExportFormat::Csv),
+            Some("json") => Ok(ExportFormat::Json),
+            _ => anyhow::bail!(
+                "Can't infer export format from '{}', pass --format csv or --format json",
+                path.display()
+            ),
+        }
+    }
+
+    /// Fails early (before a session starts) if the export could never be written.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(path) = &self.out {
+            self.resolve_format(path)?;
+            if path.exists() && !self.force {
+                anyhow::bail!("{} already exists, pass --force to overwrite it", path.display());
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the session if --out was given and returns the written path.
+    pub fn write(&self, started_at: DateTime<Local>, elapsed: Duration, laps: &[Duration], precision: Precision) -> Result<Option<PathBuf>> {
+        let Some(path) = &self.out else {
+            return Ok(None);
+        };
+        self.validate()?;
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let contents = render(self.resolve_format(path)?, started_at, elapsed, laps, precision);
+        fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(Some(path.clone()))
+    }
+}
+
+/// CSV seconds get `precision`'s digits, JSON numbers carry whatever there is.
+pub fn render(format: ExportFormat, started_at: DateTime<Local>, elapsed: Duration, laps: &[Duration], precision: Precision) -> String {
+    // (lap number, lap time, running total)
+    let rows: Vec<(usize, Duration, Duration)> = laps
+        .iter()
+        .scan(Duration::ZERO, |total, lap| {
+            *total += *lap;
+            Some(*total)
+        })
+        .zip(laps)
+        .enumerate()
+        .map(|(i, (total, lap))| (i + 1, *lap, total))
+        .collect();
+
+    match format {
+        ExportFormat::Csv => {
+            let mut out = format!(
+                "started_at,{}\nelapsed_seconds,{}\n\nlap,lap_seconds,total_seconds\n",
+                started_at.to_rfc3339(),
+                format_raw_seconds(elapsed, precision)
+            );
+            for (lap, time, total) in rows {
+                let (time, total) = (format_raw_seconds(time, precision), format_raw_seconds(total, precision));
+                out.push_str(&format!("{},{},{}\n", lap, time, total));
+            }
+            out
+        }
+        ExportFormat::Json => {
+            let laps: Vec<_> = rows
+                .iter()
+                .map(|(lap, time, total)| {
+                    json!({
+                        "lap": lap,
+                        "lap_seconds": time.as_secs_f64(),
+                        "total_seconds": total.as_secs_f64(),
+                    })
+                })
+                .collect();
+            let session = json!({
+                "started_at": started_at.to_rfc3339(),
+                "elapsed_seconds": elapsed.as_secs_f64(),
+                "laps": laps,
+            });
+            format!("{:#}\n", session)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_render_csv() {
+        let started_at = Local.with_ymd_and_hms(2024, 3, 8, 9, 0, 0).unwrap();
+        let laps = [Duration::from_millis(30_250), Duration::from_millis(29_500)];
+        let csv = render(ExportFormat::Csv, started_at, Duration::from_millis(61_000), &laps, Precision::Ms);
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("started_at,2024-03-08T09:00:00"));
+        assert_eq!(lines[1], "elapsed_seconds,61.000");
+        assert_eq!(&lines[3..], ["lap,lap_seconds,total_seconds", "1,30.250,30.250", "2,29.500,59.750"]);
+
+        let csv = render(ExportFormat::Csv, started_at, Duration::from_micros(61_000_042), &laps, Precision::Us);
+        assert_eq!(csv.lines().nth(1), Some("elapsed_seconds,61.000042"));
+        assert_eq!(csv.lines().nth(4), Some("1,30.250000,30.250000"));
+    }
+
+    #[test]
+    fn test_format_inference() {
+        let args = ExportArgs { out: Some(PathBuf::from("laps.JSON")), ..Default::default() };
+        assert_eq!(args.resolve_format(Path::new("laps.JSON")).unwrap(), ExportFormat::Json);
+        assert!(args.resolve_format(Path::new("laps.txt")).is_err());
+
+        let forced = ExportArgs { format: Some(ExportFormat::Csv), ..Default::default() };
+        assert_eq!(forced.resolve_format(Path::new("laps.txt")).unwrap(), ExportFormat::Csv);
+    }
+}
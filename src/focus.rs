@@ -0,0 +1,148 @@
+use std::{sync::Mutex, time::Duration};
+
+use crate::{config::FocusConfig, hooks};
+
+/// How a focus session ended, passed to `on_end` hooks as `CLOCKEROO_OUTCOME`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Finished,
+    Cancelled,
+    Terminated,
+}
+
+impl Outcome {
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Finished => "finished",
+            Outcome::Cancelled => "cancelled",
+            Outcome::Terminated => "terminated",
+        }
+    }
+}
+
+/// What came of running one list of hooks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub total: usize,
+    /// "command: reason" for each hook that failed
+    pub failed: Vec<String>,
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// "on_start: 2 hooks ran" or "on_end: 1 of 2 hooks failed".
+    pub fn summary(&self, stage: &str) -> String {
+        let hooks = if self.total == 1 { "hook" } else { "hooks" };
+        if self.is_ok() {
+            format!("{}: {} {} ran", stage, self.total, hooks)
+        } else {
+            format!("{}: {} of {} {} failed", stage, self.failed.len(), self.total, hooks)
+        }
+    }
+}
+
+/// Runs `commands` one after another, carrying on past failures so one broken
+/// hook doesn't leave the rest of the setup (or teardown) undone.
+fn run_all(commands: &[String], vars: &[(&str, String)]) -> Report {
+    let failed = commands
+        .iter()
+        .filter_map(|command| hooks::run(command, vars).err().map(|err| format!("{}: {:#}", command, err)))
+        .collect();
+    Report { total: commands.len(), failed }
+}
+
+/// A running focus session. Its `on_end` hooks run exactly once: when the
+/// timer finishes, when the caller ends it, or at the latest when it's dropped.
+pub struct Session {
+    on_end: Vec<String>,
+    vars: Vec<(&'static str, String)>,
+    started: Report,
+    ended: Mutex<Option<Report>>,
+}
+
+impl Session {
+    /// Runs the `on_start` hooks. Hooks see `CLOCKEROO_LABEL` and
+    /// `CLOCKEROO_DURATION` (whole seconds).
+    pub fn start(config: &FocusConfig, label: &str, duration: Duration) -> Session {
+        let vars = vec![
+            ("CLOCKEROO_KIND", "focus".to_string()),
+            ("CLOCKEROO_LABEL", label.to_string()),
+            ("CLOCKEROO_DURATION", duration.as_secs().to_string()),
+        ];
+        let started = run_all(&config.on_start, &vars);
+        Session { on_end: config.on_end.clone(), vars, started, ended: Mutex::new(None) }
+    }
+
+    pub fn started(&self) -> &Report {
+        &self.started
+    }
+
+    /// Whether there are any hooks at all, so screens can leave out the hooks line.
+    pub fn has_hooks(&self) -> bool {
+        self.started.total > 0 || !self.on_end.is_empty()
+    }
+
+    /// Runs the `on_end` hooks if they haven't run yet, and returns how they went.
+    pub fn end(&self, outcome: Outcome) -> Report {
+        let mut ended = self.ended.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        ended
+            .get_or_insert_with(|| {
+                let mut vars = self.vars.clone();
+                vars.push(("CLOCKEROO_OUTCOME", outcome.name().to_string()));
+                run_all(&self.on_end, &vars)
+            })
+            .clone()
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.end(Outcome::Cancelled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(Report { total: 2, failed: vec![] }.summary("on_start"), "on_start: 2 hooks ran");
+        let report = Report { total: 1, failed: vec!["dnd off: exit status: 1".into()] };
+        assert!(!report.is_ok());
+        assert_eq!(report.summary("on_end"), "on_end: 1 of 1 hook failed");
+    }
+
+    #[test]
+    fn test_on_end_runs_once() {
+        let marker = std::env::temp_dir().join(format!("clockeroo-focus-{}", std::process::id()));
+        let _ = std::fs::remove_file(&marker);
+        let config = FocusConfig {
+            on_start: vec!["exit 1".into(), "true".into()],
+            on_end: vec![format!("echo \"$CLOCKEROO_LABEL $CLOCKEROO_DURATION $CLOCKEROO_OUTCOME\" >> {}", marker.display())],
+        };
+        let session = Session::start(&config, "deep work", Duration::from_secs(3000));
+        // A failing hook doesn't stop the ones after it
+        assert_eq!(session.started().total, 2);
+        assert_eq!(session.started().failed.len(), 1);
+
+        assert!(session.end(Outcome::Finished).is_ok());
+        assert!(session.end(Outcome::Cancelled).is_ok());
+        drop(session);
+        assert_eq!(std::fs::read_to_string(&marker).unwrap(), "deep work 3000 finished\n");
+        std::fs::remove_file(marker).unwrap();
+    }
+
+    #[test]
+    fn test_dropped_session_still_ends() {
+        let marker = std::env::temp_dir().join(format!("clockeroo-focus-drop-{}", std::process::id()));
+        let _ = std::fs::remove_file(&marker);
+        let config = FocusConfig { on_start: vec![], on_end: vec![format!("echo $CLOCKEROO_OUTCOME > {}", marker.display())] };
+        drop(Session::start(&config, "Focus", Duration::from_secs(60)));
+        assert_eq!(std::fs::read_to_string(&marker).unwrap(), "cancelled\n");
+        std::fs::remove_file(marker).unwrap();
+    }
+}
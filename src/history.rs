@@ -0,0 +1,435 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Timelike};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::store::{data_dir, Entry, EntryKind};
+
+/// How an alarm or timer ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    /// Rang and was dismissed by the user
+    Dismissed,
+    /// Rang until `--max-ring` ran out without anyone dismissing it
+    Missed,
+    /// A timer that ran to zero, or an alarm that rang with `--no-finish-screen`
+    Finished,
+    /// A timer stopped before it reached zero, or a background alarm cancelled
+    /// before it rang
+    Cancelled,
+}
+
+/// One line of the history log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub at: DateTime<Local>,
+    pub kind: EntryKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    pub outcome: Outcome,
+    /// How long a timer was set for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+impl Record {
+    /// A daemon alarm or timer that ended with `outcome` at `at`.
+    pub fn for_entry(entry: &Entry, outcome: Outcome, at: DateTime<Local>) -> Record {
+        Record {
+            at,
+            kind: entry.kind,
+            label: entry.label.clone(),
+            tag: entry.tag.clone(),
+            outcome,
+            duration_ms: entry.total_ms,
+        }
+    }
+}
+
+pub fn history_path() -> PathBuf {
+    data_dir().join("history.jsonl")
+}
+
+/// Appends a record to the history log, one JSON object per line.
+pub fn append(record: &Record) -> Result<()> {
+    let path = history_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The most of the log `recent` reads, from its end, so a log that has grown
+/// for years doesn't slow down starting a timer.
+const RECENT_BYTES: u64 = 256 * 1024;
+
+/// The latest records, oldest first. Best effort: a missing or unreadable log
+/// and lines that don't parse are skipped.
+pub fn recent() -> Vec<Record> {
+    read_records(RECENT_BYTES)
+}
+
+/// Every record in the log, oldest first, for `clockeroo stats`. Best effort
+/// like `recent`.
+pub fn all() -> Vec<Record> {
+    read_records(u64::MAX)
+}
+
+/// The records in the last `max_bytes` of the log.
+fn read_records(max_bytes: u64) -> Vec<Record> {
+    let Ok(contents) = read_tail(&history_path(), max_bytes) else {
+        return Vec::new();
+    };
+    contents.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()
+}
+
+/// The last `max_bytes` of `path`, starting at a line boundary.
+fn read_tail(path: &Path, max_bytes: u64) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(max_bytes);
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let contents = String::from_utf8_lossy(&bytes).into_owned();
+    if start == 0 {
+        return Ok(contents);
+    }
+    // Whatever comes before the first newline is the tail end of a cut-off line
+    Ok(contents.split_once('\n').map(|(_, rest)| rest.to_string()).unwrap_or_default())
+}
+
+/// A timer the user often runs around this time of day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub duration: Duration,
+    pub label: Option<String>,
+    /// Finished runs of it near this time
+    pub runs: usize,
+}
+
+/// How far from now (by time of day) a past run still counts.
+const SUGGESTION_WINDOW_MINUTES: i64 = 60;
+
+/// Fewer runs than this is a one-off, not a habit.
+const MIN_SUGGESTION_RUNS: usize = 2;
+
+/// Up to `limit` duration and label pairs from finished timers started within
+/// an hour (either side) of `now`'s time of day, most frequent first. Runs on
+/// the same weekday count double. Empty when history is too thin to tell.
+pub fn suggest(records: &[Record], now: DateTime<Local>, limit: usize) -> Vec<Suggestion> {
+    let now_minutes = i64::from(now.num_seconds_from_midnight() / 60);
+    let mut groups: HashMap<(u64, Option<&str>), Tally> = HashMap::new();
+    for record in records {
+        let Some(duration_ms) = record.duration_ms else { continue };
+        if record.kind != EntryKind::Timer || record.outcome != Outcome::Finished {
+            continue;
+        }
+        // Records are written at the finish; what's being matched is when it was started
+        let started = record.at - chrono::Duration::milliseconds(duration_ms as i64);
+        let minutes = i64::from(started.num_seconds_from_midnight() / 60);
+        let apart = (now_minutes - minutes).rem_euclid(24 * 60);
+        if apart.min(24 * 60 - apart) > SUGGESTION_WINDOW_MINUTES {
+            continue;
+        }
+        let weight = if started.weekday() == now.weekday() { 2 } else { 1 };
+        let tally = groups.entry((duration_ms, record.label.as_deref())).or_insert(Tally { score: 0, runs: 0, latest: record.at });
+        tally.score += weight;
+        tally.runs += 1;
+        tally.latest = tally.latest.max(record.at);
+    }
+
+    let mut ranked: Vec<_> = groups.into_iter().filter(|(_, tally)| tally.runs >= MIN_SUGGESTION_RUNS).collect();
+    ranked.sort_by_key(|(_, tally)| Reverse((tally.score, tally.runs, tally.latest)));
+    ranked
+        .into_iter()
+        .take(limit)
+        .map(|((duration_ms, label), tally)| Suggestion {
+            duration: Duration::from_millis(duration_ms),
+            label: label.map(str::to_string),
+            runs: tally.runs,
+        })
+        .collect()
+}
+
+/// One duration and label's runs near the current time, for `suggest`.
+struct Tally {
+    score: usize,
+    runs: usize,
+    latest: DateTime<Local>,
+}
+
+/// What `clockeroo stats` reports for one label (or every timer).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub completed: usize,
+    pub cancelled: usize,
+    pub average: Option<Duration>,
+    pub longest: Option<Duration>,
+    /// Days in a row, up to today, with a completed session. Yesterday's
+    /// streak still counts until today is over.
+    pub current_streak: u32,
+    pub longest_streak: u32,
+}
+
+impl Stats {
+    /// Completed out of completed and cancelled, `None` before either.
+    pub fn completion_rate(&self) -> Option<f64> {
+        let ended = self.completed + self.cancelled;
+        (ended > 0).then(|| self.completed as f64 / ended as f64)
+    }
+}
+
+/// Totals and streaks over the timers labelled `label` (all of them for
+/// `None`), with `today` the local date streaks are counted up to. Sessions
+/// fall on the local date they ended on, so one finishing at 23:59 and the next
+/// at 00:01 are two days.
+pub fn stats(records: &[Record], label: Option<&str>, today: NaiveDate) -> Stats {
+    let timers = records
+        .iter()
+        .filter(|record| record.kind == EntryKind::Timer)
+        .filter(|record| label.is_none() || record.label.as_deref() == label);
+    let mut stats = Stats::default();
+    let mut lengths = Vec::new();
+    let mut days = Vec::new();
+    for record in timers {
+        match record.outcome {
+            Outcome::Finished => {
+                stats.completed += 1;
+                lengths.extend(record.duration_ms.map(Duration::from_millis));
+                days.push(record.at.date_naive());
+            }
+            Outcome::Cancelled => stats.cancelled += 1,
+            Outcome::Dismissed | Outcome::Missed => {}
+        }
+    }
+    if !lengths.is_empty() {
+        stats.average = Some(lengths.iter().sum::<Duration>() / lengths.len() as u32);
+        stats.longest = lengths.iter().max().copied();
+    }
+    (stats.current_streak, stats.longest_streak) = streaks(days, today);
+    stats
+}
+
+/// The current and longest runs of consecutive dates in `days` (in any order,
+/// repeats allowed). The current run ends today or, if today has nothing yet,
+/// yesterday; anything older has been broken.
+pub fn streaks(mut days: Vec<NaiveDate>, today: NaiveDate) -> (u32, u32) {
+    days.sort_unstable();
+    days.dedup();
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in &days {
+        run = match previous {
+            Some(previous) if previous.succ_opt() == Some(day) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+    let alive = previous.is_some_and(|last| last == today || last.succ_opt() == Some(today));
+    (if alive { run } else { 0 }, longest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_record_line_format() {
+        let record = Record {
+            at: Local.with_ymd_and_hms(2024, 3, 8, 7, 5, 0).unwrap(),
+            kind: EntryKind::Alarm,
+            label: None,
+            tag: None,
+            outcome: Outcome::Missed,
+            duration_ms: None,
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(line.contains(r#""kind":"alarm""#), "{}", line);
+        assert!(line.contains(r#""outcome":"missed""#), "{}", line);
+        assert!(!line.contains("label"));
+
+        let parsed: Record = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.outcome, Outcome::Missed);
+        assert_eq!(parsed.at, record.at);
+    }
+
+    fn timer(at: DateTime<Local>, minutes: u64, label: Option<&str>) -> Record {
+        Record {
+            at,
+            kind: EntryKind::Timer,
+            label: label.map(str::to_string),
+            tag: None,
+            outcome: Outcome::Finished,
+            duration_ms: Some(minutes * 60_000),
+        }
+    }
+
+    #[test]
+    fn test_suggest() {
+        // Monday 9:00
+        let now = Local.with_ymd_and_hms(2024, 3, 11, 9, 0, 0).unwrap();
+        let day = chrono::Duration::days(1);
+        let records = vec![
+            // Weekday mornings: a 25m focus block, a 3m tea now and then
+            timer(now - day * 7 + chrono::Duration::minutes(10), 25, Some("focus")),
+            timer(now - day * 14 - chrono::Duration::minutes(30), 25, Some("focus")),
+            timer(now - day * 3, 3, Some("tea")),
+            timer(now - day * 2, 3, Some("tea")),
+            timer(now - day, 3, Some("tea")),
+            // Evenings don't count in the morning
+            timer(now - day + chrono::Duration::hours(10), 45, None),
+            timer(now - day * 2 + chrono::Duration::hours(10), 45, None),
+            // Once isn't a habit
+            timer(now - day, 10, None),
+            // Cancelled, or an alarm, isn't a run
+            Record { outcome: Outcome::Dismissed, ..timer(now - day, 10, None) },
+            Record { kind: EntryKind::Alarm, ..timer(now - day, 10, None) },
+        ];
+
+        let suggestions = suggest(&records, now, 3);
+        // Two Monday focus runs (weighted) outrank three tea runs on other days
+        assert_eq!(
+            suggestions,
+            [
+                Suggestion { duration: Duration::from_secs(1500), label: Some("focus".into()), runs: 2 },
+                Suggestion { duration: Duration::from_secs(180), label: Some("tea".into()), runs: 3 },
+            ]
+        );
+        assert_eq!(suggest(&records, now, 1).len(), 1);
+        assert!(suggest(&records[..1], now, 3).is_empty());
+    }
+
+    #[test]
+    fn test_suggest_goes_by_start() {
+        // Monday 9:00; a 2h block started at 9 finishes at 11, outside the window
+        let now = Local.with_ymd_and_hms(2024, 3, 11, 9, 0, 0).unwrap();
+        let day = chrono::Duration::days(1);
+        let records = [
+            timer(now - day + chrono::Duration::hours(2), 120, Some("deep work")),
+            timer(now - day * 2 + chrono::Duration::hours(2), 120, Some("deep work")),
+            // Finished just now, but started at 7
+            timer(now - day, 120, Some("early")),
+            timer(now - day * 2, 120, Some("early")),
+        ];
+        let labels: Vec<_> = suggest(&records, now, 3).into_iter().map(|s| s.label).collect();
+        assert_eq!(labels, [Some("deep work".to_string())]);
+    }
+
+    #[test]
+    fn test_window_wraps_midnight() {
+        let now = Local.with_ymd_and_hms(2024, 3, 11, 0, 20, 0).unwrap();
+        let late = Local.with_ymd_and_hms(2024, 3, 9, 23, 50, 0).unwrap();
+        let records = [timer(late, 20, None), timer(late - chrono::Duration::days(1), 20, None)];
+        assert_eq!(suggest(&records, now, 3).len(), 1);
+    }
+
+    #[test]
+    fn test_streaks() {
+        let day = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        assert_eq!(streaks(vec![], day(10)), (0, 0));
+        // Out of order and repeated; 3-5 is the longest, 9-10 runs up to today
+        let days = vec![day(10), day(4), day(3), day(9), day(5), day(4), day(1)];
+        assert_eq!(streaks(days.clone(), day(10)), (2, 3));
+        // Today hasn't had a session yet, yesterday's streak still stands
+        assert_eq!(streaks(days.clone(), day(11)), (2, 3));
+        // A whole day without one breaks it
+        assert_eq!(streaks(days, day(12)), (0, 3));
+        // Across a month boundary
+        let days = vec![NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(), day(1)];
+        assert_eq!(streaks(days, day(1)), (2, 2));
+    }
+
+    #[test]
+    fn test_stats() {
+        let at = |d: u32, h: u32, m: u32| Local.with_ymd_and_hms(2024, 3, d, h, m, 0).unwrap();
+        let records = vec![
+            // Either side of midnight: two days, not one
+            timer(at(8, 23, 59), 25, Some("focus")),
+            timer(at(9, 0, 1), 50, Some("focus")),
+            Record { outcome: Outcome::Cancelled, ..timer(at(9, 12, 0), 25, Some("focus")) },
+            timer(at(10, 9, 0), 30, Some("focus")),
+            // Other labels, alarms and cancellations don't count toward it
+            timer(at(7, 9, 0), 90, Some("tea")),
+            Record { kind: EntryKind::Alarm, ..timer(at(7, 9, 0), 90, Some("focus")) },
+        ];
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+
+        let focus = stats(&records, Some("focus"), today);
+        assert_eq!(
+            focus,
+            Stats {
+                completed: 3,
+                cancelled: 1,
+                average: Some(Duration::from_secs(35 * 60)),
+                longest: Some(Duration::from_secs(50 * 60)),
+                current_streak: 3,
+                longest_streak: 3,
+            }
+        );
+        assert_eq!(focus.completion_rate(), Some(0.75));
+
+        let all = stats(&records, None, today);
+        assert_eq!((all.completed, all.longest_streak), (4, 4));
+        let none = stats(&records, Some("nap"), today);
+        assert_eq!(none, Stats::default());
+        assert_eq!(none.completion_rate(), None);
+    }
+
+    #[test]
+    fn test_daemon_timers_count_toward_stats() {
+        let at = Local.with_ymd_and_hms(2024, 3, 8, 9, 0, 0).unwrap();
+        let entry = Entry {
+            id: 3,
+            kind: EntryKind::Timer,
+            label: Some("bg".into()),
+            tag: None,
+            message: None,
+            due: at,
+            repeat: Vec::new(),
+            exec: None,
+            quiet_hours: None,
+            skip_until: None,
+            imported: None,
+            total_ms: Some(25 * 60_000),
+            progress: None,
+        };
+        let records = vec![
+            Record::for_entry(&entry, Outcome::Finished, at),
+            Record::for_entry(&entry, Outcome::Cancelled, at + chrono::Duration::hours(1)),
+        ];
+        let bg = stats(&records, Some("bg"), at.date_naive());
+        assert_eq!((bg.completed, bg.cancelled, bg.current_streak), (1, 1, 1));
+        assert_eq!(bg.longest, Some(Duration::from_secs(25 * 60)));
+    }
+
+    #[test]
+    fn test_read_tail_starts_on_a_line() {
+        let path = std::env::temp_dir().join(format!("clockeroo-history-tail-{}", std::process::id()));
+        fs::write(&path, "first line\nsecond\nthird\n").unwrap();
+        assert_eq!(read_tail(&path, 1024).unwrap(), "first line\nsecond\nthird\n");
+        assert_eq!(read_tail(&path, 10).unwrap(), "third\n");
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::{Context, Result};
+use std::process::{Command, Stdio};
+
+fn shell(command: &str, vars: &[(&str, String)]) -> Command {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+
+    cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    for (key, value) in vars {
+        cmd.env(key, value);
+    }
+    cmd
+}
+
+/// Runs a user hook through the shell without waiting for it. Context is
+/// passed as `CLOCKEROO_*` environment variables.
+pub fn spawn(command: &str, vars: &[(&str, String)]) {
+    if let Ok(mut child) = shell(command, vars).spawn() {
+        // Reap the child in the background so long-running processes don't collect zombies
+        std::thread::spawn(move || {
+            let _ = child.wait();
+        });
+    }
+}
+
+/// Runs a user hook through the shell and waits for it, failing if it exits
+/// non-zero. The first line of its stderr, if any, goes into the error.
+pub fn run(command: &str, vars: &[(&str, String)]) -> Result<()> {
+    let output = shell(command, vars)
+        .stderr(Stdio::piped())
+        .output()
+        .with_context(|| format!("Failed to start '{}'", command))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stderr.lines().next().filter(|line| !line.trim().is_empty()) {
+        Some(line) => anyhow::bail!("{} ({})", output.status, line.trim()),
+        None => anyhow::bail!("{}", output.status),
+    }
+}
+
+/// Moments a timer or alarm runs its `--on` commands at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Start,
+    Pause,
+    Resume,
+    /// The time left turned yellow (`--warn-at`)
+    Warn,
+    /// The time left turned red (`--critical-at`)
+    Critical,
+    /// Reached zero, or an alarm went off
+    Finish,
+    /// Started counting past zero (`--overtime`)
+    OvertimeStart,
+    /// Stopped before finishing: quit, `POST /stop`, Ctrl-C or a signal
+    Cancel,
+    /// Closed after finishing
+    Dismiss,
+}
+
+/// Everything a timer goes through.
+const TIMER_EVENTS: &[Event] = &[
+    Event::Start,
+    Event::Pause,
+    Event::Resume,
+    Event::Warn,
+    Event::Critical,
+    Event::Finish,
+    Event::OvertimeStart,
+    Event::Cancel,
+    Event::Dismiss,
+];
+
+/// An alarm has no pause, thresholds or overtime.
+const ALARM_EVENTS: &[Event] = &[Event::Start, Event::Finish, Event::Cancel, Event::Dismiss];
+
+impl Event {
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Start => "start",
+            Event::Pause => "pause",
+            Event::Resume => "resume",
+            Event::Warn => "warn-threshold",
+            Event::Critical => "critical-threshold",
+            Event::Finish => "finish",
+            Event::OvertimeStart => "overtime-start",
+            Event::Cancel => "cancel",
+            Event::Dismiss => "dismiss",
+        }
+    }
+}
+
+/// One `--on EVENT=COMMAND`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventHook {
+    pub event: Event,
+    pub command: String,
+}
+
+/// Parses a timer's `--on`, so a misspelled event fails before anything starts.
+pub fn parse_timer_hook(s: &str) -> Result<EventHook, String> {
+    parse_event_hook(s, TIMER_EVENTS)
+}
+
+/// Parses an alarm's `--on`, turning away the timer events an alarm never has.
+pub fn parse_alarm_hook(s: &str) -> Result<EventHook, String> {
+    parse_event_hook(s, ALARM_EVENTS).map_err(|err| {
+        let name = s.split('=').next().unwrap_or_default().trim();
+        let named = |events: &[Event]| events.iter().any(|event| event.name() == name);
+        match named(TIMER_EVENTS) && !named(ALARM_EVENTS) {
+            true => format!("'{}' is a timer event, alarms have: {}", name, event_names(ALARM_EVENTS)),
+            false => err,
+        }
+    })
+}
+
+fn event_names(events: &[Event]) -> String {
+    events.iter().map(|event| event.name()).collect::<Vec<_>>().join(", ")
+}
+
+fn parse_event_hook(s: &str, events: &[Event]) -> Result<EventHook, String> {
+    let (name, command) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected EVENT=COMMAND, where EVENT is one of: {}", event_names(events)))?;
+    let event = events
+        .iter()
+        .copied()
+        .find(|event| event.name() == name.trim())
+        .ok_or_else(|| format!("unknown event '{}' (expected one of: {})", name.trim(), event_names(events)))?;
+    if command.trim().is_empty() {
+        return Err(format!("no command given for '{}'", event.name()));
+    }
+    Ok(EventHook { event, command: command.to_string() })
+}
+
+/// The `--on` commands of one timer or alarm.
+#[derive(Debug, Clone, Default)]
+pub struct EventHooks {
+    hooks: Vec<EventHook>,
+}
+
+impl EventHooks {
+    pub fn new(hooks: Vec<EventHook>) -> EventHooks {
+        EventHooks { hooks }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Starts every command registered for `event`, detached so a slow one
+    /// can't hold up the screen. They get `CLOCKEROO_EVENT` on top of `vars`.
+    pub fn fire(&self, event: Event, vars: &[(&str, String)]) {
+        let mut vars = vars.to_vec();
+        vars.push(("CLOCKEROO_EVENT", event.name().to_string()));
+        for hook in self.hooks.iter().filter(|hook| hook.event == event) {
+            spawn(&hook.command, &vars);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_event_hook() {
+        let hook = parse_timer_hook("critical-threshold=curl -X POST http://bulb/red?a=b").unwrap();
+        assert_eq!(hook.event, Event::Critical);
+        assert_eq!(hook.command, "curl -X POST http://bulb/red?a=b");
+        assert_eq!(parse_timer_hook("overtime-start=true").unwrap().event, Event::OvertimeStart);
+
+        let err = parse_timer_hook("finsh=true").unwrap_err();
+        assert!(err.starts_with("unknown event 'finsh' (expected one of: start, pause,"), "{}", err);
+        assert!(parse_timer_hook("finish").is_err());
+        assert!(parse_timer_hook("finish= ").is_err());
+        // Every event can be named back
+        for event in TIMER_EVENTS {
+            assert_eq!(parse_timer_hook(&format!("{}=true", event.name())).unwrap().event, *event);
+        }
+    }
+
+    #[test]
+    fn test_parse_alarm_hook() {
+        for event in ALARM_EVENTS {
+            assert_eq!(parse_alarm_hook(&format!("{}=true", event.name())).unwrap().event, *event);
+        }
+        // Accepted for a timer, but an alarm would never run it
+        let err = parse_alarm_hook("pause=true").unwrap_err();
+        assert_eq!(err, "'pause' is a timer event, alarms have: start, finish, cancel, dismiss");
+        assert!(parse_alarm_hook("critical-threshold=true").is_err());
+        let err = parse_alarm_hook("ring=true").unwrap_err();
+        assert_eq!(err, "unknown event 'ring' (expected one of: start, finish, cancel, dismiss)");
+        assert_eq!(parse_alarm_hook("finish= ").unwrap_err(), "no command given for 'finish'");
+    }
+
+    #[test]
+    fn test_run_reports_failure() {
+        assert!(run("true", &[]).is_ok());
+        assert!(run("test \"$CLOCKEROO_LABEL\" = tea", &[("CLOCKEROO_LABEL", "tea".into())]).is_ok());
+        let err = run("echo nope >&2; exit 3", &[]).unwrap_err().to_string();
+        assert!(err.contains('3') && err.contains("nope"), "{}", err);
+    }
+}
@@ -0,0 +1,320 @@
+use anyhow::{Context, Result};
+use chrono::{Local, TimeZone};
+use serde_json::json;
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+    time::timeout,
+};
+
+use crate::{status::ClockStatus, unix_millis};
+
+/// Where `--http` listens when it's given without an address.
+pub const DEFAULT_ADDR: &str = "127.0.0.1:7878";
+
+/// Longest request head read, anything bigger is turned away.
+const MAX_REQUEST: usize = 8 * 1024;
+
+/// How long a client gets to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// `--http` value: "127.0.0.1:7878", "[::1]:7878", or just a port, which
+/// binds on loopback.
+pub fn parse_addr(s: &str) -> Result<SocketAddr> {
+    if let Ok(port) = s.parse::<u16>() {
+        return Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+    }
+    s.parse().with_context(|| format!("Invalid --http address '{}', expected something like {}", s, DEFAULT_ADDR))
+}
+
+/// A reply to one request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply {
+    pub code: u16,
+    pub body: String,
+    /// `POST /stop` was accepted
+    pub stop: bool,
+    /// The one method a 405's path takes, for its `Allow` header
+    pub allow: Option<&'static str>,
+}
+
+impl Reply {
+    fn json(code: u16, body: serde_json::Value) -> Reply {
+        Reply { code, body: body.to_string(), stop: false, allow: None }
+    }
+
+    fn error(code: u16, message: &str) -> Reply {
+        Reply::json(code, json!({ "error": message }))
+    }
+}
+
+/// Answers `GET /status` and `POST /stop`; everything else is a 404, or a 405
+/// for a known path with the wrong method.
+pub fn respond(method: &str, path: &str, status: Option<&ClockStatus>, now_ms: u64) -> Reply {
+    // Query strings don't change anything
+    let path = path.split('?').next().unwrap_or_default();
+    match (method, path) {
+        ("GET", "/status") => match status {
+            Some(status) => Reply::json(200, status_json(status, now_ms)),
+            None => Reply::error(503, "starting up"),
+        },
+        ("POST", "/stop") => Reply { stop: true, ..Reply::json(200, json!({ "ok": true })) },
+        (_, "/status") => Reply { allow: Some("GET"), ..Reply::error(405, "method not allowed") },
+        (_, "/stop") => Reply { allow: Some("POST"), ..Reply::error(405, "method not allowed") },
+        _ => Reply::error(404, "not found"),
+    }
+}
+
+/// Answers a whole request head for a listener bound to `bound`. Anything a
+/// browser sends is turned away: a page on another site can `POST /stop`
+/// without asking first and carries an `Origin`, and one reaching us through
+/// DNS rebinding has its own name in `Host`.
+pub fn handle(head: &str, bound: SocketAddr, status: Option<&ClockStatus>, now_ms: u64) -> Reply {
+    let Some((method, path)) = parse_request_line(head) else {
+        return Reply::error(400, "bad request");
+    };
+    if header(head, "Origin").is_some() {
+        return Reply::error(403, "cross-origin requests aren't allowed");
+    }
+    if !header(head, "Host").is_some_and(|host| host_matches(host, bound)) {
+        return Reply::error(403, "Host must be the address this listens on");
+    }
+    respond(method, path, status, now_ms)
+}
+
+/// The value of header `name` in a request head, if it's there.
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// Whether `host` names the listener: its address and port, or `localhost` when
+/// it's on loopback. Bound to every interface, any address with the port will do,
+/// but never a name other than `localhost`.
+fn host_matches(host: &str, bound: SocketAddr) -> bool {
+    if let Ok(addr) = host.parse::<SocketAddr>() {
+        return addr.port() == bound.port() && (bound.ip().is_unspecified() || addr.ip() == bound.ip());
+    }
+    (bound.ip().is_loopback() || bound.ip().is_unspecified()) && host.eq_ignore_ascii_case(&format!("localhost:{}", bound.port()))
+}
+
+/// The `GET /status` body. Seconds are whole; `ends_at` is local RFC 3339.
+pub fn status_json(status: &ClockStatus, now_ms: u64) -> serde_json::Value {
+    let ends_at = status
+        .ends_at
+        .and_then(|ms| Local.timestamp_millis_opt(ms as i64).single())
+        .map(|at| at.to_rfc3339());
+    json!({
+        "mode": status.kind.name(),
+        "label": status.label,
+        "paused": status.paused,
+        "elapsed_secs": status.elapsed(now_ms).as_secs(),
+        "total_secs": status.total_ms.map(|total_ms| total_ms / 1000),
+        "remaining_secs": status.remaining(now_ms).map(|remaining| remaining.as_secs()),
+        "ends_at": ends_at,
+    })
+}
+
+/// "GET /status HTTP/1.1" into its method and path.
+pub fn parse_request_line(head: &str) -> Option<(&str, &str)> {
+    let mut parts = head.lines().next()?.split_whitespace();
+    let method = parts.next()?;
+    let path = parts.next()?;
+    parts.next()?.starts_with("HTTP/").then_some((method, path))
+}
+
+fn reason(code: u16) -> &'static str {
+    match code {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Service Unavailable",
+    }
+}
+
+/// Serves `GET /status` and `POST /stop` for the clock running in this
+/// process until dropped.
+pub struct Endpoint {
+    status: Arc<Mutex<Option<ClockStatus>>>,
+    stop: Arc<AtomicBool>,
+    task: JoinHandle<()>,
+}
+
+impl Endpoint {
+    /// Binds `addr` right away, so a taken port fails before any TUI is up.
+    pub fn serve(addr: SocketAddr) -> Result<Endpoint> {
+        let listener = std::net::TcpListener::bind(addr).with_context(|| format!("Failed to listen on {}", addr))?;
+        listener.set_nonblocking(true)?;
+        let bound = listener.local_addr()?;
+        let listener = TcpListener::from_std(listener)?;
+
+        let status = Arc::new(Mutex::new(None));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (task_status, task_stop) = (status.clone(), stop.clone());
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (status, stop) = (task_status.clone(), task_stop.clone());
+                tokio::spawn(async move {
+                    let _ = handle_client(stream, bound, &status, &stop).await;
+                });
+            }
+        });
+        Ok(Endpoint { status, stop, task })
+    }
+
+    /// Makes `status` what the next `GET /status` sees.
+    pub fn publish(&self, status: &ClockStatus) {
+        *self.status.lock().unwrap() = Some(status.clone());
+    }
+
+    /// Whether a client has asked the clock to stop.
+    pub fn stop_requested(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Endpoint {
+    fn drop(&mut self) {
+        // Drops the listener with the task, freeing the port
+        self.task.abort();
+    }
+}
+
+async fn handle_client(mut stream: TcpStream, bound: SocketAddr, status: &Mutex<Option<ClockStatus>>, stop: &AtomicBool) -> Result<()> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    // Only the request head matters, a body (if any) is never read
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = timeout(READ_TIMEOUT, stream.read(&mut buf)).await??;
+        if n == 0 || head.len() + n > MAX_REQUEST {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let status = status.lock().unwrap().clone();
+    let reply = handle(&head, bound, status.as_ref(), unix_millis(SystemTime::now()));
+    if reply.stop {
+        stop.store(true, Ordering::Relaxed);
+    }
+
+    let allow = reply.allow.map(|method| format!("Allow: {}\r\n", method)).unwrap_or_default();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+        reply.code,
+        reason(reply.code),
+        reply.body.len(),
+        allow,
+        reply.body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::ClockKind;
+
+    fn timer() -> ClockStatus {
+        ClockStatus {
+            ends_at: Some(1_272_000),
+            ..ClockStatus::new(ClockKind::Timer, Some("tea".into()), 1_000_000, Duration::from_secs(28))
+        }
+    }
+
+    #[test]
+    fn test_parse_addr() {
+        assert_eq!(parse_addr("7878").unwrap(), DEFAULT_ADDR.parse().unwrap());
+        assert_eq!(parse_addr("0.0.0.0:9000").unwrap(), "0.0.0.0:9000".parse().unwrap());
+        assert!(parse_addr("localhost:x").is_err());
+    }
+
+    #[test]
+    fn test_status() {
+        let reply = respond("GET", "/status", Some(&timer()), 1_000_000);
+        assert_eq!(reply.code, 200);
+        assert!(!reply.stop);
+        let body: serde_json::Value = serde_json::from_str(&reply.body).unwrap();
+        assert_eq!(body["mode"], "timer");
+        assert_eq!(body["label"], "tea");
+        assert_eq!(body["remaining_secs"], 272);
+        assert_eq!(body["elapsed_secs"], 28);
+        assert!(body["ends_at"].is_string());
+
+        // A stopwatch has no end
+        let stopwatch = ClockStatus::new(ClockKind::Stopwatch, None, 1_000_000, Duration::from_secs(90));
+        let body = status_json(&stopwatch, 1_005_000);
+        assert_eq!(body["elapsed_secs"], 95);
+        assert!(body["remaining_secs"].is_null());
+        assert!(body["ends_at"].is_null());
+    }
+
+    #[test]
+    fn test_stop_and_rejected_requests() {
+        let reply = respond("POST", "/stop", Some(&timer()), 1_000_000);
+        assert_eq!((reply.code, reply.stop), (200, true));
+
+        let reply = respond("GET", "/stop", Some(&timer()), 0);
+        assert_eq!((reply.code, reply.allow), (405, Some("POST")));
+        let reply = respond("POST", "/status", Some(&timer()), 0);
+        assert_eq!((reply.code, reply.allow), (405, Some("GET")));
+        assert_eq!(respond("DELETE", "/status", Some(&timer()), 0).allow, Some("GET"));
+        assert_eq!(respond("GET", "/status", Some(&timer()), 0).allow, None);
+        assert!(!respond("GET", "/stop", Some(&timer()), 0).stop);
+        assert_eq!(respond("GET", "/", Some(&timer()), 0).code, 404);
+        assert_eq!(respond("GET", "/status?pretty=1", Some(&timer()), 0).code, 200);
+        assert_eq!(respond("GET", "/status", None, 0).code, 503);
+    }
+
+    #[test]
+    fn test_browser_requests_are_refused() {
+        let bound: SocketAddr = DEFAULT_ADDR.parse().unwrap();
+        let request = |method: &str, headers: &str| format!("{} /stop HTTP/1.1\r\n{}\r\n", method, headers);
+
+        let reply = handle(&request("POST", "Host: 127.0.0.1:7878\r\n"), bound, Some(&timer()), 0);
+        assert_eq!((reply.code, reply.stop), (200, true));
+        assert!(handle(&request("POST", "Host: localhost:7878\r\n"), bound, Some(&timer()), 0).stop);
+
+        // A form on some other site posting across origins
+        let cross_origin = request("POST", "Host: 127.0.0.1:7878\r\norigin: https://evil.example\r\n");
+        let reply = handle(&cross_origin, bound, Some(&timer()), 0);
+        assert_eq!((reply.code, reply.stop), (403, false));
+
+        // DNS rebinding: the page's own name, resolved to loopback
+        let rebound = "GET /status HTTP/1.1\r\nHost: evil.example:7878\r\n\r\n";
+        assert_eq!(handle(rebound, bound, Some(&timer()), 0).code, 403);
+        assert_eq!(handle(&request("POST", "Host: 127.0.0.1:9999\r\n"), bound, Some(&timer()), 0).code, 403);
+        assert_eq!(handle(&request("POST", ""), bound, Some(&timer()), 0).code, 403);
+
+        // Bound to every interface, a client may use any of our addresses
+        let anywhere: SocketAddr = "0.0.0.0:7878".parse().unwrap();
+        assert!(handle(&request("POST", "Host: 192.168.1.20:7878\r\n"), anywhere, Some(&timer()), 0).stop);
+        assert!(handle(&request("POST", "Host: localhost:7878\r\n"), anywhere, Some(&timer()), 0).stop);
+        assert!(!handle(&request("POST", "Host: evil.example:7878\r\n"), anywhere, Some(&timer()), 0).stop);
+    }
+
+    #[test]
+    fn test_parse_request_line() {
+        assert_eq!(parse_request_line("GET /status HTTP/1.1\r\nHost: x\r\n\r\n"), Some(("GET", "/status")));
+        assert_eq!(parse_request_line("GET /status"), None);
+        assert_eq!(parse_request_line(""), None);
+    }
+}
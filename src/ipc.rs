@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    time::Duration,
+};
+
+use crate::store::{Duplicates, Entry, ImportReport, Selector};
+
+/// One JSON line sent to the daemon socket.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+pub enum Request {
+    Status,
+    Add {
+        entry: Box<Entry>,
+        /// What to do about a timer already scheduled under the same label
+        #[serde(default)]
+        duplicates: Duplicates,
+    },
+    Cancel { id: u64 },
+    /// Cancel the background timers `which` picks, replying with them
+    CancelMatching { which: Selector },
+    /// Skip the next ring of a repeating alarm, or every one through `until`
+    Skip {
+        id: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        until: Option<NaiveDate>,
+    },
+    Unskip { id: u64 },
+    /// Merge the alarms `alarm import` read from `source`
+    Import {
+        source: PathBuf,
+        entries: Vec<Entry>,
+        #[serde(default)]
+        sync: bool,
+    },
+}
+
+/// One JSON line sent back by the daemon.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<Entry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub import: Option<ImportReport>,
+}
+
+impl Response {
+    pub fn error(message: impl Into<String>) -> Response {
+        Response {
+            ok: false,
+            error: Some(message.into()),
+            ..Default::default()
+        }
+    }
+}
+
+pub fn socket_path() -> PathBuf {
+    crate::runtime_dir().join("clockeroo.sock")
+}
+
+/// Sends a request to the running daemon and waits for its reply.
+pub fn send(request: &Request) -> Result<Response> {
+    send_with_timeout(request, Duration::from_secs(2))
+}
+
+pub fn send_with_timeout(request: &Request, timeout: Duration) -> Result<Response> {
+    let mut stream = UnixStream::connect(socket_path())
+        .context("No clockeroo daemon is running. Start one with: clockeroo daemon")?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    let response: Response = serde_json::from_str(&reply).context("Invalid reply from daemon")?;
+
+    if !response.ok {
+        anyhow::bail!("{}", response.error.unwrap_or_else(|| "Daemon request failed".to_string()));
+    }
+    Ok(response)
+}
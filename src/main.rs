@@ -1,28 +1,64 @@
-use anyhow::Result;
-use chrono::{Local, NaiveTime, TimeZone};
-use clap::{Parser, Subcommand};
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use notify_rust::Notification;
-use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
-    Terminal,
-};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use clap::{Args, Parser, Subcommand};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::style::Color;
 use std::{
     fs,
-    io::{self, Write},
-    path::PathBuf,
-    time::{Duration, Instant},
+    io::{self, IsTerminal},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tokio::time::sleep;
 
+#[cfg(unix)]
+mod alarm_import;
+mod alarm_watch;
+mod appearance;
+mod artifacts;
+mod batch;
+mod clock;
+mod config;
+#[cfg(unix)]
+mod daemon;
+mod dnd;
+mod export;
+mod focus;
+mod history;
+mod hooks;
+mod http;
+#[cfg(unix)]
+mod ipc;
+mod measure;
+mod milestones;
+mod notify;
+mod output;
+mod parallel;
+mod picker;
+mod plan;
+mod pomodoro;
+mod queue;
+mod quiet_hours;
+mod saved_alarms;
+mod sound;
+mod statefile;
+mod status;
+mod store;
+mod teardown;
+mod time_format;
+mod ui;
+mod zones;
+
+use export::ExportArgs;
+use notify::send_notification;
+use output::{info, verbose};
+use quiet_hours::{is_quiet_now, QuietHours};
+use time_format::{format_clock, format_clock_secs};
+use serde::{Deserialize, Serialize};
+use sound::{play_bell, play_event_sound, spawn_chime, SoundEvent, SoundOptions};
+
 const ASCII_HEADER: &str = r#"      _            _                        
   ___| | ___   ___| | _____ _ __ ___   ___  
  / __| |/ _ \ / __| |/ / _ \ '__/ _ \ / _ \ 
@@ -33,16 +69,279 @@ const ASCII_HEADER: &str = r#"      _            _
 #[command(name = "clockeroo")]
 #[command(about = "A simple TUI timer/stopwatch/alarm app", long_about = None)]
 struct Cli {
+    /// Only print errors and the final result line
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Only ring the terminal bell, never open an audio device (same as --bell terminal)
+    #[arg(long, global = true, conflicts_with = "bell")]
+    bell_only: bool,
+
+    /// Where bells and beeps go out: the terminal's BEL, the audio device, both or neither
+    #[arg(long, global = true, value_enum)]
+    bell: Option<sound::Bell>,
+
+    /// Use a named sound theme from the config file
+    #[arg(long, global = true)]
+    sound_theme: Option<String>,
+
+    /// Play sounds on this output device (a name or part of one, see `clockeroo devices`)
+    #[arg(long, global = true, value_name = "NAME")]
+    sound_device: Option<String>,
+
+    /// How long beeps take to swell in (default 50ms)
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_duration_or_zero)]
+    beep_fade_in: Option<Duration>,
+
+    /// How long beeps take to die away (default 50ms)
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_duration_or_zero)]
+    beep_fade_out: Option<Duration>,
+
+    /// The shape of the built-in beep's wave
+    #[arg(long, global = true, value_enum, default_value_t)]
+    waveform: sound::Waveform,
+
+    /// Hide the activity spinner next to the heading
+    #[arg(long, global = true)]
+    no_spinner: bool,
+
+    /// Show clock times in 12-hour format (e.g., 2:05 PM)
+    #[arg(long = "12h", global = true, conflicts_with = "twenty_four_hour")]
+    twelve_hour: bool,
+
+    /// Show clock times in 24-hour format (e.g., 14:05)
+    #[arg(long = "24h", global = true)]
+    twenty_four_hour: bool,
+
+    /// Also flash the whole terminal when a timer or alarm goes off
+    #[arg(long, global = true)]
+    visual_bell: bool,
+
+    /// Hide the key help footer for a cleaner look
+    #[arg(long, global = true)]
+    minimal_help: bool,
+
+    /// Draw clickable Pause/Stop/Quit buttons on the timer and stopwatch screens
+    #[arg(long, global = true)]
+    mouse: bool,
+
+    /// Don't print the banner before starting (screens still show it)
+    #[arg(long, global = true)]
+    quiet_start: bool,
+
+    /// Where screens put their content inside the border
+    #[arg(long, global = true, value_enum, default_value_t = ui::Align::Top)]
+    align: ui::Align,
+
+    /// Colors for a light or dark terminal background; auto asks the terminal
+    #[arg(long, global = true, value_enum, default_value_t = appearance::Appearance::Auto)]
+    appearance: appearance::Appearance,
+
+    /// Blank cells between the terminal edge and a screen's border
+    #[arg(long, global = true, value_name = "N")]
+    margin: Option<u16>,
+
+    /// Color of a timer's label line, apart from the accent and border (a name like magenta, or #ff8800)
+    #[arg(long, global = true, value_name = "COLOR", value_parser = parse_color)]
+    label_color: Option<Color>,
+
+    /// Print diagnostics (like the Do Not Disturb check) to stderr
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Mute every sound while Do Not Disturb is on, alarms included
+    #[arg(long, global = true, conflicts_with = "ignore_dnd")]
+    respect_dnd: bool,
+
+    /// Play sounds even while Do Not Disturb is on
+    #[arg(long, global = true)]
+    ignore_dnd: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// How an alarm runs, for `alarm <time>` and `alarm start <name>` alike.
+#[derive(Args)]
+struct AlarmArgs {
+    /// Fail instead of rolling over to tomorrow if the time already passed today
+    #[arg(long)]
+    today_only: bool,
+    /// Arm the alarm only at this clock time (e.g., 11:00pm); it rings at the first TIME after that
+    #[arg(long, value_name = "TIME", conflicts_with = "today_only")]
+    start_at: Option<String>,
+    /// Stop ringing after this long without a dismissal and exit with code 3 (e.g., 5m)
+    #[arg(long)]
+    max_ring: Option<String>,
+    /// Allow at most N snoozes ('s'), after which the alarm rings to be dismissed (default: unlimited)
+    #[arg(long, value_name = "N")]
+    snooze_max: Option<u32>,
+    /// Skip the beep and notification if it rings inside this window (e.g., 22:00-07:00)
+    #[arg(long, value_name = "START-END")]
+    quiet_hours: Option<String>,
+    /// Read a time without am/pm as 24-hour, so 7:20 is 7:20 AM (the default)
+    #[arg(long, conflicts_with = "input_12h")]
+    input_24h: bool,
+    /// Read a time without am/pm as 12-hour in the afternoon, so 7:20 is 7:20 PM
+    #[arg(long)]
+    input_12h: bool,
+    /// Hand the alarm to the daemon (starting it if needed) and exit
+    #[arg(long)]
+    background: bool,
+    /// Serve GET /status and POST /stop on this address (a bare port listens on 127.0.0.1)
+    #[arg(long, value_name = "ADDR", value_parser = http::parse_addr)]
+    http: Option<SocketAddr>,
+    /// Ring gently first: a notification, then repeated beeps, then flashing the screen
+    #[arg(long)]
+    escalate: bool,
+    /// Time between --escalate stages (default 30s)
+    #[arg(long, value_name = "DURATION", requires = "escalate")]
+    escalate_step: Option<String>,
+    /// Start ringing quiet and rise to full volume over this long (e.g., 60s)
+    #[arg(long, value_name = "DURATION")]
+    fade_in: Option<String>,
+    /// Ring once and exit instead of ringing until dismissed
+    #[arg(long, conflicts_with_all = ["max_ring", "escalate", "fade_in", "snooze_max"])]
+    no_finish_screen: bool,
+    /// Once dismissed, go straight into a timer for this long (e.g., 15m)
+    #[arg(long, value_name = "DURATION", conflicts_with_all = ["no_finish_screen", "background"])]
+    then_timer: Option<String>,
+    /// Label for the --then-timer countdown
+    #[arg(long, value_name = "LABEL", requires = "then_timer")]
+    then_label: Option<String>,
+    /// Print when it would be armed and ring, then exit without setting it
+    #[arg(long)]
+    dry_run: bool,
+    /// Print the --dry-run plan as text or JSON
+    #[arg(long, value_enum, default_value_t = measure::Format::Text, requires = "dry_run")]
+    format: measure::Format,
+    /// Run a command when something happens to the alarm, as EVENT=COMMAND (repeatable)
+    ///
+    /// Events: start (armed), finish (rings), cancel (quit, Ctrl-C or POST /stop before
+    /// it rings) and dismiss. Commands run detached through the shell with
+    /// CLOCKEROO_EVENT, CLOCKEROO_KIND, CLOCKEROO_LABEL and CLOCKEROO_REMAINING (whole
+    /// seconds) set.
+    #[arg(long = "on", value_name = "EVENT=COMMAND", value_parser = hooks::parse_alarm_hook)]
+    on: Vec<hooks::EventHook>,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Set a countdown timer (e.g., "120s", "5m", "2h")
     Timer {
-        /// Duration in format: 120s, 5m, 2h, or combinations like 1h30m
-        duration: String,
+        /// Duration in format: 120s, 5m, 1.5m, 2h, combinations like 1h30m, words like 5min or 2hrs, or ISO 8601 (PT1H30M)
+        duration: Option<String>,
+        /// Use a named preset from the config file (a given duration still wins)
+        #[arg(long)]
+        preset: Option<String>,
+        /// Wait this long before the countdown begins (e.g., 5s)
+        #[arg(long)]
+        start_in: Option<String>,
+        /// Wait until this clock time before starting (e.g., 2:00pm), tomorrow if it has passed
+        #[arg(long, value_name = "TIME", conflicts_with = "start_in")]
+        start_at: Option<String>,
+        /// Beep once when the countdown actually starts
+        #[arg(long)]
+        start_beep: bool,
+        /// Count down N seconds with a beep each second before the timer starts
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        count_in: u32,
+        /// Label shown in the UI and notification
+        #[arg(long)]
+        label: Option<String>,
+        /// Short tag (e.g., an emoji) shown before the label; defaults to the label's tag in the config
+        #[arg(long)]
+        tag: Option<String>,
+        /// Shell command to run when the timer finishes (also works with --detach)
+        #[arg(long)]
+        exec: Option<String>,
+        /// Run a command when something happens to the timer, as EVENT=COMMAND (repeatable)
+        ///
+        /// Events: start, pause, resume, warn-threshold, critical-threshold, finish,
+        /// overtime-start, cancel (quit, Ctrl-C or POST /stop before the end) and dismiss
+        /// (closed after finishing). Commands run detached through the shell with
+        /// CLOCKEROO_EVENT, CLOCKEROO_KIND, CLOCKEROO_LABEL and CLOCKEROO_REMAINING (whole
+        /// seconds) set.
+        #[arg(long = "on", value_name = "EVENT=COMMAND", value_parser = hooks::parse_timer_hook)]
+        on: Vec<hooks::EventHook>,
+        /// Skip the beep and notification if it finishes inside this window (e.g., 22:00-07:00)
+        #[arg(long, value_name = "START-END")]
+        quiet_hours: Option<String>,
+        /// Notification urgency; critical timers also sound during Do Not Disturb
+        #[arg(long, value_enum, default_value_t = notify::Urgency::Normal)]
+        urgency: notify::Urgency,
+        /// Hand the timer to the daemon and return immediately
+        #[arg(long, conflicts_with_all = [
+            "overtime", "warn_at_percent", "warn_at", "critical_at", "critical_beep", "start_beep", "no_finish_screen",
+        ])]
+        detach: bool,
+        /// With --detach, cancel a background timer with the same label and start over
+        #[arg(long, requires = "detach", conflicts_with = "allow_duplicate")]
+        replace: bool,
+        /// With --detach, start even if a background timer with the same label is running
+        #[arg(long, requires = "detach")]
+        allow_duplicate: bool,
+        /// Print when the timer would finish and exit without starting it
+        #[arg(long)]
+        estimate: bool,
+        /// Print when it would start, reach each milestone and ring (and ring again with --every), then exit without starting it
+        #[arg(long, conflicts_with_all = ["estimate", "render_once", "confirm"])]
+        dry_run: bool,
+        /// Print the --dry-run plan as text or JSON
+        #[arg(long, value_enum, default_value_t = measure::Format::Text, requires = "dry_run")]
+        format: measure::Format,
+        /// Print the first frame of the timer screen as plain text and exit
+        #[arg(long)]
+        render_once: bool,
+        /// Keep counting up past zero (+00:42) until dismissed
+        #[arg(long)]
+        overtime: bool,
+        /// Warn once when this percentage of the time has been used (e.g., 80)
+        #[arg(long, value_name = "P", value_parser = clap::value_parser!(u8).range(1..100))]
+        warn_at_percent: Option<u8>,
+        /// Turn the time yellow with this much left, as a duration or a share (e.g., 10m or 10%)
+        #[arg(long, value_name = "LEFT")]
+        warn_at: Option<String>,
+        /// Turn the time red with this much left, as a duration or a share (e.g., 2m or 2%)
+        #[arg(long, value_name = "LEFT")]
+        critical_at: Option<String>,
+        /// Beep once when the time left turns red
+        #[arg(long)]
+        critical_beep: bool,
+        /// Start the timer again each time it finishes, until quit
+        #[arg(long, conflicts_with = "overtime")]
+        every: bool,
+        /// With --every, stop restarting once this clock time has passed (e.g., 5pm)
+        #[arg(long, value_name = "TIME", requires = "every")]
+        repeat_until: Option<String>,
+        /// Serve GET /status and POST /stop on this address (a bare port listens on 127.0.0.1)
+        #[arg(long, value_name = "ADDR", value_parser = http::parse_addr)]
+        http: Option<SocketAddr>,
+        /// Keep one notification showing the time left, updated every minute (freedesktop only)
+        #[arg(long)]
+        progress_notification: bool,
+        /// Click quietly on every second of the final stretch (e.g., 30s)
+        #[arg(long, value_name = "DURATION")]
+        tick: Option<String>,
+        /// Draw the time left as a dial that empties as it runs, with the digits below
+        #[arg(long)]
+        clock_face: bool,
+        /// Show the duration before starting, to check it and adjust it with the arrow keys
+        #[arg(long)]
+        confirm: bool,
+        /// Exit as soon as the timer has rung instead of waiting on the finished screen
+        #[arg(long, conflicts_with = "overtime")]
+        no_finish_screen: bool,
+        /// More timers to run alongside the first with --parallel
+        #[arg(value_name = "DURATION[:LABEL]")]
+        more: Vec<String>,
+        /// Run every timer given as DURATION[:LABEL] at once on one screen (e.g., 8m:pasta 12m:sauce --parallel)
+        #[arg(long, requires = "duration", conflicts_with_all = [
+            "preset", "start_in", "start_at", "start_beep", "count_in", "label", "tag", "exec", "on", "detach",
+            "estimate", "dry_run", "render_once", "overtime", "warn_at_percent", "critical_beep", "every", "http",
+            "progress_notification", "tick", "clock_face", "confirm", "no_finish_screen",
+        ])]
+        parallel: bool,
     },
     /// Control a stopwatch
     Stopwatch {
@@ -50,59 +349,490 @@ enum Commands {
         action: StopwatchAction,
     },
     /// Set an alarm for a specific time (e.g., "7:20am", "19:20", "7:20pm")
+    #[command(args_conflicts_with_subcommands = true)]
     Alarm {
-        /// Time in format: 7:20am, 19:20, 7:20pm
+        #[command(subcommand)]
+        action: Option<AlarmAction>,
+        /// Time in format: 7:20am, 19:20, 7:20pm, phrases like "half past 7", or @ and Unix seconds (@1735689600)
+        time: Option<String>,
+        #[command(flatten)]
+        args: AlarmArgs,
+    },
+    /// Run the background daemon that owns alarms and detached timers
+    Daemon {
+        /// Stay attached to the terminal (for systemd units and debugging)
+        #[arg(long)]
+        foreground: bool,
+    },
+    /// Alternate work and break phases (defaults from the `pomodoro` config section)
+    Pomodoro {
+        /// Length of each work phase (e.g., 25m)
+        #[arg(long)]
+        work: Option<String>,
+        /// Length of the short breaks
+        #[arg(long)]
+        short_break: Option<String>,
+        /// Length of the long breaks
+        #[arg(long)]
+        long_break: Option<String>,
+        /// Number of work rounds
+        #[arg(long)]
+        rounds: Option<u32>,
+    },
+    /// A timer that runs the `focus` config section's on_start and on_end hooks around it
+    Focus {
+        /// Length of the session (e.g., 50m)
+        duration: String,
+        /// Label shown in the UI and passed to hooks as CLOCKEROO_LABEL
+        #[arg(long, default_value = "Focus")]
+        label: String,
+    },
+    /// Run a list of timed steps one after another and report how long each really took
+    Batch {
+        /// One step per line: a duration, then a label (e.g., "90s boil water")
+        #[arg(long, value_name = "FILE")]
+        input_file: PathBuf,
+    },
+    /// Queue timers from any terminal and run them in order in one
+    Queue {
+        #[command(subcommand)]
+        action: QueueAction,
+    },
+    /// List the timers you usually run around this time of day
+    Suggest,
+    /// Completed and cancelled sessions, average and longest length, and daily streaks from the history log
+    Stats {
+        /// Only timers with this label
+        #[arg(long)]
+        label: Option<String>,
+        /// Report as text or JSON
+        #[arg(long, value_enum, default_value_t = measure::Format::Text)]
+        format: measure::Format,
+    },
+    /// Print the time left on the soonest running timer (for shell prompts)
+    Remaining {
+        /// Output template with {label} and {remaining}
+        #[arg(long, default_value = "{remaining}")]
+        format: String,
+    },
+    /// Print how long it is from one clock time to the next (e.g., --from 22:30 --to 6:15am)
+    Between {
+        #[arg(long, value_name = "TIME", value_parser = parse_alarm_time)]
+        from: NaiveTime,
+        /// Taken as the next day if it's earlier than --from
+        #[arg(long, value_name = "TIME", value_parser = parse_alarm_time)]
+        to: NaiveTime,
+    },
+    /// Run a command and report how long it took: wall, user and system time
+    Time {
+        /// Report as text or JSON; either goes to stderr, the command keeps stdout
+        #[arg(long, value_enum, default_value_t = measure::Format::Text)]
+        format: measure::Format,
+        /// Sub-second digits: s, ds, cs, ms or us
+        #[arg(long, value_enum, default_value_t = Precision::Ms)]
+        precision: Precision,
+        /// The command and its arguments (e.g., clockeroo time -- make test)
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+    /// List files crashed runs left behind (status files, daemon pid and socket, finished queues) and remove them
+    Clean {
+        /// Remove them without asking
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Live table of every running timer, stopwatch, alarm and pomodoro
+    Dashboard,
+    /// List audio output devices for --sound-device
+    Devices,
+    /// Play the alarm sound, to check the device and volume
+    TestSound {
+        /// Ring for this long, rising from quiet to full volume as an alarm with --fade-in would
+        #[arg(long, value_name = "DURATION")]
+        fade_in: Option<String>,
+    },
+    /// Run a 10-second timer without a screen and report how late it finished
+    #[command(hide = true)]
+    Selftest,
+    /// Show where the config file is read from
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+        /// Print every setting as it's in effect (flags over config file over defaults) as JSON
+        #[arg(long)]
+        dump: bool,
+    },
+    /// List alarms and timers held by the daemon
+    Status,
+    /// Cancel an alarm or timer held by the daemon
+    Cancel {
+        /// Id shown by `clockeroo status`
+        #[arg(required_unless_present_any = ["label", "all", "last"], conflicts_with_all = ["label", "all", "last"])]
+        id: Option<u64>,
+        /// The background timer with this label; if several have it, add --all to cancel them all
+        #[arg(long, conflicts_with = "last")]
+        label: Option<String>,
+        /// Every background timer, or with --label every one with that label (alarms stay)
+        #[arg(long, conflicts_with = "last")]
+        all: bool,
+        /// The background timer started most recently
+        #[arg(long)]
+        last: bool,
+        /// Send a "cancelled" notification for each one
+        #[arg(long)]
+        notify: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Validate the config file, listing every problem; exits with 1 if there are any
+    Check,
+    /// Print where the config file is read from
+    Path,
+    /// Write a config file with every setting at its default
+    Init {
+        /// Replace an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum AlarmAction {
+    /// List pending alarms, both open in terminals and held by the daemon, and saved ones
+    List,
+    /// Save an alarm time under a name to start it later with `alarm start`
+    Save {
+        name: String,
+        /// Time in format: 7:20am, 19:20, 7:20pm, or phrases like "half past 7"
         time: String,
     },
+    /// Start an alarm saved with `alarm save`
+    Start {
+        name: String,
+        #[command(flatten)]
+        args: Box<AlarmArgs>,
+    },
+    /// Live table of the daemon's alarms that rings in place ('a' adds, 'd' deletes)
+    Watch,
+    /// Skip the next ring of a repeating alarm held by the daemon
+    Skip {
+        /// Id shown by `clockeroo alarm list`
+        id: u64,
+        /// Skip every ring up to and including this date instead (e.g., 2024-08-20)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        until: Option<NaiveDate>,
+    },
+    /// Put a skipped alarm back on its normal schedule
+    Unskip {
+        /// Id shown by `clockeroo alarm list`
+        id: u64,
+    },
+    /// Add repeating alarms from a file of `<days> <time> <label>` lines (e.g., "weekdays 08:50 standup")
+    Import {
+        file: PathBuf,
+        /// Also remove alarms imported from this file whose line is gone
+        #[arg(long)]
+        sync: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum QueueAction {
+    /// Add a timer to the back of the queue
+    Add {
+        /// Duration (e.g., 5m, 1h30m)
+        duration: String,
+        /// Shown while it runs; defaults to "Step N"
+        label: Option<String>,
+    },
+    /// Run queued timers one after another, waiting for more once they're done
+    Run,
+    /// List the timers still to run
+    List,
 }
 
 #[derive(Subcommand)]
 enum StopwatchAction {
     /// Start the stopwatch
-    Start,
+    Start {
+        /// Target lap time (e.g., 1m30s); laps are colored against this pace
+        #[arg(long)]
+        target_lap: Option<String>,
+        /// Briefly freeze the displayed time after each lap so the split is readable
+        #[arg(long)]
+        freeze_on_lap: bool,
+        /// Send a desktop notification with the split each time a lap is recorded
+        #[arg(long)]
+        notify_laps: bool,
+        /// Discard a session still running in the background instead of resuming it
+        #[arg(long)]
+        new: bool,
+        /// Beep "go" the moment a new session starts (not when resuming one); the beep is
+        /// heard a few tens of milliseconds after the clock starts, while the audio device opens
+        #[arg(long)]
+        start_beep: bool,
+        /// On stop, print only the elapsed seconds (e.g., 83.412)
+        #[arg(long)]
+        raw: bool,
+        #[command(flatten)]
+        export: ExportArgs,
+        /// Serve GET /status and POST /stop on this address (a bare port listens on 127.0.0.1)
+        #[arg(long, value_name = "ADDR", value_parser = http::parse_addr)]
+        http: Option<SocketAddr>,
+        /// Sub-second digits on the display: s, ds (tenths), cs (hundredths), ms or us
+        #[arg(long, value_enum, default_value_t = Precision::Ms)]
+        precision: Precision,
+    },
     /// Stop the stopwatch and show elapsed time
-    Stop,
+    Stop {
+        /// Print only the elapsed seconds (e.g., 83.412)
+        #[arg(long)]
+        raw: bool,
+        #[command(flatten)]
+        export: ExportArgs,
+        /// Sub-second digits of the final time and export; us gives microseconds (e.g., 83.412345)
+        #[arg(long, value_enum, default_value_t = Precision::Ms)]
+        precision: Precision,
+    },
+}
+
+/// Unit words `parse_duration` takes after a number, in milliseconds.
+const DURATION_UNITS: &[(&[&str], f64)] = &[
+    (&["h", "hr", "hrs", "hour", "hours"], 3_600_000.0),
+    (&["m", "min", "mins", "minute", "minutes"], 60_000.0),
+    (&["s", "sec", "secs", "second", "seconds"], 1_000.0),
+    (&["ms", "msec", "msecs"], 1.0),
+];
+
+/// The longest duration `parse_duration` takes, so every end time stays on the calendar.
+const MAX_DURATION: Duration = Duration::from_secs(100 * 366 * 86_400);
+
+fn too_long(s: &str) -> anyhow::Error {
+    anyhow::anyhow!("Duration '{}' is too long, the longest is 100 years", s)
 }
 
 fn parse_duration(s: &str) -> Result<Duration> {
+    if s.trim().starts_with(['P', 'p']) {
+        return parse_iso_duration(s.trim());
+    }
+    
     let s = s.to_lowercase();
-    let mut total_seconds = 0u64;
-    let mut current_num = String::new();
+    let invalid = || anyhow::anyhow!("Invalid duration format. Use formats like: 120s, 5m, 2h, 1h30m, 5min, 2hrs");
+    let mut total_ms = 0f64;
+    let mut rest = s.trim();
     
-    for ch in s.chars() {
-        if ch.is_ascii_digit() {
-            current_num.push(ch);
-        } else if ch == 'h' {
-            if !current_num.is_empty() {
-                total_seconds += current_num.parse::<u64>()? * 3600;
-                current_num.clear();
-            }
-        } else if ch == 'm' {
-            if !current_num.is_empty() {
-                total_seconds += current_num.parse::<u64>()? * 60;
-                current_num.clear();
-            }
-        } else if ch == 's' {
-            if !current_num.is_empty() {
-                total_seconds += current_num.parse::<u64>()?;
-                current_num.clear();
-            }
+    // Number, then the whole word after it as its unit, so "min" is never "m" + "in"
+    while !rest.is_empty() {
+        let num_len = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+        if num_len == 0 {
+            return Err(invalid());
+        }
+        let (num, after) = rest.split_at(num_len);
+        // Numbers may be fractional ("1.5m"), the total is kept in milliseconds
+        let value: f64 = num
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid number '{}' in duration", num))?;
+        let after = after.trim_start();
+        let unit_len = after.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_len);
+        let unit_ms = match unit {
+            // A number without a unit is seconds
+            "" => 1_000.0,
+            unit => DURATION_UNITS
+                .iter()
+                .find(|(names, _)| names.contains(&unit))
+                .map(|&(_, unit_ms)| unit_ms)
+                .ok_or_else(|| anyhow::anyhow!("Unknown unit '{}' in duration '{}'. Use h, m, s or ms (or hr, min, sec)", unit, s))?,
+        };
+        total_ms += value * unit_ms;
+        rest = after.trim_start();
+    }
+    
+    if total_ms > MAX_DURATION.as_millis() as f64 {
+        return Err(too_long(&s));
+    }
+    let total_ms = total_ms.round() as u64;
+    if total_ms == 0 {
+        if s.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            anyhow::bail!("Duration '{}' rounds to zero, the shortest duration is 1ms", s);
         }
+        return Err(invalid());
     }
     
-    // If there's a number without a unit, treat it as seconds
-    if !current_num.is_empty() {
-        total_seconds += current_num.parse::<u64>()?;
+    Ok(Duration::from_millis(total_ms))
+}
+
+/// ISO 8601 durations like `PT1H30M`, `PT45S` or `P1DT2H`. Years and months have no
+/// fixed length, so only weeks and days are accepted before the `T`.
+fn parse_iso_duration(s: &str) -> Result<Duration> {
+    let invalid = || anyhow::anyhow!("Invalid ISO 8601 duration '{}'. Use formats like: PT90M, PT1H30M, P1DT2H", s);
+    let upper = s.to_uppercase();
+    let rest = upper.strip_prefix('P').ok_or_else(invalid)?;
+    let (date, time) = match rest.split_once('T') {
+        Some((date, time)) if !time.is_empty() => (date, time),
+        Some(_) => return Err(invalid()),
+        None => (rest, ""),
+    };
+    
+    let mut total_seconds = 0u64;
+    for (part, in_time) in [(date, false), (time, true)] {
+        let mut current_num = String::new();
+        for ch in part.chars() {
+            if ch.is_ascii_digit() {
+                current_num.push(ch);
+                continue;
+            }
+            let unit = match (ch, in_time) {
+                ('W', false) => 7 * 86400,
+                ('D', false) => 86400,
+                ('Y', false) | ('M', false) => anyhow::bail!(
+                    "ISO 8601 years and months aren't supported in '{}', use days (e.g., P30D) instead",
+                    s
+                ),
+                ('H', true) => 3600,
+                ('M', true) => 60,
+                ('S', true) => 1,
+                _ => return Err(invalid()),
+            };
+            if current_num.is_empty() {
+                return Err(invalid());
+            }
+            total_seconds = current_num
+                .parse::<u64>()
+                .ok()
+                .and_then(|value| value.checked_mul(unit))
+                .and_then(|seconds| seconds.checked_add(total_seconds))
+                .filter(|&seconds| seconds <= MAX_DURATION.as_secs())
+                .ok_or_else(|| too_long(s))?;
+            current_num.clear();
+        }
+        if !current_num.is_empty() {
+            return Err(invalid());
+        }
     }
     
     if total_seconds == 0 {
-        anyhow::bail!("Invalid duration format. Use formats like: 120s, 5m, 2h, 1h30m");
+        return Err(invalid());
     }
     
     Ok(Duration::from_secs(total_seconds))
 }
 
+/// Minute words accepted after the hour, as in "7 thirty pm".
+const SPOKEN_MINUTES: &[(&str, u32)] = &[
+    ("o'clock", 0),
+    ("oclock", 0),
+    ("fifteen", 15),
+    ("twenty", 20),
+    ("thirty", 30),
+    ("forty", 40),
+    ("forty five", 45),
+    ("forty-five", 45),
+    ("fifty", 50),
+];
+
+/// Parses an hour like "8", "8pm" or "8 pm" into 24-hour form. Without am/pm the
+/// hour is taken as-is, matching how "7:20" is read as 7:20 AM.
+fn parse_spoken_hour(words: &[&str]) -> Option<u32> {
+    let joined = words.join("");
+    let (digits, is_pm) = if let Some(h) = joined.strip_suffix("pm") {
+        (h, Some(true))
+    } else if let Some(h) = joined.strip_suffix("am") {
+        (h, Some(false))
+    } else {
+        (joined.as_str(), None)
+    };
+    
+    let hour: u32 = digits.parse().ok()?;
+    match is_pm {
+        Some(_) if hour == 0 || hour > 12 => None,
+        Some(true) => Some(if hour == 12 { 12 } else { hour + 12 }),
+        Some(false) => Some(if hour == 12 { 0 } else { hour }),
+        None if hour < 24 => Some(hour),
+        None => None,
+    }
+}
+
+/// Colloquial phrasings like "half past 7", "quarter to 8pm" or "7 thirty pm".
+/// Returns None when the input doesn't look like one, so the strict parser can try.
+fn parse_natural_time(s: &str) -> Option<Result<NaiveTime>> {
+    let s = s.trim().to_lowercase();
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let to_time = |total_minutes: u32| NaiveTime::from_hms_opt(total_minutes / 60, total_minutes % 60, 0).unwrap();
+    
+    match s.as_str() {
+        "noon" => return Some(Ok(to_time(12 * 60))),
+        "midnight" => return Some(Ok(to_time(0))),
+        _ => {}
+    }
+    
+    // "<offset> past <hour>" / "<offset> to <hour>"
+    if let Some(pos) = words.iter().position(|w| *w == "past" || *w == "to") {
+        let minutes = match words[..pos].join(" ").as_str() {
+            "half" => 30,
+            "quarter" | "a quarter" => 15,
+            n => n.parse::<u32>().ok().filter(|m| (1..60).contains(m))?,
+        };
+        let phrase = words[..=pos].join(" ");
+        let hour_words = &words[pos + 1..];
+        let Some(hour) = parse_spoken_hour(hour_words) else {
+            return Some(Err(anyhow::anyhow!(
+                "Understood '{}' but couldn't parse the hour '{}'",
+                phrase,
+                hour_words.join(" ")
+            )));
+        };
+        
+        let total = if words[pos] == "past" {
+            hour * 60 + minutes
+        } else {
+            (hour * 60 + 24 * 60 - minutes) % (24 * 60)
+        };
+        return Some(Ok(to_time(total)));
+    }
+    
+    // "<hour> <minute words> [am|pm]"
+    if words.len() >= 2 && words[0].chars().all(|c| c.is_ascii_digit()) {
+        let suffix_len = usize::from(matches!(words.last(), Some(&"am") | Some(&"pm")));
+        let minute_words = words[1..words.len() - suffix_len].join(" ");
+        let minutes = SPOKEN_MINUTES.iter().find(|(word, _)| *word == minute_words)?.1;
+        
+        let mut hour_words = vec![words[0]];
+        hour_words.extend_from_slice(&words[words.len() - suffix_len..]);
+        let Some(hour) = parse_spoken_hour(&hour_words) else {
+            return Some(Err(anyhow::anyhow!(
+                "Understood '{}' but couldn't parse the hour '{}'",
+                minute_words,
+                hour_words.join(" ")
+            )));
+        };
+        return Some(Ok(to_time(hour * 60 + minutes)));
+    }
+    
+    None
+}
+
+/// How a colon time without am/pm, like `7:20`, is read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum BareTime {
+    /// 7:20 is 07:20, 19:20 is 19:20
+    #[default]
+    TwentyFourHour,
+    /// 7:20 is 19:20, 12:30 is half past noon; 13:00 and up are rejected
+    AssumePm,
+}
+
 fn parse_alarm_time(s: &str) -> Result<NaiveTime> {
+    parse_alarm_time_as(s, BareTime::default())
+}
+
+fn parse_alarm_time_as(s: &str, bare: BareTime) -> Result<NaiveTime> {
+    if let Some(result) = parse_natural_time(s) {
+        return result;
+    }
+    
     let s = s.to_lowercase();
     
     // Handle AM/PM format
@@ -137,22 +867,229 @@ fn parse_alarm_time(s: &str) -> Result<NaiveTime> {
             anyhow::bail!("Invalid time format. Use formats like: 7:20am, 7:20pm, or 19:20");
         }
         
-        let hour: u32 = parts[0].parse()?;
+        let mut hour: u32 = parts[0].parse()?;
         let minute: u32 = parts[1].parse()?;
         
+        if bare == BareTime::AssumePm {
+            if hour > 12 || hour == 0 {
+                anyhow::bail!("{} isn't a 12-hour time, drop --input-12h to use 24-hour times", s);
+            }
+            if hour != 12 {
+                hour += 12;
+            }
+        }
+        
         NaiveTime::from_hms_opt(hour, minute, 0)
             .ok_or_else(|| anyhow::anyhow!("Invalid time"))
     }
 }
 
+/// An alarm time given as `@<unix seconds>` (e.g., @1735689600), for scripts
+/// that already have a timestamp. `None` for any other time; an error if it
+/// doesn't parse or isn't after `now`.
+fn parse_epoch_target(s: &str, now: DateTime<Local>) -> Option<Result<DateTime<Local>>> {
+    let seconds = s.trim().strip_prefix('@')?;
+    let parse = || {
+        let at = seconds
+            .parse::<i64>()
+            .ok()
+            .and_then(|seconds| Local.timestamp_opt(seconds, 0).single())
+            .ok_or_else(|| anyhow::anyhow!("Invalid epoch time '{}', expected @ and Unix seconds (e.g., @1735689600)", s))?;
+        if at <= now {
+            anyhow::bail!("{} is in the past ({})", s.trim(), at.format("%Y-%m-%d %H:%M:%S"));
+        }
+        Ok(at)
+    };
+    Some(parse())
+}
+
+/// `eod` (end of today's workday) or `eow` (end of the workweek, Friday) at
+/// the configured workday `end`. `None` for any other time; an error with a
+/// hint once that end has passed, rather than quietly moving on to the next one.
+fn parse_workday_target(s: &str, now: NaiveDateTime, workday: &config::WorkdayConfig) -> Option<Result<NaiveDateTime>> {
+    let keyword = s.trim().to_lowercase();
+    let (days, over) = match keyword.as_str() {
+        "eod" => (0, "the workday"),
+        // Negative over the weekend, so it's the Friday that has passed
+        "eow" => (Weekday::Fri.num_days_from_monday() as i64 - now.weekday().num_days_from_monday() as i64, "the workweek"),
+        _ => return None,
+    };
+    let end = match workday.end() {
+        Ok(end) => end,
+        Err(err) => return Some(Err(err.context("Invalid workday.end in the config"))),
+    };
+    let target = now.date().and_time(end) + chrono::Duration::days(days);
+    if target <= now {
+        return Some(Err(anyhow::anyhow!(
+            "{} has already passed ({} ended {}); give a time instead (e.g., 9:00am) or change workday.end in the config",
+            keyword,
+            over,
+            target.format("%a %H:%M")
+        )));
+    }
+    Some(Ok(target))
+}
+
+/// `$XDG_RUNTIME_DIR` where it's set (Linux), otherwise the platform temp
+/// directory (`/tmp`, `%TEMP%` on Windows).
+fn runtime_dir() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime_dir) if cfg!(unix) && !runtime_dir.is_empty() => PathBuf::from(runtime_dir),
+        _ => std::env::temp_dir(),
+    }
+}
+
 fn get_stopwatch_file() -> PathBuf {
-    if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
-        PathBuf::from(runtime_dir).join("clockeroo.stopwatch")
+    runtime_dir().join("clockeroo.stopwatch")
+}
+
+/// What the stopwatch file records about a running session.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StopwatchState {
+    /// Wall-clock start in Unix milliseconds
+    started_at: u64,
+    /// Lap times in milliseconds
+    #[serde(default)]
+    laps: Vec<u64>,
+    /// Total length of finished pauses in milliseconds
+    #[serde(default)]
+    paused_ms: u64,
+    /// Unix milliseconds when the current pause began, if paused
+    #[serde(default)]
+    paused_since: Option<u64>,
+    /// The start and pauses again on the monotonic clock, for microseconds at `stop`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    monotonic: Option<MonotonicAnchor>,
+}
+
+/// A stopwatch session on `clock::monotonic_micros`. The wall-clock fields
+/// resume it after a reboot, these measure it finely until then.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct MonotonicAnchor {
+    started_us: u64,
+    #[serde(default)]
+    paused_us: u64,
+    #[serde(default)]
+    paused_since_us: Option<u64>,
+}
+
+impl MonotonicAnchor {
+    fn elapsed(&self, now_us: u64) -> Option<Duration> {
+        let open_pause = self.paused_since_us.map_or(0, |since| now_us.saturating_sub(since));
+        let us = now_us.checked_sub(self.started_us)?.saturating_sub(self.paused_us).saturating_sub(open_pause);
+        Some(Duration::from_micros(us))
+    }
+}
+
+impl StopwatchState {
+    /// A session starting now.
+    fn start(now_ms: u64, now_us: Option<u64>) -> StopwatchState {
+        StopwatchState {
+            started_at: now_ms,
+            monotonic: now_us.map(|started_us| MonotonicAnchor { started_us, ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    fn load(path: &Path) -> Result<StopwatchState> {
+        statefile::read_json(path)
+    }
+
+    /// The saved session, if there is one. A file that doesn't parse (left by a
+    /// crash or edited by hand) can't be resumed or stopped, so it's removed.
+    fn load_or_clear(path: &Path) -> Result<Option<StopwatchState>> {
+        match StopwatchState::load(path) {
+            Ok(state) => Ok(Some(state)),
+            Err(err) if statefile::is_not_found(&err) => Ok(None),
+            Err(err) if statefile::is_corrupt(&err) => {
+                eprintln!("[WARN] Stopwatch state is corrupted, clearing it ({:#})", err);
+                let _ = fs::remove_file(path);
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        statefile::write_json(path, self)
+    }
+
+    fn started_at(&self) -> chrono::DateTime<Local> {
+        Local
+            .timestamp_millis_opt(self.started_at as i64)
+            .single()
+            .unwrap_or_else(Local::now)
+    }
+
+    fn laps(&self) -> Vec<Duration> {
+        self.laps.iter().map(|ms| Duration::from_millis(*ms)).collect()
+    }
+
+    /// Running time at `now_ms`, leaving out every pause segment.
+    fn elapsed(&self, now_ms: u64) -> Duration {
+        let open_pause = self.paused_since.map_or(0, |since| now_ms.saturating_sub(since));
+        let ms = now_ms
+            .saturating_sub(self.started_at)
+            .saturating_sub(self.paused_ms)
+            .saturating_sub(open_pause);
+        Duration::from_millis(ms)
+    }
+
+    /// `elapsed` down to the microsecond where the monotonic clock can tell.
+    fn elapsed_precise(&self, now_ms: u64, now_us: Option<u64>) -> Duration {
+        let monotonic = self.monotonic.zip(now_us).and_then(|(anchor, now_us)| anchor.elapsed(now_us));
+        clock::precise_elapsed(self.elapsed(now_ms), monotonic)
+    }
+
+    /// Moves the wall-clock anchors along with a wall-clock step so elapsed time doesn't jump.
+    fn shift_wall_clock(&mut self, step_ms: i64) {
+        self.started_at = clock::shift_ms(self.started_at, step_ms);
+        self.paused_since = self.paused_since.map(|since| clock::shift_ms(since, step_ms));
+    }
+
+    fn toggle_pause(&mut self, now_ms: u64, now_us: Option<u64>) {
+        match self.paused_since.take() {
+            Some(since) => self.paused_ms += now_ms.saturating_sub(since),
+            None => self.paused_since = Some(now_ms),
+        }
+        // A pause the monotonic clock missed would throw it off for good
+        let Some(now_us) = now_us else {
+            self.monotonic = None;
+            return;
+        };
+        if let Some(anchor) = &mut self.monotonic {
+            match anchor.paused_since_us.take() {
+                Some(since) => anchor.paused_us += now_us.saturating_sub(since),
+                None => anchor.paused_since_us = Some(now_us),
+            }
+        }
+    }
+}
+
+fn estimate_finish(now: NaiveDateTime, duration: Duration) -> Result<NaiveDateTime> {
+    chrono::Duration::from_std(duration)
+        .ok()
+        .and_then(|duration| now.checked_add_signed(duration))
+        .ok_or_else(|| anyhow::anyhow!("{} from now is past the end of the calendar", format_duration(duration)))
+}
+
+/// "14:05" (or "2:05 PM"), with the day added when it isn't today.
+fn format_finish_time(now: NaiveDateTime, finish: NaiveDateTime, twelve_hour: bool) -> String {
+    let time = time_format::format_time(&finish, twelve_hour, false);
+    if finish.date() == now.date() {
+        time
     } else {
-        PathBuf::from("/tmp").join("clockeroo.stopwatch")
+        format!("{} on {}", time, finish.format("%a %b %-d"))
     }
 }
 
+/// Parses a color name ("red", "lightblue") or hex value ("#ff8800").
+fn parse_color(s: &str) -> Result<Color> {
+    s.trim()
+        .parse::<Color>()
+        .map_err(|_| anyhow::anyhow!("Invalid color '{}'. Use a name like red or a hex value like #ff8800", s))
+}
+
 fn format_duration(duration: Duration) -> String {
     let total_secs = duration.as_secs();
     let hours = total_secs / 3600;
@@ -166,468 +1103,2656 @@ fn format_duration(duration: Duration) -> String {
     }
 }
 
-fn send_notification(title: &str, body: &str) {
-    let _ = Notification::new()
-        .summary(title)
-        .body(body)
-        .icon("dialog-information")
-        .timeout(0)
-        .show();
+/// Like `format_duration`, but a day or more reads "2d 03:15:42" instead of
+/// "51:15:42", for alarms and daemon entries that can be days out.
+fn format_long_duration(duration: Duration) -> String {
+    let days = duration.as_secs() / 86_400;
+    if days == 0 {
+        return format_duration(duration);
+    }
+    let rest = Duration::from_secs(duration.as_secs() % 86_400);
+    let hours = rest.as_secs() / 3600;
+    format!("{}d {:02}:{:02}:{:02}", days, hours, rest.as_secs() % 3600 / 60, rest.as_secs() % 60)
 }
 
-fn play_bell() {
-    // Try terminal bell first
-    print!("\x07");
-    let _ = io::stdout().flush();
-    
-    // Also play an actual sound using rodio
-    play_sound();
+/// Machine-friendly elapsed seconds with `precision`'s sub-second digits, e.g.
+/// 83.412 for ms or 83.412345 for us.
+fn format_raw_seconds(duration: Duration, precision: Precision) -> String {
+    match precision.digits() {
+        0 => duration.as_secs().to_string(),
+        digits => format!("{}.{:0width$}", duration.as_secs(), precision.fraction(duration), width = digits as usize),
+    }
 }
 
-fn play_sound() {
-    use rodio::{OutputStream, source::Source};
-    
-    // Try to play a built-in sine wave beep
-    if let Ok((_stream, stream_handle)) = OutputStream::try_default() {
-        // Create a gentler beep sound (440 Hz sine wave for 0.3 seconds)
-        // 440 Hz is the musical note A4, much more pleasant than 1000 Hz
-        let source = rodio::source::SineWave::new(440.0)
-            .take_duration(std::time::Duration::from_millis(300))
-            .amplify(0.2)  // Reduced volume from 0.5 to 0.2
-            .fade_in(std::time::Duration::from_millis(50));  // Gentle fade-in
-        
-        // Play the sound (ignore errors if audio system unavailable)
-        let _ = stream_handle.play_raw(source.convert_samples());
-        
-        // Keep the stream alive while the sound plays
-        std::thread::sleep(std::time::Duration::from_millis(350));
-    }
+fn format_stopwatch_time(duration: Duration) -> String {
+    format_stopwatch_time_with(duration, Precision::Ms)
 }
 
-async fn run_timer_ui(duration: Duration) -> Result<()> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+/// How many sub-second digits the stopwatch shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+enum Precision {
+    /// Whole seconds
+    S,
+    /// Tenths
+    Ds,
+    /// Hundredths
+    Cs,
+    #[default]
+    /// Milliseconds
+    Ms,
+    /// Microseconds
+    Us,
+}
 
-    let start_time = Instant::now();
-    
-    loop {
-        let elapsed = start_time.elapsed();
-        
-        if elapsed >= duration {
-            // Timer finished
-            terminal.draw(|f| {
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(2)
-                    .constraints([
-                        Constraint::Length(3),
-                        Constraint::Length(3),
-                        Constraint::Length(3),
-                        Constraint::Min(0),
-                    ])
-                    .split(f.area());
-
-                let title = Paragraph::new("TIMER FINISHED!")
-                    .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
-                    .alignment(Alignment::Center)
-                    .block(Block::default().borders(Borders::ALL));
-                f.render_widget(title, chunks[0]);
-
-                let message = Paragraph::new("Your timer has completed!")
-                    .style(Style::default().fg(Color::Yellow))
-                    .alignment(Alignment::Center)
-                    .block(Block::default().borders(Borders::ALL));
-                f.render_widget(message, chunks[1]);
-
-                let help = Paragraph::new("Press 'q' or Ctrl-C to exit")
-                    .style(Style::default().fg(Color::Gray))
-                    .alignment(Alignment::Center);
-                f.render_widget(help, chunks[2]);
-            })?;
-            
-            // Send notifications
-            play_bell();
-            send_notification("Timer Finished!", "Your timer has completed!");
-            
-            // Wait for user to quit
-            loop {
-                if event::poll(Duration::from_millis(100))? {
-                    if let Event::Key(key) = event::read()? {
-                        if key.code == KeyCode::Char('q') || key.code == KeyCode::Char('c') && key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                            break;
-                        }
-                    }
-                }
-            }
-            break;
+impl Precision {
+    fn digits(self) -> u32 {
+        match self {
+            Precision::S => 0,
+            Precision::Ds => 1,
+            Precision::Cs => 2,
+            Precision::Ms => 3,
+            Precision::Us => 6,
         }
-        
-        let remaining = duration - elapsed;
-        let remaining_seconds = remaining.as_secs();
-        
-        terminal.draw(|f| {
-            let area = f.area();
-            
-            // Color based on remaining time
-            let time_color = if remaining_seconds < 10 {
-                Color::Red
-            } else if remaining_seconds < 60 {
-                Color::Yellow
-            } else {
-                Color::Green
-            };
-            
-            // Create the simple, clean content
-            let mut lines = vec![];
-            
-            // Add ASCII header lines
-            for line in ASCII_HEADER.lines() {
-                lines.push(Line::from(vec![Span::styled(line, Style::default().fg(Color::DarkGray))]));    
-            }
-            
-            // Add the rest of the content
-            lines.push(Line::from(""));
-            lines.push(Line::from(""));
-            lines.push(Line::from(vec![Span::styled("Timer Running", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]));
-            lines.push(Line::from(""));
-            lines.push(Line::from(vec![Span::styled("Time Remaining", Style::default().fg(Color::Gray))]));
-            lines.push(Line::from(vec![Span::styled(format_duration(remaining), Style::default().fg(time_color).add_modifier(Modifier::BOLD))]));
-            lines.push(Line::from(""));
-            lines.push(Line::from(""));
-            lines.push(Line::from(vec![Span::styled("Press 'q' or Ctrl-C to cancel", Style::default().fg(Color::Gray))]));
-            
-            let paragraph = Paragraph::new(lines)
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Cyan))
-                )
-                .alignment(Alignment::Center);
-                
-            f.render_widget(paragraph, area);
-        })?;
+    }
 
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if key.code == KeyCode::Char('q') || key.code == KeyCode::Char('c') && key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                    break;
-                }
-            }
-        }
-        
-        sleep(Duration::from_millis(100)).await;
+    /// The sub-second part of `duration` in this many digits, truncated.
+    fn fraction(self, duration: Duration) -> u32 {
+        duration.subsec_micros() / 10u32.pow(6 - self.digits())
     }
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    /// How often the display needs redrawing. Faster than 10ms nobody could read
+    /// the digit anyway, and whole seconds still redraw often enough for the spinner.
+    fn frame(self) -> Duration {
+        match self {
+            Precision::S => Duration::from_millis(200),
+            Precision::Ds => Duration::from_millis(100),
+            Precision::Cs | Precision::Ms | Precision::Us => Duration::from_millis(10),
+        }
+    }
+}
 
-    Ok(())
+/// Truncates rather than rounds, so the shown value never runs ahead of the clock.
+fn format_stopwatch_time_with(duration: Duration, precision: Precision) -> String {
+    let digits = precision.digits() as usize;
+    if digits == 0 {
+        return format_duration(duration);
+    }
+    format!("{}.{:0width$}", format_duration(duration), precision.fraction(duration), width = digits)
 }
 
-async fn run_stopwatch_ui() -> Result<()> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+/// Formats a signed millisecond delta as e.g. "+00:01.234" or "-00:00.500".
+fn format_delta(delta_ms: i64) -> String {
+    let sign = if delta_ms < 0 { '-' } else { '+' };
+    let abs = Duration::from_millis(delta_ms.unsigned_abs());
+    format!("{}{}", sign, format_stopwatch_time(abs))
+}
 
-    let stopwatch_file = get_stopwatch_file();
-    let start_time = Instant::now();
-    
-    // Save start time to file
-    fs::write(&stopwatch_file, format!("{:?}", start_time))?;
-    
-    loop {
-        let elapsed = start_time.elapsed();
-        
-        terminal.draw(|f| {
-            let area = f.area();
-            
-            let millis = elapsed.as_millis() % 1000;
-            let time_str = format!("{}.{:03}", format_duration(elapsed), millis);
-            
-            // Create the simple, clean content
-            let mut lines = vec![];
-            
-            // Add ASCII header lines
-            for line in ASCII_HEADER.lines() {
-                lines.push(Line::from(vec![Span::styled(line, Style::default().fg(Color::DarkGray))]));    
-            }
-            
-            // Add the rest of the content
-            lines.push(Line::from(""));
-            lines.push(Line::from(""));
-            lines.push(Line::from(vec![Span::styled("Stopwatch Running", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]));
-            lines.push(Line::from(""));
-            lines.push(Line::from(vec![Span::styled("Elapsed Time", Style::default().fg(Color::Gray))]));
-            lines.push(Line::from(vec![Span::styled(time_str, Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))]));
-            lines.push(Line::from(""));
-            lines.push(Line::from(""));
-            lines.push(Line::from(vec![Span::styled("Press 's' to stop, 'q' or Ctrl-C to quit", Style::default().fg(Color::Gray))]));
-            
-            let paragraph = Paragraph::new(lines)
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Cyan))
-                )
-                .alignment(Alignment::Center);
-                
-            f.render_widget(paragraph, area);
-        })?;
+fn lap_delta_ms(lap: Duration, target: Duration) -> i64 {
+    lap.as_millis() as i64 - target.as_millis() as i64
+}
 
-        if event::poll(Duration::from_millis(10))? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('s') => {
-                        // Stop and show final time
-                        let final_time = start_time.elapsed();
-                        disable_raw_mode()?;
-                        execute!(
-                            terminal.backend_mut(),
-                            LeaveAlternateScreen,
-                            DisableMouseCapture
-                        )?;
-                        terminal.show_cursor()?;
-                        
-                        let millis = final_time.as_millis() % 1000;
-                        println!("\n[Stopwatch stopped]");
-                        println!("   Final time: {}.{:03}", format_duration(final_time), millis);
-                        
-                        // Clean up the file
-                        let _ = fs::remove_file(&stopwatch_file);
-                        return Ok(());
-                    }
-                    KeyCode::Char('q') => {
-                        break;
-                    }
-                    KeyCode::Char('c') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                        break;
-                    }
-                    _ => {}
-                }
-            }
-        }
-        
-        sleep(Duration::from_millis(10)).await;
+/// Returns (laps on target, laps over target, average delta in ms).
+fn summarize_laps(laps: &[Duration], target: Duration) -> (usize, usize, i64) {
+    if laps.is_empty() {
+        return (0, 0, 0);
     }
+    let deltas: Vec<i64> = laps.iter().map(|lap| lap_delta_ms(*lap, target)).collect();
+    let under = deltas.iter().filter(|d| **d <= 0).count();
+    let average = deltas.iter().sum::<i64>() / deltas.len() as i64;
+    (under, laps.len() - under, average)
+}
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-    
-    // Keep the stopwatch file for later
-    println!("\n[Stopwatch still running in background]");
-    println!("Run 'clockeroo stopwatch stop' to see the final time.");
+const SPINNER_FRAMES: [char; 4] = ['|', '/', '-', '\\'];
 
-    Ok(())
+/// Heading text with an optional spinner frame. The left padding mirrors the
+/// spinner so the title itself stays centered.
+fn running_heading(title: &str, spinner_frame: Option<usize>) -> String {
+    match spinner_frame {
+        Some(frame) => format!("  {} {}", title, SPINNER_FRAMES[frame % SPINNER_FRAMES.len()]),
+        None => title.to_string(),
+    }
 }
 
-async fn show_stopwatch_time() -> Result<()> {
-    let stopwatch_file = get_stopwatch_file();
-    
-    if !stopwatch_file.exists() {
-        println!("[ERROR] No stopwatch is currently running.");
-        println!("Start one with: clockeroo stopwatch start");
-        return Ok(());
+struct TimerOptions {
+    start_in: Option<Duration>,
+    /// When a `--start-at` timer begins
+    start_at: Option<NaiveDateTime>,
+    start_beep: bool,
+    count_in: u32,
+    label: Option<String>,
+    tag: Option<String>,
+    message: Option<String>,
+    exec: Option<String>,
+    /// `--on` commands
+    on: hooks::EventHooks,
+    quiet_hours: Option<QuietHours>,
+    urgency: notify::Urgency,
+    overtime: bool,
+    warn_at_percent: Option<u8>,
+    /// When the time left turns yellow and red
+    zones: zones::Zones,
+    /// The points the user asked to hear about, in order
+    milestones: milestones::Schedule,
+    critical_beep: bool,
+    /// Final stretch clicked off second by second (`--tick`)
+    tick: Option<Duration>,
+    every: bool,
+    /// Draw the dial instead of just the digits
+    clock_face: bool,
+    /// `--no-finish-screen`: leave as soon as it has rung
+    no_finish_screen: bool,
+    /// When an `--every` timer stops restarting
+    repeat_until: Option<NaiveDateTime>,
+    http: Option<http::Endpoint>,
+    /// The `--progress-notification`, where the platform has one
+    progress: Option<notify::ProgressNotification>,
+    /// The session a `clockeroo focus` timer belongs to
+    focus: Option<Arc<focus::Session>>,
+    visual_bell: bool,
+    spinner: bool,
+    sound: SoundOptions,
+}
+
+/// A timer with none of `clockeroo timer`'s extras, for focus sessions and
+/// `alarm --then-timer`.
+fn simple_timer_options(
+    label: Option<String>,
+    tag: Option<String>,
+    zones: zones::Zones,
+    visual_bell: bool,
+    spinner: bool,
+    sound: SoundOptions,
+) -> TimerOptions {
+    TimerOptions {
+        start_in: None,
+        start_at: None,
+        start_beep: false,
+        count_in: 0,
+        label,
+        tag,
+        message: None,
+        exec: None,
+        on: hooks::EventHooks::default(),
+        quiet_hours: None,
+        urgency: notify::Urgency::Normal,
+        overtime: false,
+        warn_at_percent: None,
+        zones,
+        milestones: milestones::Schedule::default(),
+        critical_beep: false,
+        tick: None,
+        every: false,
+        clock_face: false,
+        no_finish_screen: false,
+        repeat_until: None,
+        http: None,
+        progress: None,
+        focus: None,
+        visual_bell,
+        spinner,
+        sound,
     }
-    
-    // For simplicity, we'll just show that a stopwatch is running
-    // In a real implementation, we'd need to serialize the Instant properly
-    println!("[Stopwatch is running]");
-    println!("Note: To see live time, run 'clockeroo stopwatch start' again.");
-    
-    // Clean up the file
-    let _ = fs::remove_file(&stopwatch_file);
-    
-    Ok(())
 }
 
-async fn run_alarm_ui(alarm_time: NaiveTime) -> Result<()> {
-    let now = Local::now();
-    let mut target = now.date_naive().and_time(alarm_time);
-    
-    // If the alarm time has already passed today, set it for tomorrow
-    if target <= now.naive_local() {
-        target += chrono::Duration::days(1);
+/// Runs the timer's `--on` commands for `event`.
+fn fire_timer_event(options: &TimerOptions, event: hooks::Event, remaining: Duration) {
+    if options.on.is_empty() {
+        return;
     }
+    options.on.fire(
+        event,
+        &[
+            ("CLOCKEROO_KIND", "timer".to_string()),
+            ("CLOCKEROO_LABEL", options.label.clone().unwrap_or_default()),
+            ("CLOCKEROO_REMAINING", remaining.as_secs().to_string()),
+        ],
+    );
+}
+
+/// Rings, notifies and runs the `--exec` and `finish` hooks when a timer reaches zero.
+async fn finish_timer(options: &TimerOptions, duration: Duration) {
+    // Not left behind at 01:00, quiet hours or not
+    if let Some(progress) = &options.progress {
+        progress.close();
+    }
+    // Before ringing, so whatever on_start muted (Do Not Disturb) is back on for the bell
+    if let Some(session) = &options.focus {
+        session.end(focus::Outcome::Finished);
+    }
+    if !is_quiet_now(options.quiet_hours.as_ref()) {
+        play_bell(SoundEvent::TimerDone, &options.sound);
+        let body = match (&options.message, &options.label) {
+            (Some(message), _) => message.clone(),
+            (None, Some(label)) => format!("{} has completed!", label),
+            (None, None) => "Your timer has completed!".to_string(),
+        };
+        let title = ui::with_tag(options.tag.as_deref(), "Timer Finished!");
+        notify::send_notification_with_urgency(&title, &body, options.urgency);
+        if options.visual_bell {
+            ui::visual_bell().await;
+        }
+    }
+    if let Some(command) = &options.exec {
+        hooks::spawn(command, &[("CLOCKEROO_LABEL", options.label.clone().unwrap_or_default())]);
+    }
+    fire_timer_event(options, hooks::Event::Finish, Duration::ZERO);
+    log_timer(options, duration, history::Outcome::Finished);
+}
+
+/// Runs the `cancel` hooks and logs a timer stopped with `remaining` to go.
+fn cancel_timer(options: &TimerOptions, duration: Duration, remaining: Duration) {
+    if let Some(progress) = &options.progress {
+        progress.close();
+    }
+    fire_timer_event(options, hooks::Event::Cancel, remaining);
+    log_timer(options, duration, history::Outcome::Cancelled);
+}
+
+/// Adds a timer that ended to the history log, for `suggest` and `stats`.
+fn log_timer(options: &TimerOptions, duration: Duration, outcome: history::Outcome) {
+    let record = history::Record {
+        at: Local::now(),
+        kind: store::EntryKind::Timer,
+        label: options.label.clone(),
+        tag: options.tag.clone(),
+        outcome,
+        duration_ms: Some(duration.as_millis() as u64),
+    };
+    if let Err(err) = history::append(&record) {
+        verbose!("couldn't log the timer: {:#}", err);
+    }
+}
+
+/// "04:00 remaining" in the `--progress-notification`.
+fn update_progress(progress: &notify::ProgressNotification, options: &TimerOptions, remaining: Duration) {
+    let title = ui::with_tag(options.tag.as_deref(), options.label.as_deref().unwrap_or("Timer"));
+    progress.update(&title, &format!("{} remaining", format_duration(remaining)), notify::Urgency::Normal);
+}
+
+/// How much of a `duration` long timer is left after `elapsed`, from 1 down to 0.
+fn share_left(elapsed: Duration, duration: Duration) -> f64 {
+    if duration.is_zero() {
+        return 0.0;
+    }
+    duration.saturating_sub(elapsed).as_secs_f64() / duration.as_secs_f64()
+}
+
+/// The whole second a `--tick` click is due for, with `remaining` (rounded up)
+/// left: only inside the final `window`, and never at zero, where the bell takes over.
+fn tick_second(remaining: Duration, window: Duration) -> Option<u64> {
+    (!remaining.is_zero() && remaining <= window).then_some(remaining.as_secs())
+}
+
+/// The one-off "time check" when a `--warn-at-percent` timer crosses its threshold.
+fn warn_timer(options: &TimerOptions, percent: u8, remaining: Duration) {
+    if is_quiet_now(options.quiet_hours.as_ref()) {
+        return;
+    }
+    spawn_chime(WARN_TONE, &options.sound);
+    let body = format!(
+        "{}% of {} used, {} left",
+        percent,
+        options.label.as_deref().unwrap_or("the timer"),
+        format_duration(remaining)
+    );
+    notify::send_notification_with_urgency(&ui::with_tag(options.tag.as_deref(), "Time Check"), &body, options.urgency);
+}
+
+/// The first time `until` comes around at or after `started`, so a
+/// `--repeat-until 5pm` given in the evening means 5pm tomorrow.
+fn repeat_deadline(started: NaiveDateTime, until: NaiveTime) -> NaiveDateTime {
+    resolve_alarm_target(started, until).0
+}
+
+/// Whether an `--every` timer that just finished a cycle at `now` goes again.
+fn should_repeat(now: NaiveDateTime, deadline: Option<NaiveDateTime>) -> bool {
+    deadline.is_none_or(|deadline| now < deadline)
+}
+
+/// Chime for `--warn-at-percent`, below the count-in ticks.
+const WARN_TONE: f32 = 523.0;
+
+/// Chime for `--critical-beep`, a fifth above the warning.
+const CRITICAL_TONE: f32 = 784.0;
+
+/// Count-in tick, and the higher "go" beep when the timer starts.
+const COUNT_IN_TONE: f32 = 660.0;
+const COUNT_IN_GO_TONE: f32 = 1320.0;
+
+/// Publishes `status` to `--http` clients, and reports whether one of them sent `POST /stop`.
+fn http_stop_requested(http: Option<&http::Endpoint>, status: &status::ClockStatus) -> bool {
+    http.is_some_and(|http| {
+        http.publish(status);
+        http.stop_requested()
+    })
+}
+
+/// Runs the timer on screen, or as plain lines on stdout when there's no
+/// terminal to draw on (output piped to a file or another program).
+async fn run_timer(duration: Duration, options: TimerOptions) -> Result<()> {
+    if io::stdout().is_terminal() {
+        if options.overtime && ui::is_view_only() {
+            anyhow::bail!("--overtime needs keyboard input to stop, but stdin isn't a terminal");
+        }
+        run_timer_ui(duration, options).await
+    } else {
+        if options.overtime {
+            anyhow::bail!("--overtime needs a terminal, but stdout isn't one");
+        }
+        run_timer_plain(duration, options).await
+    }
+}
+
+/// How often the plain timer checks for a stop request.
+const PLAIN_POLL: Duration = Duration::from_millis(200);
+
+/// How often the plain timer's progress line is written when stderr is a log
+/// rather than a terminal it can redraw in place (that's every second).
+const PLAIN_PROGRESS_LINES: Duration = Duration::from_secs(60);
+
+/// Width of the progress line when there's no terminal to size it to.
+const PLAIN_PROGRESS_WIDTH: u16 = 80;
+
+/// The plain timer's progress bar on stderr, beside the start and finish lines
+/// on stdout: redrawn with a carriage return where stderr is a terminal,
+/// otherwise a line every [`PLAIN_PROGRESS_LINES`]. Off under `--quiet`.
+struct PlainProgress {
+    duration: Duration,
+    redraw: bool,
+    /// Elapsed time the next line is due at
+    next: Duration,
+    /// A redrawn line is on screen, waiting for its newline
+    drawn: bool,
+}
+
+impl PlainProgress {
+    fn new(duration: Duration) -> Option<PlainProgress> {
+        (!output::is_quiet()).then(|| PlainProgress { duration, redraw: io::stderr().is_terminal(), next: Duration::ZERO, drawn: false })
+    }
+
+    fn update(&mut self, elapsed: Duration) {
+        if elapsed < self.next {
+            return;
+        }
+        let remaining = clock::ceil_secs(self.duration.saturating_sub(elapsed));
+        if self.redraw {
+            self.next = clock::next_boundary(elapsed, Duration::from_secs(1));
+            // Asked each time, so it follows the terminal being resized
+            let width = crossterm::terminal::size().map(|(width, _)| width).unwrap_or(PLAIN_PROGRESS_WIDTH);
+            // Padded over whatever a longer line before it left behind, short of the last column so it never wraps
+            let line = ui::progress_line(remaining, self.duration, width.saturating_sub(1));
+            eprint!("\r{:<1$}", line, width.saturating_sub(1) as usize);
+            self.drawn = true;
+        } else {
+            self.next = clock::next_boundary(elapsed, PLAIN_PROGRESS_LINES);
+            eprintln!("{}", ui::progress_line(remaining, self.duration, PLAIN_PROGRESS_WIDTH));
+        }
+    }
+
+    /// Ends a redrawn line, so what's printed next starts on its own.
+    fn finish(&mut self) {
+        if std::mem::take(&mut self.drawn) {
+            eprintln!();
+        }
+    }
+}
+
+/// Waits out `length` in [`PLAIN_POLL`] steps, calling `on_poll` with the time
+/// waited so far. False if a stop was requested first.
+async fn plain_wait(length: Duration, stop_requested: impl Fn() -> bool, mut on_poll: impl FnMut(Duration)) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < length {
+        if stop_requested() {
+            return false;
+        }
+        on_poll(start.elapsed());
+        sleep(PLAIN_POLL.min(length.saturating_sub(start.elapsed()))).await;
+    }
+    true
+}
+
+/// The timer without a screen: a line when it starts and one when it's done,
+/// with a progress bar on stderr in between. Returns early on a stop request
+/// (`--http`, SIGTERM), like the screen does.
+async fn run_timer_plain(duration: Duration, options: TimerOptions) -> Result<()> {
+    let stop_requested = || options.http.as_ref().is_some_and(|http| http.stop_requested()) || teardown::requested();
+
+    // The caller has already said when it starts
+    let until_start = options.start_at.map(|start| until(Local::now().naive_local(), start)).unwrap_or_default();
+    let lead = until_start + options.start_in.unwrap_or_default() + Duration::from_secs(options.count_in.into());
+    if !plain_wait(lead, stop_requested, |_| {}).await {
+        return Ok(());
+    }
+    if options.start_beep {
+        play_bell(SoundEvent::TimerDone, &options.sound);
+    }
+    let mut repeating = false;
+    loop {
+        if repeating {
+            info!("[TIMER] Starting timer for {} again...", format_duration(duration));
+        }
+        let now_ms = unix_millis(SystemTime::now());
+        let timer_status = status::ClockStatus {
+            tag: options.tag.clone(),
+            ..status::ClockStatus::countdown(status::ClockKind::Timer, options.label.clone(), now_ms, duration)
+        };
+        let _status_file = status::publish(&timer_status).ok();
+        if let Some(http) = &options.http {
+            http.publish(&timer_status);
+        }
+        fire_timer_event(&options, hooks::Event::Start, duration);
+        let started = Instant::now();
+        let mut progress = PlainProgress::new(duration);
+        let finished = plain_wait(duration, stop_requested, |elapsed| {
+            if let Some(progress) = &mut progress {
+                progress.update(elapsed);
+            }
+        })
+        .await;
+        if let Some(progress) = &mut progress {
+            progress.finish();
+        }
+        if !finished {
+            cancel_timer(&options, duration, clock::ceil_secs(duration.saturating_sub(started.elapsed())));
+            return Ok(());
+        }
+        finish_timer(&options, duration).await;
+        println!("[TIMER] Finished at {}", format_clock(&Local::now().time()));
+        if !(options.every && should_repeat(Local::now().naive_local(), options.repeat_until)) {
+            return Ok(());
+        }
+        repeating = true;
+    }
+}
+
+/// Bails unless stdout is a terminal, for screens that have no plain version.
+fn require_terminal(what: &str, hint: &str) -> Result<()> {
+    if !io::stdout().is_terminal() {
+        anyhow::bail!("{} needs a terminal, but stdout isn't one{}", what, hint);
+    }
+    Ok(())
+}
+
+/// Bails unless both stdout and stdin are terminals, for screens that are
+/// nothing without the keyboard.
+fn require_keyboard(what: &str) -> Result<()> {
+    require_terminal(what, "")?;
+    if ui::is_view_only() {
+        anyhow::bail!("{} needs keyboard input, but stdin isn't a terminal", what);
+    }
+    Ok(())
+}
+
+/// What a key on the finished timer screen asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FinishedAction {
+    /// Run the same timer again from its full duration
+    Restart,
+    Quit,
+}
+
+fn finished_action(key: &KeyEvent) -> Option<FinishedAction> {
+    match key.code {
+        KeyCode::Char('r') => Some(FinishedAction::Restart),
+        _ if ui::is_dismiss_event(key) => Some(FinishedAction::Quit),
+        _ => None,
+    }
+}
+
+/// Waits on the finished screen for 'r' or a dismiss key. There's nobody to
+/// press either in view-only mode, so that (like a stop request) quits.
+fn wait_on_finished_screen(options: &TimerOptions) -> Result<FinishedAction> {
+    while !ui::is_view_only() {
+        if options.http.as_ref().is_some_and(|http| http.stop_requested()) || teardown::requested() {
+            break;
+        }
+        if let Some(action) = ui::poll_key(Duration::from_millis(100))?.and_then(|key| finished_action(&key)) {
+            return Ok(action);
+        }
+    }
+    Ok(FinishedAction::Quit)
+}
+
+/// Timer redraw interval. Divides a second evenly so every whole second is a frame boundary.
+const TIMER_FRAME: Duration = Duration::from_millis(200);
+
+/// How long `clockeroo selftest` runs its timer.
+const SELFTEST_LENGTH: Duration = Duration::from_secs(10);
+
+/// `run_timer_ui`'s countdown and frame waits without the screen, and how long
+/// they really took to run out.
+fn run_selftest(duration: Duration) -> Result<Duration> {
+    // Nothing to read keys for, so each poll waits its frame out as on a view-only screen
+    ui::set_view_only(true);
+    let countdown = clock::Countdown::new(duration);
+    let start_time = Instant::now();
+    loop {
+        let elapsed = countdown.elapsed(start_time.elapsed());
+        if elapsed >= duration {
+            break;
+        }
+        ui::poll_key(timer_frame_wait(&countdown, start_time, elapsed, duration))?;
+    }
+    Ok(start_time.elapsed())
+}
+
+/// How long the timer screen waits for a key after drawing the frame for
+/// `elapsed`: to the next frame boundary counted from the start (or right at
+/// the finish), not a fixed sleep after each frame, so nothing drifts.
+fn timer_frame_wait(countdown: &clock::Countdown, start_time: Instant, elapsed: Duration, duration: Duration) -> Duration {
+    if countdown.is_paused() {
+        return TIMER_FRAME;
+    }
+    let mut next_tick = clock::next_boundary(elapsed, TIMER_FRAME);
+    if elapsed < duration {
+        next_tick = next_tick.min(duration);
+    }
+    next_tick.saturating_sub(countdown.elapsed(start_time.elapsed()))
+}
+
+/// "expected 10.000s, actual 10.013s, drift +13ms".
+fn format_drift(expected: Duration, actual: Duration) -> String {
+    let drift = ((actual.as_secs_f64() - expected.as_secs_f64()) * 1000.0).round() as i64;
+    format!("expected {:.3}s, actual {:.3}s, drift {:+}ms", expected.as_secs_f64(), actual.as_secs_f64(), drift)
+}
+
+async fn run_timer_ui(duration: Duration, options: TimerOptions) -> Result<()> {
+    let mut terminal = ui::enter_screen()?;
+
+    if let Some(start) = options.start_at {
+        let what = format!("Timer: {}", format_duration(duration));
+        if !wait_for_start(&mut terminal, start, &what, options.spinner).await? {
+            ui::leave_screen(terminal)?;
+            return Ok(());
+        }
+    }
+
+    if let Some(delay) = options.start_in {
+        let what = format!("Timer: {}", format_duration(duration));
+        let warmup_start = Instant::now();
+        
+        while warmup_start.elapsed() < delay {
+            // Round up so the pre-phase starts on the full delay and ends at 00:01
+            let left = clock::ceil_secs(delay.saturating_sub(warmup_start.elapsed()));
+            
+            terminal.draw(|f| ui::draw_start_in(f, left, &what))?;
+            
+            let quit = ui::poll_key(Duration::from_millis(100))?.is_some_and(|key| ui::is_quit_event(&key));
+            if quit || teardown::requested() {
+                // Cancelled before the timer ever started
+                ui::leave_screen(terminal)?;
+                return Ok(());
+            }
+        }
+    }
+
+    if options.count_in > 0 {
+        let count_start = Instant::now();
+        let mut beeped_at = None;
+        
+        loop {
+            let elapsed = count_start.elapsed().as_secs() as u32;
+            if elapsed >= options.count_in {
+                break;
+            }
+            let left = options.count_in - elapsed;
+            if beeped_at != Some(left) {
+                beeped_at = Some(left);
+                spawn_chime(COUNT_IN_TONE, &options.sound);
+            }
+            
+            terminal.draw(|f| ui::draw_count_in(f, left))?;
+            
+            let quit = ui::poll_key(Duration::from_millis(100))?.is_some_and(|key| ui::is_quit_event(&key));
+            if quit || teardown::requested() {
+                ui::leave_screen(terminal)?;
+                return Ok(());
+            }
+        }
+        
+        spawn_chime(COUNT_IN_GO_TONE, &options.sound);
+    }
+
+    let mut start_time = Instant::now();
+    let mut countdown = clock::Countdown::new(duration);
+    let mut frame = 0usize;
+    let now_ms = unix_millis(SystemTime::now());
+    let mut timer_status = status::ClockStatus {
+        tag: options.tag.clone(),
+        ..status::ClockStatus::countdown(status::ClockKind::Timer, options.label.clone(), now_ms, duration)
+    };
+    let mut status_file = status::publish(&timer_status).ok();
+    // The countdown runs on the monotonic clock, only the published end time follows the wall clock
+    let mut anchor = clock::WallAnchor::new(Instant::now(), unix_millis(SystemTime::now()));
+    
+    let mut overtime_fired = false;
+    let mut schedule = options.milestones.clone();
+    // Reached zero this cycle, so leaving is a dismissal rather than a cancel
+    let mut finished = false;
+    // The zone as of the last frame, to beep on the way into the critical one
+    let mut zone = None;
+    // Whole minutes left as of the last --progress-notification update
+    let mut progress_minutes = None;
+    let ticker = options.tick.and_then(|_| sound::Ticker::new(&options.sound));
+    // The second the last --tick click was for
+    let mut ticked = None;
+    // What the big time shows, 't' switches it
+    let mut readout = ui::Readout::default();
     
+    if options.start_beep {
+        play_bell(SoundEvent::TimerDone, &options.sound);
+    }
+    fire_timer_event(&options, hooks::Event::Start, duration);
+    
+    loop {
+        let now = start_time.elapsed();
+        let elapsed = countdown.elapsed(now);
+        let paused = countdown.is_paused();
+        frame = frame.wrapping_add(1);
+        let spinner = (options.spinner && !paused).then_some(frame);
+        
+        let now_ms = unix_millis(SystemTime::now());
+        timer_status.touch(now_ms, elapsed);
+        // Derived from the countdown every frame, so pauses and clock steps both carry through
+        timer_status.ends_at = Some(countdown.ends_at_ms(now, now_ms));
+        if let Some(step) = anchor.check(Instant::now(), now_ms) {
+            timer_status.started_at = clock::shift_ms(timer_status.started_at, step);
+            if let Some(file) = &mut status_file {
+                file.update(&timer_status).ok();
+            }
+            verbose!("wall clock stepped by {}ms, timer end time republished", step);
+        } else if let Some(file) = &mut status_file {
+            file.refresh(&timer_status);
+        }
+        if http_stop_requested(options.http.as_ref(), &timer_status) || teardown::requested() {
+            break;
+        }
+        
+        if elapsed >= duration && !options.overtime {
+            // Timer finished, ring before the (slower) redraw
+            finish_timer(&options, duration).await;
+            finished = true;
+            
+            // Checked once per finished cycle, a cycle already running always completes
+            let restart = if options.every && should_repeat(Local::now().naive_local(), options.repeat_until) {
+                true
+            } else if options.no_finish_screen {
+                false
+            } else {
+                terminal.draw(ui::draw_timer_finished)?;
+                wait_on_finished_screen(&options)? == FinishedAction::Restart
+            };
+            if restart {
+                start_time = Instant::now();
+                countdown = clock::Countdown::new(duration);
+                schedule.restart();
+                zone = None;
+                progress_minutes = None;
+                ticked = None;
+                finished = false;
+                fire_timer_event(&options, hooks::Event::Start, duration);
+                let now_ms = unix_millis(SystemTime::now());
+                timer_status.started_at = now_ms;
+                timer_status.touch(now_ms, Duration::ZERO);
+                timer_status.ends_at = Some(now_ms + duration.as_millis() as u64);
+                if let Some(file) = &mut status_file {
+                    file.update(&timer_status).ok();
+                }
+                continue;
+            }
+            break;
+        }
+        
+        // In overtime the loop keeps running, fire only on the first tick past zero
+        let overtime = elapsed.checked_sub(duration);
+        if overtime.is_some() && !overtime_fired {
+            overtime_fired = true;
+            finish_timer(&options, duration).await;
+            finished = true;
+            timer_status.overtime = true;
+            if let Some(file) = &mut status_file {
+                file.update(&timer_status).ok();
+            }
+            fire_timer_event(&options, hooks::Event::OvertimeStart, Duration::ZERO);
+        }
+        let remaining = clock::ceil_secs(duration.saturating_sub(elapsed));
+        let now_zone = options.zones.zone(remaining);
+        if zone != Some(now_zone) {
+            // Not on a timer that starts out in the red, only on crossing into it
+            if options.critical_beep && now_zone == zones::Zone::Critical && zone.is_some() && !is_quiet_now(options.quiet_hours.as_ref()) {
+                spawn_chime(CRITICAL_TONE, &options.sound);
+            }
+            match now_zone {
+                zones::Zone::Warn => fire_timer_event(&options, hooks::Event::Warn, remaining),
+                zones::Zone::Critical => fire_timer_event(&options, hooks::Event::Critical, remaining),
+                zones::Zone::Normal => {}
+            }
+            zone = Some(now_zone);
+            timer_status.zone = zone;
+            if let Some(file) = &mut status_file {
+                file.update(&timer_status).ok();
+            }
+        }
+        
+        // Each fires once, on the first frame past it; the time check's accent stays after
+        for milestone in schedule.advance(elapsed) {
+            if let (milestones::Kind::TimeCheck, Some(percent)) = (milestone.kind, options.warn_at_percent) {
+                warn_timer(&options, percent, remaining);
+            }
+        }
+        
+        // Paused, the countdown stands still and so do the clicks
+        if let (Some(ticker), Some(window)) = (ticker.as_ref().filter(|_| !paused), options.tick) {
+            let second = tick_second(remaining, window);
+            if second.is_some() && second != ticked {
+                ticked = second;
+                ticker.tick();
+            }
+        }
+        
+        if let Some(progress) = options.progress.as_ref().filter(|_| !paused && overtime.is_none()) {
+            let minutes = remaining.as_secs().div_ceil(60);
+            if progress_minutes != Some(minutes) {
+                progress_minutes = Some(minutes);
+                update_progress(progress, &options, Duration::from_secs(minutes * 60));
+            }
+        }
+        
+        terminal.draw(|f| {
+            let view = ui::TimerView {
+                label: options.label.as_deref(),
+                tag: options.tag.as_deref(),
+                remaining,
+                total: duration,
+                readout,
+                zone: now_zone,
+                overtime,
+                paused,
+                warning: schedule.has_passed(milestones::Kind::TimeCheck),
+                next_milestone: schedule.next().map(|next| (next.name.as_str(), clock::ceil_secs(next.at.saturating_sub(elapsed)))),
+                spinner,
+                hooks: options.focus.as_ref().filter(|session| session.has_hooks()).map(|session| session.started()),
+            };
+            if options.clock_face {
+                ui::draw_timer_face(f, &view, share_left(elapsed, duration));
+            } else {
+                ui::draw_timer_running(f, &view);
+            }
+        })?;
+
+        if let Some(key) = ui::poll_key(timer_frame_wait(&countdown, start_time, elapsed, duration))? {
+            if ui::is_quit_event(&key) || overtime.is_some() && ui::is_dismiss_event(&key) {
+                break;
+            }
+            if key.code == KeyCode::Char('t') {
+                readout = readout.next();
+            }
+            if key.code == KeyCode::Char('p') && overtime.is_none() {
+                let now = start_time.elapsed();
+                countdown.toggle_pause(now);
+                let event = if countdown.is_paused() { hooks::Event::Pause } else { hooks::Event::Resume };
+                fire_timer_event(&options, event, remaining);
+                let now_ms = unix_millis(SystemTime::now());
+                timer_status.touch(now_ms, countdown.elapsed(now));
+                timer_status.paused = countdown.is_paused();
+                timer_status.ends_at = Some(countdown.ends_at_ms(now, now_ms));
+                // Readers extrapolate from the last write, so tell them right away
+                if let Some(file) = &mut status_file {
+                    file.update(&timer_status).ok();
+                }
+            }
+        }
+    }
+    if finished {
+        fire_timer_event(&options, hooks::Event::Dismiss, Duration::ZERO);
+    } else {
+        let remaining = clock::ceil_secs(duration.saturating_sub(countdown.elapsed(start_time.elapsed())));
+        cancel_timer(&options, duration, remaining);
+    }
+
+    ui::leave_screen(terminal)?;
+
+    Ok(())
+}
+
+/// How long the running display holds still after a lap with --freeze-on-lap
+const LAP_FREEZE: Duration = Duration::from_millis(1500);
+
+/// Stopwatch spinner pace, the same as a timer's redraws
+const SPINNER_STEP: Duration = TIMER_FRAME;
+
+struct StopwatchOptions {
+    /// Fresh session, or one left running by an earlier `stopwatch start`, already saved
+    state: StopwatchState,
+    target_lap: Option<Duration>,
+    freeze_on_lap: bool,
+    notify_laps: bool,
+    raw: bool,
+    export: ExportArgs,
+    http: Option<http::Endpoint>,
+    precision: Precision,
+    spinner: bool,
+    sound: SoundOptions,
+}
+
+/// Title and body of the `--notify-laps` notification for lap number `index` (1-based)
+fn lap_notification(index: usize, lap: Duration, precision: Precision) -> (String, String) {
+    (format!("Lap {}", index), format!("Split: {}", format_stopwatch_time_with(lap, precision)))
+}
+
+async fn run_stopwatch_ui(options: StopwatchOptions) -> Result<()> {
+    let mut terminal = ui::enter_screen()?;
+
+    let stopwatch_file = get_stopwatch_file();
+    
+    // Keep laps and pauses saved as they come so `stopwatch stop` and later
+    // `stopwatch start` runs can pick the session up again
+    let mut state = options.state;
+    
+    let now_ms = unix_millis(SystemTime::now());
+    let mut clock_status = status::ClockStatus {
+        started_at: state.started_at,
+        paused: state.paused_since.is_some(),
+        ..status::ClockStatus::new(status::ClockKind::Stopwatch, None, now_ms, state.elapsed(now_ms))
+    };
+    let mut status_file = status::publish(&clock_status).ok();
+    
+    let mut target_lap = options.target_lap;
+    let mut laps = state.laps();
+    let mut last_lap_at: Duration = laps.iter().sum();
+    // Display value held until the given instant after a lap (timing continues underneath)
+    let mut frozen: Option<(Instant, Duration)> = None;
+    let frame = options.precision.frame();
+    // Steps of the wall clock are taken out, a suspend isn't where the boot
+    // clock can tell the two apart (it did pass, for whatever is being timed)
+    let mut anchor = clock::WallAnchor::with_boot_clock(Instant::now(), unix_millis(SystemTime::now()), clock::boottime_micros());
+    // Never show less than was already on screen, even if the clock misbehaves,
+    // nor less than the laps already taken add up to
+    let mut last_elapsed = last_lap_at;
+    
+    loop {
+        // One sample per frame, everything on screen is derived from it
+        let now = Instant::now();
+        let now_ms = unix_millis(SystemTime::now());
+        if let Some(step) = anchor.check_awake(now, now_ms, clock::boottime_micros()) {
+            state.shift_wall_clock(step);
+            let _ = state.save(&stopwatch_file);
+            verbose!("wall clock stepped by {}ms, stopwatch start adjusted to match", step);
+        }
+        let elapsed = state.elapsed_precise(now_ms, clock::monotonic_micros()).max(last_elapsed);
+        last_elapsed = elapsed;
+        let paused = state.paused_since.is_some();
+        clock_status.touch(now_ms, elapsed);
+        clock_status.started_at = state.started_at;
+        if let Some(file) = &mut status_file {
+            if clock_status.paused != paused {
+                clock_status.paused = paused;
+                file.update(&clock_status).ok();
+            } else {
+                file.refresh(&clock_status);
+            }
+        }
+        if http_stop_requested(options.http.as_ref(), &clock_status) {
+            break;
+        }
+        // Step the spinner at the pace of the other screens, however fast this loop redraws
+        let spinner = options.spinner.then_some((elapsed.as_millis() / frame.max(SPINNER_STEP).as_millis()) as usize);
+        let shown = match frozen {
+            Some((until, value)) if now < until => value,
+            _ => elapsed,
+        };
+        
+        terminal.draw(|f| {
+            let view = ui::StopwatchView { shown, paused, spinner, target_lap, laps: &laps, precision: options.precision };
+            ui::draw_stopwatch(f, &view);
+        })?;
+
+        // Wake on the next frame boundary counted from the start, not "now + frame",
+        // so slow draws don't push every later frame back
+        let wait = if paused { frame } else { clock::next_boundary(elapsed, frame) - elapsed };
+        if let Some(key) = ui::poll_key(wait)? {
+            match key.code {
+                KeyCode::Char('l') => {
+                    let now = elapsed;
+                    let lap = now.saturating_sub(last_lap_at);
+                    laps.push(lap);
+                    last_lap_at = now;
+                    state.laps.push(lap.as_millis() as u64);
+                    let _ = state.save(&stopwatch_file);
+                    if options.freeze_on_lap {
+                        frozen = Some((Instant::now() + LAP_FREEZE, now));
+                    }
+                    play_event_sound(SoundEvent::Lap, &options.sound);
+                    if options.notify_laps {
+                        // Off the UI loop: an unresponsive notification daemon
+                        // would otherwise stall the display for seconds
+                        let (title, body) = lap_notification(laps.len(), lap, options.precision);
+                        tokio::task::spawn_blocking(move || send_notification(&title, &body));
+                    }
+                }
+                KeyCode::Char('p') => {
+                    state.toggle_pause(unix_millis(SystemTime::now()), clock::monotonic_micros());
+                    let _ = state.save(&stopwatch_file);
+                }
+                KeyCode::Char('+') | KeyCode::Char('=') => {
+                    target_lap = target_lap.map(|t| t + Duration::from_secs(5));
+                }
+                KeyCode::Char('-') => {
+                    target_lap = target_lap.map(|t| t.saturating_sub(Duration::from_secs(5)).max(Duration::from_secs(1)));
+                }
+                KeyCode::Char('s') => {
+                    // Stop and show final time
+                    let final_time = elapsed;
+                    ui::leave_screen(terminal)?;
+                    
+                    // The final time keeps at least the milliseconds it always had
+                    let precision = options.precision.max(Precision::Ms);
+                    let exported = options.export.write(state.started_at(), final_time, &laps, precision)?;
+                    print_stop_summary(final_time, &laps, target_lap, exported, options.raw, precision);
+                    
+                    // Clean up the file
+                    let _ = fs::remove_file(&stopwatch_file);
+                    return Ok(());
+                }
+                _ if ui::is_quit_event(&key) => {
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    ui::leave_screen(terminal)?;
+    
+    // Keep the stopwatch file for later
+    info!("\n[Stopwatch still running in background]");
+    info!("Run 'clockeroo stopwatch start' to reopen it, or 'clockeroo stopwatch stop' to see the final time.");
+
+    Ok(())
+}
+
+fn print_stop_summary(
+    final_time: Duration,
+    laps: &[Duration],
+    target_lap: Option<Duration>,
+    exported: Option<PathBuf>,
+    raw: bool,
+    precision: Precision,
+) {
+    if raw {
+        println!("{}", format_raw_seconds(final_time, precision));
+        return;
+    }
+    if output::is_quiet() {
+        println!("{}", format_stopwatch_time_with(final_time, precision));
+        return;
+    }
+    
+    println!("\n[Stopwatch stopped]");
+    println!("   Final time: {}", format_stopwatch_time_with(final_time, precision));
+    print_lap_summary(laps, target_lap, precision);
+    if let Some(path) = exported {
+        println!("   Saved to: {}", path.display());
+    }
+}
+
+fn print_lap_summary(laps: &[Duration], target_lap: Option<Duration>, precision: Precision) {
+    if laps.is_empty() {
+        return;
+    }
+    
+    println!("   Laps:");
+    for (i, lap) in laps.iter().enumerate() {
+        match target_lap {
+            Some(target) => println!(
+                "     {:>2}. {}  {}",
+                i + 1,
+                format_stopwatch_time_with(*lap, precision),
+                format_delta(lap_delta_ms(*lap, target))
+            ),
+            None => println!("     {:>2}. {}", i + 1, format_stopwatch_time_with(*lap, precision)),
+        }
+    }
+    
+    if let Some(target) = target_lap {
+        let (under, _, average) = summarize_laps(laps, target);
+        println!("   Target: {} ({}/{} laps on target)", format_stopwatch_time(target), under, laps.len());
+        println!("   Average delta: {}", format_delta(average));
+    }
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+async fn show_stopwatch_time(raw: bool, export: &ExportArgs, precision: Precision) -> Result<()> {
+    let stopwatch_file = get_stopwatch_file();
+    
+    // Read and remove under the lock so a `start` can't adopt the session in between
+    let _lock = statefile::lock(&stopwatch_file)?;
+    if !stopwatch_file.exists() {
+        println!("[ERROR] No stopwatch is currently running.");
+        println!("Start one with: clockeroo stopwatch start");
+        return Ok(());
+    }
+    
+    let Some(state) = StopwatchState::load_or_clear(&stopwatch_file)? else {
+        return Ok(());
+    };
+    let elapsed = state.elapsed_precise(unix_millis(SystemTime::now()), clock::monotonic_micros());
+    let laps = state.laps();
+    let exported = export.write(state.started_at(), elapsed, &laps, precision)?;
+    print_stop_summary(elapsed, &laps, None, exported, raw, precision);
+    
+    // Clean up the file
+    let _ = fs::remove_file(&stopwatch_file);
+    
+    Ok(())
+}
+
+/// Resolves the next occurrence of `alarm_time` after `now`.
+/// Returns the target and whether it rolled over to tomorrow.
+fn resolve_alarm_target(now: NaiveDateTime, alarm_time: NaiveTime) -> (NaiveDateTime, bool) {
+    let target = now.date().and_time(alarm_time);
+    
+    // If the alarm time has already passed today, set it for tomorrow
+    if target <= now {
+        (target + chrono::Duration::days(1), true)
+    } else {
+        (target, false)
+    }
+}
+
+/// How long from `from` to the next `to`, across midnight if `to` is earlier.
+fn duration_between(from: NaiveTime, to: NaiveTime) -> Duration {
+    let span = to - from;
+    let span = if span < chrono::Duration::zero() { span + chrono::Duration::days(1) } else { span };
+    span.to_std().unwrap_or_default()
+}
+
+/// When a `--start-at` clock time next comes around, and whether that's tomorrow.
+fn start_target(now: NaiveDateTime, start_at: NaiveTime) -> (NaiveDateTime, bool) {
+    resolve_alarm_target(now, start_at)
+}
+
+/// How long from `now` until `then`, zero if it's already past.
+fn until(now: NaiveDateTime, then: NaiveDateTime) -> Duration {
+    (then - now).to_std().unwrap_or_default()
+}
+
+/// The `--start-at` pre-phase: counts down to `start` on the "Waiting to Start"
+/// screen. Returns false if it was cancelled first.
+async fn wait_for_start(
+    terminal: &mut ratatui::Terminal<ui::ScreenBackend>,
+    start: NaiveDateTime,
+    what: &str,
+    spinner: bool,
+) -> Result<bool> {
+    let start_datetime = Local
+        .from_local_datetime(&start)
+        .earliest()
+        .ok_or_else(|| anyhow::anyhow!("Invalid datetime conversion"))?;
+    let mut frame = 0usize;
+
+    let started = clock::wait_until(&clock::SystemClock, start_datetime, ALARM_FRAME, |now| {
+        if teardown::requested() {
+            return Ok(false);
+        }
+        frame = frame.wrapping_add(1);
+        terminal.draw(|f| {
+            let view = ui::StartWaitView {
+                start_at: start.time(),
+                until: start_datetime.signed_duration_since(now),
+                tomorrow: start.date() > now.date_naive(),
+                what,
+                spinner: spinner.then_some(frame),
+            };
+            ui::draw_start_waiting(f, &view);
+        })?;
+        let quit = ui::poll_key(Duration::ZERO)?.is_some_and(|key| ui::is_quit_event(&key));
+        Ok(!quit)
+    })
+    .await?;
+    Ok(started.is_some())
+}
+
+struct AlarmOptions {
+    /// When a `--start-at` alarm is armed
+    start_at: Option<NaiveDateTime>,
+    max_ring: Option<Duration>,
+    /// `--snooze-max`, unlimited without it
+    snooze_max: Option<u32>,
+    /// Time between `--escalate` stages
+    escalate: Option<Duration>,
+    /// `--fade-in`: ring continuously, rising to full volume over this long
+    fade_in: Option<Duration>,
+    /// `--no-finish-screen`: leave as soon as it has rung
+    no_finish_screen: bool,
+    http: Option<http::Endpoint>,
+    quiet_hours: Option<QuietHours>,
+    /// `--on` commands
+    on: hooks::EventHooks,
+    visual_bell: bool,
+    spinner: bool,
+    sound: SoundOptions,
+}
+
+/// How many history suggestions the timer picker and `suggest` offer.
+const SUGGESTIONS: usize = 3;
+
+/// Exit code when an alarm hit `--max-ring` without being dismissed, so wrappers can escalate.
+const EXIT_ALARM_UNACKNOWLEDGED: i32 = 3;
+
+/// Default time between `--escalate` stages.
+const ESCALATE_STEP: Duration = Duration::from_secs(30);
+
+/// How often an escalating alarm beeps (and, once flashing, flashes) again.
+const ESCALATE_REPEAT: Duration = Duration::from_secs(2);
+
+/// How far an `--escalate` alarm has got. Each stage keeps what came before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum RingStage {
+    Notify,
+    Sound,
+    Flash,
+}
+
+/// The stage an escalating alarm is at `since_fire` after it went off, moving
+/// on one stage every `step`.
+fn escalation_stage(since_fire: Duration, step: Duration) -> RingStage {
+    if since_fire >= step * 2 {
+        RingStage::Flash
+    } else if since_fire >= step {
+        RingStage::Sound
+    } else {
+        RingStage::Notify
+    }
+}
+
+/// How long 's' puts a ringing alarm off for.
+const SNOOZE: Duration = Duration::from_secs(9 * 60);
+
+/// Snoozes taken so far on one alarm, against `--snooze-max`.
+struct Snoozes {
+    taken: u32,
+    max: Option<u32>,
+}
+
+impl Snoozes {
+    /// Whether another snooze would be allowed.
+    fn left(&self) -> bool {
+        self.max.is_none_or(|max| self.taken < max)
+    }
+
+    /// Counts a snooze, or refuses it once `max` have been taken.
+    fn take(&mut self) -> bool {
+        let allowed = self.left();
+        if allowed {
+            self.taken += 1;
+        }
+        allowed
+    }
+}
+
+/// How often the waiting alarm screen redraws and checks for keys.
+const ALARM_FRAME: Duration = Duration::from_millis(100);
+
+/// Rings the alarm at `target`. Returns how the ringing ended, or `None` if it was
+/// cancelled before it rang or there was no keyboard to dismiss it with.
+async fn run_alarm_ui(
+    clock: &impl clock::Clock,
+    alarm_time: NaiveTime,
+    target: NaiveDateTime,
+    options: AlarmOptions,
+) -> Result<Option<history::Outcome>> {
     let target_datetime = Local
         .from_local_datetime(&target)
         .single()
         .ok_or_else(|| anyhow::anyhow!("Invalid datetime conversion"))?;
     
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let mut terminal = ui::enter_screen()?;
+
+    if let Some(start) = options.start_at {
+        let what = format!("Alarm: {}", format_clock(&alarm_time));
+        if !wait_for_start(&mut terminal, start, &what, options.spinner).await? {
+            ui::leave_screen(terminal)?;
+            return Ok(None);
+        }
+    }
+    let mut frame = 0usize;
+    let mut outcome = None;
+    let armed_at = Instant::now();
+    let mut clock_status = status::ClockStatus {
+        ends_at: Some(target_datetime.timestamp_millis() as u64),
+        ..status::ClockStatus::new(status::ClockKind::Alarm, None, unix_millis(SystemTime::now()), Duration::ZERO)
+    };
+    let mut status_file = status::publish(&clock_status).ok();
+    let fire_event = |event: hooks::Event, due: DateTime<Local>| {
+        let remaining = due.signed_duration_since(clock.now()).to_std().unwrap_or_default();
+        options.on.fire(
+            event,
+            &[
+                ("CLOCKEROO_KIND", "alarm".to_string()),
+                ("CLOCKEROO_LABEL", String::new()),
+                ("CLOCKEROO_REMAINING", clock::ceil_secs(remaining).as_secs().to_string()),
+            ],
+        );
+    };
+    fire_event(hooks::Event::Start, target_datetime);
+    let mut snoozes = Snoozes { taken: 0, max: options.snooze_max };
+    let mut due = target_datetime;
 
+    // Wait for it to ring, then ring until dismissed; a snooze waits again
     loop {
-        let now = Local::now();
-        
-        if now >= target_datetime {
-            // Alarm triggered
+        // After any --start-at wait, which may have crossed midnight
+        // Further out than that, the countdown's days say it
+        let tomorrow = clock.now().date_naive().succ_opt() == Some(due.date_naive());
+        let alarm_time = due.time();
+        clock_status.ends_at = Some(due.timestamp_millis() as u64);
+        let fired = clock::wait_until(clock, due, ALARM_FRAME, |now| {
+            frame = frame.wrapping_add(1);
+            let spinner = options.spinner.then_some(frame);
+            clock_status.touch(now.timestamp_millis() as u64, armed_at.elapsed());
+            if let Some(file) = &mut status_file {
+                file.refresh(&clock_status);
+            }
+            if http_stop_requested(options.http.as_ref(), &clock_status) || teardown::requested() {
+                return Ok(false);
+            }
+
+            let duration_until = due.signed_duration_since(now);
             terminal.draw(|f| {
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(2)
-                    .constraints([
-                        Constraint::Length(3),
-                        Constraint::Length(3),
-                        Constraint::Length(3),
-                        Constraint::Min(0),
-                    ])
-                    .split(f.area());
-
-                let title = Paragraph::new("ALARM!")
-                    .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK))
-                    .alignment(Alignment::Center)
-                    .block(Block::default().borders(Borders::ALL));
-                f.render_widget(title, chunks[0]);
-
-                let time_str = format!("It's {}!", alarm_time.format("%I:%M %p"));
-                let message = Paragraph::new(time_str)
-                    .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
-                    .alignment(Alignment::Center)
-                    .block(Block::default().borders(Borders::ALL));
-                f.render_widget(message, chunks[1]);
-
-                let help = Paragraph::new("Press 'q' or Ctrl-C to exit")
-                    .style(Style::default().fg(Color::Gray))
-                    .alignment(Alignment::Center);
-                f.render_widget(help, chunks[2]);
+                let view = ui::AlarmView { alarm_time, until: duration_until, tomorrow, spinner };
+                ui::draw_alarm_waiting(f, &view);
             })?;
+
+            // The clock does the waiting, only take keys that are already there
+            let quit = ui::poll_key(Duration::ZERO)?.is_some_and(|key| ui::is_quit_event(&key));
+            Ok(!quit)
+        })
+        .await?;
+
+        if fired.is_none() {
+            fire_event(hooks::Event::Cancel, due);
+            break;
+        }
+        fire_event(hooks::Event::Finish, due);
+        let quiet = is_quiet_now(options.quiet_hours.as_ref());
+        let mut ringer = None;
+        if !quiet {
+            // An escalating alarm starts with just the notification
+            if options.escalate.is_none() {
+                match options.fade_in {
+                    Some(fade_in) => ringer = Some(sound::Ringer::start(SoundEvent::Alarm, &options.sound, fade_in)),
+                    None => play_bell(SoundEvent::Alarm, &options.sound),
+                }
+            }
+            notify::send_alarm_notification("Alarm!", &format!("It's {}!", format_clock(&alarm_time)));
+            if options.visual_bell && options.escalate.is_none() {
+                ui::visual_bell().await;
+            }
+        }
+        let ring_start = Instant::now();
+        let mut rung_at: Option<Instant> = None;
+        let mut snoozed = false;
+        let snooze = if snoozes.left() { ui::Snooze::Available } else { ui::Snooze::UsedUp };
+
+        // Ring until dismissed or snoozed, or until --max-ring runs out
+        loop {
+            // Rung once already, leave it at that
+            if options.no_finish_screen {
+                outcome = Some(history::Outcome::Finished);
+                break;
+            }
+
+            // Nothing could dismiss it in view-only mode, so ring once and close rather than hang on the screen
+            if ui::is_view_only() && options.max_ring.is_none() && options.http.is_none() {
+                break;
+            }
+
+            if let Some(step) = options.escalate.filter(|_| !quiet) {
+                let stage = escalation_stage(ring_start.elapsed(), step);
+                if stage >= RingStage::Sound && rung_at.is_none_or(|at| at.elapsed() >= ESCALATE_REPEAT) {
+                    rung_at = Some(Instant::now());
+                    // A fading alarm rings on its own from the sound stage on
+                    match options.fade_in {
+                        Some(fade_in) if ringer.is_none() => ringer = Some(sound::Ringer::start(SoundEvent::Alarm, &options.sound, fade_in)),
+                        Some(_) => {}
+                        None => play_bell(SoundEvent::Alarm, &options.sound),
+                    }
+                    if stage == RingStage::Flash {
+                        ui::visual_bell().await;
+                    }
+                }
+            }
+
+            let auto_dismiss_in = options.max_ring.map(|max| max.saturating_sub(ring_start.elapsed()));
+            if auto_dismiss_in == Some(Duration::ZERO) {
+                if !quiet {
+                    notify::send_alarm_notification(
+                        "Alarm unacknowledged",
+                        &format!("The {} alarm rang for {} without being dismissed.", format_clock(&alarm_time), format_duration(ring_start.elapsed())),
+                    );
+                }
+                outcome = Some(history::Outcome::Missed);
+                break;
+            }
+
+            terminal.draw(|f| ui::draw_alarm_ringing(f, alarm_time, auto_dismiss_in, snooze))?;
+            if options.http.as_ref().is_some_and(|http| http.stop_requested()) || teardown::requested() {
+                outcome = Some(history::Outcome::Dismissed);
+                break;
+            }
+
+            if let Some(key) = ui::poll_key(Duration::from_millis(100))? {
+                if ui::is_dismiss_event(&key) {
+                    outcome = Some(history::Outcome::Dismissed);
+                    break;
+                }
+                // Past --snooze-max, 's' does nothing
+                if key.code == KeyCode::Char('s') && snoozes.take() {
+                    snoozed = true;
+                    break;
+                }
+            }
+        }
+        drop(ringer);
+        if snoozed {
+            due = clock.now() + SNOOZE;
+            continue;
+        }
+        if outcome == Some(history::Outcome::Dismissed) {
+            fire_event(hooks::Event::Dismiss, due);
+        }
+        break;
+    }
+
+    ui::leave_screen(terminal)?;
+
+    Ok(outcome)
+}
+
+/// `config --dump`: the config file's settings with defaults filled in and
+/// global flags applied, as JSON.
+fn effective_config(
+    config: &config::Config,
+    twelve_hour: bool,
+    sound: &SoundOptions,
+    sound_theme: Option<&str>,
+    display: serde_json::Value,
+) -> serde_json::Value {
+    let mut settings = serde_json::to_value(config).unwrap_or_default();
+    settings["config_file"] = serde_json::json!(config::config_path());
+    settings["quick_durations"] = serde_json::json!(config.quick_durations());
+    // "auto" resolved against the locale (or a flag), as every screen will show it
+    settings["time_format"] = serde_json::json!(if twelve_hour { "12h" } else { "24h" });
+    settings["sound"] = serde_json::json!({
+        "bell": sound.bell,
+        "theme": sound_theme,
+        "dnd": format!("{:?}", sound.dnd).to_lowercase(),
+        "device": sound.device,
+        "beep_fade_in": format!("{}ms", sound.envelope.fade_in.as_millis()),
+        "beep_fade_out": format!("{}ms", sound.envelope.fade_out.as_millis()),
+        "waveform": format!("{:?}", sound.waveform).to_lowercase(),
+    });
+    settings["display"] = display;
+    settings
+}
+
+/// One line for a batch of focus hooks, plus a warning for each that failed.
+fn print_focus_report(stage: &str, report: &focus::Report) {
+    info!("[FOCUS] {}", report.summary(stage));
+    for failure in &report.failed {
+        eprintln!("[WARN] {} hook failed: {}", stage, failure);
+    }
+}
+
+/// A duration that may be zero: `--beep-fade-in`/`--beep-fade-out` for a hard
+/// edge, `notifications.coalesce` to send every notification as it comes.
+fn parse_duration_or_zero(s: &str) -> Result<Duration> {
+    match s.trim().trim_end_matches(|c: char| c.is_ascii_alphabetic()).trim_end().parse::<f64>() {
+        Ok(0.0) => Ok(Duration::ZERO),
+        _ => parse_duration(s),
+    }
+}
+
+/// The sound flags this run was given, for a daemon started to ring in the background.
+fn sound_args(cli: &Cli) -> Vec<String> {
+    use clap::ValueEnum;
+
+    let name = |value: Option<clap::builder::PossibleValue>| value.map(|value| value.get_name().to_string());
+    let with_values = [
+        ("--bell", cli.bell.and_then(|bell| name(bell.to_possible_value()))),
+        ("--sound-theme", cli.sound_theme.clone()),
+        ("--sound-device", cli.sound_device.clone()),
+        ("--beep-fade-in", cli.beep_fade_in.map(|fade| format!("{}ms", fade.as_millis()))),
+        ("--beep-fade-out", cli.beep_fade_out.map(|fade| format!("{}ms", fade.as_millis()))),
+        ("--waveform", name(cli.waveform.to_possible_value())),
+    ];
+    let switches = [("--bell-only", cli.bell_only), ("--respect-dnd", cli.respect_dnd), ("--ignore-dnd", cli.ignore_dnd)];
+    with_values
+        .into_iter()
+        .filter_map(|(flag, value)| Some([flag.to_string(), value?]))
+        .flatten()
+        .chain(switches.into_iter().filter(|(_, given)| *given).map(|(flag, _)| flag.to_string()))
+        .collect()
+}
+
+/// The beep's fades from the flags, shortened with a warning to fit in a beep.
+fn beep_envelope(fade_in: Option<Duration>, fade_out: Option<Duration>) -> sound::Envelope {
+    let default = sound::Envelope::default();
+    let fade_in = fade_in.unwrap_or(default.fade_in);
+    let fade_out = fade_out.unwrap_or(default.fade_out);
+    let envelope = sound::Envelope::fitted(fade_in, fade_out);
+    if envelope.fade_in != fade_in || envelope.fade_out != fade_out {
+        eprintln!(
+            "[WARN] A beep lasts {}ms, fading in over {}ms and out over {}ms instead",
+            sound::BEEP_LENGTH.as_millis(),
+            envelope.fade_in.as_millis(),
+            envelope.fade_out.as_millis()
+        );
+    }
+    envelope
+}
+
+/// Finds the output device `--sound-device` names, warning (before any TUI is up)
+/// and falling back to the default device if nothing matches.
+fn resolve_sound_device(wanted: &str) -> Option<String> {
+    let names = sound::output_device_names().unwrap_or_default();
+    match sound::match_device(&names, wanted) {
+        Some(name) => {
+            verbose!("playing sounds on '{}'", name);
+            Some(name.to_string())
+        }
+        None => {
+            eprintln!("[WARN] No sound device matches '{}', using the default (see `clockeroo devices`)", wanted);
+            None
+        }
+    }
+}
+
+/// How often the dashboard re-reads the status files.
+const DASHBOARD_REFRESH: Duration = Duration::from_secs(1);
+
+async fn run_dashboard_ui() -> Result<()> {
+    let mut terminal = ui::enter_screen()?;
+
+    loop {
+        let clocks = status::running_clocks();
+        let now_ms = unix_millis(SystemTime::now());
+        terminal.draw(|f| ui::draw_dashboard(f, &ui::DashboardView { clocks: &clocks, now_ms }))?;
+
+        if let Some(key) = ui::poll_key(DASHBOARD_REFRESH)? {
+            if ui::is_quit_event(&key) {
+                break;
+            }
+        }
+    }
+
+    ui::leave_screen(terminal)?;
+
+    Ok(())
+}
+
+/// Resolves the timer duration, label and message from the CLI and an optional preset.
+/// An explicit duration or label on the command line overrides the preset's.
+fn resolve_timer(
+    duration: Option<&str>,
+    label: Option<String>,
+    preset: Option<&config::Preset>,
+) -> Result<(Duration, Option<String>, Option<String>)> {
+    let duration = match (duration, preset) {
+        (Some(duration), _) => parse_duration(duration)?,
+        (None, Some(preset)) => parse_duration(&preset.duration)?,
+        (None, None) => anyhow::bail!("Give the timer a duration (e.g., 5m) or a --preset"),
+    };
+    let label = label.or_else(|| preset.and_then(|p| p.label.clone()));
+    let message = preset.and_then(|p| p.message.clone());
+    Ok((duration, label, message))
+}
+
+/// How long `timer until eod` (or just `timer eod`, `eow` alike) has left to
+/// run from `now`, `None` for a plain duration. A second duration without
+/// `--parallel` is an error here, as clap leaves it to us.
+fn resolve_until(duration: Option<&str>, more: &[String], now: NaiveDateTime, workday: &config::WorkdayConfig) -> Result<Option<Duration>> {
+    let target = match (duration, more) {
+        (Some(until), [end]) if until.eq_ignore_ascii_case("until") => parse_workday_target(end, now, workday)
+            .unwrap_or_else(|| Err(anyhow::anyhow!("A timer runs until eod or eow, not '{}' (use an alarm for a clock time)", end)))?,
+        (Some(duration), []) => match parse_workday_target(duration, now, workday) {
+            Some(target) => target?,
+            None => return Ok(None),
+        },
+        (_, []) => return Ok(None),
+        _ => anyhow::bail!("More than one duration runs timers side by side, add --parallel"),
+    };
+    Ok(Some(until(now, target)))
+}
+
+/// A timer as the daemon holds it, finishing `duration` after a `lead` of waiting
+/// (--start-at, --start-in) that isn't part of its length.
+fn background_timer(
+    duration: Duration,
+    lead: Duration,
+    label: Option<String>,
+    tag: Option<String>,
+    message: Option<String>,
+    exec: Option<String>,
+    quiet_hours: Option<QuietHours>,
+) -> Result<store::Entry> {
+    let due = lead
+        .checked_add(duration)
+        .and_then(|wait| chrono::Duration::from_std(wait).ok())
+        .and_then(|wait| Local::now().checked_add_signed(wait))
+        .ok_or_else(|| anyhow::anyhow!("{} from now is past the end of the calendar", format_duration(lead.saturating_add(duration))))?;
+    Ok(store::Entry {
+        id: 0,
+        kind: store::EntryKind::Timer,
+        label,
+        tag,
+        message,
+        due,
+        repeat: Vec::new(),
+        exec,
+        quiet_hours,
+        skip_until: None,
+        imported: None,
+        total_ms: Some(duration.as_millis() as u64),
+        progress: None,
+    })
+}
+
+/// Hands a timer to the daemon, starting it first if needed.
+#[cfg(unix)]
+fn detach_timer(entry: store::Entry, duplicates: store::Duplicates, sound_args: &[String]) -> Result<()> {
+    let due = entry.due;
+    daemon::ensure_running(sound_args)?;
+    let response = ipc::send(&ipc::Request::Add { entry: Box::new(entry), duplicates })?;
+    for replaced in &response.entries {
+        println!("[TIMER] Replaced timer #{} '{}'", replaced.id, replaced.display_name());
+    }
+    println!(
+        "[TIMER] Timer #{} running in the background, finishes at {}",
+        response.id.unwrap_or_default(),
+        format_clock_secs(&due)
+    );
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn detach_timer(_entry: store::Entry, _duplicates: store::Duplicates, _sound_args: &[String]) -> Result<()> {
+    anyhow::bail!("--detach is only supported on Unix-like systems")
+}
+
+/// Registers an alarm with the daemon, starting it first if needed. Returns its id.
+#[cfg(unix)]
+fn arm_in_daemon(target: NaiveDateTime, quiet_hours: Option<QuietHours>, sound_args: &[String]) -> Result<u64> {
+    let due = Local
+        .from_local_datetime(&target)
+        .single()
+        .ok_or_else(|| anyhow::anyhow!("Invalid datetime conversion"))?;
+    let entry = store::Entry {
+        id: 0,
+        kind: store::EntryKind::Alarm,
+        label: None,
+        tag: None,
+        message: None,
+        due,
+        repeat: Vec::new(),
+        exec: None,
+        quiet_hours,
+        skip_until: None,
+        imported: None,
+        total_ms: None,
+        progress: None,
+    };
+    
+    daemon::ensure_running(sound_args)?;
+    let response = ipc::send(&ipc::Request::Add { entry: Box::new(entry), duplicates: store::Duplicates::Allow })?;
+    response.id.ok_or_else(|| anyhow::anyhow!("The daemon didn't return an id"))
+}
+
+#[cfg(not(unix))]
+fn arm_in_daemon(_target: NaiveDateTime, _quiet_hours: Option<QuietHours>, _sound_args: &[String]) -> Result<u64> {
+    anyhow::bail!("the daemon is only supported on Unix-like systems")
+}
+
+/// `alarm list`: alarms waiting in open terminals, then the ones the daemon holds.
+fn print_alarms() {
+    let now = unix_millis(SystemTime::now());
+    let foreground: Vec<status::ClockStatus> = status::running_clocks()
+        .into_iter()
+        .filter(|clock| clock.kind == status::ClockKind::Alarm)
+        .collect();
+    let mut any = !foreground.is_empty();
+    
+    for alarm in &foreground {
+        let Some(due) = alarm.ends_at.and_then(|ms| Local.timestamp_millis_opt(ms as i64).single()) else {
+            continue;
+        };
+        println!(
+            "  -    alarm  {} {}  in a terminal  (in {})",
+            due.format("%a"),
+            format_clock_secs(&due),
+            format_duration(alarm.remaining(now).unwrap_or_default())
+        );
+    }
+    
+    #[cfg(unix)]
+    if daemon::running_pid().is_some() {
+        match ipc::send(&ipc::Request::Status) {
+            Ok(response) => {
+                let alarms: Vec<store::Entry> = response
+                    .entries
+                    .into_iter()
+                    .filter(|entry| entry.kind == store::EntryKind::Alarm)
+                    .collect();
+                any |= !alarms.is_empty();
+                if !alarms.is_empty() {
+                    print_daemon_entries(&alarms);
+                }
+            }
+            Err(err) => eprintln!("[WARN] Couldn't reach the daemon: {:#}", err),
+        }
+    }
+    
+    if !any {
+        println!("No alarms set.");
+    }
+}
+
+/// The `alarm save`d alarms, for `alarm list`.
+fn print_saved_alarms() -> Result<()> {
+    let saved = saved_alarms::SavedAlarms::load(&saved_alarms::saved_alarms_path())?;
+    if saved.alarms.is_empty() {
+        return Ok(());
+    }
+    println!("Saved (start with: clockeroo alarm start <name>):");
+    let width = saved.alarms.keys().map(|name| name.chars().count()).max().unwrap_or_default();
+    for (name, time) in &saved.alarms {
+        let time = parse_alarm_time(time).map_or_else(|_| time.clone(), |at| format_clock(&at));
+        println!("  {:<width$}  {}", name, time, width = width);
+    }
+    Ok(())
+}
+
+/// Whether `command` starts with the banner on stdout. Raw output is meant for
+/// scripts and listings are read at a glance, both are kept to just the result.
+fn prints_banner(command: &Commands) -> bool {
+    !matches!(
+        command,
+        Commands::Stopwatch { action: StopwatchAction::Start { raw: true, .. } | StopwatchAction::Stop { raw: true, .. } }
+            | Commands::Timer { estimate: true, .. }
+            | Commands::Timer { render_once: true, .. }
+            | Commands::Config { .. }
+            | Commands::Status
+            | Commands::Cancel { .. }
+            | Commands::Suggest
+            | Commands::Stats { .. }
+            | Commands::Queue { action: QueueAction::Add { .. } | QueueAction::List }
+            | Commands::Devices
+            | Commands::TestSound { .. }
+            | Commands::Selftest
+            | Commands::Alarm { action: Some(AlarmAction::List | AlarmAction::Save { .. } | AlarmAction::Skip { .. } | AlarmAction::Unskip { .. } | AlarmAction::Import { .. }), .. }
+    )
+}
+
+/// The `clockeroo stats` report: a block to read, or JSON with lengths in seconds.
+fn format_stats(label: Option<&str>, stats: &history::Stats, format: measure::Format) -> String {
+    match format {
+        measure::Format::Text => {
+            let length = |duration: Option<Duration>| duration.map(format_duration).unwrap_or_else(|| "-".to_string());
+            let rate = stats.completion_rate().map(|rate| format!("{:.0}%", rate * 100.0)).unwrap_or_else(|| "-".to_string());
+            let days = |n: u32| if n == 1 { "1 day".to_string() } else { format!("{} days", n) };
+            let mut out = format!("{}\n", label.unwrap_or("All timers"));
+            out += &format!("  Completed       {}\n", stats.completed);
+            out += &format!("  Cancelled       {}\n", stats.cancelled);
+            out += &format!("  Completion rate {}\n", rate);
+            out += &format!("  Average length  {}\n", length(stats.average));
+            out += &format!("  Longest         {}\n", length(stats.longest));
+            out += &format!("  Current streak  {}\n", days(stats.current_streak));
+            out += &format!("  Longest streak  {}\n", days(stats.longest_streak));
+            out
+        }
+        measure::Format::Json => {
+            let seconds = |duration: Option<Duration>| duration.map(|duration| duration.as_secs_f64());
+            let report = serde_json::json!({
+                "label": label,
+                "completed": stats.completed,
+                "cancelled": stats.cancelled,
+                "completion_rate": stats.completion_rate(),
+                "average_seconds": seconds(stats.average),
+                "longest_seconds": seconds(stats.longest),
+                "current_streak_days": stats.current_streak,
+                "longest_streak_days": stats.longest_streak,
+            });
+            format!("{}\n", report)
+        }
+    }
+}
+
+/// `--until` dates, like 2024-08-20.
+fn parse_date(s: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| anyhow::anyhow!("Invalid date '{}', expected YYYY-MM-DD", s))
+}
+
+#[cfg(unix)]
+fn print_daemon_entries(entries: &[store::Entry]) {
+    if entries.is_empty() {
+        println!("No alarms or timers scheduled.");
+        return;
+    }
+    
+    let now = Local::now();
+    for entry in entries {
+        let remaining = (entry.due - now).to_std().unwrap_or_default();
+        // Started with --allow-duplicate, or by a client that didn't check
+        let duplicate = entries.iter().any(|other| other.id != entry.id && other.is_duplicate_of(entry));
+        let repeat = if entry.repeat.is_empty() {
+            String::new()
+        } else {
+            let days: Vec<String> = entry.repeat.iter().map(|d| d.to_string()).collect();
+            format!("  [{}]", days.join(","))
+        };
+        // A skip can push the next ring well past this week, so give the date too
+        let skipping = entry.is_skipping(now.date_naive());
+        let day = if skipping { entry.due.format("%a %b %-d") } else { entry.due.format("%a") };
+        let progress = entry.progress.map(|progress| {
+            let total = Duration::from_millis(progress.elapsed_ms + progress.remaining_ms);
+            format!(", {} elapsed of {}", format_duration(Duration::from_millis(progress.elapsed_ms)), format_duration(total))
+        });
+        println!(
+            "  #{:<3} {:<6} {} {}  {}  (in {}{}){}{}{}{}",
+            entry.id,
+            format!("{:?}", entry.kind).to_lowercase(),
+            day,
+            format_clock_secs(&entry.due),
+            ui::with_tag(entry.tag.as_deref(), &entry.display_name()),
+            format_long_duration(remaining),
+            progress.unwrap_or_default(),
+            repeat,
+            if skipping { "  (next occurrence skipped)" } else { "" },
+            entry.imported.as_ref().map(|imported| format!("  (from {})", imported.source.display())).unwrap_or_default(),
+            if duplicate { "  (duplicate label)" } else { "" }
+        );
+    }
+}
+
+fn print_config_path() {
+    match config::config_path() {
+        Some(path) if path.exists() => println!("{}", path.display()),
+        Some(path) => println!("{} (not created yet, using defaults)", path.display()),
+        None => println!("No config location (set CLOCKEROO_CONFIG or HOME), using defaults"),
+    }
+}
+
+/// `config check`, `config path` and `config init`. Returns the exit code.
+fn run_config_action(action: &ConfigAction) -> Result<i32> {
+    match action {
+        ConfigAction::Path => print_config_path(),
+        ConfigAction::Check => {
+            let Some(path) = config::config_path().filter(|path| path.exists()) else {
+                println!("ok (no config file, using defaults)");
+                return Ok(0);
+            };
+            let contents = fs::read_to_string(&path).with_context(|| format!("Failed to read config file {}", path.display()))?;
+            let problems = config::check(&contents);
+            if problems.is_empty() {
+                println!("ok");
+                return Ok(0);
+            }
+            println!("{}:", path.display());
+            for problem in &problems {
+                println!("  {}", problem);
+            }
+            return Ok(1);
+        }
+        ConfigAction::Init { force } => {
+            let path = config::config_path().ok_or_else(|| anyhow::anyhow!("No config location (set CLOCKEROO_CONFIG or HOME)"))?;
+            if path.exists() && !force {
+                anyhow::bail!("{} already exists (use --force to replace it)", path.display());
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, config::default_file()).with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("Wrote {}", path.display());
+        }
+    }
+    Ok(0)
+}
+
+/// Runs `clockeroo time`, returning the command's exit code to pass on.
+fn time_command(command: &[String], format: measure::Format, precision: Precision) -> i32 {
+    let measurement = measure::start(command).and_then(|running| {
+        measure::ignore_interrupts();
+        running.wait()
+    });
+    let measurement = match measurement {
+        Ok(measurement) => measurement,
+        Err(err) => {
+            eprintln!("[ERROR] {:#}", err);
+            return measure::launch_failure_code(&err);
+        }
+    };
+    if output::is_quiet() && format == measure::Format::Text {
+        eprintln!("{}", format_stopwatch_time_with(measurement.wall, precision));
+    } else {
+        eprint!("{}", measure::render(format, command, &measurement, precision));
+    }
+    measure::exit_code(measurement.status)
+}
+
+/// Answers `clockeroo remaining` from the timer status files, then the daemon.
+/// Returns the exit code: 1 (with no output) when nothing is running.
+fn print_remaining(format: &str) -> i32 {
+    let now = unix_millis(SystemTime::now());
+    if let Some(timer) = status::running_timers().first() {
+        println!("{}", status::render(format, timer.label.as_deref(), timer.tag.as_deref(), timer.remaining(now).unwrap_or_default()));
+        return 0;
+    }
+    
+    #[cfg(unix)]
+    if ipc::socket_path().exists() {
+        let request = ipc::Request::Status;
+        if let Ok(response) = ipc::send_with_timeout(&request, Duration::from_millis(100)) {
+            let soonest = response.entries.iter().filter(|e| e.kind == store::EntryKind::Timer).min_by_key(|e| e.due);
+            if let Some(entry) = soonest {
+                let remaining = (entry.due - Local::now()).to_std().unwrap_or_default();
+                println!("{}", status::render(format, entry.label.as_deref(), entry.tag.as_deref(), remaining));
+                return 0;
+            }
+        }
+    }
+    
+    1
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    
+    // Prompt integrations call this constantly, keep it off the runtime, config and audio
+    if let Commands::Remaining { format } = &cli.command {
+        std::process::exit(print_remaining(format));
+    }
+    // Just arithmetic, nothing to run
+    if let Commands::Between { from, to } = &cli.command {
+        println!("{}", format_duration(duration_between(*from, *to)));
+        return Ok(());
+    }
+    // The report is about the command, the runtime would only add to it
+    if let Commands::Time { format, precision, command } = &cli.command {
+        output::set_quiet(cli.quiet);
+        std::process::exit(time_command(command, *format, *precision));
+    }
+    // A broken config shouldn't stand in the way of cleaning up
+    if let Commands::Clean { yes } = &cli.command {
+        output::set_quiet(cli.quiet);
+        return artifacts::clean(&runtime_dir(), &store::data_dir(), *yes);
+    }
+    // Before the config is loaded, a broken one is what these are for
+    if let Commands::Config { action: Some(action), .. } = &cli.command {
+        std::process::exit(run_config_action(action)?);
+    }
+    
+    let result = tokio::runtime::Runtime::new()?.block_on(run(cli));
+    // For the early returns and errors, a no-op after a normal run
+    notify::flush();
+    result
+}
+
+async fn run(mut cli: Cli) -> Result<()> {
+    // A saved alarm starts just like one given its time on the command line
+    if let Commands::Alarm { action: action @ Some(AlarmAction::Start { .. }), time, args } = &mut cli.command {
+        let Some(AlarmAction::Start { name, args: start_args }) = action.take() else { unreachable!() };
+        let saved = saved_alarms::SavedAlarms::load(&saved_alarms::saved_alarms_path())?;
+        saved.resolve(&name)?;
+        *time = saved.alarms.get(&name).cloned();
+        *args = *start_args;
+    }
+    output::set_quiet(cli.quiet);
+    output::set_verbose(cli.verbose);
+    ui::set_minimal_help(cli.minimal_help);
+    ui::set_mouse(cli.mouse);
+    ui::set_placement(ui::Placement { align: cli.align, margin: cli.margin });
+    ui::set_appearance(cli.appearance);
+    if let Some(color) = cli.label_color {
+        ui::set_label_color(color);
+    }
+    // Launchers that hand over a terminal but no keyboard (`< /dev/null`) still get the screen
+    ui::set_view_only(io::stdout().is_terminal() && !io::stdin().is_terminal());
+    let config = config::load()?;
+    if let Some(window) = config.notifications.coalesce.as_deref() {
+        notify::set_coalesce_window(parse_duration_or_zero(window)?);
+    }
+    let time_format_flag = match (cli.twelve_hour, cli.twenty_four_hour) {
+        (true, _) => Some(time_format::TimeFormat::TwelveHour),
+        (_, true) => Some(time_format::TimeFormat::TwentyFourHour),
+        _ => None,
+    };
+    let input_hint = match &cli.command {
+        Commands::Alarm { args: AlarmArgs { input_12h: true, .. }, .. } => Some(true),
+        Commands::Alarm { time: Some(time), .. } => time_format::input_prefers_12h(time),
+        _ => None,
+    };
+    time_format::set_twelve_hour(time_format::resolve(time_format_flag, config.time_format, input_hint));
+    
+    let mut sound = SoundOptions {
+        bell: cli.bell.or(cli.bell_only.then_some(sound::Bell::Terminal)).or(config.bell).unwrap_or_default(),
+        theme: cli.sound_theme.as_deref().map(|name| config.sound_theme(name)).transpose()?,
+        dnd: match (cli.respect_dnd, cli.ignore_dnd) {
+            (true, _) => dnd::DndPolicy::Respect,
+            (_, true) => dnd::DndPolicy::Ignore,
+            _ => dnd::DndPolicy::Auto,
+        },
+        critical: false,
+        device: cli.sound_device.as_deref().and_then(resolve_sound_device),
+        envelope: beep_envelope(cli.beep_fade_in, cli.beep_fade_out),
+        waveform: cli.waveform,
+    };
+    if cli.verbose && sound.dnd != dnd::DndPolicy::Ignore {
+        // Query up front so the report lands before the TUI takes over the screen
+        dnd::is_active();
+    }
+
+    // Print ASCII header
+    if !cli.quiet_start && io::stdout().is_terminal() && prints_banner(&cli.command) {
+        info!("\n{}", ASCII_HEADER);
+        info!();
+    }
+
+    let daemon_sound_args = sound_args(&cli);
+    let mut exit_code = 0;
+    match cli.command {
+        Commands::Timer { parallel: true, duration, more, quiet_hours, urgency, warn_at, critical_at, .. } => {
+            let args: Vec<String> = duration.into_iter().chain(more).collect();
+            let specs = parallel::parse_specs(&args)?;
+            require_terminal("--parallel", "")?;
+            let names: Vec<String> = specs.iter().map(|spec| format!("{} {}", spec.label, format_duration(spec.duration))).collect();
+            info!("[TIMER] Starting {} timers: {}", specs.len(), names.join(", "));
+            sound.critical = urgency == notify::Urgency::Critical;
+            let options = parallel::ParallelOptions {
+                warn_at,
+                critical_at,
+                timer_config: config.timer.clone(),
+                quiet_hours: quiet_hours.as_deref().map(QuietHours::parse).transpose()?,
+                urgency,
+                visual_bell: cli.visual_bell,
+                spinner: !cli.no_spinner,
+                sound,
+            };
+            for (spec, ending) in parallel::run_parallel_ui(specs, options).await? {
+                match ending {
+                    parallel::Ending::Finished(at) => info!("[TIMER] {} finished at {}", spec.label, format_clock(&at.time())),
+                    parallel::Ending::Dismissed(left) => info!("[TIMER] {} dismissed with {} left", spec.label, format_duration(left)),
+                    parallel::Ending::Cancelled(left) => info!("[TIMER] {} cancelled with {} left", spec.label, format_duration(left)),
+                }
+            }
+            if teardown::requested() {
+                exit_code = teardown::exit_code();
+            }
+        }
+        Commands::Timer { duration, more, preset, start_in, start_at, start_beep, count_in, label, tag, exec, on, quiet_hours, urgency, detach, replace, allow_duplicate, estimate, dry_run, format, render_once, overtime, warn_at_percent, warn_at, critical_at, critical_beep, every, repeat_until, http, progress_notification, tick, clock_face, confirm, no_finish_screen, .. } => {
+            let preset = preset.as_deref().map(|name| config.preset(name)).transpose()?;
+            let until_end = resolve_until(duration.as_deref(), &more, Local::now().naive_local(), &config.workday)?;
+            if until_end.is_some() && (start_in.is_some() || start_at.is_some()) {
+                anyhow::bail!("--start-in and --start-at can't be used with a timer until eod or eow");
+            }
+            // No duration at all: let the user pick one, unless the output is for a script
+            let (duration, label, message) = if let Some(until_end) = until_end {
+                (until_end, label.or_else(|| preset.and_then(|p| p.label.clone())), preset.and_then(|p| p.message.clone()))
+            } else if duration.is_none() && preset.is_none() && !estimate && !dry_run && !render_once {
+                require_keyboard("Picking a timer")?;
+                let suggestions = history::suggest(&history::recent(), Local::now(), SUGGESTIONS);
+                match picker::pick_timer(&suggestions, &config.quick_durations())? {
+                    // A --label given up front beats the one a suggestion was run with
+                    Some((picked, picked_label)) => (picked, label.or(picked_label), None),
+                    None => return Ok(()),
+                }
+            } else {
+                resolve_timer(duration.as_deref(), label, preset)?
+            };
+            // From the config only where there's a keyboard, scripts shouldn't stop to ask
+            let keyboard = io::stdout().is_terminal() && !ui::is_view_only();
+            let duration = if (confirm || (config.timer.confirm && keyboard)) && !estimate && !dry_run && !render_once {
+                require_keyboard("--confirm")?;
+                match picker::confirm_timer(duration, label.as_deref())? {
+                    Some(confirmed) => confirmed,
+                    None => return Ok(()),
+                }
+            } else {
+                duration
+            };
+            let tag = tag.or_else(|| label.as_deref().and_then(|label| config.tag_for(label)));
+            let start_in = start_in.map(|s| parse_duration(&s)).transpose()?;
+            let now = Local::now().naive_local();
+            let mut start_rolled_over = false;
+            let start_at = match start_at {
+                Some(at) => {
+                    let (start, rolled_over) = start_target(now, parse_alarm_time(&at)?);
+                    if rolled_over && !dry_run {
+                        info!("[TIMER] {} has already passed today, starting TOMORROW ({})", format_clock(&start.time()), start.format("%a %b %-d"));
+                    }
+                    start_rolled_over = rolled_over;
+                    Some(start)
+                }
+                None => None,
+            };
+            let until_start = start_at.map(|start| until(now, start)).unwrap_or_default();
+            let quiet_hours = quiet_hours.as_deref().map(QuietHours::parse).transpose()?;
+            let tick = tick.map(|s| parse_duration(&s)).transpose()?;
+            let zones = zones::Zones::new(
+                zones::Source::pick(warn_at.as_deref(), config.timer.warn_at.as_deref()),
+                zones::Source::pick(critical_at.as_deref(), config.timer.critical_at.as_deref()),
+                duration,
+            )?;
+            // Only the thresholds the zones kept, a config one that gave way isn't a milestone
+            let milestones = milestones::Schedule::new(
+                duration,
+                warn_at_percent,
+                warn_at.as_deref().or(config.timer.warn_at.as_deref()).filter(|_| zones.keeps_warn_at()),
+                critical_at.as_deref().or(config.timer.critical_at.as_deref()).filter(|_| zones.keeps_critical_at()),
+            );
+            let repeat_until = repeat_until
+                .map(|s| parse_alarm_time(&s).map(|until| repeat_deadline(Local::now().naive_local(), until)))
+                .transpose()?;
+            sound.critical = urgency == notify::Urgency::Critical;
+
+            if dry_run {
+                let lead = until_start + start_in.unwrap_or_default() + Duration::from_secs(count_in.into());
+                let mut plan = plan::Plan::timer(now, label, duration, lead, &milestones, every, repeat_until)?;
+                if start_rolled_over {
+                    plan = plan.note("--start-at has already passed today, so it starts tomorrow");
+                }
+                if quiet_hours.as_ref().is_some_and(|quiet| quiet.contains(plan.rings.time())) {
+                    plan = plan.note("Rings inside --quiet-hours, so without a beep or notification");
+                }
+                if detach {
+                    plan = plan.note("Runs in the daemon (--detach)");
+                }
+                print!("{}", plan.format(format, time_format::is_twelve_hour()));
+                return Ok(());
+            }
             
-            // Send notifications
-            play_bell();
-            send_notification("Alarm!", &format!("It's {}!", alarm_time.format("%I:%M %p")));
+            if estimate {
+                let total = until_start + duration + start_in.unwrap_or_default() + Duration::from_secs(count_in.into());
+                let now = Local::now().naive_local();
+                println!("Finishes at {}", format_finish_time(now, estimate_finish(now, total)?, time_format::is_twelve_hour()));
+                return Ok(());
+            }
+            
+            if render_once {
+                let (width, height) = crossterm::terminal::size().unwrap_or((80, 24));
+                let view = ui::TimerView { label: label.as_deref(), tag: tag.as_deref(), remaining: duration, total: duration, readout: ui::Readout::Remaining, zone: zones.zone(duration), overtime: None, paused: false, warning: false, next_milestone: milestones.next().map(|next| (next.name.as_str(), next.at)), spinner: None, hooks: None };
+                let rendered = match clock_face {
+                    true => ui::render_to_string(width, height, |f| ui::draw_timer_face(f, &view, 1.0))?,
+                    false => ui::render_to_string(width, height, |f| ui::draw_timer_running(f, &view))?,
+                };
+                print!("{}", rendered);
+                return Ok(());
+            }
+            
+            if detach {
+                if count_in > 0 {
+                    anyhow::bail!("--count-in can't be used with --detach");
+                }
+                if every {
+                    anyhow::bail!("--every can't be used with --detach");
+                }
+                if http.is_some() {
+                    anyhow::bail!("--http can't be used with --detach");
+                }
+                if progress_notification {
+                    anyhow::bail!("--progress-notification can't be used with --detach");
+                }
+                if tick.is_some() {
+                    anyhow::bail!("--tick can't be used with --detach");
+                }
+                if !on.is_empty() {
+                    anyhow::bail!("--on can't be used with --detach (--exec still runs when it finishes)");
+                }
+                // A default value, so clap can't tell it was given
+                if urgency != notify::Urgency::Normal {
+                    anyhow::bail!("--urgency can't be used with --detach");
+                }
+                let duplicates = match (replace, allow_duplicate) {
+                    (true, _) => store::Duplicates::Replace,
+                    (_, true) => store::Duplicates::Allow,
+                    _ => store::Duplicates::Refuse,
+                };
+                let entry = background_timer(duration, until_start + start_in.unwrap_or_default(), label, tag, message, exec, quiet_hours)?;
+                return detach_timer(entry, duplicates, &daemon_sound_args);
+            }
             
-            // Wait for user to quit
-            loop {
-                if event::poll(Duration::from_millis(100))? {
-                    if let Event::Key(key) = event::read()? {
-                        if key.code == KeyCode::Char('q') || key.code == KeyCode::Char('c') && key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                            break;
+            let http = http.map(http::Endpoint::serve).transpose()?;
+            // So `cancel` hooks still run, and the cancellation is logged, when stopped by a signal rather than a key
+            teardown::catch_signals();
+            teardown::catch_interrupt();
+            match start_at {
+                Some(start) => info!("[TIMER] Timer for {} starts at {}...", format_duration(duration), format_clock(&start.time())),
+                None => info!("[TIMER] Starting timer for {}...", format_duration(duration)),
+            }
+            let options = TimerOptions {
+                start_in,
+                start_at,
+                start_beep,
+                count_in,
+                label,
+                tag,
+                message,
+                exec,
+                on: hooks::EventHooks::new(on),
+                quiet_hours,
+                urgency,
+                overtime,
+                warn_at_percent,
+                zones,
+                milestones,
+                critical_beep,
+                tick,
+                every,
+                clock_face,
+                no_finish_screen,
+                repeat_until,
+                http,
+                progress: progress_notification.then(notify::ProgressNotification::new).flatten(),
+                focus: None,
+                visual_bell: cli.visual_bell,
+                spinner: !cli.no_spinner,
+                sound,
+            };
+            run_timer(duration, options).await?;
+            if teardown::requested() {
+                exit_code = teardown::exit_code();
+            }
+        }
+        Commands::Stopwatch { action } => {
+            match action {
+                StopwatchAction::Start { target_lap, freeze_on_lap, notify_laps, new, start_beep, raw, export, http, precision } => {
+                    let target_lap = target_lap.map(|t| parse_duration(&t)).transpose()?;
+                    export.validate()?;
+                    // It never ends on its own, a stopwatch nobody can stop isn't much use
+                    require_keyboard("The stopwatch")?;
+                    let http = http.map(http::Endpoint::serve).transpose()?;
+                    let stopwatch_file = get_stopwatch_file();
+                    // Adopt or create the session under the lock so two `start`s can't both claim it
+                    let (state, resumed) = {
+                        let _lock = statefile::lock(&stopwatch_file)?;
+                        let resume = if new { None } else { StopwatchState::load_or_clear(&stopwatch_file)? };
+                        let resumed = resume.is_some();
+                        let state = resume.unwrap_or_else(|| {
+                            let state = StopwatchState::start(unix_millis(SystemTime::now()), clock::monotonic_micros());
+                            // Right as the start is taken, rather than after the file write and the screen setup
+                            if start_beep {
+                                spawn_chime(COUNT_IN_GO_TONE, &sound);
+                            }
+                            state
+                        });
+                        state.save(&stopwatch_file)?;
+                        (state, resumed)
+                    };
+                    if !raw {
+                        if resumed {
+                            info!(
+                                "[STOPWATCH] Resuming stopwatch at {}...",
+                                format_stopwatch_time(state.elapsed(unix_millis(SystemTime::now())))
+                            );
+                        } else {
+                            info!("[STOPWATCH] Starting stopwatch...");
                         }
                     }
+                    let options = StopwatchOptions {
+                        state,
+                        target_lap,
+                        freeze_on_lap,
+                        notify_laps,
+                        raw,
+                        export,
+                        http,
+                        precision,
+                        spinner: !cli.no_spinner,
+                        sound,
+                    };
+                    run_stopwatch_ui(options).await?;
                 }
+                StopwatchAction::Stop { raw, export, precision } => {
+                    export.validate()?;
+                    show_stopwatch_time(raw, &export, precision.max(Precision::Ms)).await?;
+                }
+            }
+        }
+        Commands::Alarm { action: Some(AlarmAction::List), .. } => {
+            print_alarms();
+            print_saved_alarms()?;
+        }
+        Commands::Alarm { action: Some(AlarmAction::Save { name, time }), .. } => {
+            let at = saved_alarms::save(&saved_alarms::saved_alarms_path(), &name, &time)?;
+            println!("[SAVED] {} at {}, start it with: clockeroo alarm start {}", name, format_clock(&at), name);
+        }
+        Commands::Alarm { action: Some(AlarmAction::Start { .. }), .. } => unreachable!("turned into its saved time above"),
+        #[cfg(unix)]
+        Commands::Alarm { action: Some(AlarmAction::Skip { id, until }), .. } => {
+            let response = ipc::send(&ipc::Request::Skip { id, until })?;
+            if let Some(entry) = response.entries.first() {
+                println!("[SKIPPED] #{} next rings {} {}", id, entry.due.format("%a %b %-d"), format_clock(&entry.due));
+            }
+        }
+        #[cfg(unix)]
+        Commands::Alarm { action: Some(AlarmAction::Unskip { id }), .. } => {
+            let response = ipc::send(&ipc::Request::Unskip { id })?;
+            if let Some(entry) = response.entries.first() {
+                println!("[UNSKIPPED] #{} next rings {} {}", id, entry.due.format("%a %b %-d"), format_clock(&entry.due));
             }
-            break;
         }
-        
-        let duration_until = target_datetime.signed_duration_since(now);
-        let hours = duration_until.num_hours();
-        let minutes = (duration_until.num_minutes() % 60).abs();
-        let seconds = (duration_until.num_seconds() % 60).abs();
-        
-        terminal.draw(|f| {
-            let area = f.area();
-            
-            let alarm_str = format!("Alarm will ring at {}", alarm_time.format("%I:%M %p"));
-            let time_remaining = if hours > 0 {
-                format!("{:02}:{:02}:{:02} remaining", hours, minutes, seconds)
-            } else {
-                format!("{:02}:{:02} remaining", minutes, seconds)
+        #[cfg(unix)]
+        Commands::Alarm { action: Some(AlarmAction::Import { file, sync }), .. } => {
+            let entries = alarm_import::read_file(&file, Local::now())?;
+            let source = fs::canonicalize(&file)?;
+            daemon::ensure_running(&daemon_sound_args)?;
+            let response = ipc::send(&ipc::Request::Import { source, entries, sync })?;
+            let report = response.import.unwrap_or_default();
+            println!(
+                "[IMPORTED] {} added, {} updated, {} unchanged{}",
+                report.added,
+                report.updated,
+                report.unchanged,
+                if sync { format!(", {} removed", report.removed) } else { String::new() }
+            );
+        }
+        #[cfg(not(unix))]
+        Commands::Alarm { action: Some(AlarmAction::Skip { .. } | AlarmAction::Unskip { .. } | AlarmAction::Import { .. }), .. } => {
+            anyhow::bail!("The daemon is only supported on Unix-like systems");
+        }
+        Commands::Alarm { action: Some(AlarmAction::Watch), .. } => {
+            require_keyboard("Watching alarms")?;
+            let options = alarm_watch::WatchOptions { visual_bell: cli.visual_bell, sound };
+            alarm_watch::run_alarm_watch(options).await?;
+        }
+        Commands::Alarm { action: None, time, args: AlarmArgs { today_only, start_at, max_ring, snooze_max, quiet_hours, input_24h: _, input_12h, background, http, escalate, escalate_step, fade_in, no_finish_screen, then_timer, then_label, dry_run, format, on } } => {
+            let Some(time) = time else {
+                anyhow::bail!("Give the alarm a time (e.g., 7:30am)");
+            };
+            let fixed = match parse_epoch_target(&time, Local::now()).transpose()? {
+                Some(at) => Some(at.naive_local()),
+                None => parse_workday_target(&time, Local::now().naive_local(), &config.workday).transpose()?,
+            };
+            if fixed.is_some() && start_at.is_some() {
+                anyhow::bail!("--start-at can't be used with an @epoch time, eod or eow");
+            }
+            let bare = if input_12h { BareTime::AssumePm } else { BareTime::TwentyFourHour };
+            let alarm_time = match fixed {
+                Some(at) => at.time(),
+                None => parse_alarm_time_as(&time, bare)?,
+            };
+            let max_ring = max_ring.map(|s| parse_duration(&s)).transpose()?;
+            let escalate = match escalate_step {
+                Some(step) => Some(parse_duration(&step)?),
+                None => escalate.then_some(ESCALATE_STEP),
+            };
+            let fade_in = fade_in.or(config.alarm.fade_in.clone()).map(|s| parse_duration(&s)).transpose()?;
+            let then_timer = then_timer.map(|s| parse_duration(&s)).transpose()?;
+            // Checked now rather than after the alarm has rung
+            let then_zones = then_timer
+                .map(|duration| config.timer.zones(duration))
+                .transpose()?;
+            let quiet_hours = quiet_hours.as_deref().map(QuietHours::parse).transpose()?;
+            let now = Local::now().naive_local();
+            let start_at = start_at.map(|at| parse_alarm_time(&at).map(|at| start_target(now, at).0)).transpose()?;
+            let (target, rolled_over) = match (fixed, start_at) {
+                (Some(at), _) => (at, false),
+                // Counted from when it's armed, so it rings at the first such time after that
+                (None, Some(start)) => (resolve_alarm_target(start, alarm_time).0, false),
+                (None, None) => resolve_alarm_target(now, alarm_time),
             };
             
-            // Create the simple, clean content
-            let mut lines = vec![];
-            
-            // Add ASCII header lines
-            for line in ASCII_HEADER.lines() {
-                lines.push(Line::from(vec![Span::styled(line, Style::default().fg(Color::DarkGray))]));    
+            if rolled_over {
+                let missed_by = now - (target - chrono::Duration::days(1));
+                if today_only {
+                    anyhow::bail!(
+                        "{} has already passed today (--today-only set)",
+                        format_clock(&alarm_time)
+                    );
+                }
+                if missed_by < chrono::Duration::minutes(1) && !dry_run {
+                    info!(
+                        "[ALARM] Note: {} passed only {}s ago, is your clock in sync?",
+                        format_clock(&alarm_time),
+                        missed_by.num_seconds()
+                    );
+                }
+                if !dry_run {
+                    info!(
+                        "[ALARM] {} has already passed today, setting it for TOMORROW ({})",
+                        format_clock(&alarm_time),
+                        target.format("%a %b %-d")
+                    );
+                }
+            }
+
+            if dry_run {
+                let mut plan = plan::Plan::alarm(start_at.unwrap_or(now), target);
+                if rolled_over {
+                    plan = plan.note(format!("{} has already passed today, so it rings tomorrow", format_clock(&alarm_time)));
+                }
+                if quiet_hours.as_ref().is_some_and(|quiet| quiet.contains(target.time())) {
+                    plan = plan.note("Rings inside --quiet-hours, so without a beep or notification");
+                }
+                if let Some(max_ring) = max_ring {
+                    plan = plan.note(format!("Gives up after ringing for {} (--max-ring)", format_duration(max_ring)));
+                }
+                if let Some(duration) = then_timer {
+                    let label = then_label.as_deref().map(|label| format!(" \"{}\"", label)).unwrap_or_default();
+                    plan = plan.note(format!("Once dismissed, a {} timer{} starts", format_duration(duration), label));
+                }
+                if background {
+                    plan = plan.note("Rings from the daemon (--background)");
+                }
+                print!("{}", plan.format(format, time_format::is_twelve_hour()));
+                return Ok(());
             }
             
-            // Add the rest of the content
-            lines.push(Line::from(""));
-            lines.push(Line::from(""));
-            lines.push(Line::from(vec![Span::styled("Alarm Set", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]));
-            lines.push(Line::from(""));
-            lines.push(Line::from(vec![Span::styled(alarm_str, Style::default().fg(Color::Yellow))]));
-            lines.push(Line::from(""));
-            lines.push(Line::from(vec![Span::styled("Time Until Alarm", Style::default().fg(Color::Gray))]));
-            lines.push(Line::from(vec![Span::styled(time_remaining, Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))]));
-            lines.push(Line::from(""));
-            lines.push(Line::from(vec![Span::styled("Press 'q' or Ctrl-C to cancel", Style::default().fg(Color::Gray))]));
+            if background {
+                if max_ring.is_some() {
+                    anyhow::bail!("--max-ring can't be used with --background");
+                }
+                if snooze_max.is_some() {
+                    anyhow::bail!("--snooze-max can't be used with --background");
+                }
+                if http.is_some() {
+                    anyhow::bail!("--http can't be used with --background");
+                }
+                if escalate.is_some() {
+                    anyhow::bail!("--escalate can't be used with --background");
+                }
+                if fade_in.is_some() {
+                    anyhow::bail!("--fade-in can't be used with --background");
+                }
+                if !on.is_empty() {
+                    anyhow::bail!("--on can't be used with --background");
+                }
+                if start_at.is_some() {
+                    anyhow::bail!("--start-at can't be used with --background");
+                }
+                match arm_in_daemon(target, quiet_hours, &daemon_sound_args) {
+                    Ok(id) => {
+                        let day = match (target.date() - now.date()).num_days() {
+                            0 => "today".to_string(),
+                            1 => "tomorrow".to_string(),
+                            _ => target.format("%a %b %-d").to_string(),
+                        };
+                        println!("Alarm armed for {} {} (id {})", format_clock(&alarm_time), day, id);
+                        return Ok(());
+                    }
+                    // Better a terminal left open than an alarm that never rings
+                    Err(err) => eprintln!("[WARN] Couldn't hand the alarm to the daemon ({:#}), keeping it in this terminal", err),
+                }
+            }
             
-            let paragraph = Paragraph::new(lines)
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Cyan))
-                )
-                .alignment(Alignment::Center);
-                
-            f.render_widget(paragraph, area);
-        })?;
-
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if key.code == KeyCode::Char('q') || key.code == KeyCode::Char('c') && key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                    break;
+            require_terminal("The alarm", " (try --background)")?;
+            if !on.is_empty() {
+                teardown::catch_signals();
+                teardown::catch_interrupt();
+            }
+            info!("[ALARM] Setting alarm for {}...", format_clock(&alarm_time));
+            let options = AlarmOptions {
+                start_at,
+                max_ring,
+                snooze_max,
+                escalate,
+                fade_in,
+                no_finish_screen,
+                http: http.map(http::Endpoint::serve).transpose()?,
+                quiet_hours,
+                on: hooks::EventHooks::new(on),
+                visual_bell: cli.visual_bell,
+                spinner: !cli.no_spinner,
+                sound: sound.clone(),
+            };
+            let outcome = run_alarm_ui(&clock::SystemClock, alarm_time, target, options).await?;
+            if let Some(outcome) = outcome {
+                let record = history::Record {
+                    at: Local::now(),
+                    kind: store::EntryKind::Alarm,
+                    label: None,
+                    tag: None,
+                    outcome,
+                    duration_ms: None,
+                };
+                if let Err(err) = history::append(&record) {
+                    eprintln!("[WARN] {:#}", err);
                 }
+                if outcome == history::Outcome::Missed {
+                    println!("[ALARM] Nobody dismissed the {} alarm", format_clock(&alarm_time));
+                    exit_code = EXIT_ALARM_UNACKNOWLEDGED;
+                }
+            }
+            if let (Some(history::Outcome::Dismissed), Some(duration), Some(zones)) = (outcome, then_timer, then_zones) {
+                info!("[TIMER] Starting timer for {}...", format_duration(duration));
+                let tag = then_label.as_deref().and_then(|label| config.tag_for(label));
+                let options = simple_timer_options(then_label, tag, zones, cli.visual_bell, !cli.no_spinner, sound);
+                // Logged as a timer of its own; cancelling it is a normal exit
+                run_timer(duration, options).await?;
+            }
+            if teardown::requested() {
+                exit_code = teardown::exit_code();
             }
         }
-        
-        sleep(Duration::from_millis(100)).await;
-    }
-
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+        Commands::Pomodoro { work, short_break, long_break, rounds } => {
+            let mut pomodoro = config.pomodoro.clone();
+            pomodoro.work = work.unwrap_or(pomodoro.work);
+            pomodoro.short_break = short_break.unwrap_or(pomodoro.short_break);
+            pomodoro.long_break = long_break.unwrap_or(pomodoro.long_break);
+            pomodoro.rounds = rounds.unwrap_or(pomodoro.rounds);
+            let settings = pomodoro::PomodoroSettings::from_config(&pomodoro)?;
+            require_terminal("Pomodoro", "")?;
 
-    Ok(())
-}
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
-
-    // Print ASCII header
-    println!("\n{}", ASCII_HEADER);
-    println!();
-
-    match cli.command {
-        Commands::Timer { duration } => {
+            info!(
+                "[POMODORO] {} rounds of {} work, {} breaks...",
+                settings.rounds,
+                format_duration(settings.work),
+                format_duration(settings.short_break)
+            );
+            let options = pomodoro::PomodoroOptions {
+                spinner: !cli.no_spinner,
+                sound,
+            };
+            let completed = pomodoro::run_pomodoro_ui(settings, options).await?;
+            info!("[POMODORO] Completed {} work round(s)", completed);
+        }
+        Commands::Suggest => {
+            let suggestions = history::suggest(&history::recent(), Local::now(), SUGGESTIONS);
+            if suggestions.is_empty() {
+                println!("Not enough timer history around this time yet.");
+            }
+            for suggestion in suggestions {
+                println!(
+                    "{:>8}  {}  ({} runs)",
+                    format_duration(suggestion.duration),
+                    suggestion.label.as_deref().unwrap_or("-"),
+                    suggestion.runs
+                );
+            }
+        }
+        Commands::Stats { label, format } => {
+            let stats = history::stats(&history::all(), label.as_deref(), Local::now().date_naive());
+            print!("{}", format_stats(label.as_deref(), &stats, format));
+        }
+        Commands::Batch { input_file } => {
+            let steps = batch::load_steps(&input_file)?;
+            require_terminal("A batch", "")?;
+            let planned: Duration = steps.iter().map(|step| step.planned).sum();
+            info!("[BATCH] Running {} steps ({} in total)...", steps.len(), format_duration(planned));
+            let options = batch::BatchOptions { spinner: !cli.no_spinner, sound };
+            let measured = batch::run_batch_ui(steps, options).await?;
+            let rows: Vec<batch::Row> = measured.iter().map(batch::report_row).collect();
+            // The report is the point of a batch, print it even with --quiet
+            print!("{}", batch::format_report(&rows));
+        }
+        Commands::Queue { action } => match action {
+            QueueAction::Add { duration, label } => {
+                let duration = parse_duration(&duration)?;
+                let item = queue::append(&queue::queue_file(), duration, label)?;
+                println!("[QUEUED] #{} {} ({})", item.id, item.label, format_duration(duration));
+            }
+            QueueAction::Run => {
+                require_terminal("The queue runner", "")?;
+                info!("[QUEUE] Running queued timers, add more with: clockeroo queue add 5m \"step\"");
+                let options = queue::QueueOptions { spinner: !cli.no_spinner, sound };
+                let measured = queue::run_queue_ui(options).await?;
+                if !measured.is_empty() {
+                    let rows: Vec<batch::Row> = measured.iter().map(batch::report_row).collect();
+                    print!("{}", batch::format_report(&rows));
+                }
+            }
+            QueueAction::List => queue::print_pending(&queue::queue_file())?,
+        },
+        Commands::Focus { duration, label } => {
             let duration = parse_duration(&duration)?;
-            println!("[TIMER] Starting timer for {}...", format_duration(duration));
-            run_timer_ui(duration).await?;
+            let tag = config.tag_for(&label);
+            let zones = config.timer.zones(duration)?;
+            // Caught from here on, so a SIGTERM or a Ctrl-C without raw mode still gets the on_end hooks run
+            teardown::catch_signals();
+            teardown::catch_interrupt();
+            let session = Arc::new(focus::Session::start(&config.focus, &label, duration));
+            if session.has_hooks() {
+                print_focus_report("on_start", session.started());
+            }
+            info!("[FOCUS] Focusing for {}...", format_duration(duration));
+            let options = TimerOptions {
+                focus: Some(session.clone()),
+                ..simple_timer_options(Some(label), tag, zones, cli.visual_bell, !cli.no_spinner, sound)
+            };
+            let result = run_timer(duration, options).await;
+            // Already ran if the timer finished, otherwise this is the cancel
+            let outcome = match teardown::requested() && !teardown::interrupted() {
+                true => focus::Outcome::Terminated,
+                false => focus::Outcome::Cancelled,
+            };
+            let ended = session.end(outcome);
+            if session.has_hooks() {
+                print_focus_report("on_end", &ended);
+            }
+            result?;
+            if teardown::requested() {
+                exit_code = teardown::exit_code();
+            }
         }
-        Commands::Stopwatch { action } => {
-            match action {
-                StopwatchAction::Start => {
-                    println!("[STOPWATCH] Starting stopwatch...");
-                    run_stopwatch_ui().await?;
+        #[cfg(unix)]
+        Commands::Daemon { foreground } => {
+            if foreground {
+                daemon::run_foreground(sound).await?;
+            } else {
+                let pid = daemon::spawn_background(&daemon_sound_args)?;
+                println!("[DAEMON] Started in the background (pid {})", pid);
+            }
+        }
+        Commands::Remaining { .. } | Commands::Between { .. } | Commands::Time { .. } | Commands::Clean { .. } => unreachable!("handled before the runtime starts"),
+        Commands::Dashboard => {
+            require_keyboard("The dashboard")?;
+            run_dashboard_ui().await?;
+        }
+        Commands::Config { action: Some(_), .. } => unreachable!("handled before the config is loaded"),
+        Commands::Config { action: None, dump } => {
+            if dump {
+                let display = serde_json::json!({
+                    "spinner": !cli.no_spinner,
+                    "minimal_help": cli.minimal_help,
+                    "align": format!("{:?}", cli.align).to_lowercase(),
+                    "margin": cli.margin,
+                    "appearance": format!("{:?}", cli.appearance).to_lowercase(),
+                    "label_color": cli.label_color.unwrap_or(ui::DEFAULT_LABEL_COLOR).to_string().to_lowercase(),
+                    "visual_bell": cli.visual_bell,
+                    "quiet": cli.quiet,
+                    "verbose": cli.verbose,
+                });
+                let settings = effective_config(&config, time_format::is_twelve_hour(), &sound, cli.sound_theme.as_deref(), display);
+                println!("{}", serde_json::to_string_pretty(&settings)?);
+            } else {
+                print_config_path();
+            }
+        }
+        Commands::Devices => {
+            let names = sound::output_device_names()?;
+            if names.is_empty() {
+                println!("No audio output devices found.");
+            }
+            let default = sound::default_output_device_name();
+            for name in names {
+                if Some(&name) == default.as_ref() {
+                    println!("{} (default)", name);
+                } else {
+                    println!("{}", name);
+                }
+            }
+        }
+        Commands::TestSound { fade_in } => {
+            match fade_in.or(config.alarm.fade_in.clone()).map(|s| parse_duration(&s)).transpose()? {
+                Some(fade_in) => {
+                    println!("Ringing for {}, from quiet to full volume...", format_duration(fade_in));
+                    let _ringer = sound::Ringer::start(SoundEvent::Alarm, &sound, fade_in);
+                    // A moment at full volume before it stops
+                    tokio::time::sleep(fade_in + Duration::from_secs(2)).await;
+                }
+                None => play_bell(SoundEvent::Alarm, &sound),
+            }
+        }
+        Commands::Selftest => {
+            info!("Running a {}s timer without a screen...", SELFTEST_LENGTH.as_secs());
+            let actual = run_selftest(SELFTEST_LENGTH)?;
+            println!("{}", format_drift(SELFTEST_LENGTH, actual));
+        }
+        #[cfg(unix)]
+        Commands::Status => {
+            let response = ipc::send(&ipc::Request::Status)?;
+            print_daemon_entries(&response.entries);
+        }
+        #[cfg(unix)]
+        Commands::Cancel { id, label, all, last, notify } => {
+            let request = match (id, label) {
+                (Some(id), _) => ipc::Request::Cancel { id },
+                (None, Some(label)) => ipc::Request::CancelMatching { which: store::Selector::Label { label, all } },
+                (None, None) if last => ipc::Request::CancelMatching { which: store::Selector::Last },
+                (None, None) => ipc::Request::CancelMatching { which: store::Selector::All },
+            };
+            let response = ipc::send(&request)?;
+            for entry in &response.entries {
+                println!("[CANCELLED] #{} {}", entry.id, ui::with_tag(entry.tag.as_deref(), &entry.display_name()));
+                let record = history::Record::for_entry(entry, history::Outcome::Cancelled, Local::now());
+                if let Err(err) = history::append(&record) {
+                    verbose!("couldn't log the cancellation: {:#}", err);
                 }
-                StopwatchAction::Stop => {
-                    show_stopwatch_time().await?;
+                if notify {
+                    let title = match entry.kind {
+                        store::EntryKind::Timer => "Timer Cancelled",
+                        store::EntryKind::Alarm => "Alarm Cancelled",
+                    };
+                    send_notification(title, &format!("{} was cancelled", entry.display_name()));
+                }
+            }
+            // A daemon from before cancellations were sent back only has the id
+            if response.entries.is_empty() {
+                if let Some(id) = response.id {
+                    println!("[CANCELLED] #{}", id);
                 }
             }
         }
-        Commands::Alarm { time } => {
-            let alarm_time = parse_alarm_time(&time)?;
-            println!("[ALARM] Setting alarm for {}...", alarm_time.format("%I:%M %p"));
-            run_alarm_ui(alarm_time).await?;
+        #[cfg(not(unix))]
+        Commands::Daemon { .. } | Commands::Status | Commands::Cancel { .. } => {
+            anyhow::bail!("The daemon is only supported on Unix-like systems");
         }
     }
 
+    notify::flush();
+    if let Some(hint) = notify::failure_hint() {
+        eprintln!("{}", hint);
+    }
+    if let Some(report) = sound::channel_report() {
+        verbose!("{}", report);
+    }
+
+    if exit_code != 0 {
+        artifacts::exit(exit_code);
+    }
+
     Ok(())
 }
 
@@ -646,6 +3771,78 @@ mod tests {
         assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
     }
 
+    #[test]
+    fn test_parse_fractional_duration() {
+        assert_eq!(parse_duration("1.5m").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("0.25s").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("1h0.5m").unwrap(), Duration::from_secs(3630));
+        assert_eq!(parse_duration("0.0006s").unwrap(), Duration::from_millis(1));
+
+        let err = parse_duration("0.0001s").unwrap_err().to_string();
+        assert!(err.contains("rounds to zero"), "{}", err);
+        let err = parse_duration("0.0004").unwrap_err().to_string();
+        assert!(err.contains("rounds to zero"), "{}", err);
+        let err = parse_duration("0s").unwrap_err().to_string();
+        assert!(err.starts_with("Invalid duration format"), "{}", err);
+        assert!(parse_duration("1.2.3s").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_or_zero() {
+        assert_eq!(parse_duration_or_zero("0").unwrap(), Duration::ZERO);
+        assert_eq!(parse_duration_or_zero("0ms").unwrap(), Duration::ZERO);
+        assert_eq!(parse_duration_or_zero("80ms").unwrap(), Duration::from_millis(80));
+        assert_eq!(parse_duration_or_zero("0.1s").unwrap(), Duration::from_millis(100));
+        assert!(parse_duration_or_zero("soft").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_unit_words() {
+        assert_eq!(parse_duration("5min").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("2hrs30min").unwrap(), Duration::from_secs(9000));
+        assert_eq!(parse_duration("90secs").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("1hour").unwrap(), Duration::from_secs(3600));
+        assert_eq!(parse_duration("1 hour 30 minutes").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("2H 5Sec").unwrap(), Duration::from_secs(7205));
+        // Read as one unit, not minutes followed by seconds
+        assert_eq!(parse_duration("1500ms").unwrap(), Duration::from_millis(1500));
+        // A bare number at the end is still seconds
+        assert_eq!(parse_duration("1m30").unwrap(), Duration::from_secs(90));
+
+        let err = parse_duration("5mins!").unwrap_err().to_string();
+        assert!(err.starts_with("Invalid duration format"), "{}", err);
+        let err = parse_duration("3days").unwrap_err().to_string();
+        assert!(err.starts_with("Unknown unit 'days'"), "{}", err);
+        assert!(parse_duration("min").is_err());
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn test_parse_iso_duration() {
+        assert_eq!(parse_duration("PT90M").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("PT1H30M").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("PT45S").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("pt2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("P1DT2H").unwrap(), Duration::from_secs(93600));
+
+        let err = parse_duration("P1M").unwrap_err().to_string();
+        assert!(err.contains("years and months"), "{}", err);
+        assert!(parse_duration("PT").is_err());
+        assert!(parse_duration("PT5").is_err());
+        assert!(parse_duration("P5H").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_too_long() {
+        let err = parse_duration("99999999999h").unwrap_err().to_string();
+        assert!(err.contains("too long"), "{}", err);
+        let err = parse_duration("PT99999999999H").unwrap_err().to_string();
+        assert!(err.contains("too long"), "{}", err);
+        let err = parse_duration("P99999999999999999W").unwrap_err().to_string();
+        assert!(err.contains("too long"), "{}", err);
+        assert_eq!(parse_duration("876000h").unwrap(), Duration::from_secs(876_000 * 3600));
+    }
+
     #[test]
     fn test_parse_alarm_time() {
         let time1 = parse_alarm_time("7:20am").unwrap();
@@ -668,4 +3865,525 @@ mod tests {
         assert_eq!(time5.hour(), 12);
         assert_eq!(time5.minute(), 0);
     }
+
+    #[test]
+    fn test_parse_epoch_target() {
+        let now = Local.timestamp_opt(1_735_000_000, 0).unwrap();
+        let at = parse_epoch_target("@1735689600", now).unwrap().unwrap();
+        assert_eq!(at.timestamp(), 1_735_689_600);
+        assert_eq!(at, chrono::Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+        assert!(parse_epoch_target("7:20am", now).is_none());
+
+        for bad in ["@", "@soon", "@17356896OO", "@1.5", "@99999999999999999999"] {
+            let err = parse_epoch_target(bad, now).unwrap().unwrap_err().to_string();
+            assert!(err.starts_with("Invalid epoch time"), "{}: {}", bad, err);
+        }
+        let err = parse_epoch_target("@1700000000", now).unwrap().unwrap_err().to_string();
+        assert!(err.contains("in the past"), "{}", err);
+        assert!(parse_epoch_target("@1735000000", now).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_parse_workday_target() {
+        let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        let workday = config::WorkdayConfig::default();
+        let wednesday = at("2025-01-15 10:00");  // Wed
+        assert_eq!(parse_workday_target("eod", wednesday, &workday).unwrap().unwrap(), at("2025-01-15 17:00"));
+        assert_eq!(parse_workday_target("EOW", wednesday, &workday).unwrap().unwrap(), at("2025-01-17 17:00"));
+        assert!(parse_workday_target("5pm", wednesday, &workday).is_none());
+        // After hours it's an error, not tomorrow
+        let err = parse_workday_target("eod", at("2025-01-15 17:30"), &workday).unwrap().unwrap_err().to_string();
+        assert!(err.starts_with("eod has already passed (the workday ended Wed 17:00)"), "{}", err);
+        assert!(parse_workday_target("eow", at("2025-01-18 09:00"), &workday).unwrap().is_err());
+        let workday = config::WorkdayConfig { end: Some("6:30pm".to_string()) };
+        assert_eq!(parse_workday_target("eod", at("2025-01-15 17:30"), &workday).unwrap().unwrap(), at("2025-01-15 18:30"));
+        let workday = config::WorkdayConfig { end: Some("late".to_string()) };
+        assert!(parse_workday_target("eod", wednesday, &workday).unwrap().is_err());
+        assert!(parse_workday_target("9am", wednesday, &workday).is_none());
+    }
+
+    #[test]
+    fn test_resolve_until() {
+        let now = NaiveDateTime::parse_from_str("2025-01-15 16:15", "%Y-%m-%d %H:%M").unwrap();
+        let workday = config::WorkdayConfig::default();
+        let until_eod = Some(Duration::from_secs(45 * 60));
+        assert_eq!(resolve_until(Some("until"), &["eod".to_string()], now, &workday).unwrap(), until_eod);
+        assert_eq!(resolve_until(Some("eod"), &[], now, &workday).unwrap(), until_eod);
+        assert_eq!(resolve_until(Some("5m"), &[], now, &workday).unwrap(), None);
+        assert_eq!(resolve_until(None, &[], now, &workday).unwrap(), None);
+        assert!(resolve_until(Some("until"), &["5pm".to_string()], now, &workday).is_err());
+        assert!(resolve_until(Some("5m"), &["10m".to_string()], now, &workday).is_err());
+    }
+
+    #[test]
+    fn test_timer_frame_wait() {
+        let duration = Duration::from_millis(1_100);
+        let mut countdown = clock::Countdown::new(duration);
+        // Started just now: no more than one frame to the first boundary
+        let start_time = Instant::now();
+        assert!(timer_frame_wait(&countdown, start_time, Duration::ZERO, duration) <= TIMER_FRAME);
+        // Started a second ago, so the last frame is cut short to end right at the finish
+        let start_time = Instant::now() - Duration::from_secs(1);
+        assert!(timer_frame_wait(&countdown, start_time, Duration::from_secs(1), duration) <= Duration::from_millis(100));
+        countdown.toggle_pause(Duration::from_secs(1));
+        assert_eq!(timer_frame_wait(&countdown, start_time, Duration::from_secs(1), duration), TIMER_FRAME);
+    }
+
+    #[test]
+    fn test_format_drift() {
+        let expected = Duration::from_secs(10);
+        assert_eq!(format_drift(expected, Duration::from_millis(10_013)), "expected 10.000s, actual 10.013s, drift +13ms");
+        assert_eq!(format_drift(expected, Duration::from_millis(9_998)), "expected 10.000s, actual 9.998s, drift -2ms");
+        assert_eq!(format_drift(expected, expected), "expected 10.000s, actual 10.000s, drift +0ms");
+    }
+
+    #[test]
+    fn test_duration_between() {
+        let time = |s| parse_alarm_time(s).unwrap();
+        assert_eq!(duration_between(time("9:15"), time("17:45")), Duration::from_secs(8 * 3600 + 30 * 60));
+        assert_eq!(format_duration(duration_between(time("9:15am"), time("9:40am"))), "25:00");
+        // Over midnight
+        assert_eq!(duration_between(time("22:30"), time("6:15am")), Duration::from_secs(7 * 3600 + 45 * 60));
+        assert_eq!(duration_between(time("23:59"), time("0:00")), Duration::from_secs(60));
+        assert_eq!(duration_between(time("8:00"), time("8:00")), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_parse_bare_alarm_time_modes() {
+        let time = parse_alarm_time_as("7:20", BareTime::TwentyFourHour).unwrap();
+        assert_eq!((time.hour(), time.minute()), (7, 20));
+        let time = parse_alarm_time_as("7:20", BareTime::AssumePm).unwrap();
+        assert_eq!((time.hour(), time.minute()), (19, 20));
+        assert_eq!(parse_alarm_time_as("12:30", BareTime::AssumePm).unwrap().hour(), 12);
+        assert!(parse_alarm_time_as("19:20", BareTime::AssumePm).is_err());
+        // An explicit am/pm always wins
+        assert_eq!(parse_alarm_time_as("7:20am", BareTime::AssumePm).unwrap().hour(), 7);
+    }
+
+    #[test]
+    fn test_parse_natural_alarm_time() {
+        let cases = [
+            ("half past 7", 7, 30),
+            ("half past 7pm", 19, 30),
+            ("half past 19", 19, 30),
+            ("quarter past 11 pm", 23, 15),
+            ("quarter to 8", 7, 45),
+            ("a quarter to 8pm", 19, 45),
+            ("quarter to 12am", 23, 45),
+            ("quarter to 12pm", 11, 45),
+            ("quarter to 1", 0, 45),
+            ("10 past 6", 6, 10),
+            ("20 to 9 pm", 20, 40),
+            ("7 thirty pm", 19, 30),
+            ("7 thirty", 7, 30),
+            ("12 fifteen am", 0, 15),
+            ("9 o'clock pm", 21, 0),
+            ("Noon", 12, 0),
+            ("midnight", 0, 0),
+        ];
+        for (input, hour, minute) in cases {
+            let time = parse_alarm_time(input).unwrap_or_else(|e| panic!("{}: {}", input, e));
+            assert_eq!((time.hour(), time.minute()), (hour, minute), "{}", input);
+        }
+
+        let err = parse_alarm_time("quarter to 8x").unwrap_err().to_string();
+        assert!(err.contains("'quarter to'") && err.contains("'8x'"), "{}", err);
+        assert!(parse_alarm_time("half past 13pm").is_err());
+        assert!(parse_alarm_time("7 thirty 25").is_err());
+    }
+
+    #[test]
+    fn test_running_heading_spinner() {
+        assert_eq!(running_heading("Timer Running", None), "Timer Running");
+        assert_eq!(running_heading("Timer Running", Some(0)), "  Timer Running |");
+        assert_eq!(running_heading("Timer Running", Some(3)), "  Timer Running \\");
+        assert_eq!(running_heading("Timer Running", Some(5)), "  Timer Running /");
+    }
+
+    #[test]
+    fn test_resolve_timer_preset() {
+        let preset = config::Preset {
+            duration: "3m".to_string(),
+            label: Some("tea".to_string()),
+            message: Some("Tea's ready".to_string()),
+        };
+
+        let (duration, label, message) = resolve_timer(None, None, Some(&preset)).unwrap();
+        assert_eq!(duration, Duration::from_secs(180));
+        assert_eq!(label.as_deref(), Some("tea"));
+        assert_eq!(message.as_deref(), Some("Tea's ready"));
+
+        // CLI values override the preset
+        let (duration, label, _) = resolve_timer(Some("5m"), Some("green tea".into()), Some(&preset)).unwrap();
+        assert_eq!(duration, Duration::from_secs(300));
+        assert_eq!(label.as_deref(), Some("green tea"));
+
+        assert!(resolve_timer(None, None, None).is_err());
+    }
+
+    #[test]
+    fn test_resolve_alarm_target() {
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let now = today.and_hms_opt(7, 25, 0).unwrap();
+
+        let (target, rolled) = resolve_alarm_target(now, NaiveTime::from_hms_opt(7, 20, 0).unwrap());
+        assert!(rolled);
+        assert_eq!(target, today.succ_opt().unwrap().and_hms_opt(7, 20, 0).unwrap());
+
+        let (target, rolled) = resolve_alarm_target(now, NaiveTime::from_hms_opt(8, 0, 0).unwrap());
+        assert!(!rolled);
+        assert_eq!(target, today.and_hms_opt(8, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_start_target() {
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let now = today.and_hms_opt(13, 15, 30).unwrap();
+
+        let (start, rolled) = start_target(now, parse_alarm_time("2:00pm").unwrap());
+        assert!(!rolled);
+        assert_eq!(start, today.and_hms_opt(14, 0, 0).unwrap());
+        assert_eq!(until(now, start), Duration::from_secs(44 * 60 + 30));
+
+        // Already past, even by seconds: the same time tomorrow
+        for at in ["1:00pm", "13:15"] {
+            let (start, rolled) = start_target(now, parse_alarm_time(at).unwrap());
+            assert!(rolled, "{}", at);
+            assert_eq!(start.date(), today.succ_opt().unwrap());
+        }
+        assert_eq!(until(now, today.and_hms_opt(13, 0, 0).unwrap()), Duration::ZERO);
+
+        // An alarm armed late in the evening rings the next morning
+        let (start, _) = start_target(now, parse_alarm_time("11:00pm").unwrap());
+        let (ring, _) = resolve_alarm_target(start, parse_alarm_time("7:00am").unwrap());
+        assert_eq!(ring, today.succ_opt().unwrap().and_hms_opt(7, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_escalation_stage() {
+        let step = Duration::from_secs(30);
+        assert_eq!(escalation_stage(Duration::ZERO, step), RingStage::Notify);
+        assert_eq!(escalation_stage(Duration::from_millis(29_999), step), RingStage::Notify);
+        assert_eq!(escalation_stage(step, step), RingStage::Sound);
+        assert_eq!(escalation_stage(Duration::from_secs(59), step), RingStage::Sound);
+        assert_eq!(escalation_stage(Duration::from_secs(60), step), RingStage::Flash);
+        // Flashing is as far as it goes
+        assert_eq!(escalation_stage(Duration::from_secs(3600), step), RingStage::Flash);
+    }
+
+    #[test]
+    fn test_snooze_max() {
+        let mut snoozes = Snoozes { taken: 0, max: Some(2) };
+        assert!(snoozes.take());
+        assert!(snoozes.left());
+        assert!(snoozes.take());
+        // The third ring is the final alarm: 's' is refused and nothing is counted
+        assert!(!snoozes.left());
+        assert!(!snoozes.take());
+        assert_eq!(snoozes.taken, 2);
+
+        assert!(!Snoozes { taken: 0, max: Some(0) }.take());
+        let mut unlimited = Snoozes { taken: 0, max: None };
+        assert!((0..100).all(|_| unlimited.take()));
+    }
+
+    #[test]
+    fn test_stopwatch_elapsed_skips_pauses() {
+        let mut state = StopwatchState { started_at: 1_000, ..Default::default() };
+        assert_eq!(state.elapsed(11_000), Duration::from_secs(10));
+
+        state.toggle_pause(11_000, None);
+        assert_eq!(state.elapsed(20_000), Duration::from_secs(10));
+        state.toggle_pause(21_000, None);
+        assert_eq!(state.paused_ms, 10_000);
+        assert_eq!(state.elapsed(26_000), Duration::from_secs(15));
+
+        // Files written before pauses existed still load
+        let old: StopwatchState = serde_json::from_str(r#"{"started_at":1000,"laps":[500]}"#).unwrap();
+        assert_eq!(old.elapsed(3_000), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_stopwatch_elapsed_to_the_microsecond() {
+        let mut state = StopwatchState::start(1_000, Some(5_000_000));
+        assert_eq!(state.elapsed_precise(11_000, Some(15_000_123)), Duration::from_micros(10_000_123));
+
+        state.toggle_pause(11_000, Some(15_000_123));
+        state.toggle_pause(21_000, Some(25_000_000));
+        assert_eq!(state.elapsed_precise(26_000, Some(30_000_500)), Duration::from_micros(15_000_623));
+        // Read back by `stop` in another process
+        let saved: StopwatchState = serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+        assert_eq!(saved.elapsed_precise(26_000, Some(30_000_500)), Duration::from_micros(15_000_623));
+
+        // After a reboot the monotonic clock starts over, the wall clock carries on
+        assert_eq!(state.elapsed_precise(26_000, Some(1_000)), Duration::from_secs(15));
+        // And a pause it didn't see leaves only the wall clock
+        state.toggle_pause(30_000, None);
+        assert_eq!(state.monotonic, None);
+        assert_eq!(state.elapsed_precise(40_000, Some(30_000_500)), Duration::from_secs(19));
+    }
+
+    #[test]
+    fn test_stopwatch_keeps_time_through_a_step_back() {
+        // 10s in, paused, then NTP sets the clock back 5s
+        let mut state = StopwatchState::start(100_000, Some(5_000_000));
+        state.toggle_pause(110_000, Some(15_000_000));
+        state.shift_wall_clock(-5_000);
+        assert_eq!(state.paused_since, Some(105_000));
+
+        // Resumed 2s later by the stepped clock: the pause is 2s, not nothing
+        state.toggle_pause(107_000, Some(17_000_000));
+        assert_eq!(state.paused_ms, 2_000);
+        assert_eq!(state.elapsed(112_000), Duration::from_secs(15));
+        // The wall clock agrees with the monotonic one again, so it's still to the microsecond
+        assert_eq!(state.elapsed_precise(112_000, Some(22_000_123)), Duration::from_micros(15_000_123));
+    }
+
+    #[test]
+    fn test_background_timer_starts_after_its_lead() {
+        let before = Local::now();
+        let entry = background_timer(Duration::from_secs(600), Duration::from_secs(300), None, None, Some("Tea's ready".into()), None, None).unwrap();
+        assert_eq!(entry.total_ms, Some(600_000));
+        assert_eq!(entry.message.as_deref(), Some("Tea's ready"));
+        // Waiting for the start isn't time the timer has run
+        let progress = entry.progress_at(before).unwrap();
+        assert_eq!((progress.elapsed_ms, progress.remaining_ms), (0, 600_000));
+        assert!(entry.due >= before + chrono::Duration::seconds(900));
+    }
+
+    #[test]
+    fn test_background_timer_past_the_calendar() {
+        let err = background_timer(Duration::from_secs(99_999_999_999 * 3600), Duration::ZERO, None, None, None, None, None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("end of the calendar"), "{}", err);
+    }
+
+    #[test]
+    fn test_estimate_finish() {
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 3, 8).unwrap().and_hms_opt(13, 20, 0).unwrap();
+        let finish = estimate_finish(now, parse_duration("45m").unwrap()).unwrap();
+        assert_eq!(format_finish_time(now, finish, false), "14:05");
+        assert_eq!(format_finish_time(now, finish, true), "2:05 PM");
+
+        let finish = estimate_finish(now, parse_duration("12h").unwrap()).unwrap();
+        assert_eq!(format_finish_time(now, finish, false), "01:20 on Sat Mar 9");
+
+        // Past what the calendar holds is an error, not a panic
+        let err = estimate_finish(now, Duration::from_secs(99_999_999_999 * 3600)).unwrap_err().to_string();
+        assert!(err.contains("end of the calendar"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("red").unwrap(), Color::Red);
+        assert_eq!(parse_color(" lightblue ").unwrap(), Color::LightBlue);
+        assert_eq!(parse_color("#ff8800").unwrap(), Color::Rgb(0xff, 0x88, 0x00));
+        assert!(parse_color("sparkly").is_err());
+    }
+
+    #[test]
+    fn test_format_long_duration() {
+        let secs = Duration::from_secs;
+        assert_eq!(format_long_duration(secs(2 * 86_400 + 3 * 3600 + 15 * 60 + 42)), "2d 03:15:42");
+        assert_eq!(format_long_duration(secs(86_400)), "1d 00:00:00");
+        // Under a day it's the compact form
+        assert_eq!(format_long_duration(secs(23 * 3600 + 59 * 60 + 59)), "23:59:59");
+        assert_eq!(format_long_duration(secs(45 * 60 + 5)), "45:05");
+    }
+
+    #[test]
+    fn test_format_raw_seconds() {
+        assert_eq!(format_raw_seconds(Duration::from_millis(83_412), Precision::Ms), "83.412");
+        assert_eq!(format_raw_seconds(Duration::from_millis(5), Precision::Ms), "0.005");
+        assert_eq!(format_raw_seconds(Duration::from_secs(3600), Precision::Ms), "3600.000");
+    }
+
+    #[test]
+    fn test_stopwatch_precision() {
+        let elapsed = Duration::from_millis(83_789);
+        assert_eq!(format_stopwatch_time(elapsed), "01:23.789");
+        assert_eq!(format_stopwatch_time_with(elapsed, Precision::Cs), "01:23.78");
+        assert_eq!(format_stopwatch_time_with(elapsed, Precision::Ds), "01:23.7");
+        // Truncated, never rounded up to the next second
+        assert_eq!(format_stopwatch_time_with(Duration::from_millis(83_999), Precision::S), "01:23");
+        assert_eq!(format_stopwatch_time_with(Duration::from_millis(5), Precision::Cs), "00:00.00");
+        assert!(Precision::S.frame() > Precision::Ms.frame());
+
+        let elapsed = Duration::from_micros(83_412_345);
+        assert_eq!(format_stopwatch_time_with(elapsed, Precision::Us), "01:23.412345");
+        assert_eq!(format_raw_seconds(elapsed, Precision::Us), "83.412345");
+        assert_eq!(format_raw_seconds(Duration::from_micros(5), Precision::Us), "0.000005");
+        assert_eq!(format_raw_seconds(elapsed, Precision::S), "83");
+    }
+
+    #[test]
+    fn test_lap_notification() {
+        let (title, body) = lap_notification(3, Duration::from_millis(83_789), Precision::Cs);
+        assert_eq!(title, "Lap 3");
+        assert_eq!(body, "Split: 01:23.78");
+    }
+
+    #[test]
+    fn test_lap_target_summary() {
+        let target = Duration::from_secs(90);
+        let laps = [
+            Duration::from_secs(88),
+            Duration::from_secs(92),
+            Duration::from_millis(89_500),
+        ];
+        assert_eq!(summarize_laps(&laps, target), (2, 1, -166));
+        assert_eq!(summarize_laps(&[], target), (0, 0, 0));
+        assert_eq!(format_delta(lap_delta_ms(laps[0], target)), "-00:02.000");
+        assert_eq!(format_delta(lap_delta_ms(laps[1], target)), "+00:02.000");
+    }
+
+    #[test]
+    fn test_effective_config() {
+        let config = config::Config::parse(r#"{ "time_format": "auto", "pomodoro": { "work": "50m" }, "tags": { "tea": "🍵" } }"#).unwrap();
+        let sound = SoundOptions { bell: sound::Bell::Terminal, dnd: dnd::DndPolicy::Respect, ..Default::default() };
+        let display = serde_json::json!({ "spinner": false });
+        let settings = effective_config(&config, true, &sound, None, display);
+
+        assert_eq!(settings["time_format"], "12h");
+        assert_eq!(settings["sound"], serde_json::json!({
+            "bell": "terminal", "theme": null, "dnd": "respect", "device": null, "beep_fade_in": "50ms", "beep_fade_out": "50ms", "waveform": "sine",
+        }));
+        assert_eq!(settings["display"]["spinner"], false);
+        // Config values win, defaults fill the rest
+        assert_eq!(settings["pomodoro"]["work"], "50m");
+        assert_eq!(settings["pomodoro"]["short_break"], "5m");
+        assert_eq!(settings["quick_durations"], serde_json::json!(["1m", "5m", "10m", "25m", "1h"]));
+        assert_eq!(settings["tags"]["tea"], "🍵");
+        assert!(settings["presets"].as_object().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_repeat_until() {
+        let at = |h, m| chrono::NaiveDate::from_ymd_opt(2024, 3, 8).unwrap().and_hms_opt(h, m, 0).unwrap();
+        let five_pm = NaiveTime::from_hms_opt(17, 0, 0).unwrap();
+
+        let deadline = repeat_deadline(at(9, 0), five_pm);
+        assert_eq!(deadline, at(17, 0));
+        assert!(should_repeat(at(16, 59), Some(deadline)));
+        assert!(!should_repeat(at(17, 0), Some(deadline)));
+        assert!(!should_repeat(at(17, 40), Some(deadline)));
+        assert!(should_repeat(at(23, 0), None));
+
+        // Started after 5pm: keep going until 5pm tomorrow
+        let deadline = repeat_deadline(at(18, 0), five_pm);
+        assert!(should_repeat(at(23, 59), Some(deadline)));
+        assert_eq!(deadline, at(17, 0) + chrono::Duration::days(1));
+    }
+
+    #[test]
+    fn test_prints_banner() {
+        let banner = |args: &[&str]| prints_banner(&Cli::try_parse_from(args).unwrap().command);
+        assert!(banner(&["clockeroo", "timer", "5m"]));
+        assert!(banner(&["clockeroo", "alarm", "7:30am"]));
+        assert!(!banner(&["clockeroo", "timer", "5m", "--estimate"]));
+        assert!(!banner(&["clockeroo", "alarm", "list"]));
+        assert!(!banner(&["clockeroo", "suggest"]));
+        assert!(!banner(&["clockeroo", "queue", "add", "5m", "steep"]));
+        assert!(banner(&["clockeroo", "queue", "run"]));
+    }
+
+    #[test]
+    fn test_sound_args_reach_the_daemon() {
+        let cli = Cli::try_parse_from(["clockeroo", "--sound-device", "USB", "daemon", "--waveform", "square", "--beep-fade-in", "0.5s", "--ignore-dnd"]).unwrap();
+        let args = sound_args(&cli);
+        assert_eq!(args, ["--sound-device", "USB", "--beep-fade-in", "500ms", "--waveform", "square", "--ignore-dnd"]);
+        // And parse back to the same
+        let forwarded = Cli::try_parse_from(["clockeroo"].into_iter().chain(args.iter().map(String::as_str)).chain(["daemon", "--foreground"])).unwrap();
+        assert_eq!(sound_args(&forwarded), args);
+    }
+
+    #[test]
+    fn test_share_left() {
+        let duration = Duration::from_secs(600);
+        assert_eq!(share_left(Duration::ZERO, duration), 1.0);
+        assert_eq!(share_left(Duration::from_secs(150), duration), 0.75);
+        // Overtime leaves an empty dial
+        assert_eq!(share_left(Duration::from_secs(700), duration), 0.0);
+    }
+
+    #[test]
+    fn test_finished_screen_keys() {
+        use crossterm::event::KeyModifiers;
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(finished_action(&key(KeyCode::Char('r'))), Some(FinishedAction::Restart));
+        for code in [KeyCode::Char('q'), KeyCode::Esc, KeyCode::Enter] {
+            assert_eq!(finished_action(&key(code)), Some(FinishedAction::Quit));
+        }
+        assert_eq!(finished_action(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)), Some(FinishedAction::Quit));
+        // Anything else leaves the screen up
+        assert_eq!(finished_action(&key(KeyCode::Char('p'))), None);
+    }
+
+    #[test]
+    fn test_tick_second() {
+        let window = Duration::from_secs(30);
+        assert_eq!(tick_second(Duration::from_secs(31), window), None);
+        assert_eq!(tick_second(Duration::from_secs(30), window), Some(30));
+        assert_eq!(tick_second(Duration::from_secs(1), window), Some(1));
+        assert_eq!(tick_second(Duration::ZERO, window), None);
+    }
+
+    #[test]
+    fn test_corrupt_stopwatch_file_is_cleared() {
+        let dir = std::env::temp_dir().join(format!("clockeroo-stopwatch-corrupt-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("clockeroo.stopwatch");
+
+        assert!(StopwatchState::load_or_clear(&path).unwrap().is_none());
+
+        let state = StopwatchState { started_at: 1_000, laps: vec![90_000], ..Default::default() };
+        state.save(&path).unwrap();
+        assert_eq!(StopwatchState::load_or_clear(&path).unwrap().unwrap().laps, vec![90_000]);
+
+        // Truncated by a crash mid-write
+        fs::write(&path, r#"{"started_at": 1000, "la"#).unwrap();
+        assert!(StopwatchState::load_or_clear(&path).unwrap().is_none());
+        assert!(!path.exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_format_stats() {
+        let stats = history::Stats {
+            completed: 3,
+            cancelled: 1,
+            average: Some(Duration::from_secs(1500)),
+            longest: Some(Duration::from_secs(3000)),
+            current_streak: 1,
+            longest_streak: 4,
+        };
+        let text = format_stats(Some("focus"), &stats, measure::Format::Text);
+        assert!(text.starts_with("focus\n"), "{}", text);
+        assert!(text.contains("Completion rate 75%"), "{}", text);
+        assert!(text.contains("Current streak  1 day\n"), "{}", text);
+        assert!(text.contains("Longest streak  4 days\n"), "{}", text);
+
+        let json: serde_json::Value = serde_json::from_str(&format_stats(None, &stats, measure::Format::Json)).unwrap();
+        assert_eq!(json["label"], serde_json::Value::Null);
+        assert_eq!(json["average_seconds"], 1500.0);
+        assert_eq!(json["completion_rate"], 0.75);
+
+        let empty = format_stats(None, &history::Stats::default(), measure::Format::Text);
+        assert!(empty.contains("Average length  -\n"), "{}", empty);
+    }
+
+    #[test]
+    fn test_stopwatch_file_in_runtime_or_temp_dir() {
+        let path = get_stopwatch_file();
+        assert!(!path.as_os_str().is_empty());
+        assert_eq!(path.file_name().unwrap(), "clockeroo.stopwatch");
+
+        let dir = path.parent().unwrap();
+        let xdg = std::env::var_os("XDG_RUNTIME_DIR").filter(|d| cfg!(unix) && !d.is_empty());
+        match xdg {
+            Some(xdg) => assert_eq!(dir, Path::new(&xdg)),
+            None => assert_eq!(dir, std::env::temp_dir()),
+        }
+    }
 }
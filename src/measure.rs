@@ -0,0 +1,197 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::json;
+use std::{
+    io,
+    process::{Child, Command, ExitStatus},
+    time::{Duration, Instant},
+};
+
+use crate::{format_stopwatch_time_with, Precision};
+
+/// How `clockeroo time` reports a run.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+/// What one run of a command took.
+#[derive(Debug, Clone, Copy)]
+pub struct Measurement {
+    pub wall: Duration,
+    /// CPU time of the command and the children it waited for, where the
+    /// platform reports it
+    pub user: Option<Duration>,
+    pub sys: Option<Duration>,
+    pub status: ExitStatus,
+}
+
+/// A command started by `start`, timed from just before it was spawned.
+pub struct Running {
+    child: Child,
+    started: Instant,
+}
+
+/// Starts `command` (program then arguments) on this terminal.
+pub fn start(command: &[String]) -> Result<Running> {
+    let (program, args) = command.split_first().context("No command to time")?;
+    let started = Instant::now();
+    let child = Command::new(program).args(args).spawn().with_context(|| format!("Failed to run '{}'", program))?;
+    Ok(Running { child, started })
+}
+
+impl Running {
+    pub fn wait(self) -> Result<Measurement> {
+        let (status, usage) = wait(self.child)?;
+        let wall = self.started.elapsed();
+        Ok(Measurement { wall, user: usage.map(|(user, _)| user), sys: usage.map(|(_, sys)| sys), status })
+    }
+}
+
+/// Like time(1): Ctrl-C and Ctrl-\ are the command's to handle, the report
+/// still comes once it has exited. Call it after `start`, or the command
+/// would inherit it.
+pub fn ignore_interrupts() {
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_IGN);
+        libc::signal(libc::SIGQUIT, libc::SIG_IGN);
+    }
+}
+
+/// Runs `command` to the end and times it.
+#[cfg(test)]
+fn run(command: &[String]) -> Result<Measurement> {
+    start(command)?.wait()
+}
+
+/// Waits with wait4 to get the command's resource usage along with its status.
+#[cfg(unix)]
+fn wait(child: Child) -> Result<(ExitStatus, Option<(Duration, Duration)>)> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let mut status = 0;
+    // Plain integers, all zero is a valid value
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    while unsafe { libc::wait4(child.id() as libc::pid_t, &mut status, 0, &mut usage) } < 0 {
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err).context("Failed to wait for the command");
+        }
+    }
+    let duration = |time: libc::timeval| Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64);
+    Ok((ExitStatus::from_raw(status), Some((duration(usage.ru_utime), duration(usage.ru_stime)))))
+}
+
+#[cfg(not(unix))]
+fn wait(mut child: Child) -> Result<(ExitStatus, Option<(Duration, Duration)>)> {
+    Ok((child.wait().context("Failed to wait for the command")?, None))
+}
+
+/// The exit code to pass on: the command's own, or 128 plus the signal that
+/// killed it, as a shell reports it.
+pub fn exit_code(status: ExitStatus) -> i32 {
+    match signal(status) {
+        Some(signal) => 128 + signal,
+        None => status.code().unwrap_or(1),
+    }
+}
+
+fn signal(status: ExitStatus) -> Option<i32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        status.signal()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = status;
+        None
+    }
+}
+
+/// The shell's exit code for a command that couldn't be run: 127 if it
+/// wasn't found, 126 otherwise.
+pub fn launch_failure_code(err: &anyhow::Error) -> i32 {
+    match err.downcast_ref::<io::Error>() {
+        Some(err) if err.kind() == io::ErrorKind::NotFound => 127,
+        _ => 126,
+    }
+}
+
+pub fn render(format: Format, command: &[String], measurement: &Measurement, precision: Precision) -> String {
+    match format {
+        Format::Text => {
+            let time = |duration: Option<Duration>| duration.map_or("n/a".to_string(), |duration| format_stopwatch_time_with(duration, precision));
+            let exit = match signal(measurement.status) {
+                Some(signal) => format!("{} (signal {})", exit_code(measurement.status), signal),
+                None => exit_code(measurement.status).to_string(),
+            };
+            format!(
+                "[TIME] {}\n   Wall: {}\n   User: {}\n   Sys:  {}\n   Exit: {}\n",
+                command.join(" "),
+                time(Some(measurement.wall)),
+                time(measurement.user),
+                time(measurement.sys),
+                exit
+            )
+        }
+        Format::Json => {
+            let seconds = |duration: Option<Duration>| duration.map(|duration| duration.as_secs_f64());
+            let report = json!({
+                "command": command,
+                "exit_code": exit_code(measurement.status),
+                "wall_seconds": measurement.wall.as_secs_f64(),
+                "user_seconds": seconds(measurement.user),
+                "sys_seconds": seconds(measurement.sys),
+            });
+            format!("{:#}\n", report)
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> Vec<String> {
+        ["sh", "-c", script].map(String::from).to_vec()
+    }
+
+    #[test]
+    fn test_measures_and_passes_on_exit_code() {
+        let measurement = run(&sh("sleep 0.05; exit 3")).unwrap();
+        assert_eq!(exit_code(measurement.status), 3);
+        assert!(measurement.wall >= Duration::from_millis(50));
+        assert!(measurement.user.is_some() && measurement.sys.is_some());
+
+        let killed = run(&sh("kill -9 $$")).unwrap();
+        assert_eq!(exit_code(killed.status), 137);
+        assert!(render(Format::Text, &sh("x"), &killed, Precision::Ms).contains("Exit: 137 (signal 9)"));
+
+        let err = run(&["clockeroo-no-such-command".to_string()]).unwrap_err();
+        assert_eq!(launch_failure_code(&err), 127);
+    }
+
+    #[test]
+    fn test_render() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let measurement = Measurement {
+            wall: Duration::from_micros(1_234_567),
+            user: Some(Duration::from_micros(842_001)),
+            sys: None,
+            status: ExitStatus::from_raw(0),
+        };
+        let command = ["make".to_string(), "test".to_string()];
+        let text = render(Format::Text, &command, &measurement, Precision::Us);
+        assert_eq!(text, "[TIME] make test\n   Wall: 00:01.234567\n   User: 00:00.842001\n   Sys:  n/a\n   Exit: 0\n");
+
+        let json: serde_json::Value = serde_json::from_str(&render(Format::Json, &command, &measurement, Precision::Us)).unwrap();
+        assert_eq!(json["command"], json!(["make", "test"]));
+        assert_eq!((json["exit_code"].as_i64(), json["wall_seconds"].as_f64()), (Some(0), Some(1.234567)));
+        assert!(json["sys_seconds"].is_null());
+    }
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use crate::zones::Threshold;
+
+/// What happens when a timer reaches a milestone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// The `--warn-at-percent` time check
+    TimeCheck,
+    /// The time left turns yellow
+    Warn,
+    /// The time left turns red (and beeps with `--critical-beep`)
+    Critical,
+}
+
+/// One point in a timer the user asked to hear about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Milestone {
+    /// Time into the timer it comes at
+    pub at: Duration,
+    /// As the user defined it: "80%", "10m left"
+    pub name: String,
+    pub kind: Kind,
+}
+
+/// A timer's milestones in the order they come, and how far it has got
+/// through them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schedule {
+    milestones: Vec<Milestone>,
+    passed: usize,
+}
+
+impl Schedule {
+    /// The milestones of a `total` long timer: the `--warn-at-percent` time
+    /// check, and the `--warn-at`/`--critical-at` thresholds if any were given.
+    /// The built-in thresholds aren't milestones, every timer has those.
+    pub fn new(total: Duration, warn_at_percent: Option<u8>, warn_at: Option<&str>, critical_at: Option<&str>) -> Schedule {
+        let mut milestones = vec![];
+        if let Some(percent) = warn_at_percent {
+            milestones.push(Milestone {
+                at: total * u32::from(percent) / 100,
+                name: format!("{}%", percent),
+                kind: Kind::TimeCheck,
+            });
+        }
+        for (given, kind) in [(warn_at, Kind::Warn), (critical_at, Kind::Critical)] {
+            // Already checked against the total by `Zones::new`
+            if let Some((given, threshold)) = given.and_then(|s| Some((s.trim(), Threshold::parse(s).ok()?))) {
+                let left = threshold.resolve(total);
+                milestones.push(Milestone { at: total.saturating_sub(left), name: format!("{} left", given), kind });
+            }
+        }
+        // Stable, so a time check and a threshold at the same point keep this order
+        milestones.sort_by_key(|milestone| milestone.at);
+        Schedule { milestones, passed: 0 }
+    }
+
+    /// The first milestone still to come.
+    pub fn next(&self) -> Option<&Milestone> {
+        self.milestones.get(self.passed)
+    }
+
+    /// All of them in order, passed or not.
+    pub fn all(&self) -> &[Milestone] {
+        &self.milestones
+    }
+
+    /// Moves past every milestone reached by `elapsed`, returning those.
+    pub fn advance(&mut self, elapsed: Duration) -> &[Milestone] {
+        let from = self.passed;
+        while self.next().is_some_and(|milestone| milestone.at <= elapsed) {
+            self.passed += 1;
+        }
+        &self.milestones[from..self.passed]
+    }
+
+    /// Whether a milestone of `kind` has been passed, so its effect stays on screen.
+    pub fn has_passed(&self, kind: Kind) -> bool {
+        self.milestones[..self.passed].iter().any(|milestone| milestone.kind == kind)
+    }
+
+    /// Back to the start, for the next round of an `--every` timer.
+    pub fn restart(&mut self) {
+        self.passed = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn test_schedule_order() {
+        let mut schedule = Schedule::new(secs(1200), Some(50), Some("10m"), Some("1%"));
+        let names: Vec<&str> = schedule.milestones.iter().map(|milestone| milestone.name.as_str()).collect();
+        // 10m left and halfway are both at 10:00 in
+        assert_eq!(names, ["50%", "10m left", "1% left"]);
+        assert_eq!(schedule.next().map(|milestone| milestone.at), Some(secs(600)));
+
+        assert!(schedule.advance(secs(599)).is_empty());
+        let passed: Vec<Kind> = schedule.advance(secs(600)).iter().map(|milestone| milestone.kind).collect();
+        assert_eq!(passed, [Kind::TimeCheck, Kind::Warn]);
+        assert!(schedule.has_passed(Kind::TimeCheck));
+        assert_eq!(schedule.next().unwrap().at, secs(1188));
+
+        assert_eq!(schedule.advance(secs(1300)).len(), 1);
+        assert_eq!(schedule.next(), None);
+        schedule.restart();
+        assert!(!schedule.has_passed(Kind::TimeCheck));
+        assert_eq!(schedule.next().unwrap().name, "50%");
+    }
+
+    #[test]
+    fn test_time_check_point() {
+        let at = |total: Duration, percent| Schedule::new(total, Some(percent), None, None).next().unwrap().at;
+        // 80% of 30 minutes is 24 minutes
+        assert_eq!(at(secs(30 * 60), 80), secs(24 * 60));
+        // Percentages that don't divide evenly round the point down
+        assert_eq!(at(secs(1), 33), Duration::from_millis(330));
+
+        let mut schedule = Schedule::new(secs(30 * 60), Some(80), None, None);
+        assert!(schedule.advance(Duration::from_millis(24 * 60_000 - 1)).is_empty());
+        assert_eq!(schedule.advance(secs(24 * 60))[0].kind, Kind::TimeCheck);
+    }
+
+    #[test]
+    fn test_no_milestones() {
+        let mut schedule = Schedule::new(secs(60), None, None, None);
+        assert_eq!(schedule.next(), None);
+        assert!(schedule.advance(secs(60)).is_empty());
+    }
+}
@@ -0,0 +1,420 @@
+use notify_rust::{Notification, NotificationHandle};
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        mpsc, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// How long a notification may take before we stop waiting on the daemon.
+const SEND_TIMEOUT: Duration = Duration::from_secs(2);
+
+static SENT: AtomicUsize = AtomicUsize::new(0);
+static FAILED: AtomicUsize = AtomicUsize::new(0);
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+/// How long after one notification others are held back and sent as one.
+pub const COALESCE_WINDOW: Duration = Duration::from_secs(2);
+
+static WINDOW_MS: AtomicU64 = AtomicU64::new(COALESCE_WINDOW.as_millis() as u64);
+static DISPATCHER: OnceLock<mpsc::Sender<Message>> = OnceLock::new();
+
+/// How insistent a notification is. Only honoured by freedesktop notification daemons.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Urgency {
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// Shows a desktop notification. Never panics or blocks: notifications go out
+/// from one dispatcher thread, which holds back ones that come right after
+/// another and sends them together (see [`Coalescer`]). Failures and hung
+/// notification daemons are recorded and reported once via `failure_hint`.
+pub fn send_notification(title: &str, body: &str) {
+    send_notification_with_urgency(title, body, Urgency::Normal);
+}
+
+pub fn send_notification_with_urgency(title: &str, body: &str, urgency: Urgency) {
+    let note = Note { title: title.to_string(), body: body.to_string(), urgency };
+    let _ = dispatcher().send(Message::Show(note));
+}
+
+/// Shows an alarm's notification, see [`Note::alarm`].
+pub fn send_alarm_notification(title: &str, body: &str) {
+    let _ = dispatcher().send(Message::Show(Note::alarm(title, body)));
+}
+
+/// Sets the coalescing window (`notifications.coalesce`), zero to send each
+/// notification as it comes. Only before the first notification.
+pub fn set_coalesce_window(window: Duration) {
+    WINDOW_MS.store(window.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// Sends whatever is being held back and waits for it to go out, so nothing
+/// is lost when the process exits.
+pub fn flush() {
+    let Some(dispatcher) = DISPATCHER.get() else {
+        return;
+    };
+    let (done, wait) = mpsc::channel();
+    if dispatcher.send(Message::Flush(done)).is_ok() {
+        let _ = wait.recv_timeout(SEND_TIMEOUT * 2);
+    }
+}
+
+/// A notification on its way out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    pub title: String,
+    pub body: String,
+    pub urgency: Urgency,
+}
+
+impl Note {
+    /// An alarm going off, or one that went unanswered. Always critical, so
+    /// it's never held back or folded in with others.
+    pub fn alarm(title: &str, body: &str) -> Note {
+        Note { title: title.to_string(), body: body.to_string(), urgency: Urgency::Critical }
+    }
+}
+
+enum Message {
+    Show(Note),
+    Flush(mpsc::Sender<()>),
+}
+
+fn dispatcher() -> &'static mpsc::Sender<Message> {
+    DISPATCHER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        let window = Duration::from_millis(WINDOW_MS.load(Ordering::Relaxed));
+        std::thread::spawn(move || dispatch(rx, Coalescer::new(window)));
+        tx
+    })
+}
+
+fn dispatch(messages: mpsc::Receiver<Message>, mut coalescer: Coalescer) {
+    let deliver = |note: Note| {
+        show(build(&note.title, &note.body, note.urgency), |_| ());
+    };
+    loop {
+        let message = match coalescer.due() {
+            Some(due) => match messages.recv_timeout(due.saturating_duration_since(Instant::now())) {
+                Ok(message) => Some(message),
+                Err(mpsc::RecvTimeoutError::Timeout) => None,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            },
+            None => match messages.recv() {
+                Ok(message) => Some(message),
+                Err(_) => return,
+            },
+        };
+        match message {
+            Some(Message::Show(note)) => {
+                if let Some(note) = coalescer.push(note, Instant::now()) {
+                    deliver(note);
+                }
+            }
+            Some(Message::Flush(done)) => {
+                if let Some(note) = coalescer.take_all() {
+                    deliver(note);
+                }
+                let _ = done.send(());
+            }
+            None => {}
+        }
+        if let Some(note) = coalescer.take_due(Instant::now()) {
+            deliver(note);
+        }
+    }
+}
+
+/// Keeps notifications from stacking up when several come at once (milestones,
+/// parallel timers finishing together): the first goes out right away, any
+/// within `window` of the last one sent wait for the window to end and go out
+/// as one. So there's at most one notification per window, however many
+/// events. Critical notifications skip all this and go out on their own.
+#[derive(Debug)]
+pub struct Coalescer {
+    window: Duration,
+    last_sent: Option<Instant>,
+    held: Vec<Note>,
+}
+
+impl Coalescer {
+    pub fn new(window: Duration) -> Coalescer {
+        Coalescer { window, last_sent: None, held: Vec::new() }
+    }
+
+    /// What to send for `note` arriving at `now`: itself, or nothing for now.
+    pub fn push(&mut self, note: Note, now: Instant) -> Option<Note> {
+        if note.urgency == Urgency::Critical {
+            return Some(note);
+        }
+        let quiet = self.last_sent.is_none_or(|sent| now.duration_since(sent) >= self.window);
+        if self.held.is_empty() && quiet {
+            self.last_sent = Some(now);
+            return Some(note);
+        }
+        self.held.push(note);
+        None
+    }
+
+    /// When the held notifications are due to go out.
+    pub fn due(&self) -> Option<Instant> {
+        if self.held.is_empty() {
+            return None;
+        }
+        self.last_sent.map(|sent| sent + self.window)
+    }
+
+    /// The held notifications as one, if they're due by `now`.
+    pub fn take_due(&mut self, now: Instant) -> Option<Note> {
+        if self.due().is_none_or(|due| now < due) {
+            return None;
+        }
+        self.last_sent = Some(now);
+        self.take_all()
+    }
+
+    /// The held notifications as one, due or not.
+    pub fn take_all(&mut self) -> Option<Note> {
+        combine(std::mem::take(&mut self.held))
+    }
+}
+
+/// One notification saying what `notes` did: their bodies under the shared
+/// title, or each title with its body when they differ.
+fn combine(notes: Vec<Note>) -> Option<Note> {
+    let first = notes.first()?;
+    if notes.len() == 1 {
+        return Some(first.clone());
+    }
+    let same_title = notes.iter().all(|note| note.title == first.title);
+    let parts: Vec<String> = notes
+        .iter()
+        .map(|note| match same_title {
+            true => note.body.clone(),
+            false => format!("{}: {}", note.title.trim_end_matches(['!', '.']), note.body),
+        })
+        .collect();
+    Some(Note {
+        title: if same_title { first.title.clone() } else { format!("{} notifications", notes.len()) },
+        body: parts.join(" · "),
+        urgency: Urgency::Normal,
+    })
+}
+
+fn build(title: &str, body: &str, urgency: Urgency) -> Notification {
+    let mut notification = Notification::new();
+    notification.summary(title).body(body).icon("dialog-information").timeout(0);
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    notification.urgency(match urgency {
+        Urgency::Normal => notify_rust::Urgency::Normal,
+        Urgency::Critical => notify_rust::Urgency::Critical,
+    });
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    let _ = urgency;
+
+    notification
+}
+
+/// Shows `notification` and hands back what `extract` takes from its handle,
+/// or `None` if it failed or the daemon didn't answer in time.
+fn show<T: Send + 'static>(notification: Notification, extract: impl FnOnce(NotificationHandle) -> T + Send + 'static) -> Option<T> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let result = notification.show().map(extract).map_err(|e| e.to_string());
+        let _ = tx.send(result);
+    });
+
+    let error = match rx.recv_timeout(SEND_TIMEOUT) {
+        Ok(Ok(value)) => {
+            SENT.fetch_add(1, Ordering::Relaxed);
+            return Some(value);
+        }
+        Ok(Err(err)) => err,
+        Err(_) => format!("no response within {}s", SEND_TIMEOUT.as_secs()),
+    };
+
+    FAILED.fetch_add(1, Ordering::Relaxed);
+    *LAST_ERROR.lock().unwrap() = Some(error);
+    None
+}
+
+/// One notification that's replaced in place on every update rather than
+/// stacking up (`--progress-notification`), and closed once the timer ends
+/// however it ends, or at the latest when this is dropped. Updates go out from
+/// a thread of its own, so a slow notification daemon never holds up the
+/// countdown. Only freedesktop notification daemons can replace a notification
+/// by id, elsewhere there's none to make.
+pub struct ProgressNotification {
+    updates: mpsc::Sender<Progress>,
+}
+
+enum Progress {
+    Show(Note),
+    Close(mpsc::Sender<()>),
+}
+
+impl ProgressNotification {
+    pub fn new() -> Option<ProgressNotification> {
+        if !cfg!(all(unix, not(target_os = "macos"))) {
+            return None;
+        }
+        let (updates, rx) = mpsc::channel();
+        std::thread::spawn(move || run_progress(rx));
+        Some(ProgressNotification { updates })
+    }
+
+    /// Shows the notification, or replaces what it said last time.
+    pub fn update(&self, title: &str, body: &str, urgency: Urgency) {
+        let note = Note { title: title.to_string(), body: body.to_string(), urgency };
+        let _ = self.updates.send(Progress::Show(note));
+    }
+
+    /// Takes the notification down; the next update puts up a new one.
+    pub fn close(&self) {
+        let (done, _) = mpsc::channel();
+        let _ = self.updates.send(Progress::Close(done));
+    }
+}
+
+impl Drop for ProgressNotification {
+    /// Waits (a little) for it to be closed, so it doesn't outlive the process.
+    fn drop(&mut self) {
+        let (done, wait) = mpsc::channel();
+        if self.updates.send(Progress::Close(done)).is_ok() {
+            let _ = wait.recv_timeout(SEND_TIMEOUT);
+        }
+    }
+}
+
+fn run_progress(updates: mpsc::Receiver<Progress>) {
+    let mut shown: Option<NotificationHandle> = None;
+    for update in updates {
+        match update {
+            Progress::Show(note) => {
+                let mut notification = build(&note.title, &note.body, note.urgency);
+                #[cfg(all(unix, not(target_os = "macos")))]
+                if let Some(handle) = &shown {
+                    notification.id(handle.id());
+                }
+                // A failed update leaves the last one up, to be replaced or closed later
+                if let Some(handle) = show(notification, |handle| handle) {
+                    shown = Some(handle);
+                }
+            }
+            Progress::Close(done) => {
+                if let Some(handle) = shown.take() {
+                    handle.close();
+                }
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+/// A one-time hint for stderr if every notification this run failed.
+pub fn failure_hint() -> Option<String> {
+    hint_for(
+        SENT.load(Ordering::Relaxed),
+        FAILED.load(Ordering::Relaxed),
+        LAST_ERROR.lock().unwrap().as_deref(),
+    )
+}
+
+fn hint_for(sent: usize, failed: usize, last_error: Option<&str>) -> Option<String> {
+    if failed == 0 || sent > 0 {
+        return None;
+    }
+    Some(format!(
+        "[HINT] Desktop notifications aren't working ({}). Is a notification daemon running?",
+        last_error.unwrap_or("unknown error")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(title: &str, body: &str) -> Note {
+        Note { title: title.to_string(), body: body.to_string(), urgency: Urgency::Normal }
+    }
+
+    #[test]
+    fn test_coalescing() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut coalescer = Coalescer::new(Duration::from_secs(2));
+
+        // The first goes straight out, the next two within the window wait
+        assert_eq!(coalescer.push(note("Timer Finished!", "pasta has completed!"), at(0)), Some(note("Timer Finished!", "pasta has completed!")));
+        assert_eq!(coalescer.push(note("Time Check", "sauce: 04:00 left"), at(300)), None);
+        assert_eq!(coalescer.push(note("Time Check", "rice: 08:00 left"), at(900)), None);
+        assert_eq!(coalescer.due(), Some(at(2000)));
+        assert_eq!(coalescer.take_due(at(1999)), None);
+
+        let combined = coalescer.take_due(at(2000)).unwrap();
+        assert_eq!(combined.title, "Time Check");
+        assert_eq!(combined.body, "sauce: 04:00 left · rice: 08:00 left");
+        assert_eq!(coalescer.due(), None);
+
+        // Rate capped: right after that one, the next waits a whole window again
+        assert_eq!(coalescer.push(note("Timer Finished!", "rice has completed!"), at(2500)), None);
+        assert_eq!(coalescer.due(), Some(at(4000)));
+        // Once it's quiet again, straight out
+        assert!(coalescer.take_due(at(4000)).is_some());
+        assert!(coalescer.push(note("Alarm!", "It's 07:00!"), at(6000)).is_some());
+    }
+
+    #[test]
+    fn test_alarm_note_is_critical() {
+        let alarm = Note::alarm("Alarm!", "It's 07:00!");
+        assert_eq!((alarm.title.as_str(), alarm.body.as_str()), ("Alarm!", "It's 07:00!"));
+        // Which is what keeps it out of the coalescer's bursts
+        assert_eq!(alarm.urgency, Urgency::Critical);
+    }
+
+    #[test]
+    fn test_critical_goes_straight_out() {
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new(Duration::from_secs(2));
+        coalescer.push(note("Time Check", "80% done"), start);
+        assert_eq!(coalescer.push(note("Time Check", "90% done"), start), None);
+
+        let critical = Note { urgency: Urgency::Critical, ..note("Back to work!", "Break is over.") };
+        assert_eq!(coalescer.push(critical.clone(), start + Duration::from_millis(10)), Some(critical));
+        // And doesn't hold up or join the others
+        assert_eq!(coalescer.due(), Some(start + Duration::from_secs(2)));
+        assert_eq!(coalescer.take_all().unwrap().body, "90% done");
+    }
+
+    #[test]
+    fn test_combined_titles() {
+        let combined = combine(vec![note("Timer Finished!", "pasta has completed!"), note("Time Check", "sauce: 04:00 left")]).unwrap();
+        assert_eq!(combined.title, "2 notifications");
+        assert_eq!(combined.body, "Timer Finished: pasta has completed! · Time Check: sauce: 04:00 left");
+        assert_eq!(combine(vec![]), None);
+    }
+
+    #[test]
+    fn test_no_window_sends_everything() {
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new(Duration::ZERO);
+        assert!(coalescer.push(note("a", "1"), start).is_some());
+        assert!(coalescer.push(note("b", "2"), start).is_some());
+        assert_eq!(coalescer.due(), None);
+    }
+
+    #[test]
+    fn test_hint_only_when_notifications_consistently_fail() {
+        assert_eq!(hint_for(0, 0, None), None);
+        assert_eq!(hint_for(2, 1, Some("timeout")), None);
+
+        let hint = hint_for(0, 2, Some("org.freedesktop.Notifications was not provided")).unwrap();
+        assert!(hint.contains("was not provided"));
+    }
+}
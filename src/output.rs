@@ -0,0 +1,42 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// Prints decorative or progress output (banners, hints, "Starting..." lines).
+/// Dropped under `--quiet`; result lines and errors use plain `println!`/`eprintln!`.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Diagnostics for `--verbose`, written to stderr so they never mix with results.
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if $crate::output::is_verbose() {
+            eprintln!("[verbose] {}", format_args!($($arg)*));
+        }
+    };
+}
+
+pub(crate) use info;
+pub(crate) use verbose;
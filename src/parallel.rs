@@ -0,0 +1,252 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use crossterm::event::KeyCode;
+use std::time::{Duration, Instant};
+
+use crate::{
+    clock::{self, Countdown},
+    config,
+    history,
+    notify,
+    output::verbose,
+    parse_duration,
+    quiet_hours::{is_quiet_now, QuietHours},
+    sound::{play_bell, SoundEvent, SoundOptions},
+    store::EntryKind,
+    teardown,
+    ui::{self, ParallelState, ParallelTimer, ParallelView},
+    zones::{Source, Zones},
+};
+
+/// Redraw interval, the same as a single timer's.
+const PARALLEL_FRAME: Duration = Duration::from_millis(200);
+
+/// How much '+' and '-' move the selected timer.
+const ADJUST_STEP: Duration = Duration::from_secs(60);
+
+/// One timer from the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spec {
+    pub label: String,
+    pub duration: Duration,
+}
+
+/// Reads `DURATION[:LABEL]` arguments ("8m:pasta"). A timer without a label is
+/// named after its place.
+pub fn parse_specs(args: &[String]) -> Result<Vec<Spec>> {
+    args.iter()
+        .enumerate()
+        .map(|(index, arg)| {
+            // Durations never have a colon, labels may
+            let (duration, label) = arg.split_once(':').unwrap_or((arg, ""));
+            let duration = parse_duration(duration).with_context(|| format!("Invalid timer '{}'", arg))?;
+            let label = match label.trim() {
+                "" => format!("Timer {}", index + 1),
+                label => label.to_string(),
+            };
+            Ok(Spec { label, duration })
+        })
+        .collect()
+}
+
+/// How a timer's run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    Finished(DateTime<Local>),
+    /// Stopped with 'd', with this much left
+    Dismissed(Duration),
+    /// Still running when the screen was quit
+    Cancelled(Duration),
+}
+
+pub struct ParallelOptions {
+    /// `--warn-at`/`--critical-at`
+    pub warn_at: Option<String>,
+    pub critical_at: Option<String>,
+    /// The config's `timer` section, for the ones not given
+    pub timer_config: config::TimerConfig,
+    pub quiet_hours: Option<QuietHours>,
+    pub urgency: notify::Urgency,
+    pub visual_bell: bool,
+    pub spinner: bool,
+    pub sound: SoundOptions,
+}
+
+struct Slot {
+    spec: Spec,
+    countdown: Countdown,
+    zones: Zones,
+    ending: Option<Ending>,
+}
+
+impl Slot {
+    /// Records how the timer ended, and logs it to the history like a lone timer.
+    fn end(&mut self, ending: Ending) {
+        self.ending = Some(ending);
+        let outcome = match ending {
+            Ending::Finished(_) => history::Outcome::Finished,
+            Ending::Dismissed(_) | Ending::Cancelled(_) => history::Outcome::Cancelled,
+        };
+        let record = history::Record {
+            at: Local::now(),
+            kind: EntryKind::Timer,
+            label: Some(self.spec.label.clone()),
+            tag: None,
+            outcome,
+            duration_ms: Some(self.spec.duration.as_millis() as u64),
+        };
+        if let Err(err) = history::append(&record) {
+            verbose!("couldn't log the timer: {:#}", err);
+        }
+    }
+
+    fn state(&self) -> ParallelState {
+        match self.ending {
+            None | Some(Ending::Cancelled(_)) => ParallelState::Running,
+            Some(Ending::Finished(_)) => ParallelState::Finished,
+            Some(Ending::Dismissed(_)) => ParallelState::Dismissed,
+        }
+    }
+}
+
+/// The next selection after `selected` going `forward` (Tab) or back (Shift-Tab).
+fn cycle(selected: usize, count: usize, forward: bool) -> usize {
+    match forward {
+        true => (selected + 1) % count,
+        false => (selected + count - 1) % count,
+    }
+}
+
+async fn ring(spec: &Spec, options: &ParallelOptions) {
+    if is_quiet_now(options.quiet_hours.as_ref()) {
+        return;
+    }
+    play_bell(SoundEvent::TimerDone, &options.sound);
+    notify::send_notification_with_urgency("Timer Finished!", &format!("{} has completed!", spec.label), options.urgency);
+    if options.visual_bell {
+        ui::visual_bell().await;
+    }
+}
+
+/// Runs every timer at once on one screen until the screen is quit, or
+/// dismissed once each timer has finished or been dismissed. Returns how each
+/// one ended, in order.
+pub async fn run_parallel_ui(specs: Vec<Spec>, options: ParallelOptions) -> Result<Vec<(Spec, Ending)>> {
+    let mut slots = specs
+        .into_iter()
+        .map(|spec| {
+            let zones = Zones::new(
+                Source::pick(options.warn_at.as_deref(), options.timer_config.warn_at.as_deref()),
+                Source::pick(options.critical_at.as_deref(), options.timer_config.critical_at.as_deref()),
+                spec.duration,
+            )
+                .with_context(|| format!("For {}", spec.label))?;
+            Ok(Slot { countdown: Countdown::new(spec.duration), zones, spec, ending: None })
+        })
+        .collect::<Result<Vec<Slot>>>()?;
+
+    let mut terminal = ui::enter_screen()?;
+    let start = Instant::now();
+    let mut selected = 0;
+    let mut frame = 0usize;
+
+    loop {
+        let now = start.elapsed();
+        for slot in slots.iter_mut().filter(|slot| slot.ending.is_none()) {
+            if slot.countdown.remaining(now).is_zero() {
+                slot.end(Ending::Finished(Local::now()));
+                ring(&slot.spec, &options).await;
+            }
+        }
+        // Like a single timer's finished screen, this waits to be dismissed,
+        // unless there's nobody at the keyboard to do it
+        let ended = slots.iter().all(|slot| slot.ending.is_some());
+        if ended && ui::is_view_only() || teardown::requested() {
+            break;
+        }
+        frame = frame.wrapping_add(1);
+
+        terminal.draw(|f| {
+            let timers: Vec<ParallelTimer> = slots
+                .iter()
+                .map(|slot| {
+                    let remaining = clock::ceil_secs(slot.countdown.remaining(now));
+                    ParallelTimer {
+                        label: &slot.spec.label,
+                        remaining,
+                        zone: slot.zones.zone(remaining),
+                        paused: slot.countdown.is_paused(),
+                        state: slot.state(),
+                    }
+                })
+                .collect();
+            let view = ParallelView { timers: &timers, selected, spinner: options.spinner.then_some(frame) };
+            ui::draw_parallel(f, &view);
+        })?;
+
+        // Wake right when the next running timer reaches zero
+        let next_end = slots
+            .iter()
+            .filter(|slot| slot.ending.is_none() && !slot.countdown.is_paused())
+            .map(|slot| slot.countdown.remaining(now))
+            .min()
+            .unwrap_or(PARALLEL_FRAME);
+        let Some(key) = ui::poll_key(PARALLEL_FRAME.min(next_end))? else {
+            continue;
+        };
+        if ui::is_quit_event(&key) || ended && ui::is_dismiss_event(&key) {
+            break;
+        }
+        let now = start.elapsed();
+        let count = slots.len();
+        let slot = &mut slots[selected];
+        match key.code {
+            KeyCode::Tab => selected = cycle(selected, count, true),
+            KeyCode::BackTab => selected = cycle(selected, count, false),
+            _ if slot.ending.is_some() => {}
+            KeyCode::Char('p') => slot.countdown.toggle_pause(now),
+            KeyCode::Char('+') | KeyCode::Char('=') => slot.countdown.lengthen(ADJUST_STEP),
+            KeyCode::Char('-') => slot.countdown.shorten(ADJUST_STEP, now),
+            KeyCode::Char('d') => slot.end(Ending::Dismissed(clock::ceil_secs(slot.countdown.remaining(now)))),
+            _ => {}
+        }
+    }
+
+    let now = start.elapsed();
+    for slot in slots.iter_mut().filter(|slot| slot.ending.is_none()) {
+        slot.end(Ending::Cancelled(clock::ceil_secs(slot.countdown.remaining(now))));
+    }
+
+    ui::leave_screen(terminal)?;
+
+    Ok(slots.into_iter().filter_map(|slot| Some((slot.spec, slot.ending?))).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_specs() {
+        let args = ["8m:pasta", "12m:sauce: marinara", "90s", "1m30s:"].map(String::from);
+        assert_eq!(
+            parse_specs(&args).unwrap(),
+            [
+                Spec { label: "pasta".into(), duration: Duration::from_secs(480) },
+                Spec { label: "sauce: marinara".into(), duration: Duration::from_secs(720) },
+                Spec { label: "Timer 3".into(), duration: Duration::from_secs(90) },
+                Spec { label: "Timer 4".into(), duration: Duration::from_secs(90) },
+            ]
+        );
+        let err = parse_specs(&["5m:ok".to_string(), "soon:bad".to_string()]).unwrap_err();
+        assert!(format!("{:#}", err).starts_with("Invalid timer 'soon:bad'"), "{:#}", err);
+    }
+
+    #[test]
+    fn test_cycle() {
+        assert_eq!(cycle(0, 3, true), 1);
+        assert_eq!(cycle(2, 3, true), 0);
+        assert_eq!(cycle(0, 3, false), 2);
+        assert_eq!(cycle(0, 1, true), 0);
+    }
+}
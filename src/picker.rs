@@ -0,0 +1,471 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders},
+    Frame,
+};
+use std::time::Duration;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    format_duration,
+    history::Suggestion,
+    parse_duration,
+    ui::{self, fit_width},
+};
+
+/// Widest a choice's text gets before it's cut short.
+const MAX_CHOICE_WIDTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    List,
+    Input,
+}
+
+/// What a key press did to the picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PickerEvent {
+    Continue,
+    Picked(Duration, Option<String>),
+    Cancelled,
+}
+
+/// One row of the list.
+#[derive(Debug, Clone)]
+struct Choice {
+    text: String,
+    duration: Duration,
+    /// Set for suggestions from history, which come with the label they were run with
+    label: Option<String>,
+    suggested: bool,
+}
+
+/// Suggestions from history and quick choices, plus a free-form duration field.
+#[derive(Debug)]
+pub struct Picker {
+    choices: Vec<Choice>,
+    selected: usize,
+    focus: Focus,
+    input: String,
+}
+
+impl Picker {
+    /// Suggestions are listed first. Fails if a configured choice isn't a valid duration.
+    pub fn new(suggestions: &[Suggestion], choices: &[String]) -> Result<Picker> {
+        let suggested = suggestions.iter().map(|suggestion| Choice {
+            text: suggestion.label.clone().unwrap_or_else(|| "timer".to_string()),
+            duration: suggestion.duration,
+            label: suggestion.label.clone(),
+            suggested: true,
+        });
+        let quick = choices.iter().map(|choice| {
+            parse_duration(choice)
+                .map(|duration| Choice { text: choice.clone(), duration, label: None, suggested: false })
+                .map_err(|err| anyhow::anyhow!("Invalid quick duration '{}': {}", choice, err))
+        });
+        let choices = suggested.map(Ok).chain(quick).collect::<Result<Vec<_>>>()?;
+        let focus = if choices.is_empty() { Focus::Input } else { Focus::List };
+        Ok(Picker { choices, selected: 0, focus, input: String::new() })
+    }
+
+    /// The live parse error for the typed duration, if any.
+    fn input_error(&self) -> Option<String> {
+        if self.input.trim().is_empty() {
+            return None;
+        }
+        parse_duration(&self.input).err().map(|err| err.to_string())
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> PickerEvent {
+        match (code, self.focus) {
+            (KeyCode::Esc, _) => return PickerEvent::Cancelled,
+            (KeyCode::Tab, _) if !self.choices.is_empty() => {
+                self.focus = match self.focus {
+                    Focus::List => Focus::Input,
+                    Focus::Input => Focus::List,
+                };
+            }
+            (KeyCode::Up, Focus::List) => self.selected = self.selected.saturating_sub(1),
+            (KeyCode::Down, Focus::List) => self.selected = (self.selected + 1).min(self.choices.len() - 1),
+            (KeyCode::Char(c @ '1'..='9'), Focus::List) => {
+                let index = c as usize - '1' as usize;
+                if index < self.choices.len() {
+                    self.selected = index;
+                }
+            }
+            (KeyCode::Enter, Focus::List) => {
+                let choice = &self.choices[self.selected];
+                return PickerEvent::Picked(choice.duration, choice.label.clone());
+            }
+            (KeyCode::Char(c), Focus::Input) => self.input.push(c),
+            (KeyCode::Backspace, Focus::Input) => {
+                self.input.pop();
+            }
+            (KeyCode::Enter, Focus::Input) => {
+                if let Ok(duration) = parse_duration(&self.input) {
+                    return PickerEvent::Picked(duration, None);
+                }
+            }
+            _ => {}
+        }
+        PickerEvent::Continue
+    }
+
+    fn draw(&self, f: &mut Frame) {
+        let mut lines = screen_lines("Start a Timer");
+
+        // Labels of suggestions can be longer than "25m", widen the column (up to a point) to fit
+        let text_width = self.choices.iter().map(|choice| choice.text.width()).max().unwrap_or_default().clamp(6, MAX_CHOICE_WIDTH);
+        let any_suggested = self.choices.iter().any(|choice| choice.suggested);
+        for (i, choice) in self.choices.iter().enumerate() {
+            let selected = self.focus == Focus::List && i == self.selected;
+            let style = match (selected, choice.suggested) {
+                (true, _) => Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD),
+                (false, true) => Style::default().fg(Color::Green),
+                (false, false) => Style::default().fg(Color::White),
+            };
+            let text = fit_width(&choice.text, text_width);
+            let padding = " ".repeat(text_width - text.width());
+            let marker = match (any_suggested, choice.suggested) {
+                (false, _) => "",
+                (true, true) => "usual ",
+                (true, false) => "      ",
+            };
+            lines.push(Line::from(vec![Span::styled(
+                format!(" {}  {}{} {:>8} {}", i + 1, text, padding, format_duration(choice.duration), marker),
+                style,
+            )]));
+        }
+
+        lines.push(Line::from(""));
+        let field_style = if self.focus == Focus::Input {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        let cursor = if self.focus == Focus::Input { "_" } else { "" };
+        lines.push(Line::from(vec![Span::styled(format!("Duration: {}{}", self.input, cursor), field_style)]));
+        match self.input_error() {
+            Some(err) => lines.push(Line::from(vec![Span::styled(err, Style::default().fg(Color::Red))])),
+            None => lines.push(Line::from("")),
+        }
+
+        lines.push(Line::from(""));
+        let help = if self.choices.is_empty() {
+            "Type a duration, Enter to start, Esc to exit"
+        } else {
+            "Up/Down or 1-9 to choose, Tab to type a duration, Enter to start, Esc to exit"
+        };
+        lines.push(Line::from(vec![Span::styled(help, Style::default().fg(Color::Gray))]));
+        draw_screen(f, lines);
+    }
+}
+
+impl Screen for Picker {
+    type Output = (Duration, Option<String>);
+
+    fn draw(&self, f: &mut Frame) {
+        Picker::draw(self, f)
+    }
+
+    fn on_key(&mut self, code: KeyCode) -> Option<Option<Self::Output>> {
+        match self.handle_key(code) {
+            PickerEvent::Continue => None,
+            PickerEvent::Picked(duration, label) => Some(Some((duration, label))),
+            PickerEvent::Cancelled => Some(None),
+        }
+    }
+}
+
+/// The part of a duration Up/Down change on the confirm screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Hours,
+    Minutes,
+    Seconds,
+}
+
+impl Unit {
+    fn step(self) -> Duration {
+        match self {
+            Unit::Hours => Duration::from_secs(3600),
+            Unit::Minutes => Duration::from_secs(60),
+            Unit::Seconds => Duration::from_secs(1),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Unit::Hours => "hours",
+            Unit::Minutes => "minutes",
+            Unit::Seconds => "seconds",
+        }
+    }
+
+    /// The biggest unit `duration` has any of, where a typo would hurt most.
+    fn largest_in(duration: Duration) -> Unit {
+        match duration.as_secs() {
+            3600.. => Unit::Hours,
+            60.. => Unit::Minutes,
+            _ => Unit::Seconds,
+        }
+    }
+}
+
+/// What a key press did on the confirm screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfirmEvent {
+    Continue,
+    Confirmed(Duration),
+    Cancelled,
+}
+
+/// `--confirm`: the duration about to run, in big digits, for a last look and
+/// a tweak before it starts.
+#[derive(Debug)]
+pub struct Confirm {
+    duration: Duration,
+    unit: Unit,
+    label: Option<String>,
+}
+
+impl Confirm {
+    pub fn new(duration: Duration, label: Option<&str>) -> Confirm {
+        Confirm { duration, unit: Unit::largest_in(duration), label: label.map(str::to_string) }
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> ConfirmEvent {
+        match code {
+            KeyCode::Esc => return ConfirmEvent::Cancelled,
+            KeyCode::Left => {
+                self.unit = match self.unit {
+                    Unit::Seconds => Unit::Minutes,
+                    _ => Unit::Hours,
+                }
+            }
+            KeyCode::Right => {
+                self.unit = match self.unit {
+                    Unit::Hours => Unit::Minutes,
+                    _ => Unit::Seconds,
+                }
+            }
+            KeyCode::Up => self.duration += self.unit.step(),
+            // Stops at zero rather than wrapping round or going negative
+            KeyCode::Down => self.duration = self.duration.saturating_sub(self.unit.step()),
+            // Nothing to time
+            KeyCode::Enter if !self.duration.is_zero() => return ConfirmEvent::Confirmed(self.duration),
+            _ => {}
+        }
+        ConfirmEvent::Continue
+    }
+
+    fn draw(&self, f: &mut Frame) {
+        let mut lines = screen_lines("Start this Timer?");
+        if let Some(label) = &self.label {
+            lines.push(Line::from(vec![Span::styled(label.clone(), Style::default().fg(Color::Gray))]));
+            lines.push(Line::from(""));
+        }
+
+        // "MM:SS" or "HH:MM:SS", whichever `format_duration` gives now
+        let text = format_duration(self.duration);
+        let units: &[Unit] = match text.matches(':').count() {
+            2 => &[Unit::Hours, Unit::Minutes, Unit::Seconds],
+            _ => &[Unit::Minutes, Unit::Seconds],
+        };
+        let unit_at: Vec<Option<Unit>> = text
+            .split(':')
+            .zip(units)
+            .flat_map(|(part, unit)| part.chars().map(|_| Some(*unit)).chain([None]))
+            .collect();
+        let time_color = if self.duration.is_zero() { Color::Red } else { Color::Green };
+        lines.extend(ui::big_text(&text, |i| match unit_at.get(i).copied().flatten() {
+            Some(unit) if unit == self.unit => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            _ => Style::default().fg(time_color),
+        }));
+
+        lines.push(Line::from(""));
+        let status = match self.duration.is_zero() {
+            true => Span::styled("Nothing to time, press Up to add some", Style::default().fg(Color::Red)),
+            false => Span::styled(format!("Changing {}", self.unit.name()), Style::default().fg(Color::Yellow)),
+        };
+        lines.push(Line::from(vec![status]));
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![Span::styled(
+            "Left/Right pick a unit, Up/Down change it, Enter to start, Esc to exit",
+            Style::default().fg(Color::Gray),
+        )]));
+        draw_screen(f, lines);
+    }
+}
+
+impl Screen for Confirm {
+    type Output = Duration;
+
+    fn draw(&self, f: &mut Frame) {
+        Confirm::draw(self, f)
+    }
+
+    fn on_key(&mut self, code: KeyCode) -> Option<Option<Duration>> {
+        match self.handle_key(code) {
+            ConfirmEvent::Continue => None,
+            ConfirmEvent::Confirmed(duration) => Some(Some(duration)),
+            ConfirmEvent::Cancelled => Some(None),
+        }
+    }
+}
+
+/// A full-screen prompt run by `run_screen`.
+trait Screen {
+    type Output;
+
+    fn draw(&self, f: &mut Frame);
+
+    /// `Some` once the prompt is over: the answer, or `None` if it was cancelled.
+    fn on_key(&mut self, code: KeyCode) -> Option<Option<Self::Output>>;
+}
+
+/// The header and a title, the top of every prompt screen.
+fn screen_lines(title: &str) -> Vec<Line<'static>> {
+    let mut lines = ui::header_lines();
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(title.to_string(), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]));
+    lines.push(Line::from(""));
+    lines
+}
+
+fn draw_screen(f: &mut Frame, lines: Vec<Line>) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    ui::draw_boxed(f, lines, block);
+}
+
+/// Shows `screen` until it has an answer; Ctrl-C cancels like Esc.
+fn run_screen<S: Screen>(screen: &mut S) -> Result<Option<S::Output>> {
+    let mut terminal = ui::enter_screen()?;
+
+    let answer = loop {
+        terminal.draw(|f| screen.draw(f))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(event::KeyModifiers::CONTROL) {
+                break None;
+            }
+            if let Some(answer) = screen.on_key(key.code) {
+                break answer;
+            }
+        }
+    };
+
+    ui::leave_screen(terminal)?;
+
+    Ok(answer)
+}
+
+/// Shows the picker until a duration (and, for a suggestion, its label) is
+/// chosen, or Esc is pressed (`None`).
+pub fn pick_timer(suggestions: &[Suggestion], choices: &[String]) -> Result<Option<(Duration, Option<String>)>> {
+    run_screen(&mut Picker::new(suggestions, choices)?)
+}
+
+/// Shows `duration` to confirm or adjust before a timer starts. `None` if
+/// it was called off.
+pub fn confirm_timer(duration: Duration, label: Option<&str>) -> Result<Option<Duration>> {
+    run_screen(&mut Confirm::new(duration, label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picker() -> Picker {
+        Picker::new(&[], &["1m".to_string(), "5m".to_string(), "25m".to_string()]).unwrap()
+    }
+
+    #[test]
+    fn test_pick_from_list() {
+        let mut picker = picker();
+        assert_eq!(picker.handle_key(KeyCode::Down), PickerEvent::Continue);
+        assert_eq!(picker.handle_key(KeyCode::Down), PickerEvent::Continue);
+        assert_eq!(picker.handle_key(KeyCode::Down), PickerEvent::Continue);
+        assert_eq!(picker.handle_key(KeyCode::Enter), PickerEvent::Picked(Duration::from_secs(1500), None));
+
+        let mut picker = self::picker();
+        picker.handle_key(KeyCode::Char('2'));
+        assert_eq!(picker.handle_key(KeyCode::Enter), PickerEvent::Picked(Duration::from_secs(300), None));
+        assert_eq!(picker.handle_key(KeyCode::Esc), PickerEvent::Cancelled);
+    }
+
+    #[test]
+    fn test_typed_duration_with_live_error() {
+        let mut picker = picker();
+        picker.handle_key(KeyCode::Tab);
+        picker.handle_key(KeyCode::Char('0'));
+        assert!(picker.input_error().is_some());
+        // Invalid input doesn't start anything
+        assert_eq!(picker.handle_key(KeyCode::Enter), PickerEvent::Continue);
+
+        picker.handle_key(KeyCode::Backspace);
+        for c in "90s".chars() {
+            picker.handle_key(KeyCode::Char(c));
+        }
+        assert_eq!(picker.input_error(), None);
+        assert_eq!(picker.handle_key(KeyCode::Enter), PickerEvent::Picked(Duration::from_secs(90), None));
+    }
+
+    #[test]
+    fn test_confirm_adjusts_the_chosen_unit() {
+        // Starts on the biggest unit there is, so "5h for 5m" is one Down away from fixed
+        let mut confirm = Confirm::new(Duration::from_secs(5 * 3600), None);
+        assert_eq!(confirm.unit, Unit::Hours);
+        confirm.handle_key(KeyCode::Down);
+        confirm.handle_key(KeyCode::Right);
+        confirm.handle_key(KeyCode::Up);
+        assert_eq!(confirm.handle_key(KeyCode::Enter), ConfirmEvent::Confirmed(Duration::from_secs(4 * 3600 + 60)));
+
+        let mut confirm = Confirm::new(Duration::from_secs(90), None);
+        assert_eq!(confirm.unit, Unit::Minutes);
+        for _ in 0..3 {
+            confirm.handle_key(KeyCode::Right);
+        }
+        assert_eq!(confirm.unit, Unit::Seconds);
+        confirm.handle_key(KeyCode::Up);
+        assert_eq!(confirm.handle_key(KeyCode::Enter), ConfirmEvent::Confirmed(Duration::from_secs(91)));
+        assert_eq!(confirm.handle_key(KeyCode::Esc), ConfirmEvent::Cancelled);
+    }
+
+    #[test]
+    fn test_confirm_clamps_at_zero() {
+        let mut confirm = Confirm::new(Duration::from_secs(90), None);
+        confirm.handle_key(KeyCode::Down);
+        confirm.handle_key(KeyCode::Down);
+        assert_eq!(confirm.duration, Duration::ZERO);
+        // A zero timer doesn't start
+        assert_eq!(confirm.handle_key(KeyCode::Enter), ConfirmEvent::Continue);
+        confirm.handle_key(KeyCode::Left);
+        confirm.handle_key(KeyCode::Up);
+        assert_eq!(confirm.handle_key(KeyCode::Enter), ConfirmEvent::Confirmed(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn test_invalid_configured_choice() {
+        let err = Picker::new(&[], &["soon".to_string()]).unwrap_err().to_string();
+        assert!(err.contains("'soon'"), "{}", err);
+    }
+
+    #[test]
+    fn test_suggestions_come_first_with_their_label() {
+        let suggestions = [Suggestion { duration: Duration::from_secs(1500), label: Some("focus".into()), runs: 4 }];
+        let mut picker = Picker::new(&suggestions, &["5m".to_string()]).unwrap();
+        assert_eq!(picker.handle_key(KeyCode::Enter), PickerEvent::Picked(Duration::from_secs(1500), Some("focus".into())));
+        picker.handle_key(KeyCode::Char('2'));
+        assert_eq!(picker.handle_key(KeyCode::Enter), PickerEvent::Picked(Duration::from_secs(300), None));
+    }
+}
@@ -0,0 +1,206 @@
+//! `--dry-run`: what a timer or alarm would do and when, worked out without
+//! touching the terminal, the speakers or any state file.
+
+use anyhow::Result;
+use chrono::{Local, NaiveDateTime, SecondsFormat, TimeZone};
+use serde_json::json;
+use std::time::Duration;
+
+use crate::{estimate_finish, format_duration, measure, milestones::Schedule, should_repeat, store::EntryKind, time_format::format_time};
+
+/// How many rings of an `--every` timer are listed after the first.
+pub const REPEATS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub kind: EntryKind,
+    pub label: Option<String>,
+    /// How long a timer runs; alarms have none
+    pub duration: Option<Duration>,
+    /// When a timer's countdown begins, or an alarm is armed
+    pub starts: NaiveDateTime,
+    pub rings: NaiveDateTime,
+    pub milestones: Vec<(String, NaiveDateTime)>,
+    /// The rings of an `--every` timer after the first
+    pub repeats: Vec<NaiveDateTime>,
+    /// Anything else that changes what happens, one line each
+    pub notes: Vec<String>,
+}
+
+impl Plan {
+    /// A `duration` long timer started at `now` that waits `lead` first
+    /// (`--start-at`, `--start-in`, `--count-in`).
+    pub fn timer(
+        now: NaiveDateTime,
+        label: Option<String>,
+        duration: Duration,
+        lead: Duration,
+        milestones: &Schedule,
+        every: bool,
+        repeat_until: Option<NaiveDateTime>,
+    ) -> Result<Plan> {
+        let starts = estimate_finish(now, lead)?;
+        let rings = estimate_finish(starts, duration)?;
+        let milestones = milestones
+            .all()
+            .iter()
+            .map(|milestone| Ok((milestone.name.clone(), estimate_finish(starts, milestone.at)?)))
+            .collect::<Result<_>>()?;
+        let mut repeats: Vec<NaiveDateTime> = Vec::new();
+        if every {
+            let mut last = rings;
+            // The same check the timer makes each time it finishes
+            while repeats.len() < REPEATS && should_repeat(last, repeat_until) {
+                last = estimate_finish(last, duration)?;
+                repeats.push(last);
+            }
+        }
+        Ok(Plan { kind: EntryKind::Timer, label, duration: Some(duration), starts, rings, milestones, repeats, notes: Vec::new() })
+    }
+
+    /// An alarm armed at `starts` that rings at `rings`.
+    pub fn alarm(starts: NaiveDateTime, rings: NaiveDateTime) -> Plan {
+        Plan {
+            kind: EntryKind::Alarm,
+            label: None,
+            duration: None,
+            starts,
+            rings,
+            milestones: Vec::new(),
+            repeats: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn note(mut self, note: impl Into<String>) -> Plan {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// A block to read, or one JSON object with local RFC 3339 times.
+    pub fn format(&self, format: measure::Format, twelve_hour: bool) -> String {
+        match format {
+            measure::Format::Text => self.format_text(twelve_hour),
+            measure::Format::Json => format!("{}\n", self.to_json()),
+        }
+    }
+
+    fn format_text(&self, twelve_hour: bool) -> String {
+        let at = |time: &NaiveDateTime| format!("{} {}", time.format("%a %b %-d"), format_time(time, twelve_hour, true));
+        let mut out = match self.kind {
+            EntryKind::Timer => "Timer".to_string(),
+            EntryKind::Alarm => "Alarm".to_string(),
+        };
+        if let Some(label) = &self.label {
+            out += &format!(" \"{}\"", label);
+        }
+        if let Some(duration) = self.duration {
+            out += &format!(", {}", format_duration(duration));
+        }
+        out.push('\n');
+        let starts = match self.kind {
+            EntryKind::Timer => "Starts",
+            EntryKind::Alarm => "Armed",
+        };
+        out += &format!("  {:<12}{}\n", starts, at(&self.starts));
+        for (name, time) in &self.milestones {
+            out += &format!("  {:<12}{}\n", name, at(time));
+        }
+        out += &format!("  {:<12}{}\n", "Rings", at(&self.rings));
+        for (i, time) in self.repeats.iter().enumerate() {
+            out += &format!("  {:<12}{}\n", if i == 0 { "Again" } else { "" }, at(time));
+        }
+        for note in &self.notes {
+            out += &format!("  {}\n", note);
+        }
+        out
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "kind": self.kind,
+            "label": self.label,
+            "duration_seconds": self.duration.map(|duration| duration.as_secs_f64()),
+            "starts_at": rfc3339(&self.starts),
+            "rings_at": rfc3339(&self.rings),
+            "milestones": self.milestones.iter().map(|(name, at)| json!({ "name": name, "at": rfc3339(at) })).collect::<Vec<_>>(),
+            "repeats": self.repeats.iter().map(rfc3339).collect::<Vec<_>>(),
+            "notes": self.notes,
+        })
+    }
+}
+
+/// With the local offset; a time skipped by a DST change has none and goes out without it.
+fn rfc3339(time: &NaiveDateTime) -> String {
+    match Local.from_local_datetime(time).earliest() {
+        Some(time) => time.to_rfc3339_opts(SecondsFormat::Secs, false),
+        None => time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(h: u32, m: u32, s: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 8).unwrap().and_hms_opt(h, m, s).unwrap()
+    }
+
+    fn mins(m: u64) -> Duration {
+        Duration::from_secs(m * 60)
+    }
+
+    #[test]
+    fn test_timer_plan() {
+        let milestones = Schedule::new(mins(25), Some(80), None, Some("1m"));
+        let plan = Plan::timer(at(9, 0, 0), Some("focus".into()), mins(25), Duration::from_secs(5), &milestones, false, None).unwrap();
+        assert_eq!(plan.starts, at(9, 0, 5));
+        assert_eq!(plan.rings, at(9, 25, 5));
+        assert_eq!(plan.milestones, [("80%".to_string(), at(9, 20, 5)), ("1m left".to_string(), at(9, 24, 5))]);
+        assert!(plan.repeats.is_empty());
+
+        let text = plan.format(measure::Format::Text, false);
+        assert_eq!(
+            text,
+            "Timer \"focus\", 25:00\n  Starts      Fri Mar 8 09:00:05\n  80%         Fri Mar 8 09:20:05\n  1m left     Fri Mar 8 09:24:05\n  Rings       Fri Mar 8 09:25:05\n"
+        );
+    }
+
+    #[test]
+    fn test_every_repeats() {
+        let none = Schedule::default();
+        let plan = Plan::timer(at(9, 0, 0), None, mins(30), Duration::ZERO, &none, true, None).unwrap();
+        assert_eq!(plan.repeats.len(), REPEATS);
+        assert_eq!(plan.repeats[0], at(10, 0, 0));
+        assert_eq!(plan.repeats[REPEATS - 1], at(12, 0, 0));
+
+        // Finishing at 10:00 is still before 10:15, finishing at 10:30 isn't
+        let plan = Plan::timer(at(9, 0, 0), None, mins(30), Duration::ZERO, &none, true, Some(at(10, 15, 0))).unwrap();
+        assert_eq!(plan.repeats, [at(10, 0, 0), at(10, 30, 0)]);
+        let text = plan.format(measure::Format::Text, true);
+        assert!(text.contains("  Again       Fri Mar 8 10:00:00 AM\n              Fri Mar 8 10:30:00 AM\n"), "{}", text);
+    }
+
+    #[test]
+    fn test_timer_past_the_calendar() {
+        let none = Schedule::default();
+        let huge = Duration::from_secs(99_999_999_999 * 3600);
+        assert!(Plan::timer(at(9, 0, 0), None, huge, Duration::ZERO, &none, false, None).is_err());
+        assert!(Plan::timer(at(9, 0, 0), None, mins(5), huge, &none, false, None).is_err());
+    }
+
+    #[test]
+    fn test_alarm_plan_json() {
+        let plan = Plan::alarm(at(22, 0, 0), at(22, 0, 0) + chrono::Duration::hours(9)).note("Rolled over to tomorrow");
+        let json: serde_json::Value = serde_json::from_str(&plan.format(measure::Format::Json, false)).unwrap();
+        assert_eq!(json["kind"], "alarm");
+        assert_eq!(json["duration_seconds"], serde_json::Value::Null);
+        assert!(json["rings_at"].as_str().unwrap().starts_with("2024-03-09T07:00:00"), "{}", json);
+        assert_eq!(json["notes"][0], "Rolled over to tomorrow");
+        assert_eq!(json["repeats"], json!([]));
+
+        let text = plan.format(measure::Format::Text, false);
+        assert!(text.starts_with("Alarm\n  Armed       Fri Mar 8 22:00:00\n  Rings       Sat Mar 9 07:00:00\n"), "{}", text);
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::{Context, Result};
+use crossterm::event::KeyCode;
+use ratatui::style::Color;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::time::sleep;
+
+use crate::{
+    config::PomodoroConfig,
+    format_duration,
+    notify::{self, Urgency},
+    parse_color, parse_duration,
+    sound::{play_chime, SoundOptions},
+    status, ui, unix_millis,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    fn title(self) -> &'static str {
+        match self {
+            Phase::Work => "Work",
+            Phase::ShortBreak => "Short Break",
+            Phase::LongBreak => "Long Break",
+        }
+    }
+}
+
+/// Work sessions with a break between each; every `long_break_every`-th break is a long one.
+pub fn schedule(rounds: u32, long_break_every: u32) -> Vec<Phase> {
+    let mut phases = vec![];
+    for round in 1..=rounds {
+        phases.push(Phase::Work);
+        if round < rounds {
+            if long_break_every > 0 && round % long_break_every == 0 {
+                phases.push(Phase::LongBreak);
+            } else {
+                phases.push(Phase::ShortBreak);
+            }
+        }
+    }
+    phases
+}
+
+/// The `pomodoro` config section with durations and colors parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PomodoroSettings {
+    pub work: Duration,
+    pub short_break: Duration,
+    pub long_break: Duration,
+    pub rounds: u32,
+    pub long_break_every: u32,
+    pub work_color: Color,
+    pub break_color: Color,
+    pub work_end_tone: f32,
+    pub break_end_tone: f32,
+}
+
+impl PomodoroSettings {
+    pub fn from_config(config: &PomodoroConfig) -> Result<Self> {
+        if config.rounds == 0 {
+            anyhow::bail!("pomodoro.rounds must be at least 1");
+        }
+        Ok(PomodoroSettings {
+            work: parse_duration(&config.work).context("Invalid pomodoro.work")?,
+            short_break: parse_duration(&config.short_break).context("Invalid pomodoro.short_break")?,
+            long_break: parse_duration(&config.long_break).context("Invalid pomodoro.long_break")?,
+            rounds: config.rounds,
+            long_break_every: config.long_break_every,
+            work_color: parse_color(&config.work_color).context("Invalid pomodoro.work_color")?,
+            break_color: parse_color(&config.break_color).context("Invalid pomodoro.break_color")?,
+            work_end_tone: config.work_end_tone,
+            break_end_tone: config.break_end_tone,
+        })
+    }
+
+    fn duration(&self, phase: Phase) -> Duration {
+        match phase {
+            Phase::Work => self.work,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break,
+        }
+    }
+
+    fn color(&self, phase: Phase) -> Color {
+        match phase {
+            Phase::Work => self.work_color,
+            Phase::ShortBreak | Phase::LongBreak => self.break_color,
+        }
+    }
+}
+
+pub struct PomodoroOptions {
+    pub spinner: bool,
+    pub sound: SoundOptions,
+}
+
+/// Announces the end of `finished`: "take a break" is a normal notification,
+/// "back to work" is critical so it isn't missed.
+fn announce(finished: Phase, next: Option<Phase>, settings: &PomodoroSettings, sound: &SoundOptions) {
+    match finished {
+        Phase::Work => play_chime(settings.work_end_tone, sound),
+        Phase::ShortBreak | Phase::LongBreak => play_chime(settings.break_end_tone, sound),
+    }
+
+    match next {
+        Some(Phase::Work) => notify::send_notification_with_urgency("Back to work!", "Break is over.", Urgency::Critical),
+        Some(next) => notify::send_notification("Take a break", &format!("{} for {}", next.title(), format_duration(settings.duration(next)))),
+        None => notify::send_notification("Pomodoro complete!", &format!("{} rounds done.", settings.rounds)),
+    }
+}
+
+/// Work rounds a phase adds to the finished ones: a work phase that ran out,
+/// not one skipped with 'n'.
+fn rounds_finished(phase: Phase, ran_out: bool) -> u32 {
+    u32::from(phase == Phase::Work && ran_out)
+}
+
+/// Runs every phase in order. Returns the number of finished work rounds.
+pub async fn run_pomodoro_ui(settings: PomodoroSettings, options: PomodoroOptions) -> Result<u32> {
+    let mut terminal = ui::enter_screen()?;
+
+    let phases = schedule(settings.rounds, settings.long_break_every);
+    let mut completed = 0;
+    let mut frame = 0usize;
+    let mut status_file: Option<status::StatusFile> = None;
+
+    'phases: for (index, &phase) in phases.iter().enumerate() {
+        let next = phases.get(index + 1).copied();
+        let round = phases[..=index].iter().filter(|p| **p == Phase::Work).count();
+        let duration = settings.duration(phase);
+        let color = settings.color(phase);
+        let up_next = match next {
+            Some(next) => format!("Next: {} ({})", next.title(), format_duration(settings.duration(next))),
+            None => "Last round".to_string(),
+        };
+        let start = Instant::now();
+
+        let now_ms = unix_millis(SystemTime::now());
+        let mut clock_status = status::ClockStatus::countdown(status::ClockKind::Pomodoro, Some(phase.title().to_string()), now_ms, duration);
+        match &mut status_file {
+            Some(file) => {
+                file.update(&clock_status).ok();
+            }
+            None => status_file = status::publish(&clock_status).ok(),
+        }
+
+        let mut ran_out = false;
+        loop {
+            let elapsed = start.elapsed();
+            if elapsed >= duration {
+                ran_out = true;
+                break;
+            }
+            clock_status.touch(unix_millis(SystemTime::now()), elapsed);
+            if let Some(file) = &mut status_file {
+                file.refresh(&clock_status);
+            }
+            let remaining = duration - elapsed;
+            frame = frame.wrapping_add(1);
+
+            let view = ui::PomodoroView {
+                phase: phase.title(),
+                round,
+                rounds: settings.rounds,
+                remaining,
+                up_next: &up_next,
+                color,
+                spinner: options.spinner.then_some(frame),
+            };
+            terminal.draw(|f| ui::draw_pomodoro(f, &view))?;
+
+            if let Some(key) = ui::poll_key(Duration::from_millis(100))? {
+                if ui::is_quit_event(&key) {
+                    break 'phases;
+                }
+                if key.code == KeyCode::Char('n') {
+                    break;
+                }
+            }
+
+            sleep(Duration::from_millis(100)).await;
+        }
+
+        completed += rounds_finished(phase, ran_out);
+        // A skipped phase goes straight on to the next, without the end-of-phase chime
+        if ran_out {
+            announce(phase, next, &settings, &options.sound);
+        }
+    }
+
+    ui::leave_screen(terminal)?;
+
+    Ok(completed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule() {
+        use Phase::*;
+        assert_eq!(schedule(1, 4), [Work]);
+        assert_eq!(schedule(3, 4), [Work, ShortBreak, Work, ShortBreak, Work]);
+        assert_eq!(schedule(5, 2), [Work, ShortBreak, Work, LongBreak, Work, ShortBreak, Work, LongBreak, Work]);
+        assert_eq!(schedule(3, 0), [Work, ShortBreak, Work, ShortBreak, Work]);
+    }
+
+    #[test]
+    fn test_skipped_work_isnt_finished() {
+        assert_eq!(rounds_finished(Phase::Work, true), 1);
+        assert_eq!(rounds_finished(Phase::Work, false), 0);
+        assert_eq!(rounds_finished(Phase::ShortBreak, true), 0);
+        assert_eq!(rounds_finished(Phase::LongBreak, false), 0);
+    }
+
+    #[test]
+    fn test_settings_from_config() {
+        let settings = PomodoroSettings::from_config(&PomodoroConfig::default()).unwrap();
+        assert_eq!(settings.work, Duration::from_secs(25 * 60));
+        assert_eq!(settings.color(Phase::Work), Color::Red);
+        assert_eq!(settings.color(Phase::LongBreak), Color::Green);
+
+        let config = PomodoroConfig { break_color: "#00ff88".into(), ..Default::default() };
+        let settings = PomodoroSettings::from_config(&config).unwrap();
+        assert_eq!(settings.break_color, Color::Rgb(0x00, 0xff, 0x88));
+
+        let config = PomodoroConfig { work_color: "sparkly".into(), ..Default::default() };
+        let err = PomodoroSettings::from_config(&config).unwrap_err();
+        assert!(format!("{:#}", err).contains("pomodoro.work_color"));
+    }
+}
@@ -0,0 +1,285 @@
+use anyhow::Result;
+use chrono::Local;
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    batch::{Ending, Measured, Step},
+    clock, format_duration, notify, runtime_dir,
+    sound::{spawn_chime, SoundOptions},
+    statefile, status, ui, unix_millis,
+};
+
+/// Chime at the end of each step, like a batch.
+const STEP_TONE: f32 = 784.0;
+
+/// Queue redraw interval, and how often an empty queue is looked at again.
+const QUEUE_FRAME: Duration = Duration::from_millis(200);
+
+pub fn queue_file() -> PathBuf {
+    runtime_dir().join("clockeroo.queue")
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemState {
+    #[default]
+    Pending,
+    Done,
+    /// Cut short with 'n'
+    Skipped,
+}
+
+/// One timer waiting in (or already run from) the queue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Item {
+    pub id: u64,
+    pub label: String,
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub state: ItemState,
+}
+
+impl Item {
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+}
+
+/// The queue file: timers added by `queue add`, run in order by `queue run`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Queue {
+    #[serde(default)]
+    next_id: u64,
+    #[serde(default)]
+    pub items: Vec<Item>,
+}
+
+impl Queue {
+    /// The queue in `path`, empty if nobody has added to it yet.
+    pub fn load(path: &Path) -> Result<Queue> {
+        match statefile::read_json(path) {
+            Err(err) if statefile::is_not_found(&err) => Ok(Queue::default()),
+            result => result,
+        }
+    }
+
+    /// Adds a timer at the back. Without a label it's named after its place
+    /// among the timers still to run.
+    pub fn push(&mut self, duration: Duration, label: Option<String>) -> &Item {
+        self.next_id = self.next_id.max(self.items.iter().map(|item| item.id).max().unwrap_or(0)) + 1;
+        let label = label.unwrap_or_else(|| format!("Step {}", self.pending().count() + 1));
+        self.items.push(Item { id: self.next_id, label, duration_ms: duration.as_millis() as u64, state: ItemState::Pending });
+        self.items.last().unwrap()
+    }
+
+    /// Timers not run yet, in the order they'll run.
+    pub fn pending(&self) -> impl Iterator<Item = &Item> {
+        self.items.iter().filter(|item| item.state == ItemState::Pending)
+    }
+
+    fn set_state(&mut self, id: u64, state: ItemState) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+            item.state = state;
+        }
+    }
+
+    /// Forgets timers an earlier run got through.
+    fn prune(&mut self) {
+        self.items.retain(|item| item.state == ItemState::Pending);
+    }
+}
+
+/// Changes the queue in `path` under its lock, so an `add` from another
+/// terminal can't land between the read and the write and be lost.
+fn update<T>(path: &Path, change: impl FnOnce(&mut Queue) -> T) -> Result<T> {
+    let _lock = statefile::lock(path)?;
+    let mut queue = Queue::load(path)?;
+    let result = change(&mut queue);
+    statefile::write_json(path, &queue)?;
+    Ok(result)
+}
+
+/// `queue add`: appends a timer for a runner (now or later) to pick up.
+pub fn append(path: &Path, duration: Duration, label: Option<String>) -> Result<Item> {
+    update(path, |queue| queue.push(duration, label).clone())
+}
+
+/// `queue list`: what's still to run.
+pub fn print_pending(path: &Path) -> Result<()> {
+    let queue = Queue::load(path)?;
+    let pending: Vec<&Item> = queue.pending().collect();
+    if pending.is_empty() {
+        println!("The queue is empty. Add to it with: clockeroo queue add 5m \"step\"");
+    }
+    for item in pending {
+        println!("  #{:<3} {}  {}", item.id, format_duration(item.duration()), item.label);
+    }
+    Ok(())
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+pub struct QueueOptions {
+    pub spinner: bool,
+    pub sound: SoundOptions,
+}
+
+/// Runs queued timers one after another, picking up ones added while it
+/// runs, and waits for more when it's through them. Returns each timer that
+/// started, as it actually ran. Quitting leaves the current timer queued.
+pub async fn run_queue_ui(options: QueueOptions) -> Result<Vec<Measured>> {
+    let path = queue_file();
+    update(&path, Queue::prune)?;
+    let mut terminal = ui::enter_screen()?;
+
+    let mut measured = vec![];
+    let mut frame = 0usize;
+    let mut status_file: Option<status::StatusFile> = None;
+
+    'items: loop {
+        let mut queue = Queue::load(&path)?;
+        let mut seen = modified(&path);
+        let Some(item) = queue.pending().next().cloned() else {
+            // Nothing to run: drop the status so nothing claims a timer is going
+            status_file = None;
+            frame = frame.wrapping_add(1);
+            terminal.draw(|f| ui::draw_queue_waiting(f, measured.len(), options.spinner.then_some(frame)))?;
+            if let Some(key) = ui::poll_key(QUEUE_FRAME)? {
+                if ui::is_quit_event(&key) {
+                    break 'items;
+                }
+            }
+            continue;
+        };
+
+        let planned = item.duration();
+        let start = Instant::now();
+        let started = Local::now();
+        let now_ms = unix_millis(SystemTime::now());
+        let mut clock_status = status::ClockStatus::countdown(status::ClockKind::Timer, Some(item.label.clone()), now_ms, Duration::from_millis(item.duration_ms));
+        match &mut status_file {
+            Some(file) => {
+                file.update(&clock_status).ok();
+            }
+            None => status_file = status::publish(&clock_status).ok(),
+        }
+
+        let mut ending = Ending::Finished;
+        loop {
+            let elapsed = start.elapsed();
+            if elapsed >= planned {
+                break;
+            }
+            clock_status.touch(unix_millis(SystemTime::now()), elapsed);
+            if let Some(file) = &mut status_file {
+                file.refresh(&clock_status);
+            }
+            // Only read again when something was added, for the "Next" line and the count
+            let now_modified = modified(&path);
+            if now_modified != seen {
+                seen = now_modified;
+                if let Ok(reloaded) = Queue::load(&path) {
+                    queue = reloaded;
+                }
+            }
+            frame = frame.wrapping_add(1);
+
+            let after: Vec<&Item> = queue.pending().filter(|pending| pending.id != item.id).collect();
+            terminal.draw(|f| {
+                let view = ui::BatchView {
+                    index: measured.len(),
+                    total: measured.len() + 1 + after.len(),
+                    label: &item.label,
+                    remaining: clock::ceil_secs(planned - elapsed),
+                    next: after.first().map(|next| next.label.as_str()),
+                    spinner: options.spinner.then_some(frame),
+                };
+                ui::draw_batch(f, &view);
+            })?;
+
+            // Wake right at the end of the timer rather than up to a frame late
+            if let Some(key) = ui::poll_key(QUEUE_FRAME.min(planned - elapsed))? {
+                if ui::is_quit_event(&key) {
+                    ending = Ending::Stopped;
+                    break;
+                }
+                if key.code == KeyCode::Char('n') {
+                    ending = Ending::Skipped;
+                    break;
+                }
+            }
+        }
+
+        measured.push(Measured {
+            step: Step { label: item.label.clone(), planned },
+            started,
+            finished: Local::now(),
+            ending,
+        });
+        let state = match ending {
+            Ending::Finished => ItemState::Done,
+            Ending::Skipped => ItemState::Skipped,
+            Ending::Stopped => break 'items,
+        };
+        update(&path, |queue| queue.set_state(item.id, state))?;
+        spawn_chime(STEP_TONE, &options.sound);
+        if state == ItemState::Done && Queue::load(&path).is_ok_and(|queue| queue.pending().next().is_none()) {
+            notify::send_notification("Queue done!", &format!("{} finished, nothing left in the queue.", item.label));
+        }
+    }
+
+    ui::leave_screen(terminal)?;
+
+    Ok(measured)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_and_pending() {
+        let mut queue = Queue::default();
+        assert_eq!(queue.push(Duration::from_secs(300), Some("boil".into())).id, 1);
+        assert_eq!(queue.push(Duration::from_secs(60), None).label, "Step 2");
+        queue.set_state(1, ItemState::Done);
+        // Numbered among what's left, ids keep counting up
+        let item = queue.push(Duration::from_secs(90), None).clone();
+        assert_eq!((item.id, item.label.as_str()), (3, "Step 2"));
+
+        let pending: Vec<u64> = queue.pending().map(|item| item.id).collect();
+        assert_eq!(pending, [2, 3]);
+        queue.prune();
+        assert_eq!(queue.items.len(), 2);
+        assert_eq!(queue.push(Duration::from_secs(1), None).id, 4);
+    }
+
+    #[test]
+    fn test_queue_file() {
+        let dir = std::env::temp_dir().join(format!("clockeroo-queue-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("clockeroo.queue");
+
+        assert_eq!(Queue::load(&path).unwrap(), Queue::default());
+        append(&path, Duration::from_secs(300), Some("steep".into())).unwrap();
+        append(&path, Duration::from_millis(1_500), None).unwrap();
+        let queue = Queue::load(&path).unwrap();
+        assert_eq!(queue.items[0], Item { id: 1, label: "steep".into(), duration_ms: 300_000, state: ItemState::Pending });
+        assert_eq!(queue.items[1].duration(), Duration::from_millis(1_500));
+
+        // Hand-written entries only need the basics
+        fs::write(&path, r#"{"items": [{"id": 7, "label": "pour", "duration_ms": 5000}]}"#).unwrap();
+        let item = append(&path, Duration::from_secs(1), None).unwrap();
+        assert_eq!((item.id, item.label.as_str()), (8, "Step 2"));
+        assert_eq!(Queue::load(&path).unwrap().items[0].state, ItemState::Pending);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::parse_alarm_time;
+
+/// A daily window (e.g. 22:00-07:00) in which the beep and desktop notification
+/// are suppressed. The window may cross midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Parses `START-END`, each side in any format the alarm parser accepts.
+    pub fn parse(s: &str) -> Result<QuietHours> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| anyhow::anyhow!("Invalid quiet hours '{}'. Use START-END, e.g. 22:00-07:00", s))?;
+        let start = parse_alarm_time(start.trim()).with_context(|| format!("Invalid quiet hours start in '{}'", s))?;
+        let end = parse_alarm_time(end.trim()).with_context(|| format!("Invalid quiet hours end in '{}'", s))?;
+        if start == end {
+            anyhow::bail!("Quiet hours '{}' start and end at the same time", s);
+        }
+        Ok(QuietHours { start, end })
+    }
+
+    /// Whether `time` falls in the window (start inclusive, end exclusive).
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            // Crosses midnight
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// True if the current local time is inside `quiet_hours`.
+pub fn is_quiet_now(quiet_hours: Option<&QuietHours>) -> bool {
+    quiet_hours.is_some_and(|window| window.contains(Local::now().time()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_daytime_window() {
+        let window = QuietHours::parse("13:00-14:30").unwrap();
+        assert!(window.contains(t(13, 0)));
+        assert!(window.contains(t(14, 29)));
+        assert!(!window.contains(t(14, 30)));
+        assert!(!window.contains(t(12, 59)));
+    }
+
+    #[test]
+    fn test_window_crossing_midnight() {
+        let window = QuietHours::parse("10:30pm-7:00am").unwrap();
+        assert_eq!(window, QuietHours { start: t(22, 30), end: t(7, 0) });
+        assert!(window.contains(t(23, 15)));
+        assert!(window.contains(t(0, 0)));
+        assert!(window.contains(t(6, 59)));
+        assert!(!window.contains(t(7, 0)));
+        assert!(!window.contains(t(22, 29)));
+        assert!(!window.contains(t(12, 0)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(QuietHours::parse("22:00").is_err());
+        assert!(QuietHours::parse("22:00-25:00").is_err());
+        assert!(QuietHours::parse("7am-7am").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use anyhow::Result;
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{parse_alarm_time, statefile, store::data_dir};
+
+pub fn saved_alarms_path() -> PathBuf {
+    data_dir().join("alarms.json")
+}
+
+/// Alarms kept by `alarm save` for `alarm start`, by name. Times are kept as
+/// typed, so a saved "7am" starts just like typing it again would.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedAlarms {
+    #[serde(default)]
+    pub alarms: BTreeMap<String, String>,
+}
+
+impl SavedAlarms {
+    /// The saved alarms in `path`, none if nothing was saved yet.
+    pub fn load(path: &Path) -> Result<SavedAlarms> {
+        match statefile::read_json(path) {
+            Err(err) if statefile::is_not_found(&err) => Ok(SavedAlarms::default()),
+            result => result,
+        }
+    }
+
+    /// The time saved under `name`.
+    pub fn resolve(&self, name: &str) -> Result<NaiveTime> {
+        let Some(time) = self.alarms.get(name) else {
+            if self.alarms.is_empty() {
+                anyhow::bail!("No saved alarm named '{}'. Save one with: clockeroo alarm save {} 7:00am", name, name);
+            }
+            let names: Vec<&str> = self.alarms.keys().map(String::as_str).collect();
+            anyhow::bail!("No saved alarm named '{}' (saved: {})", name, names.join(", "));
+        };
+        parse_alarm_time(time)
+    }
+}
+
+/// `alarm save`: keeps `time` under `name`, replacing what was saved under it.
+/// Returns the time as it will start.
+pub fn save(path: &Path, name: &str, time: &str) -> Result<NaiveTime> {
+    let parsed = parse_alarm_time(time)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let _lock = statefile::lock(path)?;
+    let mut saved = SavedAlarms::load(path)?;
+    saved.alarms.insert(name.to_string(), time.trim().to_string());
+    statefile::write_json(path, &saved)?;
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("clockeroo-saved-alarms-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("alarms.json")
+    }
+
+    #[test]
+    fn test_save_list_and_resolve() {
+        let path = test_path("roundtrip");
+        assert_eq!(SavedAlarms::load(&path).unwrap(), SavedAlarms::default());
+
+        assert_eq!(save(&path, "wake", "7:00am").unwrap(), NaiveTime::from_hms_opt(7, 0, 0).unwrap());
+        save(&path, "standup", "half past 9").unwrap();
+        // Saving again under a name replaces it
+        save(&path, "wake", "6:45am").unwrap();
+
+        let saved = SavedAlarms::load(&path).unwrap();
+        let names: Vec<&str> = saved.alarms.keys().map(String::as_str).collect();
+        assert_eq!(names, ["standup", "wake"]);
+        assert_eq!(saved.alarms["wake"], "6:45am");
+        assert_eq!(saved.resolve("wake").unwrap(), NaiveTime::from_hms_opt(6, 45, 0).unwrap());
+        assert_eq!(saved.resolve("standup").unwrap(), NaiveTime::from_hms_opt(9, 30, 0).unwrap());
+
+        let err = saved.resolve("lunch").unwrap_err().to_string();
+        assert_eq!(err, "No saved alarm named 'lunch' (saved: standup, wake)");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_time_is_not_saved() {
+        let path = test_path("invalid");
+        assert!(save(&path, "wake", "25:00").is_err());
+        assert!(!path.exists());
+        assert!(SavedAlarms::default().resolve("wake").unwrap_err().to_string().contains("alarm save wake"));
+    }
+}
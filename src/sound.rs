@@ -0,0 +1,601 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use rodio::{
+    cpal::{self, traits::{DeviceTrait, HostTrait}},
+    source::Source,
+    buffer::SamplesBuffer,
+    Decoder, OutputStream, OutputStreamHandle, Sink,
+};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufReader, IsTerminal, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, SyncSender},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{config::SoundTheme, dnd::DndPolicy};
+
+/// Things that can make a noise, keyed the same way as sound themes in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundEvent {
+    TimerDone,
+    Alarm,
+    Lap,
+    Tick,
+}
+
+impl SoundEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            SoundEvent::TimerDone => "timer_done",
+            SoundEvent::Alarm => "alarm",
+            SoundEvent::Lap => "lap",
+            SoundEvent::Tick => "tick",
+        }
+    }
+}
+
+/// Which channels a bell, beep or chime goes out on (`--bell`, `bell` in the config).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bell {
+    /// The terminal's BEL, however the terminal is set up to sound it
+    Terminal,
+    /// A beep (or themed sound) on the audio device
+    Audio,
+    #[default]
+    Both,
+    None,
+}
+
+impl Bell {
+    fn terminal(self) -> bool {
+        matches!(self, Bell::Terminal | Bell::Both)
+    }
+
+    fn audio(self) -> bool {
+        matches!(self, Bell::Audio | Bell::Both)
+    }
+}
+
+/// What went out on each channel this run, for `--verbose`.
+static TERMINAL_RANG: AtomicUsize = AtomicUsize::new(0);
+static TERMINAL_FAILED: AtomicUsize = AtomicUsize::new(0);
+static AUDIO_PLAYED: AtomicUsize = AtomicUsize::new(0);
+static AUDIO_FAILED: AtomicUsize = AtomicUsize::new(0);
+static LAST_AUDIO_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default)]
+pub struct SoundOptions {
+    pub bell: Bell,
+    pub theme: Option<SoundTheme>,
+    pub dnd: DndPolicy,
+    /// Critical sounds punch through Do Not Disturb like alarms do
+    pub critical: bool,
+    /// Output device name (or part of it) from `--sound-device`, default device if unset
+    pub device: Option<String>,
+    pub envelope: Envelope,
+    pub waveform: Waveform,
+}
+
+/// The shape of the built-in beep's wave (`--waveform`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Waveform {
+    /// Soft and round
+    #[default]
+    Sine,
+    /// Hollow and buzzy, the hardest to miss
+    Square,
+    /// Between sine and square
+    Triangle,
+    /// Bright and raspy
+    Sawtooth,
+}
+
+impl Waveform {
+    /// The wave `phase` (0 to 1) of the way through a cycle, from -1 to 1.
+    fn sample(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Sine => (std::f32::consts::TAU * phase).sin(),
+            Waveform::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Sawtooth => 2.0 * phase - 1.0,
+        }
+    }
+}
+
+/// How long a beep lasts, fades included.
+pub const BEEP_LENGTH: Duration = Duration::from_millis(300);
+
+/// How a beep swells in and dies away (`--beep-fade-in`, `--beep-fade-out`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Envelope {
+    pub fade_in: Duration,
+    pub fade_out: Duration,
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Envelope { fade_in: Duration::from_millis(50), fade_out: Duration::from_millis(50) }
+    }
+}
+
+impl Envelope {
+    /// Fades that fit in a beep: the fade-in takes at most the whole beep, and
+    /// the fade-out is shortened to what's left after it.
+    pub fn fitted(fade_in: Duration, fade_out: Duration) -> Envelope {
+        let fade_in = fade_in.min(BEEP_LENGTH);
+        Envelope { fade_in, fade_out: fade_out.min(BEEP_LENGTH - fade_in) }
+    }
+
+    /// Gain `at` into the beep, from 0 to 1.
+    fn gain(&self, at: Duration) -> f32 {
+        let rising = match self.fade_in.is_zero() {
+            true => 1.0,
+            false => at.as_secs_f32() / self.fade_in.as_secs_f32(),
+        };
+        let left = BEEP_LENGTH.saturating_sub(at);
+        let falling = match self.fade_out.is_zero() {
+            true => 1.0,
+            false => left.as_secs_f32() / self.fade_out.as_secs_f32(),
+        };
+        rising.min(falling).clamp(0.0, 1.0)
+    }
+}
+
+impl SoundOptions {
+    fn muted(&self, event: SoundEvent) -> bool {
+        self.dnd.mutes(self.critical || event == SoundEvent::Alarm)
+    }
+
+    fn themed_path(&self, event: SoundEvent) -> Option<&Path> {
+        self.theme.as_ref().and_then(|theme| theme.path_for(event))
+    }
+}
+
+pub fn play_bell(event: SoundEvent, options: &SoundOptions) {
+    if options.muted(event) {
+        return;
+    }
+
+    if options.bell.terminal() {
+        ring_terminal();
+    }
+    if !options.bell.audio() {
+        return;
+    }
+
+    // Prefer the theme's sound for this event, fall back to the generated beep
+    if let Some(path) = options.themed_path(event) {
+        if record_audio(play_file(path, options.device.as_deref())) {
+            return;
+        }
+    }
+
+    // 440 Hz is the musical note A4, much more pleasant than 1000 Hz
+    record_audio(play_tone(440.0, options.waveform, options.envelope, options.device.as_deref()));
+}
+
+/// Writes BEL to the controlling terminal itself rather than stdout, which a
+/// screen has in the alternate screen (where some terminals swallow it) and
+/// which may not be a terminal at all. Falls back to stdout if there's no tty.
+fn ring_terminal() {
+    #[cfg(unix)]
+    let rang = OpenOptions::new()
+        .write(true)
+        .open("/dev/tty")
+        .and_then(|mut tty| tty.write_all(b"\x07"))
+        .is_ok();
+    #[cfg(not(unix))]
+    let rang = false;
+
+    let rang = rang || {
+        let mut stdout = io::stdout();
+        stdout.is_terminal() && stdout.write_all(b"\x07").and_then(|()| stdout.flush()).is_ok()
+    };
+    let counter = if rang { &TERMINAL_RANG } else { &TERMINAL_FAILED };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts an attempt to play audio, and reports whether it played.
+fn record_audio(result: Result<()>) -> bool {
+    match result {
+        Ok(()) => {
+            AUDIO_PLAYED.fetch_add(1, Ordering::Relaxed);
+            true
+        }
+        Err(err) => {
+            AUDIO_FAILED.fetch_add(1, Ordering::Relaxed);
+            *LAST_AUDIO_ERROR.lock().unwrap() = Some(format!("{:#}", err));
+            false
+        }
+    }
+}
+
+/// Which channels actually made a sound this run, or `None` if nothing rang.
+pub fn channel_report() -> Option<String> {
+    report_for(
+        [TERMINAL_RANG.load(Ordering::Relaxed), TERMINAL_FAILED.load(Ordering::Relaxed)],
+        [AUDIO_PLAYED.load(Ordering::Relaxed), AUDIO_FAILED.load(Ordering::Relaxed)],
+        LAST_AUDIO_ERROR.lock().unwrap().as_deref(),
+    )
+}
+
+fn report_for(terminal: [usize; 2], audio: [usize; 2], last_error: Option<&str>) -> Option<String> {
+    if terminal == [0, 0] && audio == [0, 0] {
+        return None;
+    }
+    let mut report = format!(
+        "bell: terminal rang {} of {} times, audio played {} of {} times",
+        terminal[0],
+        terminal[0] + terminal[1],
+        audio[0],
+        audio[0] + audio[1]
+    );
+    if let Some(err) = last_error.filter(|_| audio[1] > 0) {
+        report.push_str(&format!(" (last audio error: {})", err));
+    }
+    Some(report)
+}
+
+/// Plays the themed sound for a minor event (like a lap) without blocking the UI.
+/// Like `play_bell`, a theme with no sound for it, or one that won't play,
+/// gets the built-in beep; without a theme minor events stay silent.
+pub fn play_event_sound(event: SoundEvent, options: &SoundOptions) {
+    if !options.bell.audio() || options.muted(event) || options.theme.is_none() {
+        return;
+    }
+
+    let path = options.themed_path(event).map(Path::to_path_buf);
+    let options = options.clone();
+    std::thread::spawn(move || {
+        if let Some(path) = path {
+            if record_audio(play_file(&path, options.device.as_deref())) {
+                return;
+            }
+        }
+        record_audio(play_tone(440.0, options.waveform, options.envelope, options.device.as_deref()));
+    });
+}
+
+/// Names of the audio output devices, for `clockeroo devices`. The default one comes first.
+pub fn output_device_names() -> Result<Vec<String>> {
+    let host = cpal::default_host();
+    let default = host.default_output_device().and_then(|device| device.name().ok());
+    let mut names: Vec<String> = host.output_devices()?.filter_map(|device| device.name().ok()).collect();
+    if let Some(default) = &default {
+        if let Some(index) = names.iter().position(|name| name == default) {
+            let default = names.remove(index);
+            names.insert(0, default);
+        }
+    }
+    Ok(names)
+}
+
+pub fn default_output_device_name() -> Option<String> {
+    cpal::default_host().default_output_device().and_then(|device| device.name().ok())
+}
+
+/// The device `wanted` refers to: an exact name, else the first containing it, ignoring case.
+pub fn match_device<'a>(names: &'a [String], wanted: &str) -> Option<&'a str> {
+    let wanted = wanted.to_lowercase();
+    names
+        .iter()
+        .find(|name| name.to_lowercase() == wanted)
+        .or_else(|| names.iter().find(|name| name.to_lowercase().contains(&wanted)))
+        .map(String::as_str)
+}
+
+/// Opens the named output device, or the default one if it's unset or gone.
+fn open_stream(device: Option<&str>) -> Result<(OutputStream, OutputStreamHandle)> {
+    if let Some(wanted) = device {
+        let found = cpal::default_host()
+            .output_devices()?
+            .find(|device| device.name().is_ok_and(|name| name == wanted));
+        if let Some(device) = found {
+            return Ok(OutputStream::try_from_device(&device)?);
+        }
+    }
+    Ok(OutputStream::try_default()?)
+}
+
+fn play_file(path: &Path, device: Option<&str>) -> Result<()> {
+    let (_stream, stream_handle) = open_stream(device)?;
+    let sink = Sink::try_new(&stream_handle)?;
+    let source = Decoder::new(BufReader::new(File::open(path)?))?;
+    sink.append(source);
+
+    // Keep the stream alive until the file finishes
+    sink.sleep_until_end();
+    Ok(())
+}
+
+/// Rings the bell and plays a beep at `frequency`, so different transitions sound different.
+pub fn play_chime(frequency: f32, options: &SoundOptions) {
+    if options.dnd.mutes(options.critical) {
+        return;
+    }
+
+    if options.bell.terminal() {
+        ring_terminal();
+    }
+    if options.bell.audio() {
+        record_audio(play_tone(frequency, options.waveform, options.envelope, options.device.as_deref()));
+    }
+}
+
+/// Like `play_chime`, but on a background thread so a ticking UI doesn't stall.
+pub fn spawn_chime(frequency: f32, options: &SoundOptions) {
+    let options = options.clone();
+    std::thread::spawn(move || play_chime(frequency, &options));
+}
+
+/// A gentle beep (a sine wave unless `--waveform` says otherwise), shaped by
+/// `envelope` so it neither clicks on nor stops dead. Rendered up front: rodio
+/// only fades sources in, not out.
+fn render_beep(frequency: f32, waveform: Waveform, envelope: Envelope) -> Vec<f32> {
+    let length = (BEEP_LENGTH.as_secs_f32() * RENDER_RATE as f32) as usize;
+    (0..length)
+        .map(|i| {
+            let t = i as f32 / RENDER_RATE as f32;
+            // Reduced volume from 0.5 to 0.2
+            waveform.sample((frequency * t).fract()) * 0.2 * envelope.gain(Duration::from_secs_f32(t))
+        })
+        .collect()
+}
+
+fn play_tone(frequency: f32, waveform: Waveform, envelope: Envelope, device: Option<&str>) -> Result<()> {
+    // Try to play a built-in beep
+    let (_stream, stream_handle) = open_stream(device)?;
+    stream_handle.play_raw(SamplesBuffer::new(1, RENDER_RATE, render_beep(frequency, waveform, envelope)))?;
+
+    // Keep the stream alive until the fade-out has played
+    std::thread::sleep(BEEP_LENGTH + Duration::from_millis(50));
+    Ok(())
+}
+
+/// Share of full volume a `--fade-in` alarm starts at.
+const FADE_FLOOR: f32 = 0.05;
+
+/// Silence between beeps when a ringing alarm has no themed sound.
+const RING_GAP: Duration = Duration::from_millis(700);
+
+/// How often a ringing alarm's volume is brought up to date.
+const RING_STEP: Duration = Duration::from_millis(50);
+
+/// Volume `elapsed` into a fade-in lasting `over`: quiet at first, full once it's over.
+pub fn fade_volume(elapsed: Duration, over: Duration) -> f32 {
+    if over.is_zero() {
+        return 1.0;
+    }
+    let progress = (elapsed.as_secs_f32() / over.as_secs_f32()).min(1.0);
+    FADE_FLOOR + (1.0 - FADE_FLOOR) * progress
+}
+
+/// Rings an alarm's sound (the themed file, else beeps) over and over on its
+/// own thread, fading in from quiet over `fade_in`, until dropped.
+pub struct Ringer {
+    stop: Arc<AtomicBool>,
+}
+
+impl Ringer {
+    pub fn start(event: SoundEvent, options: &SoundOptions, fade_in: Duration) -> Ringer {
+        let stop = Arc::new(AtomicBool::new(false));
+        if options.muted(event) {
+            return Ringer { stop };
+        }
+        if options.bell.terminal() {
+            ring_terminal();
+        }
+        if !options.bell.audio() {
+            return Ringer { stop };
+        }
+
+        let path = options.themed_path(event).map(Path::to_path_buf);
+        let device = options.device.clone();
+        let beep = render_beep(440.0, options.waveform, options.envelope);
+        let stopped = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            let opened = open_stream(device.as_deref()).and_then(|(stream, handle)| Ok((stream, Sink::try_new(&handle)?)));
+            let (_stream, sink) = match opened {
+                Ok(opened) => opened,
+                Err(err) => {
+                    record_audio(Err(err));
+                    return;
+                }
+            };
+            record_audio(Ok(()));
+
+            let started = Instant::now();
+            while !stopped.load(Ordering::Relaxed) {
+                sink.set_volume(fade_volume(started.elapsed(), fade_in));
+                if sink.empty() {
+                    let file = path.as_deref().and_then(|path| Decoder::new(BufReader::new(File::open(path).ok()?)).ok());
+                    match file {
+                        Some(file) => sink.append(file),
+                        None => {
+                            sink.append(SamplesBuffer::new(1, RENDER_RATE, beep.clone()));
+                            sink.append(rodio::source::Zero::<f32>::new(1, RENDER_RATE).take_duration(RING_GAP));
+                        }
+                    }
+                }
+                std::thread::sleep(RING_STEP);
+            }
+        });
+        Ringer { stop }
+    }
+}
+
+impl Drop for Ringer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Sample rate of the pre-rendered beep and tick.
+const RENDER_RATE: u32 = 48_000;
+
+/// A short, quiet click: a 2kHz blip that dies away within a few milliseconds.
+fn render_tick() -> Vec<f32> {
+    let length = RENDER_RATE as usize * 12 / 1000;
+    (0..length)
+        .map(|i| {
+            let t = i as f32 / RENDER_RATE as f32;
+            (std::f32::consts::TAU * 2000.0 * t).sin() * (-t / 0.003).exp() * 0.15
+        })
+        .collect()
+}
+
+/// A themed sound decoded up front, so each tick only appends samples:
+/// channels, sample rate and the samples.
+fn decode_samples(path: &Path) -> Option<(u16, u32, Vec<f32>)> {
+    let decoder = Decoder::new(BufReader::new(File::open(path).ok()?)).ok()?;
+    let (channels, rate) = (decoder.channels(), decoder.sample_rate());
+    Some((channels, rate, decoder.convert_samples().collect()))
+}
+
+/// Plays `--tick` clicks on a thread that keeps the output stream open, so a
+/// tick costs no more than appending a few milliseconds of samples. The
+/// theme's `tick` sound replaces the click if it has one that decodes.
+pub struct Ticker {
+    requests: SyncSender<()>,
+}
+
+impl Ticker {
+    /// `None` when ticks would be silent anyway (no audio bell, Do Not Disturb).
+    pub fn new(options: &SoundOptions) -> Option<Ticker> {
+        if !options.bell.audio() || options.muted(SoundEvent::Tick) {
+            return None;
+        }
+        // Room for one waiting tick, never a queue of them
+        let (requests, ticks) = mpsc::sync_channel(1);
+        let device = options.device.clone();
+        let path = options.themed_path(SoundEvent::Tick).map(Path::to_path_buf);
+        std::thread::spawn(move || {
+            let Ok((_stream, handle)) = open_stream(device.as_deref()) else {
+                return;
+            };
+            let Ok(sink) = Sink::try_new(&handle) else {
+                return;
+            };
+            let (channels, rate, click) = path
+                .as_deref()
+                .and_then(decode_samples)
+                .unwrap_or_else(|| (1, RENDER_RATE, render_tick()));
+            for () in ticks {
+                // A slow device still busy with the last click skips this one
+                if sink.empty() {
+                    sink.append(SamplesBuffer::new(channels, rate, click.clone()));
+                }
+            }
+        });
+        Some(Ticker { requests })
+    }
+
+    /// Clicks once, or not at all if the last click hasn't gone out yet.
+    pub fn tick(&self) {
+        let _ = self.requests.try_send(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_device() {
+        let names = vec![
+            "HDA Intel PCH, ALC3246 Analog".to_string(),
+            "USB Headset".to_string(),
+            "Speakers".to_string(),
+            "Speakers (HDMI)".to_string(),
+        ];
+        assert_eq!(match_device(&names, "headset"), Some("USB Headset"));
+        assert_eq!(match_device(&names, "ALC3246"), Some("HDA Intel PCH, ALC3246 Analog"));
+        // An exact name beats an earlier partial match
+        assert_eq!(match_device(&names, "speakers (hdmi)"), Some("Speakers (HDMI)"));
+        assert_eq!(match_device(&names, "speakers"), Some("Speakers"));
+        assert_eq!(match_device(&names, "bluetooth"), None);
+    }
+
+    #[test]
+    fn test_bell_channels() {
+        assert!(Bell::Both.terminal() && Bell::Both.audio());
+        assert!(Bell::Terminal.terminal() && !Bell::Terminal.audio());
+        assert!(!Bell::Audio.terminal() && Bell::Audio.audio());
+        assert!(!Bell::None.terminal() && !Bell::None.audio());
+    }
+
+    #[test]
+    fn test_channel_report() {
+        assert_eq!(report_for([0, 0], [0, 0], None), None);
+        assert_eq!(report_for([2, 0], [0, 0], None).unwrap(), "bell: terminal rang 2 of 2 times, audio played 0 of 0 times");
+        let report = report_for([1, 1], [0, 2], Some("no output device")).unwrap();
+        assert!(report.contains("audio played 0 of 2 times (last audio error: no output device)"), "{}", report);
+    }
+
+    #[test]
+    fn test_fade_volume() {
+        let over = Duration::from_secs(60);
+        assert_eq!(fade_volume(Duration::ZERO, over), FADE_FLOOR);
+        assert!((fade_volume(Duration::from_secs(30), over) - 0.525).abs() < 1e-6);
+        assert_eq!(fade_volume(over, over), 1.0);
+        assert_eq!(fade_volume(Duration::from_secs(600), over), 1.0);
+        assert_eq!(fade_volume(Duration::ZERO, Duration::ZERO), 1.0);
+    }
+
+    #[test]
+    fn test_envelope_fits_in_the_beep() {
+        let ms = Duration::from_millis;
+        assert_eq!(Envelope::fitted(ms(50), ms(50)), Envelope::default());
+        assert_eq!(Envelope::fitted(ms(0), ms(0)), Envelope { fade_in: ms(0), fade_out: ms(0) });
+        // Too long together: the fade-out gives way
+        assert_eq!(Envelope::fitted(ms(200), ms(200)), Envelope { fade_in: ms(200), fade_out: ms(100) });
+        assert_eq!(Envelope::fitted(ms(1000), ms(50)), Envelope { fade_in: BEEP_LENGTH, fade_out: ms(0) });
+    }
+
+    #[test]
+    fn test_beep_fades_both_ways() {
+        let beep = render_beep(440.0, Waveform::Sine, Envelope::default());
+        assert_eq!(beep.len(), 14_400);
+        assert!(beep.iter().all(|sample| sample.abs() <= 0.2));
+        // Silent at both ends, full in the middle
+        assert!(beep[..48].iter().all(|sample| sample.abs() < 0.01));
+        assert!(beep[beep.len() - 48..].iter().all(|sample| sample.abs() < 0.01));
+        assert!(beep[7000..7200].iter().any(|sample| sample.abs() > 0.19));
+        assert_eq!(Envelope::default().gain(Duration::from_millis(150)), 1.0);
+    }
+
+    #[test]
+    fn test_waveforms() {
+        use clap::ValueEnum;
+        assert_eq!(Waveform::from_str("sawtooth", true).unwrap(), Waveform::Sawtooth);
+        assert_eq!(Waveform::from_str("Square", true).unwrap(), Waveform::Square);
+        assert!(Waveform::from_str("noise", true).is_err());
+        assert_eq!(Waveform::default(), Waveform::Sine);
+        for waveform in Waveform::value_variants() {
+            let beep = render_beep(440.0, *waveform, Envelope::default());
+            assert_eq!(beep.len(), 14_400, "{:?}", waveform);
+            assert!(beep.iter().all(|sample| sample.abs() <= 0.2), "{:?}", waveform);
+            assert!(beep[7000..7200].iter().any(|sample| sample.abs() > 0.15), "{:?}", waveform);
+        }
+        // A quarter of the way through a cycle
+        assert_eq!(Waveform::Square.sample(0.25), 1.0);
+        assert_eq!(Waveform::Triangle.sample(0.25), 0.0);
+        assert_eq!(Waveform::Sawtooth.sample(0.25), -0.5);
+    }
+
+    #[test]
+    fn test_tick_is_short_and_quiet() {
+        let click = render_tick();
+        assert_eq!(click.len(), 576);
+        assert!(click.iter().all(|sample| sample.abs() <= 0.15));
+        // Faded out by the end, so it doesn't pop
+        assert!(click[click.len() - 1].abs() < 0.001);
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// How long a reader waits before its one retry of a file that didn't parse.
+const RETRY_DELAY: Duration = Duration::from_millis(5);
+
+/// Replaces `path` with `contents` via a temp file in the same directory and a
+/// rename, so readers see either the old file or the new one, never half of one.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let tmp = tmp_path(path);
+    fs::write(&tmp, contents).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    write_atomic(path, &serde_json::to_string(value)?)
+}
+
+/// Reads a JSON state file. If it doesn't parse, it's read once more after a
+/// short pause in case a writer that doesn't rename was caught mid-write.
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    read_json_with_retry(path, RETRY_DELAY)
+}
+
+fn read_json_with_retry<T: DeserializeOwned>(path: &Path, delay: Duration) -> Result<T> {
+    let read = || -> Result<T> {
+        let contents = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents).with_context(|| format!("Invalid state file {}", path.display()))
+    };
+    match read() {
+        Err(err) if !is_not_found(&err) => {
+            std::thread::sleep(delay);
+            read()
+        }
+        result => result,
+    }
+}
+
+pub fn is_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<std::io::Error>().is_some_and(|err| err.kind() == std::io::ErrorKind::NotFound)
+}
+
+/// Whether a `read_json` error means the file was there but isn't valid state,
+/// even after the retry, rather than that it couldn't be read at all.
+pub fn is_corrupt(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<serde_json::Error>().is_some()
+}
+
+/// Unique per process, so two writers never share a temp file.
+fn tmp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("state");
+    path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
+
+/// An advisory lock on a state file, held until dropped. Only other lockers
+/// wait for it; plain readers and writers aren't blocked. The lock file is
+/// removed again on drop, so none are left next to the state.
+pub struct Lock {
+    path: PathBuf,
+    _file: File,
+}
+
+/// Blocks until this process holds the lock for `path`, for read-modify-write
+/// sequences that must not interleave with another process doing the same.
+pub fn lock(path: &Path) -> Result<Lock> {
+    let lock_path = path.with_file_name(format!(
+        "{}.lock",
+        path.file_name().and_then(|n| n.to_str()).unwrap_or("state")
+    ));
+    loop {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::{fs::MetadataExt, io::AsRawFd};
+            // Released when the file is closed
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+                return Err(std::io::Error::last_os_error())
+                    .with_context(|| format!("Failed to lock {}", lock_path.display()));
+            }
+            // The holder we waited on removed the file as it let go, so what we
+            // locked is gone from the path; lock whatever is there now instead
+            let held = file.metadata()?;
+            let current = fs::metadata(&lock_path).ok();
+            if current.is_none_or(|current| (current.dev(), current.ino()) != (held.dev(), held.ino())) {
+                continue;
+            }
+        }
+
+        return Ok(Lock { path: lock_path, _file: file });
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        // Still held here, the file is only closed after
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct State {
+        started_at: u64,
+        laps: Vec<u64>,
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("clockeroo-statefile-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_then_read() {
+        let dir = test_dir("roundtrip");
+        let path = dir.join("clockeroo.stopwatch");
+        let state = State { started_at: 1_000, laps: vec![90_000] };
+
+        write_json(&path, &state).unwrap();
+        assert_eq!(read_json::<State>(&path).unwrap(), state);
+        // Nothing left behind next to it
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_partially_written_file() {
+        let dir = test_dir("partial");
+        let path = dir.join("clockeroo.stopwatch");
+
+        // A writer died halfway through
+        fs::write(&path, r#"{"started_at": 1000, "la"#).unwrap();
+        let err = read_json::<State>(&path).unwrap_err();
+        assert!(format!("{:#}", err).contains("Invalid state file"), "{:#}", err);
+        assert!(is_corrupt(&err));
+
+        // A writer finishes while the reader waits for its retry
+        fs::write(&path, r#"{"started_at": 1000, "la"#).unwrap();
+        let writer = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(20));
+                fs::write(&path, r#"{"started_at": 1000, "laps": []}"#).unwrap();
+            })
+        };
+        let state: State = read_json_with_retry(&path, Duration::from_millis(500)).unwrap();
+        assert_eq!(state.started_at, 1000);
+        writer.join().unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lock_is_exclusive_and_cleaned_up() {
+        let dir = test_dir("lock");
+        let path = dir.join("clockeroo.queue");
+        let counter = dir.join("counter");
+        fs::write(&counter, "0").unwrap();
+
+        // Unlocked, some of these read-modify-writes would be lost
+        let workers: Vec<_> = (0..8)
+            .map(|_| {
+                let (path, counter) = (path.clone(), counter.clone());
+                std::thread::spawn(move || {
+                    for _ in 0..20 {
+                        let _lock = lock(&path).unwrap();
+                        let n: u32 = fs::read_to_string(&counter).unwrap().parse().unwrap();
+                        fs::write(&counter, (n + 1).to_string()).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        assert_eq!(fs::read_to_string(&counter).unwrap(), "160");
+        assert!(!dir.join("clockeroo.queue.lock").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_file_is_not_retried() {
+        let dir = test_dir("missing");
+        let err = read_json_with_retry::<State>(&dir.join("nope"), Duration::from_secs(60)).unwrap_err();
+        assert!(is_not_found(&err));
+        assert!(!is_corrupt(&err));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{artifacts::Artifact, format_duration, runtime_dir, statefile, unix_millis, zones::Zone};
+
+pub const FILE_PREFIX: &str = "clockeroo.status.";
+
+/// How often a running clock rewrites its status file even if nothing changed.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Files not rewritten for this long belong to a process that died without cleaning up.
+const STALE_AFTER_MS: u64 = 30_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockKind {
+    Timer,
+    Stopwatch,
+    Alarm,
+    Pomodoro,
+}
+
+impl ClockKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ClockKind::Timer => "timer",
+            ClockKind::Stopwatch => "stopwatch",
+            ClockKind::Alarm => "alarm",
+            ClockKind::Pomodoro => "pomodoro",
+        }
+    }
+}
+
+/// What a running clockeroo publishes for `clockeroo remaining` and `clockeroo dashboard`.
+/// Times are Unix milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClockStatus {
+    pub kind: ClockKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    pub started_at: u64,
+    /// Wall-clock end or alarm time, if the clock has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<u64>,
+    #[serde(default)]
+    pub paused: bool,
+    /// A timer counting on past zero (`--overtime`), which stays live until stopped
+    #[serde(default)]
+    pub overtime: bool,
+    /// How close a timer is to zero, by its `--warn-at`/`--critical-at` thresholds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<Zone>,
+    /// Elapsed time as of `updated_at`
+    #[serde(default)]
+    pub elapsed_ms: u64,
+    /// A countdown's whole length, adjustments included
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_ms: Option<u64>,
+    /// A countdown's time left as of `updated_at`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_ms: Option<u64>,
+    pub updated_at: u64,
+}
+
+impl ClockStatus {
+    /// A clock that started `elapsed` before `now_ms`.
+    pub fn new(kind: ClockKind, label: Option<String>, now_ms: u64, elapsed: Duration) -> ClockStatus {
+        let elapsed_ms = elapsed.as_millis() as u64;
+        ClockStatus {
+            kind,
+            label,
+            tag: None,
+            started_at: now_ms.saturating_sub(elapsed_ms),
+            ends_at: None,
+            paused: false,
+            overtime: false,
+            zone: None,
+            elapsed_ms,
+            total_ms: None,
+            remaining_ms: None,
+            updated_at: now_ms,
+        }
+    }
+
+    /// A countdown of `total` starting at `now_ms`.
+    pub fn countdown(kind: ClockKind, label: Option<String>, now_ms: u64, total: Duration) -> ClockStatus {
+        let total_ms = total.as_millis() as u64;
+        ClockStatus {
+            ends_at: Some(now_ms + total_ms),
+            total_ms: Some(total_ms),
+            remaining_ms: Some(total_ms),
+            ..ClockStatus::new(kind, label, now_ms, Duration::ZERO)
+        }
+    }
+
+    /// Records the elapsed time as of `now_ms`.
+    pub fn touch(&mut self, now_ms: u64, elapsed: Duration) {
+        self.elapsed_ms = elapsed.as_millis() as u64;
+        self.remaining_ms = self.total_ms.map(|total_ms| total_ms.saturating_sub(self.elapsed_ms));
+        self.updated_at = now_ms;
+    }
+
+    /// Time left now. A paused clock's end moves with the wall clock, so what's
+    /// left is held at its value as of the last update.
+    pub fn remaining(&self, now_ms: u64) -> Option<Duration> {
+        let now_ms = if self.paused { self.updated_at } else { now_ms };
+        self.ends_at.map(|ends_at| Duration::from_millis(ends_at.saturating_sub(now_ms)))
+    }
+
+    /// Elapsed time now, extrapolated from the last update unless paused.
+    pub fn elapsed(&self, now_ms: u64) -> Duration {
+        let since_update = if self.paused { 0 } else { now_ms.saturating_sub(self.updated_at) };
+        Duration::from_millis(self.elapsed_ms + since_update)
+    }
+
+    /// Past its end (unless in overtime), or left behind by a process that's gone.
+    pub fn is_stale(&self, now_ms: u64) -> bool {
+        let ended = !self.paused && !self.overtime && self.ends_at.is_some_and(|ends_at| ends_at <= now_ms);
+        ended || now_ms.saturating_sub(self.updated_at) > STALE_AFTER_MS
+    }
+}
+
+/// A published status file, removed again when dropped.
+pub struct StatusFile {
+    file: Artifact,
+    written_at: Instant,
+}
+
+impl StatusFile {
+    /// Rewrites the file in place, for changes readers should see right away.
+    pub fn update(&mut self, status: &ClockStatus) -> Result<()> {
+        statefile::write_json(self.file.path(), status)?;
+        self.written_at = Instant::now();
+        Ok(())
+    }
+
+    /// Rewrites the file if it's due for its periodic refresh. Best effort, a
+    /// failed write just leaves the previous contents.
+    pub fn refresh(&mut self, status: &ClockStatus) {
+        if self.written_at.elapsed() >= REFRESH_INTERVAL {
+            let _ = self.update(status);
+        }
+    }
+}
+
+/// Writes this process's status file.
+pub fn publish(status: &ClockStatus) -> Result<StatusFile> {
+    let path = runtime_dir().join(format!("{}{}.json", FILE_PREFIX, std::process::id()));
+    statefile::write_json(&path, status)?;
+    Ok(StatusFile { file: Artifact::register(path), written_at: Instant::now() })
+}
+
+/// Every clock still running, soonest end first, open-ended ones last.
+pub fn running_clocks() -> Vec<ClockStatus> {
+    let now = unix_millis(SystemTime::now());
+    let Ok(dir) = fs::read_dir(runtime_dir()) else {
+        return Vec::new();
+    };
+
+    let mut clocks: Vec<ClockStatus> = dir
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            name.starts_with(FILE_PREFIX) && name.ends_with(".json")
+        })
+        .filter_map(|path| statefile::read_json::<ClockStatus>(&path).ok())
+        .filter(|status| !status.is_stale(now))
+        .collect();
+    clocks.sort_by_key(|status| (status.ends_at.is_none(), status.ends_at, status.started_at));
+    clocks
+}
+
+/// Status of every interactive timer still running, soonest first.
+pub fn running_timers() -> Vec<ClockStatus> {
+    running_clocks().into_iter().filter(|status| status.kind == ClockKind::Timer).collect()
+}
+
+/// Fills `{tag}`, `{label}` and `{remaining}` in a `--format` template.
+pub fn render(format: &str, label: Option<&str>, tag: Option<&str>, remaining: Duration) -> String {
+    format
+        .replace("{tag}", tag.unwrap_or_default())
+        .replace("{label}", label.unwrap_or("timer"))
+        .replace("{remaining}", &format_duration(remaining))
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let remaining = Duration::from_secs(272);
+        assert_eq!(render("{remaining}", None, None, remaining), "04:32");
+        assert_eq!(render("{label} {remaining}", Some("tea"), None, remaining), "tea 04:32");
+        assert_eq!(render("{label} {remaining}", None, None, remaining), "timer 04:32");
+        assert_eq!(render("{tag} {label} {remaining}", Some("tea"), Some("🍵"), remaining), "🍵 tea 04:32");
+        // No tag, no stray space up front
+        assert_eq!(render("{tag} {remaining}", None, None, remaining), "04:32");
+    }
+
+    #[test]
+    fn test_remaining_never_negative() {
+        let status = ClockStatus { ends_at: Some(10_000), ..ClockStatus::new(ClockKind::Timer, None, 4_000, Duration::ZERO) };
+        assert_eq!(status.remaining(4_000), Some(Duration::from_secs(6)));
+        assert_eq!(status.remaining(12_000), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_paused_timer_holds_remaining() {
+        // Paused with 6s left, the owner last wrote at 4s
+        let mut status = ClockStatus { ends_at: Some(10_000), ..ClockStatus::new(ClockKind::Timer, None, 4_000, Duration::ZERO) };
+        status.paused = true;
+        assert_eq!(status.remaining(25_000), Some(Duration::from_secs(6)));
+        // Its old end time passing doesn't make it stale
+        assert!(!status.is_stale(25_000));
+    }
+
+    #[test]
+    fn test_countdown_fields_stay_in_step() {
+        let mut status = ClockStatus::countdown(ClockKind::Timer, None, 4_000, Duration::from_secs(25 * 60));
+        assert_eq!((status.ends_at, status.total_ms, status.remaining_ms), (Some(1_504_000), Some(1_500_000), Some(1_500_000)));
+        status.touch(758_000, Duration::from_secs(754));
+        assert_eq!((status.elapsed_ms, status.remaining_ms), (754_000, Some(746_000)));
+        // Into overtime what's left stays at zero
+        status.touch(1_600_000, Duration::from_secs(1596));
+        assert_eq!(status.remaining_ms, Some(0));
+        // A stopwatch has neither
+        let mut stopwatch = ClockStatus::new(ClockKind::Stopwatch, None, 0, Duration::ZERO);
+        stopwatch.touch(1_000, Duration::from_secs(1));
+        assert_eq!((stopwatch.total_ms, stopwatch.remaining_ms), (None, None));
+    }
+
+    #[test]
+    fn test_elapsed_holds_while_paused() {
+        let mut status = ClockStatus::new(ClockKind::Stopwatch, None, 100_000, Duration::from_secs(40));
+        assert_eq!(status.started_at, 60_000);
+        assert_eq!(status.elapsed(103_000), Duration::from_secs(43));
+        status.paused = true;
+        assert_eq!(status.elapsed(103_000), Duration::from_secs(40));
+    }
+
+    #[test]
+    fn test_stale() {
+        let mut status = ClockStatus::new(ClockKind::Stopwatch, None, 100_000, Duration::ZERO);
+        assert!(!status.is_stale(110_000));
+        // Owner stopped refreshing it
+        assert!(status.is_stale(100_000 + STALE_AFTER_MS + 1));
+        status.ends_at = Some(105_000);
+        assert!(status.is_stale(105_000));
+        // Counting on past zero, it's live for as long as its owner keeps writing
+        status.overtime = true;
+        status.touch(160_000, Duration::from_secs(60));
+        assert!(!status.is_stale(165_000));
+        assert!(status.is_stale(160_000 + STALE_AFTER_MS + 1));
+    }
+}
@@ -0,0 +1,609 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{format_duration, quiet_hours::QuietHours, statefile};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    Alarm,
+    Timer,
+}
+
+/// A scheduled alarm or background timer owned by the daemon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    #[serde(default)]
+    pub id: u64,
+    pub kind: EntryKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// A background timer's `--tag`, shown before its label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// A background timer's notification text, from its preset, in place of "<label> has completed!"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub due: DateTime<Local>,
+    /// Days an alarm repeats on; empty means it fires once
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repeat: Vec<Weekday>,
+    /// Shell command run when the entry fires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec: Option<String>,
+    /// Window in which firing stays silent (no beep or notification)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<QuietHours>,
+    /// A repeating alarm stays silent on every day up to and including this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_until: Option<NaiveDate>,
+    /// The file `alarm import` read this alarm from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported: Option<Imported>,
+    /// A background timer's whole duration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_ms: Option<u64>,
+    /// How far along a timer is, only filled in for `status` replies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<Progress>,
+}
+
+/// A timer's elapsed and remaining time as of a `status` request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    pub elapsed_ms: u64,
+    pub remaining_ms: u64,
+}
+
+/// Where an imported alarm came from, so importing the file again finds it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Imported {
+    pub source: PathBuf,
+    /// Hash of the label, which stays the same when the line's time or days are edited
+    pub key: String,
+    /// Hash of the whole line, to tell whether it changed
+    pub hash: String,
+}
+
+/// What an `alarm import` did to the store.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+}
+
+impl Entry {
+    pub fn display_name(&self) -> String {
+        match (&self.label, self.kind) {
+            (Some(label), _) => label.clone(),
+            (None, EntryKind::Alarm) => "alarm".to_string(),
+            (None, EntryKind::Timer) => "timer".to_string(),
+        }
+    }
+
+    /// Another background timer under the same label, which a double-fired
+    /// hotkey would leave running twice.
+    pub fn is_duplicate_of(&self, other: &Entry) -> bool {
+        self.kind == EntryKind::Timer && other.kind == EntryKind::Timer && self.label.is_some() && self.label == other.label
+    }
+
+    /// Elapsed and remaining time at `now`, for timers that know their duration.
+    pub fn progress_at(&self, now: DateTime<Local>) -> Option<Progress> {
+        let total_ms = self.total_ms?;
+        let remaining_ms = (self.due - now).num_milliseconds().clamp(0, total_ms as i64) as u64;
+        Some(Progress { elapsed_ms: total_ms - remaining_ms, remaining_ms })
+    }
+
+    /// Whether occurrences are being skipped as of `today` (`alarm skip`).
+    pub fn is_skipping(&self, today: NaiveDate) -> bool {
+        self.skip_until.is_some_and(|until| until >= today)
+    }
+
+    /// "once", "daily", "weekdays", "weekends" or the days, like "Mon,Thu".
+    pub fn repeat_spec(&self) -> String {
+        use Weekday::*;
+        let has = |days: &[Weekday]| self.repeat.len() == days.len() && days.iter().all(|d| self.repeat.contains(d));
+        if self.repeat.is_empty() {
+            "once".to_string()
+        } else if has(&[Mon, Tue, Wed, Thu, Fri, Sat, Sun]) {
+            "daily".to_string()
+        } else if has(&[Mon, Tue, Wed, Thu, Fri]) {
+            "weekdays".to_string()
+        } else if has(&[Sat, Sun]) {
+            "weekends".to_string()
+        } else {
+            let days: Vec<String> = self.repeat.iter().map(|d| d.to_string()).collect();
+            days.join(",")
+        }
+    }
+}
+
+/// Reads a repeat spec as [`Entry::repeat_spec`] writes it: "daily",
+/// "weekdays", "weekends", or days like "mon,thu" ("monday" works too).
+pub fn parse_repeat_spec(spec: &str) -> Result<Vec<Weekday>> {
+    use Weekday::*;
+    let days = match spec.trim().to_lowercase().as_str() {
+        "daily" => vec![Mon, Tue, Wed, Thu, Fri, Sat, Sun],
+        "weekdays" => vec![Mon, Tue, Wed, Thu, Fri],
+        "weekends" => vec![Sat, Sun],
+        days => days
+            .split(',')
+            .map(|day| {
+                day.trim()
+                    .parse::<Weekday>()
+                    .map_err(|_| anyhow::anyhow!("Unknown day '{}'. Use daily, weekdays, weekends or days like mon,thu", day.trim()))
+            })
+            .collect::<Result<_>>()?,
+    };
+    Ok(days)
+}
+
+/// What adding a labeled timer does when one with that label is already scheduled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Duplicates {
+    /// Run both (`--allow-duplicate`, and what clients from before this existed get)
+    #[default]
+    Allow,
+    /// Turn the new one down
+    Refuse,
+    /// Cancel the old one and start over (`--replace`)
+    Replace,
+}
+
+/// Which background timers `clockeroo cancel` means.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Selector {
+    /// The one with this label; every one of them with `all`
+    Label { label: String, all: bool },
+    /// Every background timer (alarms stay)
+    All,
+    /// The one started most recently
+    Last,
+}
+
+/// Persisted alarms and background timers (`store.json` in the data directory).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Store {
+    #[serde(default)]
+    next_id: u64,
+    #[serde(default)]
+    pub entries: Vec<Entry>,
+}
+
+pub fn data_dir() -> PathBuf {
+    std::env::var("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(|_| std::env::temp_dir())
+        .join("clockeroo")
+}
+
+pub fn store_path() -> PathBuf {
+    data_dir().join("store.json")
+}
+
+impl Store {
+    pub fn load() -> Result<Store> {
+        let path = store_path();
+        if !path.exists() {
+            return Ok(Store::default());
+        }
+
+        statefile::read_json(&path)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = store_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Pretty, since people do open this one in an editor
+        statefile::write_atomic(&path, &serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn add(&mut self, mut entry: Entry) -> u64 {
+        self.next_id = self.next_id.max(self.entries.iter().map(|e| e.id).max().unwrap_or(0)) + 1;
+        entry.id = self.next_id;
+        self.entries.push(entry);
+        self.next_id
+    }
+
+    /// Adds `entry`, first checking it against timers already scheduled under
+    /// its label. Returns its id and whatever it replaced.
+    pub fn add_checked(&mut self, entry: Entry, duplicates: Duplicates, now: DateTime<Local>) -> Result<(u64, Vec<Entry>)> {
+        let mut replaced = Vec::new();
+        match duplicates {
+            Duplicates::Allow => {}
+            Duplicates::Refuse => {
+                if let Some(existing) = self.entries.iter().find(|e| e.is_duplicate_of(&entry)) {
+                    anyhow::bail!(
+                        "Timer #{} '{}' is already running ({} left). Use --replace to restart it or --allow-duplicate to run both",
+                        existing.id,
+                        existing.display_name(),
+                        format_duration((existing.due - now).to_std().unwrap_or_default())
+                    );
+                }
+            }
+            Duplicates::Replace => {
+                let (old, kept) = std::mem::take(&mut self.entries).into_iter().partition(|e| e.is_duplicate_of(&entry));
+                self.entries = kept;
+                replaced = old;
+            }
+        }
+        Ok((self.add(entry), replaced))
+    }
+
+    /// Merges the alarms read from `source` by `alarm import`, matching them
+    /// to the ones imported from it before by their key. With `sync`, alarms
+    /// whose line is gone from the file are removed.
+    pub fn import(&mut self, source: &Path, entries: Vec<Entry>, sync: bool) -> ImportReport {
+        let mut report = ImportReport::default();
+        let from_source = |entry: &Entry| entry.imported.as_ref().filter(|imported| imported.source == source).map(|imported| imported.key.clone());
+        let keys: Vec<String> = entries.iter().filter_map(from_source).collect();
+
+        for new in entries {
+            let key = from_source(&new);
+            match self.entries.iter_mut().find(|entry| key.is_some() && from_source(entry) == key) {
+                Some(existing) if existing.imported == new.imported => report.unchanged += 1,
+                Some(existing) => {
+                    *existing = Entry { id: existing.id, ..new };
+                    report.updated += 1;
+                }
+                None => {
+                    self.add(new);
+                    report.added += 1;
+                }
+            }
+        }
+        if sync {
+            let before = self.entries.len();
+            self.entries.retain(|entry| from_source(entry).is_none_or(|key| keys.contains(&key)));
+            report.removed = before - self.entries.len();
+        }
+        report
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<Entry> {
+        let index = self.entries.iter().position(|e| e.id == id)?;
+        Some(self.entries.remove(index))
+    }
+
+    /// Removes and returns the timers `selector` picks. An error, and nothing
+    /// removed, if none match or a label matches more than one without `all`.
+    pub fn remove_matching(&mut self, selector: &Selector, now: DateTime<Local>) -> Result<Vec<Entry>> {
+        let timers = self.entries.iter().filter(|e| e.kind == EntryKind::Timer);
+        let ids: Vec<u64> = match selector {
+            Selector::Label { label, all } => {
+                let matches: Vec<&Entry> = timers.filter(|e| e.label.as_deref() == Some(label.as_str())).collect();
+                if matches.len() > 1 && !all {
+                    let listed: Vec<String> = matches
+                        .iter()
+                        .map(|e| format!("#{} ({} left)", e.id, format_duration((e.due - now).to_std().unwrap_or_default())))
+                        .collect();
+                    anyhow::bail!(
+                        "{} background timers are labelled '{}': {}. Cancel one by id or all of them with --all",
+                        matches.len(),
+                        label,
+                        listed.join(", ")
+                    );
+                }
+                if matches.is_empty() {
+                    anyhow::bail!("No background timer labelled '{}'", label);
+                }
+                matches.iter().map(|e| e.id).collect()
+            }
+            Selector::All => timers.map(|e| e.id).collect(),
+            // Ids only go up, so the highest is the newest
+            Selector::Last => timers.map(|e| e.id).max().into_iter().collect(),
+        };
+        if ids.is_empty() {
+            anyhow::bail!("No background timers are running");
+        }
+        Ok(ids.into_iter().filter_map(|id| self.remove(id)).collect())
+    }
+
+    pub fn next_due(&self) -> Option<DateTime<Local>> {
+        self.entries.iter().map(|e| e.due).min()
+    }
+
+    /// Removes and returns every entry due at `now`. Repeating alarms are
+    /// rolled forward to their next occurrence instead of being removed.
+    pub fn take_due(&mut self, now: DateTime<Local>) -> Vec<Entry> {
+        self.take_due_where(now, |_| true)
+    }
+
+    /// `take_due` for just the entries in `ids`, leaving every other due one
+    /// to whoever rings it.
+    pub fn take_due_ids(&mut self, ids: &[u64], now: DateTime<Local>) -> Vec<Entry> {
+        self.take_due_where(now, |entry| ids.contains(&entry.id))
+    }
+
+    fn take_due_where(&mut self, now: DateTime<Local>, wanted: impl Fn(&Entry) -> bool) -> Vec<Entry> {
+        let mut fired = Vec::new();
+
+        self.entries.retain_mut(|entry| {
+            if entry.due > now || !wanted(entry) {
+                return true;
+            }
+            fired.push(entry.clone());
+
+            match next_unskipped(now, entry.due.time(), &entry.repeat, entry.skip_until) {
+                Some(next) => {
+                    entry.due = next;
+                    // Skips that are behind us have done their job
+                    entry.skip_until = entry.skip_until.filter(|until| *until >= now.date_naive());
+                    true
+                }
+                None => false,
+            }
+        });
+
+        fired
+    }
+
+    /// Skips the next occurrence of repeating alarm `id`, or every one up to and
+    /// including `until`. An occurrence that's ringing right now is already past,
+    /// so the skip applies to the one after it. Returns the alarm, rolled forward.
+    pub fn skip(&mut self, id: u64, until: Option<NaiveDate>, now: DateTime<Local>) -> Result<&Entry> {
+        let entry = self.repeating_alarm(id)?;
+        let time = entry.due.time();
+        let next = match entry.due > now {
+            true => entry.due,
+            false => next_unskipped(now, time, &entry.repeat, entry.skip_until).unwrap_or(entry.due),
+        };
+        let until = until.unwrap_or(next.date_naive());
+        if until < next.date_naive() {
+            anyhow::bail!("Alarm #{} doesn't ring again until {}, nothing to skip", id, next.format("%a %b %-d"));
+        }
+        entry.skip_until = Some(until);
+        if let Some(due) = next_unskipped(now, time, &entry.repeat, entry.skip_until) {
+            entry.due = due;
+        }
+        Ok(entry)
+    }
+
+    /// Drops the skips on alarm `id`, putting it back on its normal schedule.
+    pub fn unskip(&mut self, id: u64, now: DateTime<Local>) -> Result<&Entry> {
+        let entry = self.repeating_alarm(id)?;
+        if entry.skip_until.take().is_none() {
+            anyhow::bail!("Alarm #{} isn't skipping anything", id);
+        }
+        if let Some(due) = next_occurrence(now, entry.due.time(), &entry.repeat) {
+            entry.due = due;
+        }
+        Ok(entry)
+    }
+
+    fn repeating_alarm(&mut self, id: u64) -> Result<&mut Entry> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or_else(|| anyhow::anyhow!("No alarm or timer with id {}", id))?;
+        if entry.kind != EntryKind::Alarm || entry.repeat.is_empty() {
+            anyhow::bail!("#{} doesn't repeat, cancel it instead", id);
+        }
+        Ok(entry)
+    }
+
+    /// Moves background timer deadlines by `delta`, used when the wall clock jumps
+    /// so timers keep their remaining duration. Alarms stay anchored to the clock.
+    pub fn shift_timers(&mut self, delta: chrono::Duration) {
+        for entry in self.entries.iter_mut().filter(|e| e.kind == EntryKind::Timer) {
+            entry.due += delta;
+        }
+    }
+}
+
+/// Next datetime strictly after `after` at `time` on one of `days`.
+pub fn next_occurrence(after: DateTime<Local>, time: NaiveTime, days: &[Weekday]) -> Option<DateTime<Local>> {
+    if days.is_empty() {
+        return None;
+    }
+
+    (0..=7)
+        .map(|offset| after.date_naive() + chrono::Duration::days(offset))
+        .filter(|date| days.contains(&date.weekday()))
+        .filter_map(|date| Local.from_local_datetime(&date.and_time(time)).earliest())
+        .find(|candidate| *candidate > after)
+}
+
+/// Like [`next_occurrence`], but passing over every day up to and including `skip_until`.
+pub fn next_unskipped(after: DateTime<Local>, time: NaiveTime, days: &[Weekday], skip_until: Option<NaiveDate>) -> Option<DateTime<Local>> {
+    let mut next = next_occurrence(after, time, days)?;
+    while skip_until.is_some_and(|until| next.date_naive() <= until) {
+        next = next_occurrence(next, time, days)?;
+    }
+    Some(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn entry(kind: EntryKind, due: DateTime<Local>, repeat: Vec<Weekday>) -> Entry {
+        Entry { id: 0, kind, label: None, tag: None, message: None, due, repeat, exec: None, quiet_hours: None, skip_until: None, imported: None, total_ms: None, progress: None }
+    }
+
+    #[test]
+    fn test_next_occurrence_skips_to_matching_day() {
+        // 2024-03-08 is a Friday
+        let after = local(2024, 3, 8, 9, 0);
+        let time = NaiveTime::from_hms_opt(8, 30, 0).unwrap();
+        let weekdays = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+
+        assert_eq!(next_occurrence(after, time, &weekdays), Some(local(2024, 3, 11, 8, 30)));
+        assert_eq!(next_occurrence(after, time, &[Weekday::Fri]), Some(local(2024, 3, 15, 8, 30)));
+        assert_eq!(next_occurrence(after, time, &[]), None);
+    }
+
+    #[test]
+    fn test_remove_matching() {
+        let now = local(2024, 3, 8, 9, 0);
+        let timer = |label: &str, minutes| Entry { label: Some(label.to_string()), ..entry(EntryKind::Timer, now + chrono::Duration::minutes(minutes), vec![]) };
+        let mut store = Store::default();
+        let focus = store.add(timer("focus", 25));
+        let tea = store.add(timer("tea", 3));
+        let second_focus = store.add(timer("focus", 50));
+        let alarm = store.add(entry(EntryKind::Alarm, local(2024, 3, 9, 7, 0), vec![]));
+        let ids = |removed: Vec<Entry>| removed.iter().map(|e| e.id).collect::<Vec<_>>();
+
+        let err = store.remove_matching(&Selector::Label { label: "focus".into(), all: false }, now).unwrap_err().to_string();
+        assert!(err.contains(&format!("#{} (25:00 left), #{} (50:00 left)", focus, second_focus)), "{}", err);
+        assert_eq!(store.entries.len(), 4);
+        assert!(store.remove_matching(&Selector::Label { label: "nap".into(), all: false }, now).is_err());
+
+        assert_eq!(ids(store.remove_matching(&Selector::Last, now).unwrap()), [second_focus]);
+        assert_eq!(ids(store.remove_matching(&Selector::Label { label: "focus".into(), all: false }, now).unwrap()), [focus]);
+        // Alarms aren't background timers
+        assert_eq!(ids(store.remove_matching(&Selector::All, now).unwrap()), [tea]);
+        assert!(store.remove_matching(&Selector::Last, now).is_err());
+        assert_eq!(ids(store.entries.clone()), [alarm]);
+    }
+
+    #[test]
+    fn test_add_checked_duplicates() {
+        let now = local(2024, 3, 8, 9, 0);
+        let focus = Entry { label: Some("focus".to_string()), ..entry(EntryKind::Timer, local(2024, 3, 8, 9, 25), vec![]) };
+        let mut store = Store::default();
+        let (first, _) = store.add_checked(focus.clone(), Duplicates::Refuse, now).unwrap();
+
+        let err = store.add_checked(focus.clone(), Duplicates::Refuse, now).unwrap_err();
+        assert!(err.to_string().contains("#1 'focus' is already running (25:00 left)"), "{}", err);
+        // Unlabeled timers and alarms never clash
+        assert!(store.add_checked(entry(EntryKind::Timer, now, vec![]), Duplicates::Refuse, now).is_ok());
+        assert!(store.add_checked(entry(EntryKind::Timer, now, vec![]), Duplicates::Refuse, now).is_ok());
+
+        let (second, replaced) = store.add_checked(focus.clone(), Duplicates::Replace, now).unwrap();
+        assert_eq!(replaced.iter().map(|e| e.id).collect::<Vec<_>>(), [first]);
+        assert!(store.add_checked(focus, Duplicates::Allow, now).is_ok());
+        let labeled: Vec<u64> = store.entries.iter().filter(|e| e.label.is_some()).map(|e| e.id).collect();
+        assert_eq!(labeled, [second, second + 1]);
+    }
+
+    #[test]
+    fn test_progress_at() {
+        let timer = Entry { total_ms: Some(25 * 60_000), ..entry(EntryKind::Timer, local(2024, 3, 8, 9, 25), vec![]) };
+        let progress = timer.progress_at(local(2024, 3, 8, 9, 10)).unwrap();
+        assert_eq!(progress, Progress { elapsed_ms: 10 * 60_000, remaining_ms: 15 * 60_000 });
+        // Clamped on both sides
+        assert_eq!(timer.progress_at(local(2024, 3, 8, 9, 30)).unwrap().remaining_ms, 0);
+        assert_eq!(timer.progress_at(local(2024, 3, 8, 8, 0)).unwrap().elapsed_ms, 0);
+        // Alarms and timers from before durations were kept have none
+        assert_eq!(entry(EntryKind::Alarm, local(2024, 3, 8, 9, 25), vec![]).progress_at(local(2024, 3, 8, 9, 0)), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tag_and_message_kept_through_the_daemon() {
+        let tagged = Entry { label: Some("tea".into()), tag: Some("🍵".into()), message: Some("Tea's ready".into()), ..entry(EntryKind::Timer, local(2024, 3, 8, 9, 25), vec![]) };
+        let request = serde_json::to_string(&crate::ipc::Request::Add { entry: Box::new(tagged), duplicates: Duplicates::Refuse }).unwrap();
+        let crate::ipc::Request::Add { entry: received, .. } = serde_json::from_str(&request).unwrap() else { panic!("{}", request) };
+        assert_eq!(received.tag.as_deref(), Some("🍵"));
+        assert_eq!(received.message.as_deref(), Some("Tea's ready"));
+        // Stores from before tags and messages load without them
+        let old: Entry = serde_json::from_str(r#"{"id":1,"kind":"timer","label":"tea","due":"2024-03-08T09:25:00+00:00"}"#).unwrap();
+        assert_eq!((old.tag, old.message), (None, None));
+    }
+
+    #[test]
+    fn test_repeat_spec() {
+        use Weekday::*;
+        let spec = |days: Vec<Weekday>| entry(EntryKind::Alarm, local(2024, 3, 8, 9, 0), days).repeat_spec();
+        assert_eq!(spec(vec![]), "once");
+        assert_eq!(spec(vec![Sun, Mon, Tue, Wed, Thu, Fri, Sat]), "daily");
+        assert_eq!(spec(vec![Mon, Tue, Wed, Thu, Fri]), "weekdays");
+        assert_eq!(spec(vec![Sun, Sat]), "weekends");
+        assert_eq!(spec(vec![Mon, Thu]), "Mon,Thu");
+    }
+
+    #[test]
+    fn test_take_due_rolls_repeating_alarms() {
+        let now = local(2024, 3, 8, 9, 0);
+        let mut store = Store::default();
+        let once = store.add(entry(EntryKind::Timer, local(2024, 3, 8, 8, 59), vec![]));
+        let daily = store.add(entry(EntryKind::Alarm, local(2024, 3, 8, 9, 0), vec![Weekday::Fri, Weekday::Sat]));
+        let later = store.add(entry(EntryKind::Alarm, local(2024, 3, 8, 10, 0), vec![]));
+
+        let fired: Vec<u64> = store.take_due(now).iter().map(|e| e.id).collect();
+        assert_eq!(fired, vec![once, daily]);
+
+        let ids: Vec<u64> = store.entries.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![daily, later]);
+        assert_eq!(store.entries[0].due, local(2024, 3, 9, 9, 0));
+    }
+
+    #[test]
+    fn test_take_due_ids_leaves_the_rest() {
+        let now = local(2024, 3, 8, 9, 0);
+        let mut store = Store::default();
+        let timer = store.add(entry(EntryKind::Timer, local(2024, 3, 8, 8, 59), vec![]));
+        let rung = store.add(entry(EntryKind::Alarm, local(2024, 3, 8, 9, 0), vec![]));
+        let other = store.add(entry(EntryKind::Alarm, local(2024, 3, 8, 8, 30), vec![]));
+
+        let fired: Vec<u64> = store.take_due_ids(&[rung], now).iter().map(|e| e.id).collect();
+        assert_eq!(fired, vec![rung]);
+        // Still due, for the daemon to ring when it's back
+        let ids: Vec<u64> = store.entries.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![timer, other]);
+    }
+
+    #[test]
+    fn test_skip_next_occurrence_and_unskip() {
+        // Weekdays at 7:00, asked on Thursday evening: Friday is skipped, Monday rings
+        let now = local(2024, 3, 7, 20, 0);
+        let weekdays = vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+        let mut store = Store::default();
+        let id = store.add(entry(EntryKind::Alarm, local(2024, 3, 8, 7, 0), weekdays));
+
+        let skipped = store.skip(id, None, now).unwrap();
+        assert_eq!(skipped.due, local(2024, 3, 11, 7, 0));
+        assert!(skipped.is_skipping(now.date_naive()));
+
+        // Friday passes without ringing, Monday does and the skip is cleared
+        assert!(store.take_due(local(2024, 3, 8, 7, 0)).is_empty());
+        assert_eq!(store.take_due(local(2024, 3, 11, 7, 0)).len(), 1);
+        assert_eq!(store.entries[0].due, local(2024, 3, 12, 7, 0));
+        assert_eq!(store.entries[0].skip_until, None);
+
+        store.skip(id, None, local(2024, 3, 11, 8, 0)).unwrap();
+        let restored = store.unskip(id, local(2024, 3, 11, 8, 0)).unwrap();
+        assert_eq!((restored.due, restored.skip_until), (local(2024, 3, 12, 7, 0), None));
+        assert!(store.unskip(id, now).is_err());
+    }
+
+    #[test]
+    fn test_skip_range_and_ringing_alarm() {
+        let daily = vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun];
+        let mut store = Store::default();
+        let id = store.add(entry(EntryKind::Alarm, local(2024, 3, 8, 7, 0), daily));
+
+        // Ringing right now: today's is already happening, tomorrow's is skipped
+        let skipped = store.skip(id, None, local(2024, 3, 8, 7, 0)).unwrap();
+        assert_eq!(skipped.skip_until, NaiveDate::from_ymd_opt(2024, 3, 9));
+        assert_eq!(skipped.due, local(2024, 3, 10, 7, 0));
+
+        // A vacation: nothing until the day after it ends
+        let until = NaiveDate::from_ymd_opt(2024, 3, 20);
+        assert_eq!(store.skip(id, until, local(2024, 3, 8, 8, 0)).unwrap().due, local(2024, 3, 21, 7, 0));
+        // Before the next ring there's nothing to skip
+        assert!(store.skip(id, NaiveDate::from_ymd_opt(2024, 3, 9), local(2024, 3, 8, 8, 0)).is_err());
+
+        let once = store.add(entry(EntryKind::Alarm, local(2024, 3, 8, 9, 0), vec![]));
+        assert!(store.skip(once, None, local(2024, 3, 8, 8, 0)).is_err());
+    }
+}
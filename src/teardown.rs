@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Exit code after winding down for SIGTERM, as if the signal had killed us.
+pub const EXIT_TERMINATED: i32 = 128 + 15;
+
+/// Exit code after winding down for Ctrl-C (SIGINT).
+pub const EXIT_INTERRUPTED: i32 = 128 + 2;
+
+/// Turns SIGTERM and SIGHUP into a request to wind down, seen through
+/// [`requested`], so a clock with cleanup to do (focus `on_end` hooks, the
+/// terminal) gets to do it instead of dying mid-run. Must be called from
+/// inside the runtime.
+pub fn catch_signals() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        // Registered before returning, so a signal that comes right away isn't missed
+        let (Ok(mut terminate), Ok(mut hangup)) = (signal(SignalKind::terminate()), signal(SignalKind::hangup())) else {
+            return;
+        };
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = terminate.recv() => {}
+                _ = hangup.recv() => {}
+            }
+            REQUESTED.store(true, Ordering::Relaxed);
+        });
+    }
+}
+
+/// Whether a signal caught by [`catch_signals`] asked this process to stop.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
+
+/// Turns Ctrl-C into a request to wind down too, for clocks that have to
+/// run `cancel` hooks when stopped without a screen to read the key from.
+/// Must be called from inside the runtime.
+pub fn catch_interrupt() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let Ok(mut interrupt) = signal(SignalKind::interrupt()) else {
+            return;
+        };
+        tokio::spawn(async move {
+            interrupt.recv().await;
+            INTERRUPTED.store(true, Ordering::Relaxed);
+            REQUESTED.store(true, Ordering::Relaxed);
+        });
+    }
+}
+
+/// Whether the stop [`requested`] came from Ctrl-C, which is the user cancelling
+/// rather than the system shutting us down.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// The exit code for the signal that [`requested`] a stop.
+pub fn exit_code() -> i32 {
+    if interrupted() {
+        EXIT_INTERRUPTED
+    } else {
+        EXIT_TERMINATED
+    }
+}
@@ -0,0 +1,127 @@
+use chrono::Timelike;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The `time_format` config setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TimeFormat {
+    #[serde(rename = "12h")]
+    TwelveHour,
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    /// Follow the alarm time as typed, or the locale
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+}
+
+/// Locales whose convention is a 12-hour clock.
+const TWELVE_HOUR_LOCALES: &[&str] = &["en_US", "en_CA", "en_AU", "en_NZ", "en_PH", "en_IN", "hi_IN"];
+
+static TWELVE_HOUR: AtomicBool = AtomicBool::new(false);
+
+/// Sets the clock style for every displayed time, decided once at startup.
+pub fn set_twelve_hour(twelve_hour: bool) {
+    TWELVE_HOUR.store(twelve_hour, Ordering::Relaxed);
+}
+
+pub fn is_twelve_hour() -> bool {
+    TWELVE_HOUR.load(Ordering::Relaxed)
+}
+
+/// Picks 12- or 24-hour display. A `--12h`/`--24h` flag wins, then the config
+/// setting; "auto" follows how the alarm time was typed (am/pm means 12-hour, a
+/// bare `19:30` 24-hour) and otherwise the `LC_TIME` locale.
+pub fn resolve(flag: Option<TimeFormat>, config: TimeFormat, input_hint: Option<bool>) -> bool {
+    match flag.unwrap_or(config) {
+        TimeFormat::TwelveHour => true,
+        TimeFormat::TwentyFourHour => false,
+        TimeFormat::Auto => match input_hint {
+            Some(twelve_hour) => twelve_hour,
+            None => locale_prefers_12h(&current_locale()),
+        },
+    }
+}
+
+/// `Some(true)` for "7am"/"7:30 PM", `Some(false)` for "19:30", `None` if it says nothing.
+pub fn input_prefers_12h(input: &str) -> Option<bool> {
+    let input = input.trim().to_lowercase();
+    if input.ends_with("am") || input.ends_with("pm") {
+        Some(true)
+    } else if input.contains(':') {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// The first of `LC_ALL`, `LC_TIME` and `LANG` that's set, like libc does.
+fn current_locale() -> String {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+}
+
+fn locale_prefers_12h(locale: &str) -> bool {
+    // "en_US.UTF-8" and "en_US@euro" both come down to "en_US"
+    let name = locale.split(['.', '@']).next().unwrap_or_default();
+    TWELVE_HOUR_LOCALES.contains(&name)
+}
+
+/// "7:05 PM" or "19:05", per the startup setting.
+pub fn format_clock(time: &impl Timelike) -> String {
+    format_time(time, is_twelve_hour(), false)
+}
+
+/// Like [`format_clock`] with seconds: "7:05:30 PM" or "19:05:30".
+pub fn format_clock_secs(time: &impl Timelike) -> String {
+    format_time(time, is_twelve_hour(), true)
+}
+
+pub fn format_time(time: &impl Timelike, twelve_hour: bool, seconds: bool) -> String {
+    let secs = if seconds { format!(":{:02}", time.second()) } else { String::new() };
+    if twelve_hour {
+        let (pm, hour) = time.hour12();
+        format!("{}:{:02}{} {}", hour, time.minute(), secs, if pm { "PM" } else { "AM" })
+    } else {
+        format!("{:02}:{:02}{}", time.hour(), time.minute(), secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveTime;
+
+    #[test]
+    fn test_format_time() {
+        let evening = NaiveTime::from_hms_opt(19, 5, 30).unwrap();
+        let morning = NaiveTime::from_hms_opt(7, 5, 0).unwrap();
+        assert_eq!(format_time(&evening, true, false), "7:05 PM");
+        assert_eq!(format_time(&evening, false, false), "19:05");
+        assert_eq!(format_time(&evening, true, true), "7:05:30 PM");
+        assert_eq!(format_time(&morning, false, true), "07:05:00");
+        assert_eq!(format_time(&NaiveTime::MIN, true, false), "12:00 AM");
+    }
+
+    #[test]
+    fn test_resolve() {
+        use TimeFormat::*;
+        // The flag beats the config, the config beats the input
+        assert!(resolve(Some(TwelveHour), TwentyFourHour, input_prefers_12h("19:30")));
+        assert!(!resolve(None, TwentyFourHour, input_prefers_12h("7:30pm")));
+        assert!(resolve(None, Auto, input_prefers_12h("7:30pm")));
+        assert!(!resolve(None, Auto, input_prefers_12h("19:30")));
+    }
+
+    #[test]
+    fn test_locale_prefers_12h() {
+        assert!(locale_prefers_12h("en_US.UTF-8"));
+        assert!(!locale_prefers_12h("en_GB.UTF-8"));
+        assert!(!locale_prefers_12h("de_DE@euro"));
+        assert!(!locale_prefers_12h("C"));
+        assert!(!locale_prefers_12h(""));
+    }
+}
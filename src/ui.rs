@@ -0,0 +1,1968 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveTime};
+use crossterm::{
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::{Backend, ClearType, CrosstermBackend, TestBackend, WindowSize},
+    buffer,
+    layout::{Alignment, Constraint, Direction, Flex, Layout, Margin, Position, Rect, Size},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    symbols::Marker,
+    widgets::{
+        canvas::{Canvas, Circle, Points},
+        Block, BorderType, Borders, Cell, Paragraph, Row, Table, Wrap,
+    },
+    Frame, Terminal,
+};
+use std::{
+    io::{self, IsTerminal, Stdout, Write},
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{
+    appearance::{self, Appearance},
+    focus, format_delta, format_duration, format_long_duration, format_stopwatch_time_with, lap_delta_ms, running_heading, summarize_laps,
+    Precision,
+    status::ClockStatus,
+    store::Entry,
+    time_format::format_clock,
+    zones::Zone,
+    ASCII_HEADER,
+};
+
+/// What the running timer screen shows.
+pub struct TimerView<'a> {
+    pub label: Option<&'a str>,
+    pub tag: Option<&'a str>,
+    pub remaining: Duration,
+    /// The whole timer, shown with the elapsed time under the time left
+    pub total: Duration,
+    pub readout: Readout,
+    /// How close to zero, which colors the time left
+    pub zone: Zone,
+    /// Time past zero with --overtime
+    pub overtime: Option<Duration>,
+    pub paused: bool,
+    /// Past the `--warn-at-percent` threshold
+    pub warning: bool,
+    /// The next milestone still to come, and the time until it
+    pub next_milestone: Option<(&'a str, Duration)>,
+    pub spinner: Option<usize>,
+    /// How the `on_start` hooks of a focus session went
+    pub hooks: Option<&'a focus::Report>,
+}
+
+/// What the timer's big time shows, switched with 't'.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Readout {
+    #[default]
+    Remaining,
+    Elapsed,
+    /// Time left over the whole timer
+    Both,
+}
+
+impl Readout {
+    pub fn next(self) -> Readout {
+        match self {
+            Readout::Remaining => Readout::Elapsed,
+            Readout::Elapsed => Readout::Both,
+            Readout::Both => Readout::Remaining,
+        }
+    }
+
+    /// The caption and time for `remaining` of a `total` timer.
+    pub fn text(self, remaining: Duration, total: Duration) -> (&'static str, String) {
+        match self {
+            Readout::Remaining => ("Time Remaining", format_duration(remaining)),
+            Readout::Elapsed => ("Time Elapsed", format_duration(total.saturating_sub(remaining))),
+            Readout::Both => ("Remaining / Total", format!("{} / {}", format_duration(remaining), format_duration(total))),
+        }
+    }
+}
+
+/// What the stopwatch screen shows.
+pub struct StopwatchView<'a> {
+    /// Elapsed time on display (held briefly after a lap with --freeze-on-lap)
+    pub shown: Duration,
+    pub paused: bool,
+    pub spinner: Option<usize>,
+    pub target_lap: Option<Duration>,
+    pub laps: &'a [Duration],
+    pub precision: Precision,
+}
+
+/// What the alarm screen shows while waiting.
+/// Whether a ringing alarm can be put off with 's'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Snooze {
+    Unavailable,
+    Available,
+    /// `--snooze-max` snoozes taken, this is the final alarm
+    UsedUp,
+}
+
+pub struct AlarmView {
+    pub alarm_time: NaiveTime,
+    pub until: chrono::Duration,
+    pub tomorrow: bool,
+    pub spinner: Option<usize>,
+}
+
+/// What the `--start-at` screen shows before the timer or alarm begins.
+pub struct StartWaitView<'a> {
+    pub start_at: NaiveTime,
+    pub until: chrono::Duration,
+    pub tomorrow: bool,
+    /// What starts then, e.g. "Timer: 20:00"
+    pub what: &'a str,
+    pub spinner: Option<usize>,
+}
+
+/// What the dashboard shows: every running clock as of `now_ms`.
+pub struct DashboardView<'a> {
+    pub clocks: &'a [ClockStatus],
+    pub now_ms: u64,
+}
+
+/// What `alarm watch` shows: pending alarms soonest first, as of `now`.
+pub struct AlarmWatchView<'a> {
+    pub alarms: &'a [Entry],
+    pub selected: usize,
+    pub now: DateTime<Local>,
+    /// The add-alarm line while it's open
+    pub input: Option<&'a str>,
+    pub message: Option<&'a str>,
+}
+
+/// What `batch` shows while a step runs.
+pub struct BatchView<'a> {
+    /// Zero-based
+    pub index: usize,
+    pub total: usize,
+    pub label: &'a str,
+    pub remaining: Duration,
+    pub next: Option<&'a str>,
+    pub spinner: Option<usize>,
+}
+
+/// What `pomodoro` shows during a phase.
+pub struct PomodoroView<'a> {
+    /// "Work", "Short Break"
+    pub phase: &'a str,
+    /// One-based
+    pub round: usize,
+    pub rounds: u32,
+    pub remaining: Duration,
+    /// What comes after this phase, e.g. "Next: Short Break (05:00)"
+    pub up_next: &'a str,
+    pub color: Color,
+    pub spinner: Option<usize>,
+}
+
+/// One timer on the `timer --parallel` screen.
+pub struct ParallelTimer<'a> {
+    pub label: &'a str,
+    pub remaining: Duration,
+    pub zone: Zone,
+    pub paused: bool,
+    pub state: ParallelState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParallelState {
+    Running,
+    Finished,
+    Dismissed,
+}
+
+/// What `timer --parallel` shows.
+pub struct ParallelView<'a> {
+    pub timers: &'a [ParallelTimer<'a>],
+    /// The timer the keys act on
+    pub selected: usize,
+    pub spinner: Option<usize>,
+}
+
+static LABEL_COLOR: OnceLock<Color> = OnceLock::new();
+
+/// Color of timer labels when `--label-color` isn't given.
+pub const DEFAULT_LABEL_COLOR: Color = Color::White;
+
+/// Sets `--label-color` for the rest of the run, only the first call counts.
+pub fn set_label_color(color: Color) {
+    let _ = LABEL_COLOR.set(color);
+}
+
+/// How a timer's label (and tag) is drawn, apart from the accent and border colors.
+fn label_style() -> Style {
+    label_style_for(LABEL_COLOR.get().copied())
+}
+
+/// `label_style` for a `--label-color`, if one was given.
+fn label_style_for(color: Option<Color>) -> Style {
+    Style::default().fg(color.unwrap_or(DEFAULT_LABEL_COLOR))
+}
+
+static MINIMAL_HELP: AtomicBool = AtomicBool::new(false);
+
+/// Hides the key help footer on every screen (`--minimal-help`).
+pub fn set_minimal_help(minimal: bool) {
+    MINIMAL_HELP.store(minimal, Ordering::Relaxed);
+}
+
+static MOUSE: AtomicBool = AtomicBool::new(false);
+
+/// Clickable buttons for the footer actions (`--mouse`). Off by default, since
+/// capturing the mouse stops the terminal from selecting text.
+pub fn set_mouse(mouse: bool) {
+    MOUSE.store(mouse, Ordering::Relaxed);
+}
+
+fn is_mouse() -> bool {
+    MOUSE.load(Ordering::Relaxed) && !is_view_only()
+}
+
+/// Where the buttons of the last frame drawn are, for clicks to be matched against.
+static BUTTONS: Mutex<Vec<(Rect, Action)>> = Mutex::new(Vec::new());
+
+/// Where a screen's content sits in the space left inside its border (`--align`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Align {
+    /// Right under the top border
+    #[default]
+    Top,
+    /// Halfway down
+    Center,
+}
+
+/// `--align` and `--margin`, shared by every full-screen view.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Placement {
+    pub align: Align,
+    /// Blank cells between the terminal edge and the border; each screen's own default if unset
+    pub margin: Option<u16>,
+}
+
+static PLACEMENT: OnceLock<Placement> = OnceLock::new();
+
+/// Sets the placement for the rest of the run, only the first call counts.
+pub fn set_placement(placement: Placement) {
+    let _ = PLACEMENT.set(placement);
+}
+
+fn placement() -> Placement {
+    PLACEMENT.get().copied().unwrap_or_default()
+}
+
+/// Top padding, content, bottom padding: how `height` rows of content are
+/// placed in a box.
+pub fn content_constraints(align: Align, height: u16) -> [Constraint; 3] {
+    match align {
+        Align::Top => [Constraint::Length(0), Constraint::Length(height), Constraint::Fill(1)],
+        Align::Center => [Constraint::Fill(1), Constraint::Length(height), Constraint::Fill(1)],
+    }
+}
+
+/// The box a bordered screen is drawn in: the whole terminal, less `--margin`.
+fn box_area(area: Rect) -> Rect {
+    let margin = placement().margin.unwrap_or(0);
+    area.inner(Margin::new(margin, margin))
+}
+
+/// Widest a line of text can be inside a bordered screen, with a little room
+/// to spare on each side.
+pub fn text_width(area: Rect) -> usize {
+    usize::from(box_area(area).width.saturating_sub(4))
+}
+
+/// Smallest terminal the screens are drawn in, enough for a bordered time.
+const MIN_WIDTH: u16 = 20;
+const MIN_HEIGHT: u16 = 6;
+
+fn is_too_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
+
+/// Below the smallest workable size, says so instead of the screen and returns true.
+fn draw_too_small(f: &mut Frame) -> bool {
+    if !is_too_small(f.area()) {
+        return false;
+    }
+    draw_needs(f, MIN_WIDTH, MIN_HEIGHT);
+    true
+}
+
+/// "terminal too small", and the size a screen needs instead.
+fn draw_needs(f: &mut Frame, width: u16, height: u16) {
+    let area = f.area();
+    let need = format!("(need {}×{})", width, height);
+    let lines: Vec<Line> = ["terminal too small", need.as_str()]
+        .iter()
+        .map(|text| Line::from(vec![Span::styled(fit_width(text, usize::from(area.width)), Style::default().fg(Color::Yellow))]))
+        .collect();
+    let [rows] = Layout::vertical([Constraint::Length(2)]).flex(Flex::Center).areas(area);
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), rows);
+}
+
+/// What of `lines` fits in `area`. The header goes first when it's too wide or
+/// everything won't fit the height, then blank lines, and whatever is left is
+/// cut to the width with an ellipsis rather than wrapped.
+fn fit_lines<'a>(mut lines: Vec<Line<'a>>, area: Rect) -> Vec<Line<'a>> {
+    let height = usize::from(area.height);
+    let width = usize::from(area.width);
+    let header = header_lines();
+    let header_width = crate::ASCII_HEADER.lines().map(|line| line.trim_end().width()).max().unwrap_or(0);
+    if lines.starts_with(&header) && (header_width > width || lines.len() > height) {
+        lines.drain(..header.len());
+        let blank = lines.iter().take_while(|line| line.width() == 0).count();
+        lines.drain(..blank);
+    }
+    if lines.len() > height {
+        lines.retain(|line| line.width() > 0);
+    }
+    lines.into_iter().map(|line| fit_line(line, width)).collect()
+}
+
+/// `line` cut to `max_width` columns, ending in "…" in the style of the span it cut.
+fn fit_line(line: Line, max_width: usize) -> Line {
+    if line.width() <= max_width {
+        return line;
+    }
+    let mut fitted = Line { spans: vec![], ..line.clone() };
+    let mut width = 0;
+    for span in line.spans {
+        let mut content = String::new();
+        for c in span.content.chars() {
+            let w = c.width().unwrap_or(0);
+            // Keep a column for the ellipsis
+            if width + w + 1 > max_width {
+                if max_width > 0 {
+                    content.push('…');
+                }
+                fitted.spans.push(Span::styled(content, span.style));
+                return fitted;
+            }
+            content.push(c);
+            width += w;
+        }
+        fitted.spans.push(Span::styled(content, span.style));
+    }
+    fitted
+}
+
+/// Draws `lines` centered across `block`, placed up or down per `--align`.
+pub fn draw_boxed(f: &mut Frame, lines: Vec<Line>, block: Block) {
+    if draw_too_small(f) {
+        return;
+    }
+    let area = box_area(f.area());
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let lines = fit_lines(lines, inner);
+    let height = u16::try_from(lines.len()).unwrap_or(u16::MAX);
+    let chunks = Layout::vertical(content_constraints(placement().align, height)).split(inner);
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), chunks[1]);
+}
+
+static VIEW_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Screens are drawn but the keyboard is never read, for when stdin isn't a
+/// terminal (`clockeroo timer 5m < /dev/null`). Key help is hidden too.
+pub fn set_view_only(view_only: bool) {
+    VIEW_ONLY.store(view_only, Ordering::Relaxed);
+}
+
+pub fn is_view_only() -> bool {
+    VIEW_ONLY.load(Ordering::Relaxed)
+}
+
+static APPEARANCE: OnceLock<Appearance> = OnceLock::new();
+static RESOLVED_APPEARANCE: OnceLock<Appearance> = OnceLock::new();
+
+/// Sets `--appearance` for the rest of the run, only the first call counts.
+/// `auto` is looked into the first time a screen opens, not before.
+pub fn set_appearance(appearance: Appearance) {
+    let _ = APPEARANCE.set(appearance);
+}
+
+fn appearance() -> Appearance {
+    *RESOLVED_APPEARANCE.get_or_init(|| APPEARANCE.get().copied().unwrap_or_default().resolve(!is_view_only()))
+}
+
+/// Crossterm output with every color shaded for the terminal's background,
+/// so the screens only ever pick colors for a dark one.
+pub struct ScreenBackend {
+    inner: CrosstermBackend<Stdout>,
+    appearance: Appearance,
+}
+
+impl Write for ScreenBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Write::flush(&mut self.inner)
+    }
+}
+
+impl Backend for ScreenBackend {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a buffer::Cell)>,
+    {
+        if self.appearance != Appearance::Light {
+            return self.inner.draw(content);
+        }
+        let shaded: Vec<(u16, u16, buffer::Cell)> = content
+            .map(|(x, y, cell)| {
+                let mut cell = cell.clone();
+                cell.fg = appearance::shade(cell.fg, self.appearance);
+                (x, y, cell)
+            })
+            .collect();
+        self.inner.draw(shaded.iter().map(|(x, y, cell)| (*x, *y, cell)))
+    }
+
+    fn append_lines(&mut self, n: u16) -> io::Result<()> {
+        self.inner.append_lines(n)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.inner.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.inner.show_cursor()
+    }
+
+    fn get_cursor_position(&mut self) -> io::Result<Position> {
+        self.inner.get_cursor_position()
+    }
+
+    fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> io::Result<()> {
+        self.inner.set_cursor_position(position)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.inner.clear()
+    }
+
+    fn clear_region(&mut self, clear_type: ClearType) -> io::Result<()> {
+        self.inner.clear_region(clear_type)
+    }
+
+    fn size(&self) -> io::Result<Size> {
+        self.inner.size()
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        self.inner.window_size()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Backend::flush(&mut self.inner)
+    }
+}
+
+/// The full-screen view from [`enter_screen`]. Dropping it puts the terminal
+/// back, so an error part way through a screen doesn't leave it in raw mode.
+pub struct Screen {
+    terminal: Terminal<ScreenBackend>,
+    left: bool,
+}
+
+impl Deref for Screen {
+    type Target = Terminal<ScreenBackend>;
+
+    fn deref(&self) -> &Terminal<ScreenBackend> {
+        &self.terminal
+    }
+}
+
+impl DerefMut for Screen {
+    fn deref_mut(&mut self) -> &mut Terminal<ScreenBackend> {
+        &mut self.terminal
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        if !self.left {
+            let _ = self.restore();
+        }
+    }
+}
+
+impl Screen {
+    fn restore(&mut self) -> Result<()> {
+        self.left = true;
+        disable_raw_mode()?;
+        execute!(self.terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+        self.terminal.show_cursor()?;
+        Ok(())
+    }
+}
+
+/// Closes the full-screen view, unlike dropping the [`Screen`] saying if that failed.
+pub fn leave_screen(mut screen: Screen) -> Result<()> {
+    screen.restore()
+}
+
+/// Switches to the full-screen view. Raw mode and mouse capture are left out
+/// in view-only mode, there's no keyboard for them to serve.
+pub fn enter_screen() -> Result<Screen> {
+    // Before raw mode, the background query switches it on and off itself
+    let appearance = appearance();
+    let mut stdout = io::stdout();
+    if is_view_only() {
+        // Printed before the alternate screen, so it's still there once the screen closes
+        eprintln!("[WARN] stdin isn't a terminal: keys are ignored and the screen closes by itself when done");
+    } else {
+        enable_raw_mode().context("Couldn't put the terminal in raw mode (is stdin a terminal?)")?;
+        if is_mouse() {
+            execute!(stdout, EnableMouseCapture).context("Couldn't set up the terminal")?;
+        }
+    }
+    BUTTONS.lock().unwrap_or_else(|err| err.into_inner()).clear();
+    execute!(stdout, EnterAlternateScreen).context("Couldn't switch to the full-screen view (is stdout a terminal?)")?;
+    let terminal = Terminal::new(ScreenBackend { inner: CrosstermBackend::new(stdout), appearance })?;
+    Ok(Screen { terminal, left: false })
+}
+
+/// The next key press within `timeout`, if any. A click on a button comes as
+/// its key. View-only screens just wait the timeout out.
+pub fn poll_key(timeout: Duration) -> Result<Option<KeyEvent>> {
+    if is_view_only() {
+        thread::sleep(timeout);
+        return Ok(None);
+    }
+    if event::poll(timeout)? {
+        match event::read()? {
+            Event::Key(key) => return Ok(Some(key)),
+            Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+                let buttons = BUTTONS.lock().unwrap_or_else(|err| err.into_inner());
+                let key = button_at(&buttons, mouse.column, mouse.row).and_then(Action::key);
+                return Ok(key.map(|code| KeyEvent::new(code, KeyModifiers::NONE)));
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// The `--mouse` buttons for `actions` in a row across the middle of `row`,
+/// with where each one is. Actions without a button are left out.
+fn layout_buttons(row: Rect, actions: &[Action]) -> Vec<(Rect, Action)> {
+    let buttons: Vec<(String, Action)> = actions
+        .iter()
+        .filter_map(|action| Some((format!("[ {} ]", action.button()?), *action)))
+        .collect();
+    let width: u16 = buttons.iter().map(|(text, _)| text.width() as u16 + 1).sum::<u16>().saturating_sub(1);
+    let mut x = row.x + row.width.saturating_sub(width) / 2;
+    let mut placed = vec![];
+    for (text, action) in buttons {
+        let area = Rect::new(x, row.y, text.width() as u16, 1).intersection(row);
+        placed.push((area, action));
+        x += text.width() as u16 + 1;
+    }
+    placed
+}
+
+/// The action of the button at `column`, `row`, if any.
+fn button_at(buttons: &[(Rect, Action)], column: u16, row: u16) -> Option<Action> {
+    buttons
+        .iter()
+        .find(|(area, _)| area.contains(Position::new(column, row)))
+        .map(|(_, action)| *action)
+}
+
+/// Draws the `--mouse` buttons on the last row of `area`, if they're on.
+pub fn draw_buttons(f: &mut Frame, area: Rect, actions: &[Action]) {
+    if !is_mouse() || area.height == 0 || is_too_small(f.area()) {
+        return;
+    }
+    let row = Rect { y: area.bottom() - 1, height: 1, ..area };
+    let buttons = layout_buttons(row, actions);
+    for (button, action) in &buttons {
+        let text = format!("[ {} ]", action.button().unwrap_or_default());
+        f.render_widget(Paragraph::new(text).style(Style::default().fg(Color::Black).bg(Color::Gray)), *button);
+    }
+    *BUTTONS.lock().unwrap_or_else(|err| err.into_inner()) = buttons;
+}
+
+/// Inside the border of a screen drawn with `draw_boxed`.
+fn boxed_inner(area: Rect) -> Rect {
+    box_area(area).inner(Margin::new(1, 1))
+}
+
+/// Something the user can do from a screen, listed in its footer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Lap,
+    Pause,
+    AdjustTarget,
+    SkipPhase,
+    SkipStep,
+    AddAlarm,
+    DeleteAlarm,
+    ConfirmAdd,
+    CloseInput,
+    Stop,
+    Quit,
+    Cancel,
+    Dismiss,
+    Exit,
+    Restart,
+    Select,
+    AdjustTime,
+    DismissTimer,
+    Readout,
+    Snooze,
+}
+
+impl Action {
+    fn hint(self) -> &'static str {
+        match self {
+            Action::Lap => "'l' for lap",
+            Action::Pause => "'p' to pause",
+            Action::AdjustTarget => "'+'/'-' to adjust target",
+            Action::SkipPhase => "'n' to skip this phase",
+            Action::SkipStep => "'n' to skip this step",
+            Action::AddAlarm => "'a' to add",
+            Action::DeleteAlarm => "'d' to delete",
+            Action::ConfirmAdd => "Enter to add",
+            Action::CloseInput => "Esc to cancel",
+            Action::Stop => "'s' to stop",
+            Action::Quit => "'q' or Esc to quit",
+            Action::Cancel => "'q' or Esc to cancel",
+            Action::Dismiss => "Enter, 'q' or Esc to dismiss",
+            Action::Exit => "Enter, 'q' or Esc to exit",
+            Action::Restart => "'r' to restart",
+            Action::Select => "Tab to select",
+            Action::AdjustTime => "'+'/'-' to adjust",
+            Action::DismissTimer => "'d' to dismiss",
+            Action::Readout => "'t' to toggle",
+            Action::Snooze => "'s' to snooze",
+        }
+    }
+
+    /// The label of its `--mouse` button, for the actions that have one.
+    fn button(self) -> Option<&'static str> {
+        match self {
+            Action::Lap => Some("Lap"),
+            Action::Pause => Some("Pause"),
+            Action::Stop => Some("Stop"),
+            Action::Quit => Some("Quit"),
+            Action::Cancel => Some("Cancel"),
+            Action::Dismiss => Some("Dismiss"),
+            Action::Exit => Some("Exit"),
+            Action::Restart => Some("Restart"),
+            Action::Snooze => Some("Snooze"),
+            _ => None,
+        }
+    }
+
+    /// The key a click on its button stands for.
+    fn key(self) -> Option<KeyCode> {
+        match self {
+            Action::Lap => Some(KeyCode::Char('l')),
+            Action::Pause => Some(KeyCode::Char('p')),
+            Action::Stop => Some(KeyCode::Char('s')),
+            Action::Quit | Action::Cancel => Some(KeyCode::Char('q')),
+            Action::Dismiss | Action::Exit => Some(KeyCode::Enter),
+            Action::Restart => Some(KeyCode::Char('r')),
+            Action::Snooze => Some(KeyCode::Char('s')),
+            _ => None,
+        }
+    }
+}
+
+/// `q`, Esc or Ctrl-C: leaves any screen.
+pub fn is_quit_event(key: &KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => true,
+        KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
+
+/// A quit key, or Enter, on screens that only wait to be acknowledged
+/// (a finished timer, a ringing alarm).
+pub fn is_dismiss_event(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Enter || is_quit_event(key)
+}
+
+/// "Press 'l' for lap, 's' to stop, ..." for the given actions, or `None` under `--minimal-help`.
+pub fn footer(actions: &[Action]) -> Option<String> {
+    if MINIMAL_HELP.load(Ordering::Relaxed) || is_view_only() {
+        return None;
+    }
+    Some(footer_text(actions))
+}
+
+fn footer_text(actions: &[Action]) -> String {
+    let hints: Vec<&str> = actions.iter().map(|action| action.hint()).collect();
+    format!("Press {}", hints.join(", "))
+}
+
+/// The footer as a gray line, if shown.
+pub fn footer_line(actions: &[Action]) -> Option<Line<'static>> {
+    footer(actions).map(|help| Line::from(vec![Span::styled(help, Style::default().fg(Color::Gray))]))
+}
+
+/// "🍵 tea": the tag, if any, in front of `text`.
+pub fn with_tag(tag: Option<&str>, text: &str) -> String {
+    match tag {
+        Some(tag) if !text.is_empty() => format!("{} {}", tag, text),
+        Some(tag) => tag.to_string(),
+        None => text.to_string(),
+    }
+}
+
+/// Cuts `text` to at most `max_width` terminal columns, ending in "…" if it
+/// had to cut. Counts display width, so emoji and CJK take two columns each.
+pub fn fit_width(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    let mut fitted = String::new();
+    let mut width = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        // Keep a column for the ellipsis
+        if width + w + 1 > max_width {
+            break;
+        }
+        fitted.push(c);
+        width += w;
+    }
+    if max_width > 0 {
+        fitted.push('…');
+    }
+    fitted
+}
+
+/// The ASCII header at the top of every screen.
+pub fn header_lines() -> Vec<Line<'static>> {
+    ASCII_HEADER
+        .lines()
+        .map(|line| Line::from(vec![Span::styled(line, Style::default().fg(Color::DarkGray))]))
+        .collect()
+}
+
+/// Block digits for `big_text`, five rows of three cells.
+const BIG_DIGITS: [[&str; 5]; 10] = [
+    ["███", "█ █", "█ █", "█ █", "███"],
+    [" █ ", "██ ", " █ ", " █ ", "███"],
+    ["███", "  █", "███", "█  ", "███"],
+    ["███", "  █", "███", "  █", "███"],
+    ["█ █", "█ █", "███", "  █", "  █"],
+    ["███", "█  ", "███", "  █", "███"],
+    ["███", "█  ", "███", "█ █", "███"],
+    ["███", "  █", "  █", "  █", "  █"],
+    ["███", "█ █", "███", "█ █", "███"],
+    ["███", "█ █", "███", "  █", "███"],
+];
+const BIG_COLON: [&str; 5] = [" ", "█", " ", "█", " "];
+
+/// `text` (digits and colons, like `format_duration` gives) in block digits five
+/// rows tall. `style_at` styles each character by its index in `text`; anything
+/// else in `text` is left out.
+pub fn big_text(text: &str, style_at: impl Fn(usize) -> Style) -> Vec<Line<'static>> {
+    (0..5)
+        .map(|row| {
+            let spans: Vec<Span> = text
+                .chars()
+                .enumerate()
+                .filter_map(|(i, c)| {
+                    let cells = match c {
+                        ':' => BIG_COLON[row],
+                        _ => BIG_DIGITS[c.to_digit(10)? as usize][row],
+                    };
+                    Some(Span::styled(cells, style_at(i)))
+                })
+                .flat_map(|span| [Span::raw(" "), span])
+                .skip(1)
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Title, message and help boxes used by the finished/ringing screens.
+fn banner_chunks(f: &Frame) -> std::rc::Rc<[Rect]> {
+    let placement = placement();
+    let margin = placement.margin.unwrap_or(2);
+    let area = f.area().inner(Margin::new(margin, margin));
+    let banner = Layout::vertical(content_constraints(placement.align, 9)).split(area)[1];
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+        ])
+        .split(banner)
+}
+
+/// The color of the time left in `zone`.
+fn zone_color(zone: Zone) -> Color {
+    match zone {
+        Zone::Critical => Color::Red,
+        Zone::Warn => Color::Yellow,
+        Zone::Normal => Color::Green,
+    }
+}
+
+/// The running timer's time color (by zone) and accent color.
+fn timer_colors(view: &TimerView) -> (Color, Color) {
+    let time_color = zone_color(view.zone);
+    // Past the --warn-at-percent threshold the whole screen changes accent
+    let accent = if view.warning { Color::Magenta } else { Color::Cyan };
+    (time_color, accent)
+}
+
+pub fn draw_timer_running(f: &mut Frame, view: &TimerView) {
+    let area = f.area();
+    let (time_color, accent) = timer_colors(view);
+
+    let mut lines = header_lines();
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    let (heading, (caption, time)) = match view.overtime {
+        Some(over) => ("Overtime", ("Past Zero", format_overtime(over))),
+        None if view.paused => ("Timer Paused", view.readout.text(view.remaining, view.total)),
+        None => ("Timer Running", view.readout.text(view.remaining, view.total)),
+    };
+    lines.push(Line::from(vec![Span::styled(running_heading(heading, view.spinner), Style::default().fg(accent).add_modifier(Modifier::BOLD))]));
+    if view.label.is_some() || view.tag.is_some() {
+        let label = with_tag(view.tag, view.label.unwrap_or_default());
+        // Leave room for the borders
+        let label = fit_width(&label, text_width(area));
+        lines.push(Line::from(vec![Span::styled(label, label_style())]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(caption, Style::default().fg(Color::Gray))]));
+    lines.push(Line::from(vec![Span::styled(time, Style::default().fg(time_color).add_modifier(Modifier::BOLD))]));
+    lines.extend(elapsed_line(view));
+    if let Some((name, until)) = view.next_milestone {
+        let next = fit_width(&format!("Next: {} in {}", name, format_duration(until)), text_width(area));
+        lines.push(Line::from(vec![Span::styled(next, Style::default().fg(Color::DarkGray))]));
+    }
+    if let Some(hooks) = view.hooks {
+        let color = if hooks.is_ok() { Color::DarkGray } else { Color::Red };
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![Span::styled(hooks.summary("on_start"), Style::default().fg(color))]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    let actions = timer_actions(view);
+    lines.extend(footer_line(actions));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(accent));
+    draw_boxed(f, lines, block);
+    draw_buttons(f, boxed_inner(area), actions);
+}
+
+/// "12:34 elapsed of 25:00", until the timer runs into overtime.
+fn elapsed_line(view: &TimerView) -> Option<Line<'static>> {
+    if view.overtime.is_some() {
+        return None;
+    }
+    let elapsed = view.total.saturating_sub(view.remaining);
+    let text = format!("{} elapsed of {}", format_duration(elapsed), format_duration(view.total));
+    Some(Line::from(vec![Span::styled(text, Style::default().fg(Color::DarkGray))]))
+}
+
+fn timer_actions(view: &TimerView) -> &'static [Action] {
+    if view.overtime.is_some() {
+        &[Action::Exit]
+    } else {
+        // Least needed last, where a narrow screen cuts it off first
+        &[Action::Pause, Action::Cancel, Action::Readout]
+    }
+}
+
+/// Tallest the `--clock-face` dial gets, in rows.
+const FACE_MAX_HEIGHT: u16 = 12;
+
+/// The dots of a pie covering `left` (0 to 1) of a unit circle, from 12
+/// o'clock clockwise, sampled `columns` by `rows` over the square around it.
+fn pie_points(left: f64, columns: u16, rows: u16) -> Vec<(f64, f64)> {
+    let sweep = left.clamp(0.0, 1.0) * std::f64::consts::TAU;
+    let (columns, rows) = (f64::from(columns), f64::from(rows));
+    let mut points = vec![];
+    for row in 0..rows as u32 {
+        let y = 1.0 - (f64::from(row) + 0.5) * 2.0 / rows;
+        for column in 0..columns as u32 {
+            let x = (f64::from(column) + 0.5) * 2.0 / columns - 1.0;
+            // Clockwise from straight up
+            let angle = x.atan2(y).rem_euclid(std::f64::consts::TAU);
+            if x * x + y * y <= 1.0 && angle < sweep {
+                points.push((x, y));
+            }
+        }
+    }
+    points
+}
+
+/// The running timer as a dial (`--clock-face`): a pie of the time `left`
+/// (as a share of the whole) that shrinks as it runs, the digits below it.
+pub fn draw_timer_face(f: &mut Frame, view: &TimerView, left: f64) {
+    if draw_too_small(f) {
+        return;
+    }
+    let area = f.area();
+    let (time_color, accent) = timer_colors(view);
+
+    let heading = if view.paused { "Timer Paused" } else { "Timer Running" };
+    let mut top = vec![Line::from(vec![Span::styled(running_heading(heading, view.spinner), Style::default().fg(accent).add_modifier(Modifier::BOLD))])];
+    if view.label.is_some() || view.tag.is_some() {
+        let label = fit_width(&with_tag(view.tag, view.label.unwrap_or_default()), text_width(area));
+        top.push(Line::from(vec![Span::styled(label, label_style())]));
+    }
+    top.push(Line::from(""));
+    let time = match view.overtime {
+        Some(over) => format_overtime(over),
+        None => view.readout.text(view.remaining, view.total).1,
+    };
+    let mut bottom = vec![Line::from(""), Line::from(vec![Span::styled(time, Style::default().fg(time_color).add_modifier(Modifier::BOLD))])];
+    bottom.extend(elapsed_line(view));
+    if let Some((name, until)) = view.next_milestone {
+        let next = fit_width(&format!("Next: {} in {}", name, format_duration(until)), text_width(area));
+        bottom.push(Line::from(vec![Span::styled(next, Style::default().fg(Color::DarkGray))]));
+    }
+    bottom.push(Line::from(""));
+    let actions = timer_actions(view);
+    bottom.extend(footer_line(actions));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(accent));
+    let inner = block.inner(box_area(area));
+    f.render_widget(block, box_area(area));
+
+    let text_height = (top.len() + bottom.len()) as u16;
+    // A cell is about twice as tall as wide, so twice the rows in columns draws a round dial
+    let face_height = inner.height.saturating_sub(text_height).min(FACE_MAX_HEIGHT).min(inner.width / 2);
+    let [top_area, face_area, bottom_area] = Layout::vertical([
+        Constraint::Length(top.len() as u16),
+        Constraint::Length(face_height),
+        Constraint::Length(bottom.len() as u16),
+    ])
+    .flex(Flex::Center)
+    .areas(inner);
+    let [face_area] = Layout::horizontal([Constraint::Length(face_height * 2)])
+        .flex(Flex::Center)
+        .areas(face_area);
+
+    f.render_widget(Paragraph::new(top).alignment(Alignment::Center), top_area);
+    // Braille packs 2x4 dots in a cell
+    let coords = pie_points(left, face_area.width * 2, face_area.height * 4);
+    let face = Canvas::default()
+        .marker(Marker::Braille)
+        .x_bounds([-1.0, 1.0])
+        .y_bounds([-1.0, 1.0])
+        .paint(|ctx| {
+            ctx.draw(&Circle { x: 0.0, y: 0.0, radius: 1.0, color: Color::DarkGray });
+            ctx.draw(&Points { coords: &coords, color: time_color });
+        });
+    f.render_widget(face, face_area);
+    f.render_widget(Paragraph::new(bottom).alignment(Alignment::Center), bottom_area);
+    draw_buttons(f, inner, actions);
+}
+
+pub fn draw_batch(f: &mut Frame, view: &BatchView) {
+    let area = f.area();
+    let accent = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+
+    let mut lines = header_lines();
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    let heading = format!("Step {}/{}", view.index + 1, view.total);
+    lines.push(Line::from(vec![Span::styled(running_heading(&heading, view.spinner), accent)]));
+    // Leave room for the borders
+    let label = fit_width(view.label, text_width(area));
+    lines.push(Line::from(vec![Span::styled(label, label_style())]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled("Time Remaining", Style::default().fg(Color::Gray))]));
+    lines.push(Line::from(vec![Span::styled(format_duration(view.remaining), Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))]));
+    lines.push(Line::from(""));
+    let up_next = match view.next {
+        Some(next) => fit_width(&format!("Next: {}", next), text_width(area)),
+        None => "Last step".to_string(),
+    };
+    lines.push(Line::from(vec![Span::styled(up_next, Style::default().fg(Color::Gray))]));
+    lines.push(Line::from(""));
+    lines.extend(footer_line(&[Action::SkipStep, Action::Quit]));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    draw_boxed(f, lines, block);
+}
+
+/// Shortest a `--parallel` panel gets: its border, the time and the state.
+const PANEL_MIN_HEIGHT: u16 = 4;
+/// Narrowest a `--parallel` panel gets before another column is not worth it.
+const PANEL_MIN_WIDTH: u16 = 20;
+
+/// Columns and rows for `count` panels in `area`: one column while the panels
+/// fit stacked, more as they stop fitting, as long as they stay readable.
+/// `Err` has the most readable grid there is when even that is too short.
+fn parallel_grid(count: usize, area: Rect) -> Result<(u16, u16), (u16, u16)> {
+    let count = count.max(1) as u16;
+    let mut columns = 1;
+    loop {
+        let rows = count.div_ceil(columns);
+        if area.height / rows >= PANEL_MIN_HEIGHT {
+            return Ok((columns, rows));
+        }
+        if columns == count || area.width / (columns + 1) < PANEL_MIN_WIDTH {
+            return Err((columns, rows));
+        }
+        columns += 1;
+    }
+}
+
+fn draw_parallel_panel(f: &mut Frame, area: Rect, timer: &ParallelTimer, selected: bool, spinner: Option<usize>) {
+    let (time_color, state) = match timer.state {
+        ParallelState::Finished => (Color::Red, "Finished".to_string()),
+        ParallelState::Dismissed => (Color::DarkGray, "Dismissed".to_string()),
+        ParallelState::Running if timer.paused => (zone_color(timer.zone), "Paused".to_string()),
+        ParallelState::Running => (zone_color(timer.zone), running_heading("Running", spinner)),
+    };
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(Span::styled(format!(" {} ", fit_width(timer.label, usize::from(area.width.saturating_sub(4)))), label_style()))
+        .title_alignment(Alignment::Center);
+    block = if selected {
+        block.border_type(BorderType::Thick).border_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+    } else {
+        block.border_style(Style::default().fg(Color::DarkGray))
+    };
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let time = format_duration(timer.remaining);
+    let time_style = Style::default().fg(time_color).add_modifier(Modifier::BOLD);
+    // Block digits where there's room for them, four columns a character
+    let mut lines = if inner.height >= 7 && usize::from(inner.width) >= time.len() * 4 {
+        let mut lines = big_text(&time, |_| time_style);
+        lines.push(Line::from(""));
+        lines
+    } else {
+        vec![Line::from(vec![Span::styled(time, time_style)])]
+    };
+    lines.push(Line::from(vec![Span::styled(state, Style::default().fg(Color::Gray))]));
+    let [text] = Layout::vertical([Constraint::Length(lines.len() as u16)]).flex(Flex::Center).areas(inner);
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), text);
+}
+
+/// `timer --parallel`: a panel per timer, stacked or in a grid as they fit,
+/// the selected one outlined. Once none is running only dismissing is left.
+pub fn draw_parallel(f: &mut Frame, view: &ParallelView) {
+    if draw_too_small(f) {
+        return;
+    }
+    let area = box_area(f.area());
+    let actions: &[Action] = if view.timers.iter().all(|timer| timer.state != ParallelState::Running) {
+        &[Action::Dismiss]
+    } else {
+        &[Action::Select, Action::Pause, Action::AdjustTime, Action::DismissTimer, Action::Quit]
+    };
+    let footer = footer_line(actions);
+    // One line where it fits, wrapped onto two where it doesn't
+    let footer_lines = footer.as_ref().map_or(0, |footer| if footer.width() <= usize::from(area.width) { 1 } else { 2 });
+    let footer_height = footer_lines + u16::from(is_mouse());
+    let [panels, footer_area] = Layout::vertical([Constraint::Fill(1), Constraint::Length(footer_height)]).areas(area);
+
+    let (columns, rows) = match parallel_grid(view.timers.len(), panels) {
+        Ok(grid) => grid,
+        // Panels cut below their border and time would hide timers, so ask for the room
+        Err((_, rows)) => {
+            let height = f.area().height - panels.height + rows * PANEL_MIN_HEIGHT;
+            draw_needs(f, f.area().width, height);
+            return;
+        }
+    };
+    let row_areas = Layout::vertical(vec![Constraint::Fill(1); usize::from(rows)]).split(panels);
+    for (i, timer) in view.timers.iter().enumerate() {
+        let (row, column) = (i / usize::from(columns), i % usize::from(columns));
+        let cells = Layout::horizontal(vec![Constraint::Fill(1); usize::from(columns)]).split(row_areas[row]);
+        draw_parallel_panel(f, cells[column], timer, i == view.selected, view.spinner);
+    }
+
+    if let Some(footer) = footer {
+        f.render_widget(Paragraph::new(footer).alignment(Alignment::Center).wrap(Wrap { trim: true }), footer_area);
+    }
+    draw_buttons(f, footer_area, actions);
+}
+
+pub fn draw_pomodoro(f: &mut Frame, view: &PomodoroView) {
+    let accent = Style::default().fg(view.color).add_modifier(Modifier::BOLD);
+
+    let mut lines = header_lines();
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(running_heading(view.phase, view.spinner), accent)]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled("Time Remaining", Style::default().fg(Color::Gray))]));
+    lines.push(Line::from(vec![Span::styled(format_duration(view.remaining), accent)]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(view.up_next.to_string(), Style::default().fg(Color::Gray))]));
+    lines.push(Line::from(""));
+    lines.extend(footer_line(&[Action::SkipPhase, Action::Quit]));
+
+    let title = format!(" {} - round {}/{} ", view.phase, view.round, view.rounds);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(view.color))
+        .title(Span::styled(title, accent))
+        .title_alignment(Alignment::Center);
+    draw_boxed(f, lines, block);
+}
+
+/// `queue run` with nothing left to run, waiting for a `queue add`.
+pub fn draw_queue_waiting(f: &mut Frame, done: usize, spinner: Option<usize>) {
+    let accent = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+
+    let mut lines = header_lines();
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(running_heading("Queue Empty", spinner), accent)]));
+    lines.push(Line::from(""));
+    let progress = match done {
+        0 => "Nothing queued yet".to_string(),
+        1 => "1 timer run so far".to_string(),
+        done => format!("{} timers run so far", done),
+    };
+    lines.push(Line::from(vec![Span::styled(progress, Style::default().fg(Color::White))]));
+    lines.push(Line::from(vec![Span::styled("Add one with: clockeroo queue add 5m \"step\"", Style::default().fg(Color::Gray))]));
+    lines.push(Line::from(""));
+    lines.extend(footer_line(&[Action::Quit]));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    draw_boxed(f, lines, block);
+}
+
+/// "+02:13" for time counted past zero.
+pub fn format_overtime(over: Duration) -> String {
+    format!("+{}", format_duration(over))
+}
+
+/// Narrowest the bar in [`progress_line`] gets, however little room there is.
+const MIN_BAR_WIDTH: usize = 10;
+
+/// `[█████░░░░░]`, `width` cells between the brackets with `done` (0 to 1) of them filled.
+pub fn text_bar(done: f64, width: usize) -> String {
+    let filled = ((done.clamp(0.0, 1.0) * width as f64).round() as usize).min(width);
+    format!("[{}{}]", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// `[█████░░░░░] 12:34 remaining (52%)`, the bar taking whatever of `width`
+/// columns the text leaves, for output that has no screen to draw on.
+pub fn progress_line(remaining: Duration, total: Duration, width: u16) -> String {
+    let done = if total.is_zero() { 1.0 } else { 1.0 - remaining.as_secs_f64() / total.as_secs_f64() };
+    let text = format!(" {} remaining ({}%)", format_duration(remaining), (done * 100.0).floor() as u32);
+    let bar_width = (width as usize).saturating_sub(text.width() + 2).max(MIN_BAR_WIDTH);
+    format!("{}{}", text_bar(done, bar_width), text)
+}
+
+pub fn draw_timer_finished(f: &mut Frame) {
+    if draw_too_small(f) {
+        return;
+    }
+    let chunks = banner_chunks(f);
+
+    let title = Paragraph::new("TIMER FINISHED!")
+        .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(title, chunks[0]);
+
+    let message = Paragraph::new("Your timer has completed!")
+        .style(Style::default().fg(Color::Yellow))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(message, chunks[1]);
+
+    if let Some(help) = footer(&[Action::Restart, Action::Exit]) {
+        let help = Paragraph::new(help)
+            .style(Style::default().fg(Color::Gray))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        f.render_widget(help, chunks[2]);
+    }
+    draw_buttons(f, chunks[2], &[Action::Restart, Action::Exit]);
+}
+
+pub fn draw_stopwatch(f: &mut Frame, view: &StopwatchView) {
+    let mut lines = header_lines();
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    let heading = if view.paused {
+        "Stopwatch Paused".to_string()
+    } else {
+        running_heading("Stopwatch Running", view.spinner)
+    };
+    let time_color = if view.paused { Color::Yellow } else { Color::Green };
+    lines.push(Line::from(vec![Span::styled(heading, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled("Elapsed Time", Style::default().fg(Color::Gray))]));
+    lines.push(Line::from(vec![Span::styled(format_stopwatch_time_with(view.shown, view.precision), Style::default().fg(time_color).add_modifier(Modifier::BOLD))]));
+
+    if let Some(target) = view.target_lap {
+        let (under, over, _) = summarize_laps(view.laps, target);
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled(format!("Target {}  ", format_stopwatch_time_with(target, view.precision)), Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{} under", under), Style::default().fg(Color::Green)),
+            Span::styled(" / ", Style::default().fg(Color::Gray)),
+            Span::styled(format!("{} over", over), Style::default().fg(Color::Red)),
+        ]));
+    }
+
+    if !view.laps.is_empty() {
+        lines.push(Line::from(""));
+        // Most recent laps first
+        for (i, lap) in view.laps.iter().enumerate().rev().take(5) {
+            let mut spans = vec![Span::styled(
+                format!("Lap {:>2}  {}", i + 1, format_stopwatch_time_with(*lap, view.precision)),
+                Style::default().fg(Color::White),
+            )];
+            if let Some(target) = view.target_lap {
+                let delta = lap_delta_ms(*lap, target);
+                let color = if delta <= 0 { Color::Green } else { Color::Red };
+                spans[0].style = Style::default().fg(color);
+                spans.push(Span::styled(format!("  {}", format_delta(delta)), Style::default().fg(color)));
+            }
+            lines.push(Line::from(spans));
+        }
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    let actions: &[Action] = if view.target_lap.is_some() {
+        &[Action::Lap, Action::Pause, Action::AdjustTarget, Action::Stop, Action::Quit]
+    } else {
+        &[Action::Lap, Action::Pause, Action::Stop, Action::Quit]
+    };
+    lines.extend(footer_line(actions));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    draw_boxed(f, lines, block);
+    draw_buttons(f, boxed_inner(f.area()), actions);
+}
+
+/// "01:02:03 remaining", "02:03 remaining" under an hour, or "2d 03:15:42
+/// remaining" a day or more out.
+fn format_until(until: chrono::Duration) -> String {
+    format!("{} remaining", format_long_duration(until.to_std().unwrap_or_default()))
+}
+
+pub fn draw_start_waiting(f: &mut Frame, view: &StartWaitView) {
+    let mut lines = header_lines();
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(running_heading("Waiting to Start", view.spinner), Style::default().fg(Color::Gray).add_modifier(Modifier::BOLD))]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(format!("Starts at {}", format_clock(&view.start_at)), Style::default().fg(Color::Yellow))]));
+    if view.tomorrow {
+        lines.push(Line::from(vec![Span::styled(" Tomorrow ", Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD))]));
+    }
+    lines.push(Line::from(vec![Span::styled(view.what.to_string(), Style::default().fg(Color::DarkGray))]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(format_until(view.until), Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))]));
+    lines.push(Line::from(""));
+    lines.extend(footer_line(&[Action::Cancel]));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
+    draw_boxed(f, lines, block);
+}
+
+/// The `--start-in` warm-up before a timer, `left` still to wait.
+pub fn draw_start_in(f: &mut Frame, left: Duration, what: &str) {
+    let dim = Style::default().fg(Color::DarkGray).add_modifier(Modifier::DIM);
+
+    let mut lines = header_lines();
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled("Get Ready", Style::default().fg(Color::Gray))]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(format!("Starting in {}", format_duration(left)), dim.add_modifier(Modifier::BOLD))]));
+    lines.push(Line::from(vec![Span::styled(what.to_string(), dim)]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    lines.extend(footer_line(&[Action::Cancel]));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
+    draw_boxed(f, lines, block);
+}
+
+/// The `--count-in` beeps before a timer, on beat `left`.
+pub fn draw_count_in(f: &mut Frame, left: u32) {
+    let mut lines = header_lines();
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled("Count-in", Style::default().fg(Color::Gray))]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(left.to_string(), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    lines.extend(footer_line(&[Action::Cancel]));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    draw_boxed(f, lines, block);
+}
+
+pub fn draw_alarm_waiting(f: &mut Frame, view: &AlarmView) {
+    let alarm_str = format!("Alarm will ring at {}", format_clock(&view.alarm_time));
+    let time_remaining = format_until(view.until);
+
+    let mut lines = header_lines();
+    lines.push(Line::from(""));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(running_heading("Alarm Set", view.spinner), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(alarm_str, Style::default().fg(Color::Yellow))]));
+    if view.tomorrow {
+        lines.push(Line::from(vec![Span::styled(" Tomorrow ", Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD))]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled("Time Until Alarm", Style::default().fg(Color::Gray))]));
+    lines.push(Line::from(vec![Span::styled(time_remaining, Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))]));
+    lines.push(Line::from(""));
+    lines.extend(footer_line(&[Action::Cancel]));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    draw_boxed(f, lines, block);
+}
+
+pub fn draw_alarm_ringing(f: &mut Frame, alarm_time: NaiveTime, auto_dismiss_in: Option<Duration>, snooze: Snooze) {
+    if draw_too_small(f) {
+        return;
+    }
+    let chunks = banner_chunks(f);
+
+    let title = Paragraph::new("ALARM!")
+        .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(title, chunks[0]);
+
+    let time_str = format!("It's {}!", format_clock(&alarm_time));
+    let message = Paragraph::new(time_str)
+        .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(message, chunks[1]);
+
+    let actions: &[Action] = match snooze {
+        Snooze::Available => &[Action::Snooze, Action::Dismiss],
+        Snooze::Unavailable | Snooze::UsedUp => &[Action::Dismiss],
+    };
+    let mut help: Vec<Line> = footer(actions).map(Line::from).into_iter().collect();
+    if snooze == Snooze::UsedUp {
+        help.push(Line::from("Final alarm, no more snoozes"));
+    }
+    if let Some(left) = auto_dismiss_in {
+        help.push(Line::from(format!("Stops ringing automatically in {}", format_duration(left))));
+    }
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Center);
+    f.render_widget(help, chunks[2]);
+}
+
+pub fn draw_dashboard(f: &mut Frame, view: &DashboardView) {
+    if draw_too_small(f) {
+        return;
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(f.area());
+
+    let header = Row::new(["Kind", "Label", "Elapsed", "Remaining", "State"])
+        .style(Style::default().fg(Color::Gray).add_modifier(Modifier::BOLD));
+    let rows: Vec<Row> = view
+        .clocks
+        .iter()
+        .map(|clock| {
+            let remaining = clock.remaining(view.now_ms).map(format_duration).unwrap_or_else(|| "-".to_string());
+            let (state, color) = if clock.paused { ("paused", Color::Yellow) } else { ("running", Color::Green) };
+            Row::new([
+                Cell::from(clock.kind.name()),
+                Cell::from(with_tag(clock.tag.as_deref(), clock.label.as_deref().unwrap_or_default())),
+                Cell::from(format_duration(clock.elapsed(view.now_ms))),
+                Cell::from(remaining).style(Style::default().fg(Color::Cyan)),
+                Cell::from(state).style(Style::default().fg(color)),
+            ])
+        })
+        .collect();
+
+    let title = if view.clocks.is_empty() { " No clocks running " } else { " Running clocks " };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(9),
+            Constraint::Fill(1),
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Length(7),
+        ],
+    )
+    .header(header)
+    .block(Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(Span::styled(title, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)))
+        .title_alignment(Alignment::Center)
+    );
+
+    f.render_widget(table, chunks[0]);
+    if let Some(footer) = footer_line(&[Action::Quit]) {
+        f.render_widget(Paragraph::new(footer).alignment(Alignment::Center), chunks[1]);
+    }
+}
+
+pub fn draw_alarm_watch(f: &mut Frame, view: &AlarmWatchView) {
+    if draw_too_small(f) {
+        return;
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(0), Constraint::Length(1), Constraint::Length(2)])
+        .split(f.area());
+
+    let header = Row::new(["Label", "Next", "Repeat", "In"])
+        .style(Style::default().fg(Color::Gray).add_modifier(Modifier::BOLD));
+    let rows: Vec<Row> = view
+        .alarms
+        .iter()
+        .enumerate()
+        .map(|(i, alarm)| {
+            let until = (alarm.due - view.now).to_std().unwrap_or_default();
+            let next = format!("{} {}", alarm.due.format("%a %b %-d"), format_clock(&alarm.due));
+            // The soonest alarm stands out, the selected one is inverted
+            let mut style = if i == 0 {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            if i == view.selected {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            Row::new([
+                Cell::from(alarm.display_name()),
+                Cell::from(next),
+                Cell::from(alarm.repeat_spec()),
+                Cell::from(format_duration(until)),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let title = if view.alarms.is_empty() { " No alarms set " } else { " Alarms " };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(1),
+            Constraint::Length(17),
+            Constraint::Length(9),
+            Constraint::Length(8),
+        ],
+    )
+    .header(header)
+    .block(Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(Span::styled(title, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)))
+        .title_alignment(Alignment::Center)
+    );
+    f.render_widget(table, chunks[0]);
+
+    let status = match (view.input, view.message) {
+        (_, Some(message)) => Some(Line::from(vec![Span::styled(message.to_string(), Style::default().fg(Color::Red))])),
+        (Some(input), None) => Some(Line::from(vec![Span::styled(
+            format!("New alarm at: {}_", input),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        )])),
+        (None, None) => None,
+    };
+    if let Some(status) = status {
+        f.render_widget(Paragraph::new(status).alignment(Alignment::Center), chunks[1]);
+    }
+
+    let actions: &[Action] = if view.input.is_some() {
+        &[Action::ConfirmAdd, Action::CloseInput]
+    } else {
+        &[Action::AddAlarm, Action::DeleteAlarm, Action::Quit]
+    };
+    if let Some(footer) = footer_line(actions) {
+        // Wraps onto a second line in narrow terminals
+        f.render_widget(Paragraph::new(footer).alignment(Alignment::Center).wrap(Wrap { trim: true }), chunks[2]);
+    }
+}
+
+/// Draws one frame off-screen and returns it as plain text, trailing spaces trimmed.
+pub fn render_to_string(width: u16, height: u16, draw: impl FnOnce(&mut Frame)) -> Result<String> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    terminal.draw(draw)?;
+
+    let buffer = terminal.backend().buffer();
+    let mut out = String::new();
+    for y in 0..height {
+        let row: String = (0..width).map(|x| buffer[(x, y)].symbol()).collect();
+        out.push_str(row.trim_end());
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// DECSCNM: switch the whole terminal to reverse video and back.
+const REVERSE_VIDEO_ON: &[u8] = b"\x1b[?5h";
+const REVERSE_VIDEO_OFF: &[u8] = b"\x1b[?5l";
+const VISUAL_BELL_FLASHES: usize = 3;
+const VISUAL_BELL_PAUSE: Duration = Duration::from_millis(120);
+
+/// Flashes the terminal a few times (`--visual-bell`). Terminals without
+/// reverse-video support just ignore the sequence; nothing is written when
+/// stdout isn't a terminal. Sleeps on the runtime, so the HTTP server and
+/// anything else on it keep going between flashes.
+pub async fn visual_bell() {
+    let mut stdout = io::stdout();
+    if stdout.is_terminal() {
+        // Best effort, a failed write shouldn't get in the way of the alarm
+        let _ = write_visual_bell(&mut stdout, VISUAL_BELL_FLASHES, VISUAL_BELL_PAUSE).await;
+    }
+}
+
+async fn write_visual_bell(out: &mut impl Write, flashes: usize, pause: Duration) -> io::Result<()> {
+    let result = async {
+        for _ in 0..flashes {
+            out.write_all(REVERSE_VIDEO_ON)?;
+            out.flush()?;
+            tokio::time::sleep(pause).await;
+            out.write_all(REVERSE_VIDEO_OFF)?;
+            out.flush()?;
+            tokio::time::sleep(pause).await;
+        }
+        Ok::<_, io::Error>(())
+    }
+    .await;
+    // Never leave the terminal inverted, even if a flash failed halfway
+    out.write_all(REVERSE_VIDEO_OFF)?;
+    out.flush()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    /// Compares against `src/snapshots/<name>.txt`. Run with `UPDATE_SNAPSHOTS=1` to rewrite them.
+    fn assert_snapshot(name: &str, rendered: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/snapshots").join(format!("{}.txt", name));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, rendered).unwrap();
+            return;
+        }
+        let expected = fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("missing snapshot {}, run with UPDATE_SNAPSHOTS=1", path.display()));
+        assert_eq!(rendered, expected, "snapshot {} changed", name);
+    }
+
+    const SIZES: [(u16, u16); 2] = [(80, 24), (50, 30)];
+
+    fn snapshot(name: &str, draw: impl Fn(&mut Frame)) {
+        for (width, height) in SIZES {
+            let rendered = render_to_string(width, height, &draw).unwrap();
+            assert_snapshot(&format!("{}_{}x{}", name, width, height), &rendered);
+        }
+    }
+
+    #[test]
+    fn test_narrow_and_tiny_terminals() {
+        let view = TimerView { label: Some("a rather long label for tea"), tag: None, remaining: Duration::from_secs(272), total: Duration::from_secs(300), readout: Readout::Remaining, zone: Zone::Normal, overtime: None, paused: false, warning: false, next_milestone: None, spinner: None, hooks: None };
+        // No room for the header across, then none for it down, then just the time, then nothing
+        for (width, height) in [(44, 24), (80, 12), (20, 6), (19, 6), (20, 5)] {
+            let rendered = render_to_string(width, height, |f| draw_timer_running(f, &view)).unwrap();
+            assert_snapshot(&format!("timer_narrow_{}x{}", width, height), &rendered);
+        }
+    }
+
+    #[test]
+    fn test_text_bar() {
+        assert_eq!(text_bar(0.5, 10), "[█████░░░░░]");
+        assert_eq!(text_bar(0.0, 4), "[░░░░]");
+        assert_eq!(text_bar(1.5, 4), "[████]");
+        let line = progress_line(Duration::from_secs(12 * 60 + 34), Duration::from_secs(26 * 60), 60);
+        assert!(line.ends_with("] 12:34 remaining (51%)"), "{}", line);
+        assert_eq!(line.width(), 60);
+        // Too narrow for the text, the bar keeps its minimum
+        assert!(progress_line(Duration::from_secs(30), Duration::from_secs(60), 20).starts_with("[█████░░░░░]"));
+    }
+
+    #[test]
+    fn test_label_color() {
+        assert_eq!(label_style_for(Some(Color::Magenta)).fg, Some(Color::Magenta));
+        assert_eq!(label_style_for(None).fg, Some(DEFAULT_LABEL_COLOR));
+
+        let view = TimerView { label: Some("tea"), tag: None, remaining: Duration::from_secs(272), total: Duration::from_secs(300), readout: Readout::Remaining, zone: Zone::Normal, overtime: None, paused: false, warning: false, next_milestone: None, spinner: None, hooks: None };
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|f| draw_timer_running(f, &view)).unwrap();
+        let buffer = terminal.backend().buffer();
+        // The first cell of the first line inside the border that reads `text`
+        let find = |text: &str| {
+            (0..24u16).find_map(|y| {
+                let row: String = (1..79u16).map(|x| buffer[(x, y)].symbol()).collect();
+                (row.trim() == text).then(|| (1 + row.find(text).unwrap() as u16, y))
+            })
+        };
+        assert_eq!(Some(buffer[find("tea").unwrap()].fg), label_style().fg);
+        // Just the label, the heading keeps the accent color
+        assert_ne!(Some(buffer[find("Timer Running").unwrap()].fg), label_style().fg);
+    }
+
+    #[test]
+    fn test_readout_text() {
+        let (remaining, total) = (Duration::from_secs(272), Duration::from_secs(600));
+        assert_eq!(Readout::Remaining.text(remaining, total), ("Time Remaining", "04:32".to_string()));
+        assert_eq!(Readout::Elapsed.text(remaining, total), ("Time Elapsed", "05:28".to_string()));
+        assert_eq!(Readout::Both.text(remaining, total), ("Remaining / Total", "04:32 / 10:00".to_string()));
+        assert_eq!(Readout::Both.next(), Readout::Remaining);
+        assert_eq!(Readout::default().next(), Readout::Elapsed);
+    }
+
+    #[test]
+    fn test_footer_lists_enabled_actions() {
+        assert_eq!(footer_text(&[Action::Cancel]), "Press 'q' or Esc to cancel");
+        assert_eq!(
+            footer_text(&[Action::Lap, Action::Pause, Action::Stop, Action::Quit]),
+            "Press 'l' for lap, 'p' to pause, 's' to stop, 'q' or Esc to quit"
+        );
+        assert_eq!(footer_text(&[Action::Dismiss]), "Press Enter, 'q' or Esc to dismiss");
+        let with_target = footer_text(&[Action::Lap, Action::AdjustTarget, Action::Quit]);
+        assert!(with_target.contains("'+'/'-' to adjust target"));
+    }
+
+    #[test]
+    fn test_quit_and_dismiss_keys() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        for code in [KeyCode::Char('q'), KeyCode::Esc] {
+            assert!(is_quit_event(&key(code)));
+            assert!(is_dismiss_event(&key(code)));
+        }
+        assert!(is_quit_event(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+
+        // Enter only acknowledges, it doesn't quit a running clock
+        assert!(!is_quit_event(&key(KeyCode::Enter)));
+        assert!(is_dismiss_event(&key(KeyCode::Enter)));
+
+        for code in [KeyCode::Char('c'), KeyCode::Char('p'), KeyCode::Char(' '), KeyCode::Tab] {
+            assert!(!is_quit_event(&key(code)));
+            assert!(!is_dismiss_event(&key(code)));
+        }
+    }
+
+    #[test]
+    fn test_content_constraints() {
+        let area = Rect::new(0, 0, 80, 24);
+        let content = |align, height| Layout::vertical(content_constraints(align, height)).split(area)[1];
+        assert_eq!(content(Align::Top, 10), Rect::new(0, 0, 80, 10));
+        assert_eq!(content(Align::Center, 10), Rect::new(0, 7, 80, 10));
+        // Content taller than the box starts at the top either way, the rest is cut off
+        assert_eq!(content(Align::Center, 30), Rect::new(0, 0, 80, 24));
+    }
+
+    #[test]
+    fn test_fit_width_counts_columns() {
+        assert_eq!(fit_width("tea", 10), "tea");
+        // The emoji is two columns wide
+        assert_eq!(with_tag(Some("🍵"), "tea").width(), 6);
+        assert_eq!(fit_width("🍵 green tea", 8), "🍵 gree…");
+        // A wide character that doesn't fit whole is dropped, not split
+        assert_eq!(fit_width("日本語のラベル", 6), "日本…");
+        assert_eq!(fit_width("abc", 0), "");
+    }
+
+    #[test]
+    fn test_format_overtime() {
+        assert_eq!(format_overtime(Duration::from_secs(133)), "+02:13");
+        assert_eq!(format_overtime(Duration::from_millis(900)), "+00:00");
+        assert_eq!(format_overtime(Duration::from_secs(3725)), "+01:02:05");
+    }
+
+    #[test]
+    fn test_timer_screens() {
+        let view = TimerView { label: Some("tea"), tag: None, remaining: Duration::from_secs(272), total: Duration::from_secs(300), readout: Readout::Remaining, zone: Zone::Normal, overtime: None, paused: false, warning: false, next_milestone: None, spinner: None, hooks: None };
+        snapshot("timer_running", |f| draw_timer_running(f, &view));
+        let view = TimerView { label: None, tag: None, remaining: Duration::ZERO, total: Duration::from_secs(300), readout: Readout::Remaining, zone: Zone::Critical, overtime: Some(Duration::from_secs(133)), paused: false, warning: false, next_milestone: None, spinner: None, hooks: None };
+        snapshot("timer_overtime", |f| draw_timer_running(f, &view));
+        snapshot("timer_finished", draw_timer_finished);
+        let hooks = crate::focus::Report { total: 2, failed: vec!["slack pause: exit status: 1".into()] };
+        let view = TimerView { label: Some("Focus"), tag: None, remaining: Duration::from_secs(3000), total: Duration::from_secs(3000), readout: Readout::Remaining, zone: Zone::Normal, overtime: None, paused: false, warning: false, next_milestone: None, spinner: None, hooks: Some(&hooks) };
+        snapshot("timer_focus", |f| draw_timer_running(f, &view));
+        let view = TimerView { label: Some("standup"), tag: None, remaining: Duration::from_secs(852), total: Duration::from_secs(1200), readout: Readout::Remaining, zone: Zone::Normal, overtime: None, paused: false, warning: false, next_milestone: Some(("50%", Duration::from_secs(252))), spinner: None, hooks: None };
+        snapshot("timer_milestone", |f| draw_timer_running(f, &view));
+        let view = TimerView { label: Some("tea"), tag: None, remaining: Duration::from_secs(272), total: Duration::from_secs(600), readout: Readout::Both, zone: Zone::Normal, overtime: None, paused: false, warning: false, next_milestone: None, spinner: None, hooks: None };
+        snapshot("timer_readout_both", |f| draw_timer_running(f, &view));
+    }
+
+    #[test]
+    fn test_timer_face() {
+        let view = TimerView { label: Some("tea"), tag: None, remaining: Duration::from_secs(135), total: Duration::from_secs(300), readout: Readout::Remaining, zone: Zone::Normal, overtime: None, paused: false, warning: false, next_milestone: None, spinner: None, hooks: None };
+        snapshot("timer_face", |f| draw_timer_face(f, &view, 0.75));
+    }
+
+    #[test]
+    fn test_parallel_grid() {
+        let area = Rect::new(0, 0, 80, 22);
+        // Stacked while they fit, then side by side
+        assert_eq!(parallel_grid(1, area), Ok((1, 1)));
+        assert_eq!(parallel_grid(3, area), Ok((1, 3)));
+        assert_eq!(parallel_grid(5, area), Ok((1, 5)));
+        assert_eq!(parallel_grid(6, area), Ok((2, 3)));
+        assert_eq!(parallel_grid(12, area), Ok((3, 4)));
+        // Too narrow for another column, and too short for eight stacked
+        assert_eq!(parallel_grid(8, Rect::new(0, 0, 30, 20)), Err((1, 8)));
+    }
+
+    #[test]
+    fn test_parallel_screen() {
+        let timer = |label, secs, zone, state| ParallelTimer { label, remaining: Duration::from_secs(secs), zone, paused: false, state };
+        let timers = [
+            timer("pasta", 312, Zone::Normal, ParallelState::Running),
+            ParallelTimer { paused: true, ..timer("sauce", 95, Zone::Warn, ParallelState::Running) },
+            timer("garlic bread", 0, Zone::Critical, ParallelState::Finished),
+        ];
+        snapshot("parallel", |f| draw_parallel(f, &ParallelView { timers: &timers, selected: 1, spinner: None }));
+        let labels = ["one", "two", "three", "four", "five", "six"];
+        let many: Vec<ParallelTimer> = labels.iter().map(|&label| timer(label, 42, Zone::Critical, ParallelState::Running)).collect();
+        snapshot("parallel_six", |f| draw_parallel(f, &ParallelView { timers: &many, selected: 0, spinner: None }));
+        let ended = [timer("pasta", 0, Zone::Critical, ParallelState::Finished), timer("sauce", 95, Zone::Warn, ParallelState::Dismissed)];
+        snapshot("parallel_ended", |f| draw_parallel(f, &ParallelView { timers: &ended, selected: 0, spinner: None }));
+    }
+
+    #[test]
+    fn test_parallel_too_small() {
+        let timers: Vec<ParallelTimer> = (0..8)
+            .map(|_| ParallelTimer { label: "tea", remaining: Duration::from_secs(60), zone: Zone::Normal, paused: false, state: ParallelState::Running })
+            .collect();
+        let text = render_to_string(30, 20, |f| draw_parallel(f, &ParallelView { timers: &timers, selected: 0, spinner: None })).unwrap();
+        assert!(text.contains("terminal too small"), "{}", text);
+        assert!(text.contains("(need 30×34)"), "{}", text);
+        assert!(!text.contains("tea"), "{}", text);
+    }
+
+    #[test]
+    fn test_button_hit_testing() {
+        let row = Rect::new(0, 20, 40, 1);
+        // "[ Pause ] [ Cancel ]" is 20 wide, centered from column 10; AddAlarm has no button
+        let buttons = layout_buttons(row, &[Action::Pause, Action::AddAlarm, Action::Cancel]);
+        assert_eq!(buttons, [(Rect::new(10, 20, 9, 1), Action::Pause), (Rect::new(20, 20, 10, 1), Action::Cancel)]);
+        assert_eq!(button_at(&buttons, 10, 20), Some(Action::Pause));
+        assert_eq!(button_at(&buttons, 18, 20), Some(Action::Pause));
+        // The gap between them, the row above, and off to the side
+        assert_eq!(button_at(&buttons, 19, 20), None);
+        assert_eq!(button_at(&buttons, 12, 19), None);
+        assert_eq!(button_at(&buttons, 35, 20), None);
+        assert_eq!(button_at(&buttons, 29, 20).and_then(Action::key), Some(KeyCode::Char('q')));
+        assert_eq!(Action::Pause.key(), Some(KeyCode::Char('p')));
+    }
+
+    #[test]
+    fn test_big_text() {
+        let lines = big_text("01:5", |_| Style::default());
+        let rows: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        assert_eq!(rows, ["███  █    ███", "█ █ ██  █ █  ", "█ █  █    ███", "█ █  █  █   █", "███ ███   ███"]);
+        let styled = big_text("1:2", |i| if i == 2 { Style::default().fg(Color::Yellow) } else { Style::default() });
+        assert_eq!(styled[0].spans.last().unwrap().style.fg, Some(Color::Yellow));
+    }
+
+    #[test]
+    fn test_pie_points() {
+        let count = |left| pie_points(left, 40, 40).len();
+        assert_eq!(count(0.0), 0);
+        // A full pie covers about pi/4 of the square around it
+        let full = count(1.0) as f64 / 1600.0;
+        assert!((full - std::f64::consts::FRAC_PI_4).abs() < 0.02, "{}", full);
+        assert!(count(0.25) > 0 && count(0.25) < count(0.5) && count(0.5) < count(1.0));
+        // A quarter left is the wedge from 12 to 3 o'clock
+        assert!(pie_points(0.25, 40, 40).iter().all(|&(x, y)| x >= 0.0 && y >= 0.0));
+    }
+
+    #[test]
+    fn test_batch_step() {
+        let view = BatchView {
+            index: 1,
+            total: 3,
+            label: "steep",
+            remaining: Duration::from_secs(95),
+            next: Some("pour"),
+            spinner: None,
+        };
+        snapshot("batch_step", |f| draw_batch(f, &view));
+    }
+
+    #[test]
+    fn test_start_waiting() {
+        let view = StartWaitView {
+            start_at: NaiveTime::from_hms_opt(14, 0, 0).unwrap(),
+            until: chrono::Duration::seconds(4_995),
+            tomorrow: false,
+            what: "Timer: 20:00",
+            spinner: None,
+        };
+        snapshot("start_waiting", |f| draw_start_waiting(f, &view));
+    }
+
+    #[test]
+    fn test_start_in() {
+        snapshot("start_in", |f| draw_start_in(f, Duration::from_secs(7), "Timer: 20:00"));
+    }
+
+    #[test]
+    fn test_count_in() {
+        snapshot("count_in", |f| draw_count_in(f, 3));
+    }
+
+    #[test]
+    fn test_pomodoro() {
+        let view = PomodoroView {
+            phase: "Work",
+            round: 2,
+            rounds: 4,
+            remaining: Duration::from_secs(14 * 60 + 5),
+            up_next: "Next: Short Break (05:00)",
+            color: Color::Red,
+            spinner: None,
+        };
+        snapshot("pomodoro", |f| draw_pomodoro(f, &view));
+    }
+
+    #[test]
+    fn test_queue_waiting() {
+        snapshot("queue_waiting", |f| draw_queue_waiting(f, 2, None));
+    }
+
+    #[test]
+    fn test_stopwatch_with_laps() {
+        let laps = [Duration::from_millis(88_100), Duration::from_millis(91_750)];
+        let view = StopwatchView {
+            shown: Duration::from_millis(200_420),
+            paused: false,
+            spinner: None,
+            target_lap: Some(Duration::from_secs(90)),
+            laps: &laps,
+            precision: Precision::Ms,
+        };
+        snapshot("stopwatch_laps", |f| draw_stopwatch(f, &view));
+    }
+
+    #[test]
+    fn test_alarm_screens() {
+        let alarm_time = NaiveTime::from_hms_opt(7, 30, 0).unwrap();
+        let view = AlarmView {
+            alarm_time,
+            until: chrono::Duration::seconds(3 * 3600 + 125),
+            tomorrow: true,
+            spinner: None,
+        };
+        snapshot("alarm_waiting", |f| draw_alarm_waiting(f, &view));
+        let days_out = AlarmView { until: chrono::Duration::seconds(2 * 86_400 + 3 * 3600 + 942), tomorrow: false, ..view };
+        snapshot("alarm_waiting_days", |f| draw_alarm_waiting(f, &days_out));
+        snapshot("alarm_ringing", |f| draw_alarm_ringing(f, alarm_time, Some(Duration::from_secs(240)), Snooze::Available));
+        snapshot("alarm_ringing_final", |f| draw_alarm_ringing(f, alarm_time, None, Snooze::UsedUp));
+    }
+
+    #[test]
+    fn test_dashboard() {
+        use crate::status::ClockKind;
+
+        let now_ms = 1_000_000;
+        let clocks = [
+            ClockStatus {
+                ends_at: Some(now_ms + 272_000),
+                ..ClockStatus::new(ClockKind::Timer, Some("tea".into()), now_ms, Duration::from_secs(28))
+            },
+            ClockStatus {
+                paused: true,
+                ..ClockStatus::new(ClockKind::Stopwatch, None, now_ms, Duration::from_secs(754))
+            },
+        ];
+        snapshot("dashboard", |f| draw_dashboard(f, &DashboardView { clocks: &clocks, now_ms }));
+        snapshot("dashboard_empty", |f| draw_dashboard(f, &DashboardView { clocks: &[], now_ms }));
+    }
+
+    #[test]
+    fn test_alarm_watch() {
+        use crate::store::EntryKind;
+        use chrono::{TimeZone, Weekday};
+
+        let now = Local.with_ymd_and_hms(2024, 3, 8, 6, 15, 0).unwrap();
+        let alarm = |id, h, label: Option<&str>, repeat: Vec<Weekday>| Entry {
+            id,
+            kind: EntryKind::Alarm,
+            label: label.map(str::to_string),
+            tag: None,
+            message: None,
+            due: Local.with_ymd_and_hms(2024, 3, 8, h, 30, 0).unwrap(),
+            repeat,
+            exec: None,
+            quiet_hours: None,
+            skip_until: None,
+            imported: None,
+            total_ms: None,
+            progress: None,
+        };
+        let weekdays = vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+        let alarms = [alarm(3, 7, Some("wake up"), weekdays), alarm(1, 17, None, vec![])];
+        let view = AlarmWatchView { alarms: &alarms, selected: 1, now, input: None, message: None };
+        snapshot("alarm_watch", |f| draw_alarm_watch(f, &view));
+        let view = AlarmWatchView { alarms: &[], selected: 0, now, input: Some("7:3"), message: None };
+        snapshot("alarm_watch_adding", |f| draw_alarm_watch(f, &view));
+    }
+
+    #[tokio::test]
+    async fn test_visual_bell_restores_normal_video() {
+        let mut out = Vec::new();
+        write_visual_bell(&mut out, 2, Duration::ZERO).await.unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches("\x1b[?5h").count(), 2);
+        assert!(out.ends_with("\x1b[?5l"));
+    }
+}
@@ -0,0 +1,236 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::{format_duration, parse_duration};
+
+/// Time left below which a countdown turns yellow, unless configured.
+const DEFAULT_WARN_AT: Duration = Duration::from_secs(60);
+/// Time left below which a countdown turns red, unless configured.
+const DEFAULT_CRITICAL_AT: Duration = Duration::from_secs(10);
+
+/// How close a countdown is to zero, which picks its color on screen and is
+/// published in its status file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Zone {
+    Normal,
+    Warn,
+    Critical,
+}
+
+/// A zone boundary as given by the user: an amount of time left (`"10m"`) or
+/// a share of the whole timer left (`"10%"`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Threshold {
+    Left(Duration),
+    Percent(u8),
+}
+
+impl Threshold {
+    pub fn parse(s: &str) -> Result<Threshold> {
+        match s.trim().strip_suffix('%') {
+            Some(percent) => {
+                let percent: u8 = percent
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|p| (1..100).contains(p))
+                    .ok_or_else(|| anyhow::anyhow!("Invalid percentage '{}'. Use 1% to 99%", s))?;
+                Ok(Threshold::Percent(percent))
+            }
+            None => Ok(Threshold::Left(parse_duration(s)?)),
+        }
+    }
+
+    /// Time left at the boundary of a `total` long timer.
+    pub fn resolve(self, total: Duration) -> Duration {
+        match self {
+            Threshold::Left(left) => left,
+            Threshold::Percent(percent) => total * u32::from(percent) / 100,
+        }
+    }
+}
+
+/// How much further out the built-in warn boundary is than the critical one,
+/// kept when one of them has to be worked out from the other.
+const DEFAULT_RATIO: u32 = 6;
+
+/// Where a threshold came from. A flag is what the user asked for this time,
+/// so one that can't be met is an error; a config value gives way instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source<'a> {
+    Flag(&'a str),
+    Config(&'a str),
+}
+
+impl<'a> Source<'a> {
+    /// The flag if it was given, else the config value.
+    pub fn pick(flag: Option<&'a str>, config: Option<&'a str>) -> Option<Source<'a>> {
+        flag.map(Source::Flag).or(config.map(Source::Config))
+    }
+
+    fn text(self) -> &'a str {
+        match self {
+            Source::Flag(s) | Source::Config(s) => s,
+        }
+    }
+}
+
+/// The warn and critical boundaries of one timer, as time left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zones {
+    warn_at: Duration,
+    critical_at: Duration,
+    /// The given warn_at is the boundary, rather than one worked out or the default
+    warn_kept: bool,
+    critical_kept: bool,
+}
+
+impl Default for Zones {
+    fn default() -> Self {
+        Zones { warn_at: DEFAULT_WARN_AT, critical_at: DEFAULT_CRITICAL_AT, warn_kept: false, critical_kept: false }
+    }
+}
+
+impl Zones {
+    /// Boundaries for a `total` long timer, from `--warn-at`/`--critical-at` or the
+    /// config. Flags are held to the total and, when both are given, to each
+    /// other; a config value that doesn't fit is dropped. A boundary that's
+    /// missing (or dropped) is worked out from the other so the two stay in
+    /// order, and with neither the defaults apply as before.
+    pub fn new(warn_at: Option<Source>, critical_at: Option<Source>, total: Duration) -> Result<Zones> {
+        let parse = |source: Option<Source>, name: &str| -> Result<Option<(Duration, bool)>> {
+            let Some(source) = source else {
+                return Ok(None);
+            };
+            let left = Threshold::parse(source.text()).map(|t| t.resolve(total)).with_context(|| format!("Invalid {}", name))?;
+            let flag = matches!(source, Source::Flag(_));
+            if left >= total {
+                if flag {
+                    anyhow::bail!("{} ({} left) must be less than the timer's {}", name, format_duration(left), format_duration(total));
+                }
+                return Ok(None);
+            }
+            Ok(Some((left, flag)))
+        };
+        let mut warn = parse(warn_at, "warn_at")?;
+        let mut critical = parse(critical_at, "critical_at")?;
+
+        if let (Some((warn_left, warn_flag)), Some((critical_left, critical_flag))) = (warn, critical) {
+            if critical_left >= warn_left {
+                match (warn_flag, critical_flag) {
+                    (true, true) => anyhow::bail!(
+                        "critical_at ({} left) must be less than warn_at ({} left)",
+                        format_duration(critical_left),
+                        format_duration(warn_left)
+                    ),
+                    // The flag wins, and with both from the config the warning stands
+                    (true, false) | (false, false) => critical = None,
+                    (false, true) => warn = None,
+                }
+            }
+        }
+
+        let warn_at = match (warn, critical) {
+            (Some((left, _)), _) => left,
+            (None, Some((critical_left, _))) if critical_left >= DEFAULT_WARN_AT => (critical_left * DEFAULT_RATIO).min(total),
+            (None, _) => DEFAULT_WARN_AT,
+        };
+        let critical_at = match (critical, warn) {
+            (Some((left, _)), _) => left,
+            (None, Some((warn_left, _))) if warn_left <= DEFAULT_CRITICAL_AT => warn_left / DEFAULT_RATIO,
+            (None, _) => DEFAULT_CRITICAL_AT,
+        };
+        Ok(Zones { warn_at, critical_at, warn_kept: warn.is_some(), critical_kept: critical.is_some() })
+    }
+
+    /// Whether the warn_at given to [`Zones::new`] is in use, so it's worth a milestone.
+    pub fn keeps_warn_at(&self) -> bool {
+        self.warn_kept
+    }
+
+    pub fn keeps_critical_at(&self) -> bool {
+        self.critical_kept
+    }
+
+    pub fn zone(&self, remaining: Duration) -> Zone {
+        if remaining < self.critical_at {
+            Zone::Critical
+        } else if remaining < self.warn_at {
+            Zone::Warn
+        } else {
+            Zone::Normal
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn test_default_zones() {
+        let zones = Zones::new(None, None, secs(30)).unwrap();
+        assert_eq!(zones.zone(secs(60)), Zone::Normal);
+        assert_eq!(zones.zone(secs(59)), Zone::Warn);
+        assert_eq!(zones.zone(secs(10)), Zone::Warn);
+        assert_eq!(zones.zone(secs(9)), Zone::Critical);
+    }
+
+    #[test]
+    fn test_absolute_and_relative_zones() {
+        let zones = Zones::new(Some(Source::Flag("10m")), Some(Source::Flag("2m")), secs(7200)).unwrap();
+        assert_eq!(zones.zone(secs(601)), Zone::Normal);
+        assert_eq!(zones.zone(secs(599)), Zone::Warn);
+        assert_eq!(zones.zone(secs(119)), Zone::Critical);
+
+        let zones = Zones::new(Some(Source::Flag("10%")), Some(Source::Config("1%")), secs(7200)).unwrap();
+        assert_eq!(zones.zone(secs(719)), Zone::Warn);
+        assert_eq!(zones.zone(secs(71)), Zone::Critical);
+    }
+
+    #[test]
+    fn test_zone_validation() {
+        assert!(Zones::new(Some(Source::Flag("2m")), Some(Source::Flag("10m")), secs(7200)).is_err());
+        assert!(Zones::new(Some(Source::Flag("3h")), None, secs(7200)).is_err());
+        assert!(Zones::new(Some(Source::Flag("100%")), None, secs(60)).is_err());
+        assert!(Zones::new(Some(Source::Config("soon")), None, secs(60)).is_err());
+    }
+
+    #[test]
+    fn test_missing_zone_follows_the_given_one() {
+        // 5% of 2h is 6m, so warning starts further out than the default minute
+        let zones = Zones::new(None, Some(Source::Flag("5%")), secs(7200)).unwrap();
+        assert_eq!((zones.warn_at, zones.critical_at), (secs(36 * 60), secs(6 * 60)));
+        let zones = Zones::new(None, Some(Source::Flag("5m")), secs(7200)).unwrap();
+        assert_eq!(zones.zone(secs(5 * 60 + 1)), Zone::Warn);
+        // And a warn_at under the default critical one brings that in
+        let zones = Zones::new(Some(Source::Flag("6s")), None, secs(60)).unwrap();
+        assert_eq!((zones.warn_at, zones.critical_at), (secs(6), secs(1)));
+        assert!(zones.keeps_warn_at() && !zones.keeps_critical_at());
+    }
+
+    #[test]
+    fn test_config_zones_give_way() {
+        let (warn, critical) = (Some(Source::Config("10%")), Some(Source::Config("1m")));
+        // The README's sample config on short timers: the critical_at gives way
+        let zones = Zones::new(warn, critical, secs(300)).unwrap();
+        assert_eq!((zones.warn_at, zones.critical_at), (secs(30), secs(10)));
+        assert!(!zones.keeps_critical_at());
+        let zones = Zones::new(warn, critical, secs(30)).unwrap();
+        assert_eq!((zones.warn_at, zones.critical_at), (secs(3), Duration::from_millis(500)));
+        // A flag beats the config
+        let zones = Zones::new(Some(Source::Flag("30s")), Some(Source::Config("1m")), secs(300)).unwrap();
+        assert_eq!((zones.warn_at, zones.critical_at), (secs(30), secs(10)));
+        let zones = Zones::new(Some(Source::Config("10s")), Some(Source::Flag("30s")), secs(300)).unwrap();
+        assert_eq!((zones.warn_at, zones.critical_at), (secs(60), secs(30)));
+        // A config value past the total is dropped rather than an error
+        let zones = Zones::new(Some(Source::Config("10m")), None, secs(300)).unwrap();
+        assert_eq!(zones, Zones::default());
+    }
+}